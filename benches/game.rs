@@ -0,0 +1,109 @@
+//! The benchmarks of the moves and the AI search, the baseline for the performance work.
+//! Run with ```cargo bench```. All positions are generated with ```Game::random_position``` and all games are seeded,
+//! so the benchmarks measure the same work on every run.
+//!
+//! The rough numbers on the development machine (release build), to notice the regressions:
+//! * ```update``` - about 0.4 µs (4x4, with the row tables), 0.8 µs (6x6) and 1.1 µs (8x8)
+//! * ```make_move``` - about 1.7 µs on a 4x4 board
+//! * ```playout``` - about 25 µs for a whole 4x4 game
+//! * ```moves_16x16``` - about 0.7 ms with the moves built after every move and 0.45 ms with the lazy moves
+//! * ```find_best_move``` - about 2.5 ms for the depth of 100 and 20 ms for the depth of 1000 (with the thread pool)
+
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion};
+use tools_2048::{Game, GameMove, GameState, SearchOptions};
+
+/// The seed of all generated positions and games.
+const SEED: u64 = 2048;
+
+/// Creating a game from a board validates the board and updates the possible moves, which dominates the time.
+fn update(c: &mut Criterion) {
+    let mut group = c.benchmark_group("update");
+    let board_4 = *Game::<4>::random_position(8, 10, SEED).unwrap().board();
+    let board_6 = *Game::<6>::random_position(18, 12, SEED).unwrap().board();
+    let board_8 = *Game::<8>::random_position(32, 14, SEED).unwrap().board();
+    group.bench_function(BenchmarkId::from_parameter("4x4"), |b| b.iter(|| Game::from_existing(black_box(&board_4), 0)));
+    group.bench_function(BenchmarkId::from_parameter("6x6"), |b| b.iter(|| Game::from_existing(black_box(&board_6), 0)));
+    group.bench_function(BenchmarkId::from_parameter("8x8"), |b| b.iter(|| Game::from_existing(black_box(&board_8), 0)));
+    group.finish();
+}
+
+/// A single move with the spawn of the new tile.
+fn make_move(c: &mut Criterion) {
+    c.bench_function("make_move", |b| {
+        b.iter_batched(
+            || Game::<4>::random_position(8, 10, SEED).unwrap(),
+            |mut game| {
+                let direction = GameMove::ALL.into_iter().find(|&direction| game.can_move(direction)).unwrap();
+                game.make_move(direction);
+                game
+            },
+            BatchSize::SmallInput,
+        )
+    });
+}
+
+/// A whole game, moving in the first possible direction of a fixed order.
+fn playout(c: &mut Criterion) {
+    c.bench_function("playout", |b| {
+        b.iter_batched(
+            || Game::<4>::builder().seed(SEED).build().unwrap(),
+            |mut game| {
+                while game.state() == GameState::InProgress {
+                    let direction = GameMove::ALL.into_iter().find(|&direction| game.can_move(direction)).unwrap();
+                    game.make_move(direction);
+                }
+                game
+            },
+            BatchSize::SmallInput,
+        )
+    });
+}
+
+/// 200 moves on a 16x16 board, querying only ```can_move```, with the boards after the moves built after every move or only for the made moves.
+fn lazy_moves(c: &mut Criterion) {
+    let mut group = c.benchmark_group("moves_16x16");
+    for (name, lazy) in [("eager", false), ("lazy", true)] {
+        group.bench_function(BenchmarkId::from_parameter(name), |b| {
+            b.iter_batched(
+                || {
+                    let game = Game::<16>::builder().seed(SEED).build().unwrap();
+                    if lazy {
+                        game.with_lazy_moves()
+                    } else {
+                        game
+                    }
+                },
+                |mut game| {
+                    for _ in 0..200 {
+                        let direction = GameMove::ALL.into_iter().find(|&direction| game.can_move(direction)).unwrap();
+                        game.make_move(direction);
+                    }
+                    game
+                },
+                BatchSize::SmallInput,
+            )
+        });
+    }
+    group.finish();
+}
+
+/// The Monte Carlo search on a fixed position.
+fn find_best_move(c: &mut Criterion) {
+    let mut group = c.benchmark_group("find_best_move");
+    group.sample_size(20);
+    let game = Game::<4>::random_position(8, 10, SEED).unwrap();
+    for depth in [100, 1000] {
+        let options = SearchOptions {
+            depth,
+            seed: Some(SEED),
+            ..Default::default()
+        };
+        group.bench_with_input(BenchmarkId::from_parameter(depth), &options, |b, options| {
+            b.iter(|| game.find_best_move_with_options(options))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, update, make_move, playout, lazy_moves, find_best_move);
+criterion_main!(benches);
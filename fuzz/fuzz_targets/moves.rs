@@ -0,0 +1,41 @@
+//! Applies random move sequences to random games of the sizes 4 to 8 and checks the invariants of the game:
+//! the board stays valid, the score never decreases and the counters match the board.
+//! Run with ```cargo fuzz run moves``` from the root of the repository.
+
+#![no_main]
+
+use arbitrary::{Arbitrary, Result, Unstructured};
+use libfuzzer_sys::fuzz_target;
+use tools_2048::{validate_board, Game, GameMove, GameState};
+
+/// Plays the generated moves on a generated game of the given size.
+fn play<const SIZE: usize>(u: &mut Unstructured) -> Result<()> {
+    let mut game = Game::<SIZE>::arbitrary(u)?;
+    let moves: Vec<GameMove> = u.arbitrary()?;
+    for direction in moves {
+        let score = game.score();
+        let turns = game.turns();
+        let moved = game.make_move(direction);
+
+        assert!(validate_board(game.board()).is_ok());
+        assert!(game.score() >= score);
+        assert_eq!(game.turns(), turns + u64::from(moved));
+        assert_eq!(game.empty_count(), game.board().iter().flatten().filter(|&&tile| tile == 0).count());
+        if game.state() == GameState::GameOver {
+            assert!(GameMove::ALL.into_iter().all(|direction| !game.can_move(direction)));
+        }
+    }
+    Ok(())
+}
+
+fuzz_target!(|data: &[u8]| {
+    let mut u = Unstructured::new(data);
+    let _ = match u.int_in_range(4..=8_u8) {
+        Ok(4) => play::<4>(&mut u),
+        Ok(5) => play::<5>(&mut u),
+        Ok(6) => play::<6>(&mut u),
+        Ok(7) => play::<7>(&mut u),
+        Ok(_) => play::<8>(&mut u),
+        Err(error) => Err(error),
+    };
+});
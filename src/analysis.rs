@@ -0,0 +1,301 @@
+//! A module that contains the tools for analysing the games, e.g. for reproducing the reports of bad moves.
+//! The decisions of the AI are compared by repeating the search with different seeds, so the moves chosen by chance are told apart from the moves the search is sure of.
+//! The luck of the recorded games is measured by comparing every spawned tile with the other tiles that could have been spawned.
+
+// internal imports
+use crate::core::{derive_seed, Game, GameEvent, GameMove, SearchOptions};
+use crate::error::Error;
+use crate::heuristics::{self, HeuristicWeights};
+use crate::recorder::{LogEntry, Replay};
+
+/// The fraction of the trials the most chosen move has to win for the decision to be stable.
+pub const STABLE_AGREEMENT: f64 = 0.9;
+
+/// The statistics of one move over the repeated searches.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct MoveComparison {
+    /// The number of the searches that chose the move.
+    pub wins: usize,
+    /// The mean of the average values of the simulated games of the move (see ```SearchReport::scores```).
+    pub mean_score: f64,
+    /// The sample variance of the average values between the searches (0 for a single search).
+    pub score_variance: f64,
+}
+
+/// The results of repeating the search of a position, returned by ```compare_moves```.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ComparisonReport {
+    /// The number of the searches.
+    pub trials: usize,
+    /// The statistics of every move (in the order of ```GameMove::ALL```), ```None``` for invalid moves.
+    pub moves: [Option<MoveComparison>; 4],
+    /// The move chosen by the most searches (the first one in the order of ```GameMove::ALL``` if several were chosen equally often).
+    pub best: GameMove,
+    /// The fraction of the searches that chose the best move.
+    pub agreement: f64,
+    /// Whether the decision is unstable, the best move was chosen by fewer than ```STABLE_AGREEMENT``` of the searches.
+    pub unstable: bool,
+}
+
+/// Repeats the Monte Carlo search of the position with different seeds and compares the chosen moves.
+/// The seed of every search is derived from the given seed, so the comparison is reproducible.
+/// # Arguments
+/// * ```game``` - The game whose position is searched.
+/// * ```depth``` - The number of simulated games of every search.
+/// * ```trials``` - The number of searches.
+/// * ```seed``` - The seed of the searches.
+/// # Returns
+/// * ```Ok(ComparisonReport)``` - The results of the searches.
+/// * ```Err(Error)``` - The searches couldn't be made.
+/// # Errors
+/// * ```Error::NoValidMove``` - There are no valid moves left.
+/// * ```Error::InvalidDepth``` - The depth is 0.
+/// * ```Error::InvalidConfig``` - The number of the trials is 0.
+pub fn compare_moves<const SIZE: usize>(game: &Game<SIZE>, depth: usize, trials: usize, seed: u64) -> Result<ComparisonReport, Error> {
+    if trials == 0 {
+        return Err(Error::InvalidConfig);
+    }
+
+    let mut wins = [0; 4];
+    let mut scores: [Vec<f64>; 4] = Default::default();
+    for trial in 0..trials {
+        let options = SearchOptions {
+            depth,
+            seed: Some(derive_seed(seed, trial as u64)),
+            ..Default::default()
+        };
+        let report = game.search_report(&options, None)?;
+        wins[GameMove::ALL.iter().position(|&direction| direction == report.best).unwrap()] += 1;
+        for (move_scores, score) in scores.iter_mut().zip(report.scores) {
+            move_scores.extend(score);
+        }
+    }
+
+    let moves = core::array::from_fn(|ind| {
+        game.can_move(GameMove::ALL[ind]).then(|| {
+            let (mean_score, score_variance) = mean_with_variance(&scores[ind]);
+            MoveComparison {
+                wins: wins[ind],
+                mean_score,
+                score_variance,
+            }
+        })
+    });
+    // the first of the most chosen moves
+    let best_ind = (0..4).rev().max_by_key(|&ind| wins[ind]).unwrap();
+    let agreement = wins[best_ind] as f64 / trials as f64;
+
+    Ok(ComparisonReport {
+        trials,
+        moves,
+        best: GameMove::ALL[best_ind],
+        agreement,
+        unstable: agreement < STABLE_AGREEMENT,
+    })
+}
+
+/// The luck of one spawned tile of a recorded game.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct SpawnLuck {
+    /// The number of the moves made before the tile was spawned, including the move it was spawned after.
+    pub turn: u64,
+    /// The position (row, column) of the tile.
+    pub position: (usize, usize),
+    /// The value of the tile.
+    pub value: u64,
+    /// The evaluation of the board with the tile (see ```heuristics::composite```).
+    pub evaluation: f64,
+    /// The expected evaluation of the board over all the tiles that could have been spawned.
+    pub expected_evaluation: f64,
+    /// The probability that a spawned tile would have given a worse board, the ties counting as half (0.5 is the average luck, 1 the best tile).
+    pub percentile: f64,
+}
+
+/// The luck of a recorded game, returned by ```luck```.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LuckReport {
+    /// The luck of every spawned tile, in the order they were spawned.
+    pub spawns: Vec<SpawnLuck>,
+    /// The luck of the whole game, the mean of the percentiles of the tiles (0.5 for a game without spawned tiles).
+    pub luck: f64,
+    /// The median of the percentiles of the tiles (0.5 for a game without spawned tiles).
+    pub median: f64,
+}
+
+/// Measures how lucky the spawned tiles of a recorded game were.
+/// Every spawned tile is compared with all the tiles that could have been spawned instead (see ```Game::spawn_distribution```),
+/// by the evaluation of the board with the tile (see ```heuristics::composite``` with the default weights).
+/// # Arguments
+/// * ```replay``` - The recorded game.
+/// # Returns
+/// * ```Ok(LuckReport)``` - The luck of the tiles and of the whole game.
+/// * ```Err(Error)``` - The recorded events don't fit the game, see ```Replay::play```.
+pub fn luck<const SIZE: usize>(replay: &Replay<SIZE>) -> Result<LuckReport, Error> {
+    let weights = HeuristicWeights::default();
+    let mut spawns = Vec::new();
+    replay.play_each(|game, entry| {
+        let LogEntry::Event(GameEvent::TileSpawned { position: (row, col), value }) = *entry else {
+            return;
+        };
+        let evaluate = |row: usize, col: usize, value: u64| {
+            let mut board = *game.board();
+            board[row][col] = value;
+            heuristics::composite(&board, &weights)
+        };
+        let evaluation = evaluate(row, col, value);
+        let (mut expected_evaluation, mut percentile) = (0.0, 0.0);
+        for (i, j, alternative, probability) in game.spawn_distribution() {
+            let alternative_evaluation = evaluate(i, j, alternative);
+            expected_evaluation += probability * alternative_evaluation;
+            if alternative_evaluation < evaluation {
+                percentile += probability;
+            } else if alternative_evaluation == evaluation {
+                percentile += probability / 2.0;
+            }
+        }
+        spawns.push(SpawnLuck {
+            turn: game.turns(),
+            position: (row, col),
+            value,
+            evaluation,
+            expected_evaluation,
+            percentile,
+        });
+    })?;
+
+    let mut percentiles: Vec<f64> = spawns.iter().map(|spawn| spawn.percentile).collect();
+    percentiles.sort_by(f64::total_cmp);
+    let (luck, median) = match percentiles.len() {
+        0 => (0.5, 0.5),
+        count => (
+            percentiles.iter().sum::<f64>() / count as f64,
+            (percentiles[(count - 1) / 2] + percentiles[count / 2]) / 2.0,
+        ),
+    };
+    Ok(LuckReport { spawns, luck, median })
+}
+
+/// Calculates the mean and the sample variance of the values.
+/// # Arguments
+/// * ```values``` - The values.
+/// # Returns
+/// * ```(f64, f64)``` - The mean (0 for no values) and the sample variance (0 for fewer than 2 values).
+fn mean_with_variance(values: &[f64]) -> (f64, f64) {
+    if values.is_empty() {
+        return (0.0, 0.0);
+    }
+    let mean = values.iter().sum::<f64>() / values.len() as f64;
+    if values.len() == 1 {
+        return (mean, 0.0);
+    }
+    let variance = values.iter().map(|value| (value - mean).powi(2)).sum::<f64>() / (values.len() - 1) as f64;
+    (mean, variance)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::recorder::GameRecorder;
+
+    #[test]
+    fn dominant_move() {
+        //! Test that every search agrees on a move that is much better than the others
+
+        let game = Game::from_existing(&[[8, 2, 8, 64], [4, 32, 16, 32], [1024, 1024, 2, 256], [2, 8, 256, 32]], 0).unwrap();
+        let report = compare_moves(&game, 200, 8, 3).unwrap();
+        assert_eq!(report.trials, 8);
+        assert_eq!(report.best, GameMove::Left);
+        assert_eq!((report.agreement, report.unstable), (1.0, false));
+        let left = report.moves[0].unwrap();
+        assert_eq!(left.wins, 8);
+        assert!(report
+            .moves
+            .iter()
+            .flatten()
+            .all(|comparison| comparison.mean_score <= left.mean_score && comparison.score_variance >= 0.0));
+        assert_eq!(report.moves.iter().flatten().map(|comparison| comparison.wins).sum::<usize>(), 8);
+        assert_eq!(report.moves[2], None);
+
+        // the comparison is reproducible
+        assert_eq!(compare_moves(&game, 200, 8, 3), Ok(report));
+    }
+
+    #[test]
+    fn unstable_decisions() {
+        //! Test that the searches split between the equally good moves of a symmetric position
+
+        let game = Game::from_existing(&[[2, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0]], 0).unwrap();
+        let report = compare_moves(&game, 40, 20, 9).unwrap();
+        assert_eq!(report.moves[0], None);
+        assert_eq!(report.moves[2], None);
+        let (right, down) = (report.moves[1].unwrap(), report.moves[3].unwrap());
+        assert_eq!(right.wins + down.wins, 20);
+        assert!(right.wins > 0 && down.wins > 0);
+        assert!(report.unstable);
+
+        assert_eq!(compare_moves(&game, 40, 0, 9), Err(Error::InvalidConfig));
+        assert_eq!(compare_moves(&game, 0, 1, 9), Err(Error::InvalidDepth));
+        let over = Game::from_existing(&[[2, 4], [4, 2]], 0).unwrap();
+        assert_eq!(compare_moves(&over, 40, 1, 9), Err(Error::NoValidMove));
+    }
+
+    #[test]
+    fn spawn_luck() {
+        //! Test that the best spawned tiles are luckier than the worst ones
+
+        let record = |best: bool| {
+            let mut recorder = GameRecorder::new(Game::from_existing(&[[2, 0, 0, 0], [0, 0, 0, 0], [0, 0, 4, 0], [0, 0, 0, 0]], 0).unwrap(), Vec::new());
+            for _ in 0..20 {
+                let Some(direction) = [GameMove::Left, GameMove::Down, GameMove::Right, GameMove::Up]
+                    .into_iter()
+                    .find(|&direction| recorder.can_move(direction))
+                else {
+                    break;
+                };
+                assert_eq!(recorder.make_move_without_spawn(direction), Ok(true));
+                let (row, col, value, _) = recorder
+                    .spawn_distribution()
+                    .map(|(row, col, value, _)| {
+                        let mut board = *recorder.board();
+                        board[row][col] = value;
+                        (row, col, value, heuristics::composite(&board, &HeuristicWeights::default()))
+                    })
+                    .max_by(|a, b| if best { a.3.total_cmp(&b.3) } else { b.3.total_cmp(&a.3) })
+                    .unwrap();
+                recorder.place_tile(row, col, value).unwrap();
+            }
+            let log = recorder.into_inner().unwrap().1;
+            let replay: Replay<4> = GameRecorder::read(log.as_slice()).unwrap();
+            luck(&replay).unwrap()
+        };
+
+        let (lucky, unlucky) = (record(true), record(false));
+        assert_eq!((lucky.spawns.len(), unlucky.spawns.len()), (20, 20));
+        assert!(lucky.luck > 0.5 && unlucky.luck < 0.5);
+        assert!(lucky.median > unlucky.median);
+        assert!(lucky.spawns.iter().all(|spawn| spawn.percentile > 0.5 && spawn.evaluation >= spawn.expected_evaluation));
+        assert!(unlucky.spawns.iter().all(|spawn| spawn.percentile < 0.5 && spawn.evaluation <= spawn.expected_evaluation));
+        assert_eq!((lucky.spawns[0].turn, unlucky.spawns[19].turn), (1, 20));
+
+        // a game without spawned tiles is neither lucky nor unlucky
+        let log = GameRecorder::new(Game::<4>::new().unwrap(), Vec::new()).into_inner().unwrap().1;
+        let replay: Replay<4> = GameRecorder::read(log.as_slice()).unwrap();
+        assert_eq!(
+            luck(&replay).unwrap(),
+            LuckReport {
+                spawns: Vec::new(),
+                luck: 0.5,
+                median: 0.5
+            }
+        );
+    }
+
+    #[test]
+    fn variances() {
+        //! Test the means and the sample variances
+
+        assert_eq!(mean_with_variance(&[]), (0.0, 0.0));
+        assert_eq!(mean_with_variance(&[4.0]), (4.0, 0.0));
+        assert_eq!(mean_with_variance(&[1.0, 3.0]), (2.0, 2.0));
+    }
+}
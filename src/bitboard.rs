@@ -0,0 +1,362 @@
+//! A module that contains the bitboard representation of the 4x4 board.
+//! Used internally to speed up the simulated games of the AI.
+
+// std imports
+use std::sync::OnceLock;
+
+// external imports
+use rand::Rng;
+
+// internal imports
+use crate::core::GameMove;
+use crate::spawn::SpawnConfig;
+
+/// Marks a row whose move would merge two 32768 tiles, which can't be represented in a nibble.
+const OVERFLOW: u32 = u32::MAX;
+
+/// Marks a row that the move changes, in the flags of the row tables.
+const CHANGED: u8 = 0x10;
+
+/// Precomputed moves of all possible rows.
+struct RowTables {
+    /// The row after moving left.
+    left: Vec<u16>,
+    /// The row after moving right.
+    right: Vec<u16>,
+    /// The score of moving the row left (or ```OVERFLOW```).
+    score_left: Vec<u32>,
+    /// The score of moving the row right (or ```OVERFLOW```).
+    score_right: Vec<u32>,
+    /// The exponent of the largest tile merged by moving the row left, with ```CHANGED``` if the row moves.
+    flags_left: Vec<u8>,
+    /// The exponent of the largest tile merged by moving the row right, with ```CHANGED``` if the row moves.
+    flags_right: Vec<u8>,
+}
+
+/// Returns the row tables, computing them on the first call.
+fn tables() -> &'static RowTables {
+    static TABLES: OnceLock<RowTables> = OnceLock::new();
+    TABLES.get_or_init(|| {
+        let mut tables = RowTables {
+            left: vec![0; 1 << 16],
+            right: vec![0; 1 << 16],
+            score_left: vec![0; 1 << 16],
+            score_right: vec![0; 1 << 16],
+            flags_left: vec![0; 1 << 16],
+            flags_right: vec![0; 1 << 16],
+        };
+        let flags = |row: u16, result: u16, merge: u8| if result != row { merge | CHANGED } else { merge };
+        for row in 0..=u16::MAX {
+            let (left, score_left, merge_left) = slide_row(row);
+            let (right, score_right, merge_right) = slide_row(reverse_row(row));
+            tables.left[row as usize] = left;
+            tables.right[row as usize] = reverse_row(right);
+            tables.score_left[row as usize] = score_left;
+            tables.score_right[row as usize] = score_right;
+            tables.flags_left[row as usize] = flags(row, left, merge_left);
+            tables.flags_right[row as usize] = flags(reverse_row(row), right, merge_right);
+        }
+        tables
+    })
+}
+
+/// Slides and merges a row of exponents towards the lowest nibble.
+/// # Arguments
+/// * ```row``` - The row, the lowest nibble is the first cell.
+/// # Returns
+/// * ```(u16, u32, u8)``` - The resulting row, the score of the move (```OVERFLOW``` if the move overflows)
+///   and the exponent of the largest merged tile (0 if no tiles merge or the move overflows).
+fn slide_row(row: u16) -> (u16, u32, u8) {
+    let mut line = [0_u8; 4];
+    let mut j = 0;
+    let mut merge = false;
+    let mut score = 0;
+    let mut largest = 0;
+    for elem in (0..4).map(|i| ((row >> (4 * i)) & 0xF) as u8).filter(|&x| x != 0) {
+        if merge && elem == line[j - 1] {
+            if elem == 15 {
+                return (row, OVERFLOW, 0);
+            }
+            line[j - 1] += 1;
+            score += 1 << line[j - 1];
+            largest = largest.max(line[j - 1]);
+            merge = false;
+        } else {
+            line[j] = elem;
+            j += 1;
+            merge = true;
+        }
+    }
+    let result = line.iter().enumerate().fold(0, |acc, (i, &exp)| acc | ((exp as u16) << (4 * i)));
+    (result, score, largest)
+}
+
+/// Reverses the order of the cells in a row.
+fn reverse_row(row: u16) -> u16 {
+    (row >> 12) | ((row >> 4) & 0x00F0) | ((row << 4) & 0x0F00) | (row << 12)
+}
+
+/// A move of the whole board, looked up in the row tables.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub(crate) struct TableMove {
+    /// The board after the move.
+    pub(crate) board: BitBoard,
+    /// The score of the move.
+    pub(crate) score: u64,
+    /// The largest merged tile (0 if no tiles merge).
+    pub(crate) best_merge: u64,
+    /// Whether the move changes the board.
+    pub(crate) changed: bool,
+}
+
+/// The result of a simulated game on a bitboard.
+pub(crate) enum RolloutEnd {
+    /// The game finished.
+    /// Contains the final board, the score and the number of moves made.
+    Finished(BitBoard, u64, u64),
+    /// The game reached the maximum number of moves before it finished.
+    /// Contains the board, the score and the number of moves made.
+    Stopped(BitBoard, u64, u64),
+    /// The game reached a move that can't be represented on a bitboard.
+    /// Contains the board before that move, the score and the number of moves made so far and the move.
+    Overflow(BitBoard, u64, u64, GameMove),
+}
+
+/// A 4x4 board packed into a single ```u64```.
+/// Every cell is a nibble containing the exponent of the tile (0 for empty cells).
+/// The cell in row ```i``` and column ```j``` is the nibble ```4 * i + j```, counting from the lowest one.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub(crate) struct BitBoard(u64);
+impl BitBoard {
+    /// The highest tile that fits into a nibble.
+    pub(crate) const MAX_TILE: u64 = 1 << 15;
+
+    /// Creates a bitboard from a board.
+    /// # Arguments
+    /// * ```board``` - The board to convert.
+    /// # Returns
+    /// * ```Some(BitBoard)``` - The converted board.
+    /// * ```None``` - The board is not 4x4 or contains tiles larger than 32768.
+    pub(crate) fn from_board<const SIZE: usize>(board: &[[u64; SIZE]; SIZE]) -> Option<Self> {
+        if SIZE != 4 {
+            return None;
+        }
+        let mut bits = 0;
+        for (i, row) in board.iter().enumerate() {
+            for (j, &tile) in row.iter().enumerate() {
+                if tile != 0 {
+                    let exp = tile.trailing_zeros() as u64;
+                    if !tile.is_power_of_two() || exp == 0 || exp > 15 {
+                        return None;
+                    }
+                    bits |= exp << (4 * (4 * i + j));
+                }
+            }
+        }
+        Some(Self(bits))
+    }
+
+    /// Converts the bitboard to a board.
+    /// Must only be called with ```SIZE``` equal to 4.
+    pub(crate) fn to_board<const SIZE: usize>(self) -> [[u64; SIZE]; SIZE] {
+        debug_assert_eq!(SIZE, 4);
+        let mut board = [[0; SIZE]; SIZE];
+        for (i, row) in board.iter_mut().enumerate() {
+            for (j, tile) in row.iter_mut().enumerate() {
+                let exp = (self.0 >> (4 * (4 * i + j))) & 0xF;
+                if exp != 0 {
+                    *tile = 1 << exp;
+                }
+            }
+        }
+        board
+    }
+
+    /// Transposes the board (swaps rows and columns).
+    fn transpose(self) -> Self {
+        let x = self.0;
+        let a1 = x & 0xF0F0_0F0F_F0F0_0F0F;
+        let a2 = x & 0x0000_F0F0_0000_F0F0;
+        let a3 = x & 0x0F0F_0000_0F0F_0000;
+        let a = a1 | (a2 << 12) | (a3 >> 12);
+        let b1 = a & 0xFF00_FF00_00FF_00FF;
+        let b2 = a & 0x00FF_00FF_0000_0000;
+        let b3 = a & 0x0000_0000_FF00_FF00;
+        Self(b1 | (b2 >> 24) | (b3 << 24))
+    }
+
+    /// Applies the row tables to every row of the board.
+    fn move_rows(self, result: &[u16], score: &[u32], flags: &[u8]) -> Option<TableMove> {
+        let mut bits = 0;
+        let mut total = 0;
+        let mut largest = 0;
+        let mut changed = false;
+        for i in 0..4 {
+            let row = ((self.0 >> (16 * i)) & 0xFFFF) as usize;
+            if score[row] == OVERFLOW {
+                return None;
+            }
+            bits |= (result[row] as u64) << (16 * i);
+            total += score[row] as u64;
+            largest = largest.max(flags[row] & !CHANGED);
+            changed |= flags[row] & CHANGED != 0;
+        }
+        Some(TableMove {
+            board: Self(bits),
+            score: total,
+            best_merge: if largest == 0 { 0 } else { 1 << largest },
+            changed,
+        })
+    }
+
+    /// Makes a move on the board with the row tables, without spawning a new tile.
+    /// Left and right moves look up the rows, up and down moves look up the rows of the transposed board.
+    /// # Arguments
+    /// * ```direction``` - The direction of the move.
+    /// # Returns
+    /// * ```Some(TableMove)``` - The move.
+    /// * ```None``` - The move would merge two 32768 tiles.
+    pub(crate) fn table_move(self, direction: GameMove) -> Option<TableMove> {
+        let tables = tables();
+        let transposed = |table_move: TableMove| TableMove {
+            board: table_move.board.transpose(),
+            ..table_move
+        };
+        match direction {
+            GameMove::Left => self.move_rows(&tables.left, &tables.score_left, &tables.flags_left),
+            GameMove::Right => self.move_rows(&tables.right, &tables.score_right, &tables.flags_right),
+            GameMove::Up => self.transpose().move_rows(&tables.left, &tables.score_left, &tables.flags_left).map(transposed),
+            GameMove::Down => self.transpose().move_rows(&tables.right, &tables.score_right, &tables.flags_right).map(transposed),
+        }
+    }
+
+    /// Makes a move on the board, without spawning a new tile.
+    /// # Arguments
+    /// * ```direction``` - The direction of the move.
+    /// # Returns
+    /// * ```Some((BitBoard, u64))``` - The board after the move and the score of the move.
+    /// * ```None``` - The move would merge two 32768 tiles.
+    pub(crate) fn make_move(self, direction: GameMove) -> Option<(Self, u64)> {
+        self.table_move(direction).map(|table_move| (table_move.board, table_move.score))
+    }
+
+    /// Returns the highest tile on the board.
+    pub(crate) fn highest_tile(self) -> u64 {
+        let exp = (0..16).map(|i| (self.0 >> (4 * i)) & 0xF).max().unwrap();
+        if exp == 0 {
+            0
+        } else {
+            1 << exp
+        }
+    }
+
+    /// Adds a new tile from the spawn distribution to a random empty cell.
+    /// The board must have at least one empty cell and the spawned values must fit into a nibble.
+    fn spawn<R: Rng>(&mut self, spawn_config: &SpawnConfig, rng: &mut R) {
+        let mut empty = [0; 16];
+        let mut count = 0;
+        for i in (0..16).filter(|&i| (self.0 >> (4 * i)) & 0xF == 0) {
+            empty[count] = i;
+            count += 1;
+        }
+        let cell = empty[rng.gen_range(0..count)];
+        let exp = spawn_config.sample(rng).trailing_zeros() as u64;
+        self.0 |= exp << (4 * cell);
+    }
+
+    /// Plays a simulated game with random moves until the game is over or the maximum number of moves is made.
+    /// # Arguments
+    /// * ```first_move``` - The first move to make. Must be a valid move.
+    /// * ```spawn_config``` - The distribution of the spawned tiles. The spawned values must fit into a nibble.
+    /// * ```max_moves``` - The maximum number of moves (at least 1), ```None``` for playing to the end.
+    /// * ```rng``` - The random number generator to use.
+    /// # Returns
+    /// * ```RolloutEnd::Finished(BitBoard, u64, u64)``` - The simulated game finished.
+    /// * ```RolloutEnd::Stopped(BitBoard, u64, u64)``` - The simulated game made the maximum number of moves.
+    /// * ```RolloutEnd::Overflow(BitBoard, u64, u64, GameMove)``` - The simulation can't continue on a bitboard.
+    pub(crate) fn rollout<R: Rng>(mut self, first_move: GameMove, spawn_config: &SpawnConfig, max_moves: Option<u64>, rng: &mut R) -> RolloutEnd {
+        let mut score = 0;
+        let mut turns = 0;
+        let mut direction = first_move;
+        loop {
+            match self.make_move(direction) {
+                Some((board, gained)) => {
+                    self = board;
+                    score += gained;
+                    turns += 1;
+                }
+                None => return RolloutEnd::Overflow(self, score, turns, direction),
+            }
+            self.spawn(spawn_config, rng);
+
+            let mut possible = [GameMove::Left; 4];
+            let mut count = 0;
+            for next in GameMove::ALL {
+                if self.make_move(next).is_none_or(|(board, _)| board != self) {
+                    possible[count] = next;
+                    count += 1;
+                }
+            }
+            if count == 0 {
+                return RolloutEnd::Finished(self, score, turns);
+            }
+            if max_moves == Some(turns) {
+                return RolloutEnd::Stopped(self, score, turns);
+            }
+            direction = possible[rng.gen_range(0..count)];
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::Game;
+    use crate::rules::ClassicRule;
+    use rand::thread_rng;
+    use std::sync::Arc;
+
+    #[test]
+    fn transpose() {
+        //! Test that transposing swaps rows and columns
+
+        let board = [[2, 4, 8, 16], [32, 64, 128, 256], [512, 1024, 2048, 4096], [8192, 16384, 32768, 0]];
+        let transposed = BitBoard::from_board(&board).unwrap().transpose().to_board::<4>();
+        for (i, row) in transposed.iter().enumerate() {
+            for (j, &tile) in row.iter().enumerate() {
+                assert_eq!(tile, board[j][i]);
+            }
+        }
+    }
+
+    #[test]
+    fn bitboard_moves() {
+        //! Test that the bitboard moves match the generic moves of the game over random positions
+
+        let mut rng = thread_rng();
+        for _ in 0..5_000 {
+            let mut board = [[0; 4]; 4];
+            for tile in board.iter_mut().flatten() {
+                let exp = rng.gen_range(0..=15);
+                if exp != 0 && rng.gen_bool(0.7) {
+                    *tile = 1 << exp;
+                }
+            }
+
+            let game = Game::from_existing_with_rule(&board, 0, Arc::new(ClassicRule)).unwrap();
+            let bitboard = BitBoard::from_board(&board).unwrap();
+            for direction in GameMove::ALL {
+                match bitboard.make_move(direction) {
+                    Some((moved, score)) => match game.peek_move(direction) {
+                        Some((expected, expected_score)) => {
+                            assert_eq!(moved.to_board::<4>(), expected);
+                            assert_eq!(score, expected_score);
+                        }
+                        None => assert_eq!(moved, bitboard),
+                    },
+                    None => assert!(board.iter().flatten().filter(|&&tile| tile == 32768).count() >= 2),
+                }
+            }
+        }
+    }
+}
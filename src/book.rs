@@ -0,0 +1,218 @@
+//! A module that contains the opening book of the 4x4 game with the classic rules.
+//! The book maps the canonical hashes (```symmetry::canonical_hash```) of the early positions to the recommended moves,
+//! so the Monte Carlo search can be skipped when the game is still in the book (see ```SearchOptions::use_opening_book```).
+//! The book covers the first three moves: all positions with one or two tiles, and the positions reached from the positions of the book
+//! by their recommended moves and any spawned tile.
+//! The moves are chosen by the Monte Carlo search with 20 000 simulated games, the book is generated by the ignored ```generate_book``` test:
+//! ```cargo test --release --lib generate_book -- --ignored```.
+//!
+//! The book stops after three moves because of its size and the time of its generation.
+//! The three moves take 1704 positions (81, 366 and 1257 new positions by the move, about 27 KB in the binary) and about 18 minutes
+//! of the searches on one core. Every further move multiplies the positions by three to four (5312 positions follow the third move),
+//! so ten moves would take tens of millions of positions, hundreds of megabytes in the binary and years of the searches,
+//! while the search at the start of the game is cheap anyway, since the board is mostly empty.
+
+// internal imports
+use crate::core::{Game, GameMove};
+use crate::symmetry;
+
+/// The recommended moves of the canonical positions, sorted by the hash.
+/// The moves are in the orientation of the canonical board.
+const BOOK: &[(u64, GameMove)] = &include!("book.in");
+
+/// Looks up the recommended move of the game in the opening book.
+/// Only 4x4 games with the classic rules (the classic merges and the default spawn distribution) are in the book.
+/// # Arguments
+/// * ```game``` - The game to look up.
+/// # Returns
+/// * ```Some(GameMove)``` - The recommended move, always a valid one.
+/// * ```None``` - The position is not in the book.
+pub fn lookup<const SIZE: usize>(game: &Game<SIZE>) -> Option<GameMove> {
+    if SIZE != 4 || !game.has_classic_rules() || game.awaiting_tile() {
+        return None;
+    }
+
+    let board = game.board();
+    let ind = BOOK.binary_search_by_key(&symmetry::canonical_hash(board), |&(hash, _)| hash).ok()?;
+    let variant = symmetry::canonical_index(board);
+    GameMove::ALL
+        .into_iter()
+        .find(|&direction| symmetry::transform_move(direction, variant) == BOOK[ind].1)
+        .filter(|&direction| game.can_move(direction))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{SearchBackend, SearchOptions};
+    use crate::rules::ThreesRule;
+    use std::collections::{BTreeMap, BTreeSet};
+    use std::fmt::Write;
+    use std::fs;
+    use std::sync::Arc;
+
+    /// The number of the moves covered by the book.
+    const BOOK_MOVES: usize = 3;
+
+    /// The number of the simulated games of the searches choosing the moves of the book.
+    const BOOK_DEPTH: usize = 20_000;
+
+    /// Returns the canonical positions of the start of the game, with one or two tiles.
+    /// # Returns
+    /// * ```BTreeSet<[[u64; 4]; 4]>``` - The canonical boards.
+    fn starting_positions() -> BTreeSet<[[u64; 4]; 4]> {
+        let mut positions = BTreeSet::new();
+        for first in 0..16 {
+            for value in [2, 4] {
+                let mut board = [[0; 4]; 4];
+                board[first / 4][first % 4] = value;
+                positions.insert(symmetry::canonical(&board));
+                for second in first + 1..16 {
+                    for value in [2, 4] {
+                        let mut board = board;
+                        board[second / 4][second % 4] = value;
+                        positions.insert(symmetry::canonical(&board));
+                    }
+                }
+            }
+        }
+        positions
+    }
+
+    /// Returns the canonical positions reached by the move and any spawned tile.
+    /// # Arguments
+    /// * ```board``` - The board.
+    /// * ```direction``` - The move, a valid one.
+    /// # Returns
+    /// * ```impl Iterator<Item = [[u64; 4]; 4]>``` - The canonical boards.
+    fn next_positions(board: &[[u64; 4]; 4], direction: GameMove) -> impl Iterator<Item = [[u64; 4]; 4]> {
+        let moved = Game::from_existing(board, 0).unwrap().peek_move(direction).unwrap().0;
+        (0..16).filter(move |&cell| moved[cell / 4][cell % 4] == 0).flat_map(move |cell| {
+            [2, 4].map(|value| {
+                let mut board = moved;
+                board[cell / 4][cell % 4] = value;
+                symmetry::canonical(&board)
+            })
+        })
+    }
+
+    /// Chooses the move of the position of the book by the search seeded with the hash of the position, so the book is reproducible.
+    /// # Arguments
+    /// * ```board``` - The canonical board.
+    /// * ```depth``` - The number of the simulated games.
+    /// # Returns
+    /// * ```GameMove``` - The chosen move.
+    fn choose_move(board: &[[u64; 4]; 4], depth: usize) -> GameMove {
+        let options = SearchOptions {
+            depth,
+            backend: SearchBackend::CurrentThread,
+            seed: Some(symmetry::canonical_hash(board)),
+            ..Default::default()
+        };
+        Game::from_existing(board, 0).unwrap().find_best_move_with_options(&options).unwrap()
+    }
+
+    /// Generates the book: the starting positions, and the positions reached from the positions of the book by the chosen moves and any spawned tile.
+    /// # Arguments
+    /// * ```moves``` - The number of the moves covered by the book.
+    /// * ```depth``` - The number of the simulated games of every search.
+    /// # Returns
+    /// * ```BTreeMap<u64, ([[u64; 4]; 4], GameMove)>``` - The canonical boards and their moves, by the hash.
+    fn generate(moves: usize, depth: usize) -> BTreeMap<u64, ([[u64; 4]; 4], GameMove)> {
+        let mut book = BTreeMap::new();
+        let mut positions = starting_positions();
+        for _ in 0..moves {
+            let mut next = BTreeSet::new();
+            for board in positions {
+                let hash = symmetry::canonical_hash(&board);
+                if book.contains_key(&hash) {
+                    continue;
+                }
+                let direction = choose_move(&board, depth);
+                book.insert(hash, (board, direction));
+                next.extend(next_positions(&board, direction));
+            }
+            positions = next;
+        }
+        book
+    }
+
+    #[test]
+    #[ignore = "runs the deep searches of the whole book, for minutes"]
+    fn generate_book() {
+        //! Generate the book and write it into the table included by the module
+
+        let mut table = String::from("[\n");
+        for (hash, (board, direction)) in generate(BOOK_MOVES, BOOK_DEPTH) {
+            let rows: Vec<String> = board.iter().map(|row| row.map(|tile| tile.to_string()).join(",")).collect();
+            writeln!(table, "    (0x{hash:016x}, GameMove::{direction:?}), // {}", rows.join("/")).unwrap();
+        }
+        table.push_str("]\n");
+        fs::write(concat!(env!("CARGO_MANIFEST_DIR"), "/src/book.in"), table).unwrap();
+    }
+
+    #[test]
+    fn book_coverage() {
+        //! Test that the book covers every position of the first moves played by the book
+
+        let mut positions = starting_positions();
+        let mut covered = BTreeSet::new();
+        for _ in 0..BOOK_MOVES {
+            let mut next = BTreeSet::new();
+            for board in positions {
+                let direction = lookup(&Game::from_existing(&board, 0).unwrap()).unwrap();
+                next.extend(next_positions(&board, direction));
+                covered.insert(symmetry::canonical_hash(&board));
+            }
+            positions = next;
+        }
+        // the book has no other positions, and the game leaves it after the first moves
+        assert!(BOOK.windows(2).all(|pair| pair[0].0 < pair[1].0));
+        assert_eq!(covered.len(), BOOK.len());
+        assert!(positions.iter().any(|board| lookup(&Game::from_existing(board, 0).unwrap()).is_none()));
+    }
+
+    #[test]
+    #[ignore = "runs the deep searches of some positions of the book, for minutes in the debug build"]
+    fn book_reproducible() {
+        //! Test that the generator reproduces the moves of the book
+
+        for board in starting_positions().into_iter().take(3) {
+            let hash = symmetry::canonical_hash(&board);
+            assert!(BOOK.contains(&(hash, choose_move(&board, BOOK_DEPTH))));
+        }
+    }
+
+    #[test]
+    fn book_lookup() {
+        //! Test that the starting positions are in the book and that the other positions fall back to the search
+
+        for _ in 0..100 {
+            let game: Game = Game::new().unwrap();
+            let direction = lookup(&game).unwrap();
+            assert!(game.can_move(direction));
+
+            // the symmetric positions get the symmetric moves (or the equivalent ones, if the board itself is symmetric)
+            let moved = symmetry::canonical_hash(&game.peek_move(direction).unwrap().0);
+            for board in [symmetry::flip_horizontal(game.board()), symmetry::rotate_cw(game.board())] {
+                let symmetric = Game::from_existing(&board, 0).unwrap();
+                assert_eq!(symmetry::canonical_hash(&symmetric.peek_move(lookup(&symmetric).unwrap()).unwrap().0), moved);
+            }
+        }
+
+        let options = SearchOptions {
+            depth: 100,
+            use_opening_book: true,
+            ..Default::default()
+        };
+        let game = Game::from_existing(&[[2, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 4], [0, 0, 0, 0]], 0).unwrap();
+        assert_eq!(game.find_best_move_with_options(&options), Ok(lookup(&game).unwrap()));
+
+        // off-book positions
+        let game = Game::from_existing(&[[2, 4, 8, 16], [0, 0, 4, 2], [0, 2, 8, 0], [0, 0, 0, 4]], 0).unwrap();
+        assert_eq!(lookup(&game), None);
+        assert!(game.find_best_move_with_options(&options).is_ok());
+        assert_eq!(lookup(&Game::<3>::from_existing(&[[2, 0, 0], [0, 0, 0], [0, 0, 0]], 0).unwrap()), None);
+        assert_eq!(lookup(&Game::<4>::new_with_rule(Arc::new(ThreesRule)).unwrap()), None);
+    }
+}
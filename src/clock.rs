@@ -0,0 +1,67 @@
+//! A module that contains the clocks measuring the timing of the games (see ```GameBuilder::track_time```).
+//! The clocks return the time since their own origin, so a fake clock can be driven by hand in the tests.
+
+// std imports
+use core::fmt::{self, Formatter};
+use core::time::Duration;
+use std::sync::Mutex;
+use std::time::Instant;
+
+/// A trait for the sources of the time of the games.
+pub trait Clock: Send + Sync {
+    /// Returns the current time.
+    /// # Returns
+    /// * ```Duration``` - The time since the origin of the clock. Never decreases.
+    fn now(&self) -> Duration;
+}
+impl fmt::Debug for dyn Clock {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "Clock")
+    }
+}
+
+/// The monotonic clock of the system, whose origin is the moment of its creation.
+/// This is the clock of the games unless another one is set.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct SystemClock {
+    /// The origin of the clock.
+    origin: Instant,
+}
+impl Default for SystemClock {
+    fn default() -> Self {
+        Self { origin: Instant::now() }
+    }
+}
+impl Clock for SystemClock {
+    fn now(&self) -> Duration {
+        self.origin.elapsed()
+    }
+}
+
+/// A clock that only moves when it is advanced.
+/// Useful for tests, as it makes the timing exact.
+#[derive(Debug, Default)]
+pub struct ManualClock {
+    /// The current time.
+    now: Mutex<Duration>,
+}
+impl ManualClock {
+    /// Creates the clock at the time 0.
+    /// # Returns
+    /// * ```ManualClock``` - The clock.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Moves the clock forward.
+    /// # Arguments
+    /// * ```duration``` - The time to add.
+    pub fn advance(&self, duration: Duration) {
+        *self.now.lock().unwrap() += duration;
+    }
+}
+impl Clock for ManualClock {
+    fn now(&self) -> Duration {
+        *self.now.lock().unwrap()
+    }
+}
@@ -1,19 +1,119 @@
 //! A module that contains the logic for the 2048 game.
 
 // std imports
-use std::fmt::{self, Display, Formatter, Write};
-use std::sync::{Arc, Mutex};
+use alloc::boxed::Box;
+use alloc::collections::BTreeMap;
+use alloc::string::{String, ToString};
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use core::cmp::Ordering;
+use core::fmt::{self, Display, Formatter, Write};
+use core::hash::{Hash, Hasher};
+use core::num::NonZeroU64;
+use core::str::FromStr;
+#[cfg(feature = "std")]
+use core::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering as AtomicOrdering};
+use core::time::Duration;
+#[cfg(feature = "std")]
+use std::collections::HashMap;
+#[cfg(feature = "std")]
+use std::fs;
+#[cfg(feature = "std")]
+use std::io;
+#[cfg(feature = "std")]
+use std::path::Path;
+#[cfg(feature = "std")]
+use std::sync::mpsc::{self, Receiver, Sender};
+#[cfg(all(feature = "std", not(target_arch = "wasm32")))]
+use std::sync::OnceLock;
+#[cfg(feature = "std")]
+use std::sync::{Condvar, Mutex};
+#[cfg(feature = "std")]
+use std::thread;
 
 // external imports
+use rand::rngs::StdRng;
+#[cfg(feature = "std")]
 use rand::seq::IteratorRandom;
-use rand::{random, thread_rng};
-use tinypool::ThreadPool;
+#[cfg(feature = "std")]
+use rand::thread_rng;
+use rand::{Rng, SeedableRng};
 
 // internal imports
+#[cfg(feature = "std")]
+use crate::bitboard::{BitBoard, RolloutEnd};
+#[cfg(feature = "std")]
+use crate::book;
+#[cfg(feature = "std")]
+use crate::clock::{Clock, SystemClock};
+#[cfg(feature = "std")]
+use crate::engine::{CachedEval, EvaluationCache, MoveStats, RolloutStore, SearchMemory};
 use crate::error::Error;
+#[cfg(feature = "std")]
+use crate::error::LoadError;
+#[cfg(feature = "std")]
+use crate::executor::{self, Executor};
+use crate::heuristics::{self, HeuristicWeights};
+use crate::history::{HistoryConfig, RepetitionConfig, Repetitions, Ring};
+use crate::rules::{self, ClassicRule, MergeRule};
+use crate::share;
+use crate::spawn::{position_probabilities, sample_position, SpawnConfig, SpawnPositionWeight, TileSpawner};
+#[cfg(feature = "std")]
+use crate::strategy::MonteCarloStrategy;
+use crate::strategy::Strategy;
+use crate::symmetry;
+#[cfg(feature = "serde")]
+use crate::web::WebSave;
+
+/// The smallest supported size of the board.
+pub const MIN_BOARD_SIZE: usize = 2;
+
+/// The default target tile of the classic games on the boards of 4x4 and larger.
+/// The smaller boards default to smaller targets (see ```Game::target```).
+pub const DEFAULT_WIN_TARGET: u64 = 2048;
+
+/// The probability that a tile spawned with the default spawn distribution is a 4 (otherwise it is a 2).
+pub const DEFAULT_FOUR_PROBABILITY: f64 = 0.1;
+
+/// The largest exponent of a tile of the classic rules, 2^63 is the highest tile that fits into a ```u64```.
+pub const MAX_SUPPORTED_EXPONENT: u32 = 63;
+
+/// The highest possible tile.
+/// Two tiles of this value don't merge, as the merged tile wouldn't fit into a ```u64```.
+const MAX_TILE: u64 = 1 << MAX_SUPPORTED_EXPONENT;
+
+/// The number of consecutive invalid moves after which ```Game::autoplay_with``` gives up on the strategy.
+const MAX_INVALID_MOVES: usize = 16;
+
+/// The magic header of the files saved by ```Game::save_to```.
+#[cfg(feature = "std")]
+const SAVE_MAGIC: [u8; 4] = *b"T248";
+/// The version of the format of the files saved by ```Game::save_to```.
+#[cfg(feature = "std")]
+const SAVE_VERSION: u8 = 1;
+
+/// The number of simulated games of every move in one batch of ```Game::ponder```.
+/// The results of a batch are published when it finishes, so stopping the pondering discards at most one batch.
+#[cfg(feature = "std")]
+const PONDER_BATCH: usize = 16;
+
+/// The value of the blocked cells on the board (see ```Game::with_blocked_cells```).
+/// Blocked cells never move, tiles can't pass through them or merge with them, and no tile is spawned on them.
+pub const BLOCKED_CELL: u64 = u64::MAX;
+
+/// The value of the junk tiles on the board (see ```GameBuilder::junk_every```).
+/// Junk tiles slide like the other tiles but never merge, not even with each other, so they stay on the board for the rest of the game.
+/// They are not counted as the highest tile and are accepted by ```validate_board``` although they are not powers of 2.
+pub const JUNK_TILE: u64 = u64::MAX - 1;
+
+/// The identities of the tiles, parallel to the board.
+type TileIds<const SIZE: usize> = [[Option<NonZeroU64>; SIZE]; SIZE];
 
 /// An enum that represents the moves that can be made in the game of 2048.
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub enum GameMove {
     Left,
     Right,
@@ -21,6 +121,9 @@ pub enum GameMove {
     Down,
 }
 impl GameMove {
+    /// All possible moves.
+    pub const ALL: [Self; 4] = [Self::Left, Self::Right, Self::Up, Self::Down];
+
     /// Returns the index of the move.
     /// Used internally for indexing arrays.
     /// # Returns
@@ -40,6 +143,7 @@ impl GameMove {
     /// * ```index``` - The index of the move.
     /// # Returns
     /// * ```GameMove``` - The move.
+    #[cfg(feature = "std")]
     fn from_index(index: usize) -> Self {
         match index {
             0 => Self::Left,
@@ -49,10 +153,49 @@ impl GameMove {
             _ => panic!("Invalid index: {}", index),
         }
     }
+
+    /// Returns the move that corresponds to this move on the board rotated clockwise (see ```Game::rotate_cw```).
+    pub fn rotate_cw(self) -> Self {
+        match self {
+            Self::Left => Self::Up,
+            Self::Up => Self::Right,
+            Self::Right => Self::Down,
+            Self::Down => Self::Left,
+        }
+    }
+
+    /// Returns the move that corresponds to this move on the board rotated counterclockwise (see ```Game::rotate_ccw```).
+    pub fn rotate_ccw(self) -> Self {
+        match self {
+            Self::Left => Self::Down,
+            Self::Down => Self::Right,
+            Self::Right => Self::Up,
+            Self::Up => Self::Left,
+        }
+    }
+
+    /// Returns the move that corresponds to this move on the horizontally mirrored board (see ```Game::flip_horizontal```).
+    pub fn flip_horizontal(self) -> Self {
+        match self {
+            Self::Left => Self::Right,
+            Self::Right => Self::Left,
+            vertical => vertical,
+        }
+    }
+
+    /// Returns the move that corresponds to this move on the vertically mirrored board (see ```Game::flip_vertical```).
+    pub fn flip_vertical(self) -> Self {
+        match self {
+            Self::Up => Self::Down,
+            Self::Down => Self::Up,
+            horizontal => horizontal,
+        }
+    }
 }
 
 /// An enum that represents the possible states of the 2048 game.
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum GameState {
     /// The game is in progress.
     InProgress,
@@ -62,493 +205,7878 @@ pub enum GameState {
 
 /// An enum that represents the possible results of the 2048 game.
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum GameResult {
     /// The result is not yet determined.
     Pending,
-    /// The game is won, 2048 was reached.
+    /// The game is won, the target tile (2048 by default) was reached.
+    /// The game can continue after the victory, until there are no valid moves left.
     Victory,
-    /// The game is over, 2048 was not reached, there are no valid moves left.
+    /// The game is over, the target tile was not reached, there are no valid moves left.
     Loss,
+    /// The game was won and continued after the victory, and now there are no valid moves left.
+    /// A game whose last possible move reached the target tile stays a ```Victory```.
+    VictoryThenLoss,
+    /// The game is over, the target tile was not reached before the limit of the turns (see ```GameBuilder::max_turns```).
+    /// A game that reached the target tile stays a ```Victory``` when the limit is reached, even on the same move.
+    TurnLimit,
+}
+impl GameResult {
+    /// Checks whether the target tile was reached.
+    /// # Returns
+    /// * ```true``` - The result is ```Victory``` or ```VictoryThenLoss```.
+    /// * ```false``` - The result is ```Pending```, ```Loss``` or ```TurnLimit```.
+    pub fn is_victory(self) -> bool {
+        matches!(self, GameResult::Victory | GameResult::VictoryThenLoss)
+    }
 }
 
-#[derive(Debug)]
-/// A struct that represents the 2048 game.
-pub struct Game<const SIZE: usize> {
-    /// Game tiles.
-    board: [[u64; SIZE]; SIZE],
-    /// Game score.
-    score: u64,
-    /// Additional score for each move.
-    score_next: [u64; 4],
-    /// Availability of moves.
-    moves: [bool; 4],
-    /// Board after each of the moves.
-    moves_next: [[[u64; SIZE]; SIZE]; 4],
+/// An enum that represents the reasons why a move can't be made, returned by ```Game::why_invalid```.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum InvalidReason {
+    /// The game is over, no move can be made (see ```GameResult``` for how it ended).
+    GameFinished,
+    /// The move would not change the board, but other moves can be made.
+    NoEffect,
+    /// The tile from the previous move of the two-player mode hasn't been placed yet (see ```Game::place_tile```).
+    AwaitingTile,
+    /// Without the ```std``` feature, the game has no random number generator to spawn the new tile (see ```Game::with_seed```).
+    Unseeded,
+}
+
+/// A trait for observing the events of a game.
+/// All methods have an empty default implementation, so only the interesting ones need to be overridden.
+///
+/// For each move the observer is notified in the following order:
+/// the move itself, then every merge, then the spawned tile and finally the state changes (victory, game over).
+/// Games used internally by the AI never notify observers.
+pub trait GameObserver: Send {
+    /// Called when a move is made.
+    /// # Arguments
+    /// * ```direction``` - The direction of the move.
+    /// * ```gained``` - The score gained by the move.
+    fn on_move(&mut self, direction: GameMove, gained: u64) {
+        let _ = (direction, gained);
+    }
+
+    /// Called for every merge made by a move.
+    /// # Arguments
+    /// * ```position``` - The position (row, column) of the merged tile.
+    /// * ```value``` - The value of the merged tile.
+    fn on_merge(&mut self, position: (usize, usize), value: u64) {
+        let _ = (position, value);
+    }
+
+    /// Called when a new tile is spawned.
+    /// # Arguments
+    /// * ```position``` - The position (row, column) of the new tile.
+    /// * ```value``` - The value of the new tile.
+    fn on_spawn(&mut self, position: (usize, usize), value: u64) {
+        let _ = (position, value);
+    }
+
+    /// Called when the game is won.
+    fn on_victory(&mut self) {}
+
+    /// Called when the game is over.
+    fn on_game_over(&mut self) {}
+
+    /// Called when a move reaches a position that occurred at least as many times as the threshold of the repetition tracking
+    /// (see ```Game::with_repetition_tracking```).
+    /// # Arguments
+    /// * ```count``` - The number of occurrences of the position, including this one.
+    fn on_repetition(&mut self, count: usize) {
+        let _ = count;
+    }
+}
+impl fmt::Debug for dyn GameObserver {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "GameObserver")
+    }
+}
+
+/// An event of a game, sent to the subscribers of the game (see ```Game::subscribe```).
+/// The events of a move are sent in the same order as the observer is notified (see ```GameObserver```), without the merges.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum GameEvent {
+    /// A move was made.
+    MoveMade {
+        /// The direction of the move.
+        direction: GameMove,
+        /// The score gained by the move.
+        gained: u64,
+    },
+    /// A new tile was spawned (or placed in the two-player mode).
+    TileSpawned {
+        /// The position (row, column) of the new tile.
+        position: (usize, usize),
+        /// The value of the new tile.
+        value: u64,
+    },
+    /// The game was won.
+    Victory,
+    /// The game is over.
+    GameOver,
+    /// The move reached a position that occurred at least as many times as the threshold of the repetition tracking.
+    Repetition {
+        /// The number of occurrences of the position, including this one.
+        count: usize,
+    },
+}
+
+/// A struct that summarizes a game of 2048.
+/// Returned by ```Game::summary``` and ```Game::play_with```.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GameSummary {
+    /// The score of the game.
+    pub score: u64,
+    /// The highest tile on the board.
+    pub highest_tile: u64,
+    /// The number of moves made.
+    pub turns: u64,
     /// The state of the game.
-    state: GameState,
+    pub state: GameState,
     /// The result of the game.
-    result: GameResult,
+    pub result: GameResult,
+    /// The number of merges made.
+    pub merges: u64,
+    /// The turn on which the target tile was reached, if it was.
+    pub victory_turn: Option<u64>,
+    /// The time from the start of the game to its end (or to now, if the game is not over yet).
+    /// Present only if the timing is enabled (see ```GameBuilder::track_time```).
+    pub duration: Option<Duration>,
+    /// The average time of a move (see ```Game::average_move_time```).
+    /// Present only if the timing is enabled and some move was made.
+    pub average_move_time: Option<Duration>,
+    /// The turn of the slowest move and its time (see ```Game::slowest_move```).
+    /// Present only if the timing is enabled and some move was made.
+    pub slowest_move: Option<(u64, Duration)>,
 }
-impl<const SIZE: usize> Game<SIZE> {
-    /// Creates a new game of 2048.
+impl Display for GameSummary {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Score: {}, highest tile: {}, turns: {}, merges: {}, state: {:?}, result: {:?}",
+            self.score, self.highest_tile, self.turns, self.merges, self.state, self.result
+        )?;
+        if let Some(turn) = self.victory_turn {
+            write!(f, ", victory on turn {turn}")?;
+        }
+        if let Some(duration) = self.duration {
+            write!(f, ", duration: {duration:?}")?;
+        }
+        if let Some(average) = self.average_move_time {
+            write!(f, ", average move time: {average:?}")?;
+        }
+        if let Some((turn, time)) = self.slowest_move {
+            write!(f, ", slowest move: {time:?} on turn {turn}")?;
+        }
+        Ok(())
+    }
+}
+
+/// A cell that differs between two boards (see ```diff```).
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct CellChange {
+    /// The row of the cell.
+    pub row: usize,
+    /// The column of the cell.
+    pub col: usize,
+    /// The value of the cell on the first board.
+    pub before: u64,
+    /// The value of the cell on the second board.
+    pub after: u64,
+}
+
+/// The difference between two boards, returned by ```diff``` and ```Game::diff_from```.
+/// The counts of the spawned, merged and moved tiles are inferred from the values of the tiles, not from the moves,
+/// so they are exact for two boards one move apart and only an estimate otherwise.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub struct BoardDiff {
+    /// The cells that changed, row by row.
+    pub changes: Vec<CellChange>,
+    /// The number of tiles that appeared without a merge.
+    pub spawned: usize,
+    /// The number of merges (each merge replaces two tiles with one).
+    pub merged: usize,
+    /// The number of tiles that moved to another cell without merging.
+    pub moved: usize,
+}
+impl BoardDiff {
+    /// Checks whether the boards are the same.
     /// # Returns
-    /// * ```Ok(Game)```: The game was created successfully.
-    /// * ```Err(Error)```: The game was not created successfully.
-    /// # Errors
-    /// * ```Error::InvalidSize```: The SIZE is invalid. Must be at least 4.
-    pub fn new() -> Result<Self, Error> {
-        if SIZE < 4 {
-            return Err(Error::InvalidSize);
+    /// * ```true``` - No cell changed.
+    /// * ```false``` - Some cell changed.
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+}
+impl Display for BoardDiff {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        if self.is_empty() {
+            return write!(f, "No changes");
+        }
+        write!(
+            f,
+            "{} changed cells ({} spawned, {} merged, {} moved):",
+            self.changes.len(),
+            self.spawned,
+            self.merged,
+            self.moved
+        )?;
+        for change in &self.changes {
+            write!(f, " ({}, {}) {} -> {}", change.row, change.col, change.before, change.after)?;
         }
+        Ok(())
+    }
+}
 
-        let board = [[0; SIZE]; SIZE];
-        let score = 0;
-        let score_next = [0; 4];
-        let moves = [true; 4];
-        let moves_next = [[[0; SIZE]; SIZE]; 4];
-        let state = GameState::InProgress;
-        let result = GameResult::Pending;
+/// The policy used by ```Game::find_best_move_adaptive``` to scale the search depth by the fullness of the board.
+/// The base depth is multiplied by ```SIZE * SIZE / empty_cells``` (capped at ```max_multiplier```),
+/// so that nearly empty boards use about the base depth and cramped boards, where the decisions matter the most, use more.
+/// A board without empty cells uses the maximum multiplier.
+#[cfg(feature = "std")]
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct AdaptivePolicy {
+    /// The highest multiplier of the base depth.
+    pub max_multiplier: f64,
+}
+#[cfg(feature = "std")]
+impl Default for AdaptivePolicy {
+    /// Creates the policy with the maximum multiplier of 8.
+    fn default() -> Self {
+        Self { max_multiplier: 8.0 }
+    }
+}
+#[cfg(feature = "std")]
+impl AdaptivePolicy {
+    /// Calculates the search depth to use for the given board.
+    /// # Arguments
+    /// * ```base_depth``` - The depth to scale.
+    /// * ```board``` - The board to search.
+    /// # Returns
+    /// * ```usize``` - The scaled depth.
+    pub fn depth<const SIZE: usize>(&self, base_depth: usize, board: &[[u64; SIZE]; SIZE]) -> usize {
+        self.scaled_depth::<SIZE>(base_depth, heuristics::empty_cells(board))
+    }
 
-        let mut game_object: Self = Self {
-            board,
-            score,
-            score_next,
-            moves,
-            moves_next,
-            state,
-            result,
+    /// Calculates the search depth to use for a board with the given number of empty cells.
+    /// # Arguments
+    /// * ```base_depth``` - The depth to scale.
+    /// * ```empty``` - The number of empty cells of the board.
+    /// # Returns
+    /// * ```usize``` - The scaled depth.
+    fn scaled_depth<const SIZE: usize>(&self, base_depth: usize, empty: usize) -> usize {
+        let multiplier = if empty == 0 {
+            self.max_multiplier
+        } else {
+            f64::min((SIZE * SIZE) as f64 / empty as f64, self.max_multiplier)
         };
+        (base_depth as f64 * multiplier).round() as usize
+    }
+}
 
-        game_object.new_tile();
-        game_object.update();
+/// The value of the simulated games that the Monte Carlo search maximises.
+#[cfg(feature = "std")]
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub enum SearchObjective {
+    /// The final score of the game.
+    #[default]
+    Score,
+    /// The highest tile at the end of the game.
+    MaxTile,
+    /// The number of moves the game survived.
+    Survival,
+}
+#[cfg(feature = "std")]
+impl SearchObjective {
+    /// Selects the value of a finished simulated game.
+    fn value(self, score: u64, highest_tile: u64, turns: u64) -> u64 {
+        match self {
+            Self::Score => score,
+            Self::MaxTile => highest_tile,
+            Self::Survival => turns,
+        }
+    }
 
-        Ok(game_object)
+    /// Converts the value of a simulated game started after the first move and the spawned tile
+    /// to the value of the simulated game started before the first move.
+    /// # Arguments
+    /// * ```value``` - The value of the game started after the first move.
+    /// * ```gain``` - The score gained by the first move.
+    fn before_first_move(self, value: u64, gain: u64) -> u64 {
+        match self {
+            Self::Score => value.saturating_add(gain),
+            Self::MaxTile => value,
+            Self::Survival => value + 1,
+        }
     }
+}
 
-    /// Creates a game of 2048 from an existing board.
-    /// The board must be a square matrix filled with 0 for empty tiles and powers of 2 for filled tiles.
+/// A position of the tree of ```Game::find_best_move_mcts```, where a move is chosen.
+#[cfg(feature = "std")]
+struct MctsNode<const SIZE: usize> {
+    /// The game in the position, with the score of 0.
+    game: Game<SIZE>,
+    /// The number of the playouts through the position.
+    visits: usize,
+    /// The valid moves of the position.
+    moves: Vec<MctsMove>,
+}
+#[cfg(feature = "std")]
+impl<const SIZE: usize> MctsNode<SIZE> {
+    /// Creates a position of the tree that was not visited yet.
     /// # Arguments
-    /// * ```board```: The board to use.
-    /// * ```score```: The score of the game.
+    /// * ```game``` - The game in the position.
     /// # Returns
-    /// * ```Ok(Game)```: The game was created successfully.
-    /// * ```Err(Error)```: The game was not created successfully.
-    /// # Errors
-    /// * ```Error::InvalidSize```: The SIZE is invalid. Must be at least 4.
-    /// * ```Error::InvalidValue```: The board contains invalid value. Must be 0 or a power of 2, starting from 2.
-    pub fn from_existing(board: &[[u64; SIZE]; SIZE], score: u64) -> Result<Self, Error> {
-        if SIZE < 4 {
-            return Err(Error::InvalidSize);
+    /// * ```MctsNode``` - The position.
+    fn new(game: Game<SIZE>) -> Self {
+        let moves = GameMove::ALL
+            .into_iter()
+            .filter_map(|direction| {
+                let (_, gain) = game.peek_move(direction)?;
+                Some(MctsMove {
+                    direction,
+                    gain,
+                    visits: 0,
+                    total: 0.0,
+                    children: Vec::new(),
+                })
+            })
+            .collect();
+        Self { game, visits: 0, moves }
+    }
+
+    /// Selects the move to follow with the UCT formula. The moves that were not tried yet are tried first.
+    /// # Arguments
+    /// * ```exploration``` - The weight of the exploration term.
+    /// * ```bounds``` - The lowest and the highest value of the playouts so far, which normalise the average values to [0, 1].
+    /// # Returns
+    /// * ```usize``` - The index of the selected move.
+    fn select(&self, exploration: f64, bounds: (f64, f64)) -> usize {
+        if let Some(untried) = self.moves.iter().position(|node_move| node_move.visits == 0) {
+            return untried;
         }
+        let span = bounds.1 - bounds.0;
+        let log_visits = (self.visits as f64).ln();
+        let uct = |node_move: &MctsMove| {
+            let average = node_move.total / node_move.visits as f64;
+            let exploitation = if span > 0.0 { (average - bounds.0) / span } else { 0.0 };
+            exploitation + exploration * (log_visits / node_move.visits as f64).sqrt()
+        };
+        (0..self.moves.len())
+            .max_by(|&a, &b| uct(&self.moves[a]).partial_cmp(&uct(&self.moves[b])).unwrap_or(Ordering::Equal))
+            .unwrap()
+    }
 
-        for row in board.iter() {
-            for tile in row.iter() {
-                if *tile == 1 || (*tile != 0 && !tile.is_power_of_two()) {
-                    return Err(Error::InvalidValue);
-                }
+    /// Samples the spawned tile after the move from the outcomes of ```Game::expand```.
+    /// # Arguments
+    /// * ```slot``` - The index of the move.
+    /// * ```rng``` - The random number generator to use.
+    /// # Returns
+    /// * ```(usize, [[u64; SIZE]; SIZE])``` - The index of the outcome and the board after the spawned tile.
+    fn sample_outcome<R: Rng>(&self, slot: usize, rng: &mut R) -> (usize, [[u64; SIZE]; SIZE]) {
+        let mut remaining: f64 = rng.gen();
+        let mut sampled = None;
+        // a valid move always leaves an empty cell, so there is at least one outcome
+        for (outcome, (board, probability)) in self.game.expand(self.moves[slot].direction).into_iter().flatten().enumerate() {
+            sampled = Some((outcome, board));
+            remaining -= probability;
+            if remaining < 0.0 {
+                break;
             }
         }
+        sampled.unwrap()
+    }
+}
 
-        let board = *board;
-        let score_next = [0; 4];
-        let moves = [true; 4];
-        let moves_next = [[[0; SIZE]; SIZE]; 4];
-        let state = GameState::InProgress;
-        let result = GameResult::Pending;
+/// A move of the tree of ```Game::find_best_move_mcts```, followed by the spawned tile.
+#[cfg(feature = "std")]
+struct MctsMove {
+    /// The move.
+    direction: GameMove,
+    /// The score gained by the move.
+    gain: u64,
+    /// The number of the playouts through the move.
+    visits: usize,
+    /// The summed values of the playouts through the move, as values of the whole game from the root.
+    total: f64,
+    /// The positions reached after the spawned tiles, as the indices of the outcomes (see ```Game::expand```) and of the positions.
+    children: Vec<(usize, usize)>,
+}
 
-        let mut game_object = Self {
-            board,
-            score,
-            score_next,
-            moves,
-            moves_next,
-            state,
-            result,
-        };
-        game_object.update();
+/// A move ranked by ```Game::hint```, with the numbers explaining its rank.
+#[cfg(feature = "std")]
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct MoveHint {
+    /// The move.
+    pub direction: GameMove,
+    /// The average final score of the simulated games starting with the move.
+    pub value: f64,
+    /// The score the move gains immediately.
+    pub gain: u64,
+    /// The number of empty cells after the move, before the new tile is spawned.
+    pub empty_cells: usize,
+}
 
-        Ok(game_object)
-    }
+/// The end of a simulated game with random moves, returned by ```Game::rollout```.
+#[cfg(feature = "std")]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct RolloutResult {
+    /// The final score, including the score of the game the simulated game started from.
+    pub score: u64,
+    /// The number of moves made.
+    pub moves: u64,
+    /// The highest tile on the final board.
+    pub highest_tile: u64,
+    /// The final result, ```GameResult::Pending``` or ```GameResult::Victory``` if the simulated game was stopped by the maximum number of moves.
+    pub final_result: GameResult,
+}
 
-    /// Returns the reference to the board.
-    /// The board is a square matrix filled with 0 for empty tiles and powers of 2 for filled tiles.
+/// The result of a Monte Carlo search with its statistics, returned by ```Game::find_best_move_report```.
+#[cfg(feature = "std")]
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct SearchReport {
+    /// The best move to make.
+    pub best: GameMove,
+    /// The average values of the simulated games of every move (in the order of ```GameMove::ALL```), ```None``` for invalid moves.
+    /// A move without simulated games (e.g. the only valid move) has the value of 0.
+    pub scores: [Option<f64>; 4],
+    /// The fraction of the simulated games of every move that were lost within ```SearchOptions::risk_horizon``` moves,
+    /// ```None``` for invalid moves. A move without simulated games has the rate of 0.
+    pub early_loss_rate: [Option<f64>; 4],
+    /// The number of simulated games played by this search for every move.
+    /// The moves decided without a search (the only valid move, a move from the opening book or the cache) have no simulated games.
+    pub rollouts: [usize; 4],
+    /// The duration of the search.
+    pub elapsed: Duration,
+    /// The number of threads that played the simulated games (0 if no games were played),
+    /// at most the number of the threads of the backend (see ```executor::available_threads```) or of the executor.
+    pub threads_used: usize,
+}
+
+/// The progress of a running Monte Carlo search, passed to the progress callback.
+#[cfg(feature = "std")]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct SearchProgress {
+    /// The number of finished simulated games.
+    pub rollouts_done: usize,
+    /// The total number of simulated games of the search.
+    pub rollouts_planned: usize,
+    /// The best move based on the simulated games reported so far.
+    pub best_move: Option<GameMove>,
+}
+
+/// The way the Monte Carlo search runs the simulated games in parallel.
+#[cfg(feature = "std")]
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub enum SearchBackend {
+    /// Scoped threads spawned for every search, one per available core (see ```executor::available_threads```).
+    #[default]
+    Threads,
+    /// The current thread, without spawning any threads (e.g. on WebAssembly, where threads are not available).
+    CurrentThread,
+    /// The ambient rayon thread pool (the global one, or the one the search is installed in).
+    #[cfg(feature = "rayon")]
+    Rayon,
+}
+#[cfg(feature = "std")]
+impl SearchBackend {
+    /// Returns the number of threads the simulated games are split between.
     /// # Returns
-    /// * ```&[[u64; SIZE]; SIZE]```: The board.
-    pub fn board(&self) -> &[[u64; SIZE]; SIZE] {
-        &self.board
+    /// * ```usize``` - The number of threads.
+    fn threads(self) -> usize {
+        match self {
+            SearchBackend::Threads => executor::available_threads(),
+            SearchBackend::CurrentThread => 1,
+            #[cfg(feature = "rayon")]
+            SearchBackend::Rayon => rayon::current_num_threads(),
+        }
     }
+}
 
-    /// Returns the result of the game.
+/// A token that cancels a Monte Carlo search (see ```SearchOptions::cancel```).
+/// The clones of the token share the cancellation.
+#[cfg(feature = "std")]
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+#[cfg(feature = "std")]
+impl CancellationToken {
+    /// Creates a token that is not cancelled.
     /// # Returns
-    /// * ```Result::Victory```: The game is won, 2048 was reached.
-    /// * ```Result::Pending```: The game is in progress, 2048 is not reached yet.
-    /// * ```Result::Loss```: The game is over, 2048 was not reached.
-    pub fn result(&self) -> GameResult {
-        self.result
+    /// * ```CancellationToken``` - The new token.
+    pub fn new() -> Self {
+        Self::default()
     }
 
-    /// Returns the score of the game.
-    /// # Returns
-    /// * ```u64```: The score of the game.
-    pub fn score(&self) -> u64 {
-        self.score
+    /// Cancels the searches using this token (or any of its clones).
+    pub fn cancel(&self) {
+        self.0.store(true, AtomicOrdering::Relaxed);
     }
 
-    /// Returns the size of the board.
+    /// Checks whether the token is cancelled.
     /// # Returns
-    /// * ```usize```: The size of the board. The board is ```usize```x```usize```.
-    pub fn size(&self) -> usize {
-        SIZE
+    /// * ```true``` - The token is cancelled.
+    /// * ```false``` - The token is not cancelled.
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(AtomicOrdering::Relaxed)
     }
+}
 
-    /// Returns the state of the game.
+/// A callback notified about the progress of a Monte Carlo search.
+#[cfg(feature = "std")]
+pub type ProgressCallback = Arc<dyn Fn(SearchProgress) + Send + Sync>;
+
+/// The options of the Monte Carlo search (```Game::find_best_move_with_options```).
+#[cfg(feature = "std")]
+#[derive(Clone)]
+pub struct SearchOptions {
+    /// The number of simulated games to play to determine the best move.
+    pub depth: usize,
+    /// The number of spawns to check for a forced loss after the chosen move (0 disables the check).
+    /// If the spawned tiles can force the game over within this many spawns, the next best move that passes the check is chosen instead.
+    /// If no move passes the check, the best move is chosen anyway.
+    pub loss_guard: usize,
+    /// The value of the simulated games to maximise.
+    pub objective: SearchObjective,
+    /// The callback notified about the progress of the search.
+    /// It is called from the worker threads every ```progress_interval``` finished simulated games and once more when all of them are finished.
+    pub progress: Option<ProgressCallback>,
+    /// The number of simulated games between two progress notifications (0 is treated as 1).
+    pub progress_interval: usize,
+    /// The way the simulated games are run in parallel.
+    pub backend: SearchBackend,
+    /// The executor running the simulated games instead of the backend, e.g. the thread pool of the application.
+    /// The games are split into ```Executor::threads``` jobs and the search waits until all of them are finished.
+    pub executor: Option<Arc<dyn Executor>>,
+    /// The seed of the random number generators of the simulated games, ```None``` to derive it from the game if it is seeded (see ```GameBuilder::seed```).
+    /// With a seed, the search is reproducible on the same machine (the games are split between the available threads).
+    /// The search of a game that is not seeded, without a seed, uses the entropy.
+    pub seed: Option<u64>,
+    /// Whether to play the move recommended by the opening book (```book::lookup```) without searching, if the position is in the book.
+    pub use_opening_book: bool,
+    /// The token that stops the search early. A cancelled search returns ```Error::Cancelled```.
+    pub cancel: Option<CancellationToken>,
+    /// How much the early losses penalise the moves (0 ignores them).
+    /// The best move is chosen by the average value multiplied by ```1 - risk_aversion * early_loss_rate``` (see ```SearchReport::early_loss_rate```).
+    pub risk_aversion: f64,
+    /// The number of moves, including the first one, within which a lost simulated game counts as an early loss.
+    pub risk_horizon: usize,
+}
+#[cfg(feature = "std")]
+impl Default for SearchOptions {
+    /// Creates the options with the depth of 1000, the loss guard disabled, the score objective, no progress callback, the thread pool backend, no executor, no seed, the opening book disabled,
+    /// no cancellation token and no risk aversion with the horizon of 10 moves.
+    fn default() -> Self {
+        Self {
+            depth: 1_000,
+            loss_guard: 0,
+            objective: SearchObjective::Score,
+            progress: None,
+            progress_interval: 100,
+            backend: SearchBackend::Threads,
+            executor: None,
+            seed: None,
+            use_opening_book: false,
+            cancel: None,
+            risk_aversion: 0.0,
+            risk_horizon: 10,
+        }
+    }
+}
+#[cfg(feature = "std")]
+impl fmt::Debug for SearchOptions {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SearchOptions")
+            .field("depth", &self.depth)
+            .field("loss_guard", &self.loss_guard)
+            .field("objective", &self.objective)
+            .field("progress", &self.progress.as_ref().map(|_| "ProgressCallback"))
+            .field("progress_interval", &self.progress_interval)
+            .field("backend", &self.backend)
+            .field("executor", &self.executor)
+            .field("seed", &self.seed)
+            .field("use_opening_book", &self.use_opening_book)
+            .field("cancel", &self.cancel)
+            .field("risk_aversion", &self.risk_aversion)
+            .field("risk_horizon", &self.risk_horizon)
+            .finish()
+    }
+}
+
+#[cfg(feature = "std")]
+impl SearchOptions {
+    /// Returns the number of threads (or the jobs of the executor) the simulated games are split between.
     /// # Returns
-    /// * ```State::InProgress```: The game is in progress.
-    /// * ```State::GameOver```: The game is over.
-    pub fn state(&self) -> GameState {
-        self.state
+    /// * ```usize``` - The number of threads.
+    fn threads(&self) -> usize {
+        self.executor.as_ref().map_or_else(|| self.backend.threads(), |executor| executor.threads().max(1))
     }
+}
 
-    /// Make a move in the game.
+/// The average values, the rates of the early losses and the numbers of the simulated games for every move.
+#[cfg(feature = "std")]
+type MoveEvaluation = ([Option<f64>; 4], [Option<f64>; 4], [usize; 4]);
+/// The outcome of a line of the moves of a puzzle: whether it solves the puzzle, the number of the moves it leaves unused, the highest tile and the gained score.
+#[cfg(feature = "std")]
+type PuzzleLine = (bool, u64, u64, u64);
+/// The best first moves and their lines of the positions of a puzzle already tried, by the board and the number of the moves left.
+#[cfg(feature = "std")]
+type PuzzleMemo<const SIZE: usize> = HashMap<([[u64; SIZE]; SIZE], u64), (Option<GameMove>, PuzzleLine)>;
+/// The summed values, the numbers of the simulated games and the numbers of the early losses for every move.
+#[cfg(feature = "std")]
+type MoveTotals = ([u128; 4], [usize; 4], [usize; 4]);
+
+/// Derives the seed of one stream of random numbers (a worker, a batch) from the seed of the search.
+/// The seed and the index of the stream are mixed with SplitMix64, so the streams of close seeds don't overlap.
+/// # Arguments
+/// * ```seed``` - The seed of the search.
+/// * ```stream``` - The index of the stream.
+/// # Returns
+/// * ```u64``` - The seed of the stream.
+#[cfg(feature = "std")]
+pub(crate) fn derive_seed(seed: u64, stream: u64) -> u64 {
+    split_mix64(seed ^ split_mix64(stream))
+}
+
+/// Returns the next output of the SplitMix64 generator in the given state.
+#[cfg(feature = "std")]
+fn split_mix64(state: u64) -> u64 {
+    let mut z = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+/// A sum of the values of the simulated games that is added to by many threads at once, without any lock.
+/// The sum is kept as two halves of 64 bits, the carry of the lower half is added to the upper one.
+/// The sum is exact once all the additions are finished, a sum loaded during the additions may miss a carry.
+#[cfg(feature = "std")]
+#[derive(Debug, Default)]
+struct AtomicSum {
+    /// The lower 64 bits of the sum.
+    low: AtomicU64,
+    /// The upper 64 bits of the sum.
+    high: AtomicU64,
+}
+#[cfg(feature = "std")]
+impl AtomicSum {
+    /// Adds the value to the sum.
     /// # Arguments
-    /// * ```direction```: The direction to move in.
-    /// # Returns
-    /// * ```true``` - The move was successful.
-    /// * ```false``` - The move was invalid/impossible.
-    pub fn make_move(&mut self, direction: GameMove) -> bool {
-        let next_ind = direction.index();
-        if self.moves[next_ind] {
-            self.board = self.moves_next[next_ind];
-            self.score += self.score_next[next_ind];
-            self.new_tile();
-            self.update();
-            true
-        } else {
-            false
+    /// * ```value``` - The value to add.
+    fn add(&self, value: u128) {
+        let low = value as u64;
+        let (_, carry) = self.low.fetch_add(low, AtomicOrdering::Relaxed).overflowing_add(low);
+        let high = (value >> 64) as u64 + u64::from(carry);
+        if high != 0 {
+            self.high.fetch_add(high, AtomicOrdering::Relaxed);
         }
     }
 
-    /// Add a new tile to the board.
-    fn new_tile(&mut self) {
-        // create iterator over all tiles (cartesian product of two ranges)
-        // filter only empty tiles -> get iterator over empty tiles
-        // choose one of the empty tiles with rng
-        let loc = (0..SIZE)
-            .flat_map(|ind1| (0..SIZE).map(move |ind2| (ind1, ind2)))
-            .filter(|&pos| self.board[pos.0][pos.1] == 0)
-            .choose(&mut thread_rng())
-            .unwrap();
+    /// Returns the sum.
+    /// # Returns
+    /// * ```u128``` - The sum.
+    fn load(&self) -> u128 {
+        (u128::from(self.high.load(AtomicOrdering::Relaxed)) << 64) | u128::from(self.low.load(AtomicOrdering::Relaxed))
+    }
+}
 
-        // add 2 or 4 to that tile
-        self.board[loc.0][loc.1] = if random::<f64>() < 0.9 { 2 } else { 4 };
+/// The state of a Monte Carlo search shared by the workers.
+#[cfg(feature = "std")]
+struct SharedSearch<const SIZE: usize> {
+    /// The summed values of the simulated games for every move, published when reporting the progress and when a chunk is finished.
+    moves_values: [AtomicSum; 4],
+    /// The number of the published simulated games for every move.
+    moves_rollouts: [AtomicUsize; 4],
+    /// The number of finished simulated games.
+    rollouts_done: AtomicUsize,
+    /// The total number of simulated games.
+    depth: usize,
+    /// The value of the simulated games.
+    objective: SearchObjective,
+    /// The number of moves within which a lost simulated game counts as an early loss.
+    risk_horizon: u64,
+    /// The number of the simulated games lost early for every move.
+    early_losses: [AtomicUsize; 4],
+    /// The progress callback.
+    progress: Option<ProgressCallback>,
+    /// The number of simulated games between two progress notifications.
+    progress_interval: usize,
+    /// The distribution of the values of the spawned tiles.
+    spawn_config: SpawnConfig,
+    /// The merge rule of the game, if it isn't the classic one.
+    merge_rule: Option<Arc<dyn MergeRule>>,
+    /// The policy weighting the cells of the spawned tiles, if it isn't the uniform choice.
+    spawn_weight: Option<Arc<dyn SpawnPositionWeight<SIZE>>>,
+    /// The number of turns left before the limit of the turns of the game, ```None``` for no limit.
+    max_turns: Option<u64>,
+    /// The number of turns between two junk tiles, ```None``` if the junk mode is disabled.
+    junk_every: Option<u64>,
+    /// The number of turns made in the game before the search, which keeps the junk tiles of the simulated games in step.
+    junk_offset: u64,
+    /// The token that stops the search early.
+    cancel: Option<CancellationToken>,
+    /// The statistics of the positions after the first move and the spawned tile, collected only for the warm start.
+    children: Option<Mutex<HashMap<u64, MoveStats>>>,
+}
+#[cfg(feature = "std")]
+impl<const SIZE: usize> SharedSearch<SIZE> {
+    /// Adds the results of the simulated games of a move to the shared state, without any lock.
+    /// # Arguments
+    /// * ```move_ind``` - The index of the move.
+    /// * ```score``` - The summed values of the simulated games.
+    /// * ```count``` - The number of the simulated games.
+    fn publish(&self, move_ind: usize, score: u128, count: usize) {
+        self.moves_values[move_ind].add(score);
+        self.moves_rollouts[move_ind].fetch_add(count, AtomicOrdering::Relaxed);
     }
 
-    /// Update moves, moves_next, score_next, state and result.
-    fn update(&mut self) {
-        // update left
-        self.score_next[0] = 0;
-        for (i, row) in self.board.iter().enumerate() {
-            let mut j = 0;
-            let mut merge = false;
-            for elem in row.iter().filter(|&&x| x != 0) {
-                if merge && *elem == self.moves_next[0][i][j - 1] {
-                    self.moves_next[0][i][j - 1] *= 2;
-                    self.score_next[0] += self.moves_next[0][i][j - 1];
-                    merge = false;
-                } else {
-                    self.moves_next[0][i][j] = *elem;
-                    j += 1;
-                    merge = true;
-                }
+    /// Returns the results published so far.
+    /// # Returns
+    /// * ```([u128; 4], [usize; 4])``` - The summed values and the number of the simulated games for every move.
+    fn published(&self) -> ([u128; 4], [usize; 4]) {
+        (
+            core::array::from_fn(|ind| self.moves_values[ind].load()),
+            core::array::from_fn(|ind| self.moves_rollouts[ind].load(AtomicOrdering::Relaxed)),
+        )
+    }
+
+    /// Plays a chunk of simulated games for one move.
+    /// The results are published to the shared state when the progress is reported and when the chunk is finished.
+    /// # Arguments
+    /// * ```board``` - The board to start from.
+    /// * ```move_ind``` - The index of the move to simulate.
+    /// * ```count``` - The number of simulated games to play.
+    /// * ```seed``` - The seed of the random number generator of the chunk, ```None``` for a random one.
+    fn run_chunk(&self, board: &[[u64; SIZE]; SIZE], move_ind: usize, count: usize, seed: Option<u64>) {
+        let move_type = GameMove::from_index(move_ind);
+        // the sums of the values of u64 can't overflow u128 within usize::MAX simulated games, so they don't saturate
+        let mut thread_score: u128 = 0;
+        let mut thread_count = 0;
+
+        let mut rng = seed.map_or_else(StdRng::from_entropy, StdRng::seed_from_u64);
+        let mut work_game = Game::from_board_unchecked(board, 0)
+            .with_spawn_config(self.spawn_config.clone())
+            .with_merge_rule_unchecked(self.merge_rule.clone());
+        work_game.max_turns = self.max_turns;
+        work_game.spawn_weight = self.spawn_weight.clone();
+        work_game.junk_every = self.junk_every;
+        work_game.junk_offset = self.junk_offset;
+        let mut children = self.children.as_ref().map(|_| HashMap::new());
+        if children.is_some() {
+            // the first moves are made on the game, which spawns the tiles with its own generator
+            work_game.rng = Some(StdRng::seed_from_u64(rng.gen()));
+        }
+        for _ in 0..count {
+            if self.cancel.as_ref().is_some_and(CancellationToken::is_cancelled) {
+                break;
             }
-            for empty_elem in self.moves_next[0][i].iter_mut().skip(j) {
-                *empty_elem = 0;
+            let (value, turns) = match &mut children {
+                Some(children) => work_game.simulate_recorded(board, move_type, self.objective, self.risk_horizon, &mut rng, children),
+                None => work_game.simulate(board, move_type, self.objective, &mut rng),
+            };
+            thread_score += u128::from(value);
+            thread_count += 1;
+            if turns <= self.risk_horizon {
+                self.early_losses[move_ind].fetch_add(1, AtomicOrdering::Relaxed);
             }
-        }
-        self.moves[0] = self.board != self.moves_next[0];
 
-        // update right
-        self.score_next[1] = 0;
-        for (i, row) in self.board.iter().enumerate() {
-            let mut j = SIZE - 1;
-            let mut merge = false;
-            let mut negative_index = false;
-            for elem in row.iter().filter(|&&x| x != 0).rev() {
-                if merge && *elem == self.moves_next[1][i][j + 1] {
-                    self.moves_next[1][i][j + 1] *= 2;
-                    self.score_next[1] += self.moves_next[1][i][j + 1];
-                    merge = false;
-                } else {
-                    self.moves_next[1][i][j] = *elem;
-                    j = match j.checked_sub(1) {
-                        Some(x) => x,
-                        None => {
-                            // we processed the whole row, we can safely break
-                            negative_index = true;
-                            break;
+            if let Some(callback) = &self.progress {
+                let done = self.rollouts_done.fetch_add(1, AtomicOrdering::Relaxed) + 1;
+                if done.is_multiple_of(self.progress_interval) || done == self.depth {
+                    // publish the partial results, the other moves may be published a few games later than this one
+                    self.publish(move_ind, thread_score, thread_count);
+                    let snapshot = self.published();
+                    (thread_score, thread_count) = (0, 0);
+                    callback(SearchProgress {
+                        rollouts_done: done,
+                        rollouts_planned: self.depth,
+                        best_move: Game::<SIZE>::best_average(&snapshot.0, &snapshot.1),
+                    });
+                }
+            }
+        }
+
+        if let (Some(shared_children), Some(children)) = (&self.children, children) {
+            let mut shared_children = shared_children.lock().unwrap();
+            for (key, stats) in children {
+                let entry = shared_children.entry(key).or_insert([(0, 0, 0); 4]);
+                for (total, (value, count, early_losses)) in entry.iter_mut().zip(stats) {
+                    *total = (total.0 + value, total.1 + count, total.2 + early_losses);
+                }
+            }
+        }
+        self.publish(move_ind, thread_score, thread_count);
+    }
+}
+
+/// Checks that a board can be used to create a game with ```Game::from_existing```.
+/// # Arguments
+/// * ```board``` - The board to check.
+/// # Returns
+/// * ```Ok(())``` - The board is valid.
+/// * ```Err(Error)``` - The board is invalid.
+/// # Errors
+/// * ```Error::InvalidSize``` - The SIZE is invalid. Must be at least 2.
+/// * ```Error::InvalidValue``` - The board contains invalid value. Must be 0, a power of 2, starting from 2 (up to 2^63), or ```JUNK_TILE```.
+///   The error contains the first invalid tile (row by row).
+pub fn validate_board<const SIZE: usize>(board: &[[u64; SIZE]; SIZE]) -> Result<(), Error> {
+    validate_board_with_rule(board, &ClassicRule)
+}
+
+/// Checks that a board can be used to create a game with ```Game::from_existing_with_rule```.
+/// # Arguments
+/// * ```board``` - The board to check.
+/// * ```rule``` - The merge rule the tiles are checked against.
+/// # Returns
+/// * ```Ok(())``` - The board is valid.
+/// * ```Err(Error)``` - The board is invalid.
+/// # Errors
+/// * ```Error::InvalidSize``` - The SIZE is invalid. Must be at least 2.
+/// * ```Error::InvalidValue``` - The board contains a value that is not 0, ```JUNK_TILE``` or a valid tile of the rule.
+///   The error contains the first invalid tile (row by row).
+pub fn validate_board_with_rule<const SIZE: usize>(board: &[[u64; SIZE]; SIZE], rule: &dyn MergeRule) -> Result<(), Error> {
+    if SIZE < MIN_BOARD_SIZE {
+        return Err(Error::InvalidSize { size: SIZE });
+    }
+
+    for (row, tiles) in board.iter().enumerate() {
+        for (col, &value) in tiles.iter().enumerate() {
+            if value != 0 && value != JUNK_TILE && !rule.is_valid_tile(value) {
+                return Err(Error::InvalidValue { row, col, value });
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Finds the difference between two boards: the changed cells and the numbers of the spawned, merged and moved tiles.
+/// # Arguments
+/// * ```before``` - The rows of the first board.
+/// * ```after``` - The rows of the second board.
+/// # Returns
+/// * ```Ok(BoardDiff)``` - The difference between the boards.
+/// * ```Err(Error)``` - The boards have different shapes.
+/// # Errors
+/// * ```Error::InvalidBoard``` - The number of rows or the length of some row of the second board differs from the first board.
+pub fn diff(before: &[Vec<u64>], after: &[Vec<u64>]) -> Result<BoardDiff, Error> {
+    if before.len() != after.len() {
+        return Err(Error::InvalidBoard {
+            expected: before.len(),
+            actual: after.len(),
+        });
+    }
+    let mut changes = Vec::new();
+    for (row, (before_row, after_row)) in before.iter().zip(after).enumerate() {
+        if before_row.len() != after_row.len() {
+            return Err(Error::InvalidBoard {
+                expected: before_row.len(),
+                actual: after_row.len(),
+            });
+        }
+        for (col, (&before, &after)) in before_row.iter().zip(after_row).enumerate() {
+            if before != after {
+                changes.push(CellChange { row, col, before, after });
+            }
+        }
+    }
+
+    // the change of the count of every value, a merge of two tiles of the value v takes two v and adds one 2v
+    let mut counts: BTreeMap<u64, i64> = BTreeMap::new();
+    for &tile in before.iter().flatten().filter(|&&tile| tile != 0) {
+        *counts.entry(tile).or_default() -= 1;
+    }
+    for &tile in after.iter().flatten().filter(|&&tile| tile != 0) {
+        *counts.entry(tile).or_default() += 1;
+    }
+    let (mut spawned, mut merged) = (0, 0);
+    let mut merges: BTreeMap<u64, i64> = BTreeMap::new();
+    for (&value, &count) in &counts {
+        // the smaller values are processed first, so the merges creating this value are known
+        let created = if value % 2 == 0 { merges.get(&(value / 2)).copied().unwrap_or(0) } else { 0 };
+        let remaining = count - created;
+        let value_merges = if remaining < 0 { (-remaining + 1) / 2 } else { 0 };
+        merges.insert(value, value_merges);
+        merged += value_merges as usize;
+        spawned += (remaining + 2 * value_merges) as usize;
+    }
+    let tiles_after = after.iter().flatten().filter(|&&tile| tile != 0).count();
+    let stayed = before
+        .iter()
+        .flatten()
+        .zip(after.iter().flatten())
+        .filter(|&(&before, &after)| before != 0 && before == after)
+        .count();
+    let moved = tiles_after.saturating_sub(spawned + merged + stayed);
+
+    Ok(BoardDiff { changes, spawned, merged, moved })
+}
+
+/// A builder of games with custom options, created by ```Game::builder```.
+/// The size of the board is given by the type of the game, e.g. ```Game::<5>::builder()```.
+#[derive(Clone, Debug)]
+pub struct GameBuilder<const SIZE: usize = 4> {
+    /// The seed of the random number generator of the spawned tiles.
+    seed: Option<u64>,
+    /// The tile that wins the game.
+    target: Option<u64>,
+    /// Whether the game has no target tile.
+    endless: bool,
+    /// The distribution of the values of the spawned tiles.
+    spawn_config: Option<SpawnConfig>,
+    /// The number of tiles on the board at the start.
+    starting_tiles: usize,
+    /// The rule deciding which tiles merge.
+    merge_rule: Option<Arc<dyn MergeRule>>,
+    /// The policy weighting the cells of the spawned tiles.
+    spawn_weight: Option<Arc<dyn SpawnPositionWeight<SIZE>>>,
+    /// The retention of the history.
+    history: HistoryConfig,
+    /// The number of turns after which the game is over.
+    max_turns: Option<u64>,
+    /// The number of turns between two junk tiles.
+    junk_every: Option<u64>,
+    /// The tracking of the repeated positions.
+    repetitions: Option<RepetitionConfig>,
+    /// Whether the timing of the game is tracked.
+    #[cfg(feature = "std")]
+    track_time: bool,
+    /// The clock of the timing, ```None``` for the system clock.
+    #[cfg(feature = "std")]
+    clock: Option<Arc<dyn Clock>>,
+    /// Whether the moves don't spawn tiles (see ```Game::puzzle```), only for the resumed games.
+    puzzle: bool,
+}
+impl<const SIZE: usize> Default for GameBuilder<SIZE> {
+    fn default() -> Self {
+        Self {
+            seed: None,
+            target: None,
+            endless: false,
+            spawn_config: None,
+            starting_tiles: 1,
+            merge_rule: None,
+            spawn_weight: None,
+            history: HistoryConfig::default(),
+            max_turns: None,
+            junk_every: None,
+            repetitions: None,
+            #[cfg(feature = "std")]
+            track_time: false,
+            #[cfg(feature = "std")]
+            clock: None,
+            puzzle: false,
+        }
+    }
+}
+impl<const SIZE: usize> GameBuilder<SIZE> {
+    /// Seeds the random number generator of the spawned tiles, which makes the game reproducible.
+    /// By default, the tiles are spawned with the thread-local random number generator.
+    /// # Arguments
+    /// * ```seed``` - The seed.
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// Sets the tile that wins the game.
+    /// By default, the target depends on the size of the board (see ```Game::target```).
+    /// # Arguments
+    /// * ```target``` - The value of the target tile.
+    pub fn target(mut self, target: u64) -> Self {
+        self.target = Some(target);
+        self
+    }
+
+    /// Enables or disables the endless mode, in which there is no target tile (disabled by default).
+    /// # Arguments
+    /// * ```endless``` - Whether the game is endless.
+    pub fn endless(mut self, endless: bool) -> Self {
+        self.endless = endless;
+        self
+    }
+
+    /// Sets the distribution of the values of the spawned tiles.
+    /// By default, the distribution of the merge rule is used (2 with the probability of 0.9, 4 with the probability of 0.1 for the classic rules).
+    /// # Arguments
+    /// * ```spawn_config``` - The distribution.
+    pub fn spawn_config(mut self, spawn_config: SpawnConfig) -> Self {
+        self.spawn_config = Some(spawn_config);
+        self
+    }
+
+    /// Sets the number of tiles on the board at the start (1 by default).
+    /// # Arguments
+    /// * ```starting_tiles``` - The number of tiles.
+    pub fn starting_tiles(mut self, starting_tiles: usize) -> Self {
+        self.starting_tiles = starting_tiles;
+        self
+    }
+
+    /// Sets the rule deciding which tiles merge (the classic rules by default).
+    /// # Arguments
+    /// * ```rule``` - The rule.
+    pub fn merge_rule(mut self, rule: Arc<dyn MergeRule>) -> Self {
+        self.merge_rule = Some(rule);
+        self
+    }
+
+    /// Sets the policy weighting the empty cells where the new tiles are spawned (uniform by default).
+    /// The AI simulates the games with the same policy.
+    /// # Arguments
+    /// * ```policy``` - The policy, e.g. ```spawn::NearMaxTile``` for the hard mode.
+    pub fn spawn_weight(mut self, policy: Arc<dyn SpawnPositionWeight<SIZE>>) -> Self {
+        self.spawn_weight = Some(policy);
+        self
+    }
+
+    /// Sets the retention of the history: the number of moves that can be undone and the logs of the moves and the scores
+    /// (no undo and no logs by default, see ```Game::with_history```).
+    /// # Arguments
+    /// * ```history``` - The retention of the history.
+    pub fn history(mut self, history: HistoryConfig) -> Self {
+        self.history = history;
+        self
+    }
+
+    /// Limits the number of turns (no limit by default).
+    /// Once the limit is reached, the game is over and no more moves can be made,
+    /// the result is ```GameResult::TurnLimit``` unless the target tile was reached.
+    /// # Arguments
+    /// * ```max_turns``` - The number of turns.
+    pub fn max_turns(mut self, max_turns: u64) -> Self {
+        self.max_turns = Some(max_turns);
+        self
+    }
+
+    /// Enables the junk mode (disabled by default): the tile spawned after every k-th move is a junk tile (```JUNK_TILE```),
+    /// which slides but never merges. The tiles placed by a spawner (see ```Game::with_spawner```) are never replaced by junk.
+    /// # Arguments
+    /// * ```k``` - The number of moves between two junk tiles.
+    pub fn junk_every(mut self, k: u64) -> Self {
+        self.junk_every = Some(k);
+        self
+    }
+
+    /// Enables the tracking of the repeated positions (disabled by default, see ```Game::with_repetition_tracking```).
+    /// # Arguments
+    /// * ```config``` - The number of the remembered positions and the threshold of the reported repetitions.
+    pub fn track_repetitions(mut self, config: RepetitionConfig) -> Self {
+        self.repetitions = Some(config);
+        self
+    }
+
+    /// Enables or disables the timing of the game (disabled by default): the duration of the game and the times of the moves,
+    /// measured from the start of the game or from the previous move (see ```Game::elapsed```, ```Game::average_move_time```
+    /// and ```Game::slowest_move```). The timing is reported in the summary of the game.
+    /// # Arguments
+    /// * ```track_time``` - Whether the timing is tracked.
+    #[cfg(feature = "std")]
+    pub fn track_time(mut self, track_time: bool) -> Self {
+        self.track_time = track_time;
+        self
+    }
+
+    /// Sets the clock of the timing (the system clock by default), e.g. a ```ManualClock``` in the tests.
+    /// The clock is used only if the timing is tracked (see ```track_time```).
+    /// # Arguments
+    /// * ```clock``` - The clock.
+    #[cfg(feature = "std")]
+    pub fn clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = Some(clock);
+        self
+    }
+
+    /// Makes the moves of the game not spawn tiles, like in a puzzle (see ```Game::puzzle```).
+    /// Only for the games resumed with ```build_resumed```, the new games would have no tiles to move.
+    /// # Arguments
+    /// * ```puzzle``` - Whether the game is a puzzle.
+    #[cfg(feature = "std")]
+    pub(crate) fn puzzle(mut self, puzzle: bool) -> Self {
+        self.puzzle = puzzle;
+        self
+    }
+
+    /// Builds the game.
+    /// # Returns
+    /// * ```Ok(Game)``` - The game was created successfully.
+    /// * ```Err(Error)``` - The options are invalid.
+    /// # Errors
+    /// * ```Error::InvalidSize``` - The SIZE is invalid. Must be at least 2.
+    /// * ```Error::InvalidConfig``` - The options don't fit together: the spawned values or the target are not valid tiles of the merge rule,
+    ///   the target is not larger than the largest tile that can start on the board, a target is set in the endless mode,
+    ///   the number of starting tiles is not between 1 and the number of cells, or the limit of the turns or the period of the junk tiles is 0.
+    ///   Without the ```std``` feature, also when the game is not seeded (see ```seed```).
+    pub fn build(self) -> Result<Game<SIZE>, Error> {
+        self.build_game(None)
+    }
+
+    /// Builds the game continuing from a saved position instead of the starting tiles, e.g. the active game of a saved session.
+    /// The options of the builder apply to the game, a seeded game spawns its next tiles from the start of its seed.
+    /// # Arguments
+    /// * ```board``` - The board, with ```BLOCKED_CELL``` in the blocked cells.
+    /// * ```score``` - The score.
+    /// * ```turns``` - The number of the moves made, counted against the limit of the turns.
+    /// # Returns
+    /// * ```Ok(Game)``` - The game was created successfully.
+    /// * ```Err(Error)``` - The options or the board are invalid.
+    /// # Errors
+    /// * ```Error::InvalidSize```, ```Error::InvalidConfig``` - See ```build```.
+    /// * ```Error::InvalidValue``` - The board contains a value that is not 0, a blocked cell, a junk tile or a valid tile of the merge rule.
+    #[cfg(any(feature = "std", feature = "serde"))]
+    pub(crate) fn build_resumed(self, board: &[[u64; SIZE]; SIZE], score: u64, turns: u64) -> Result<Game<SIZE>, Error> {
+        self.build_game(Some((board, score, turns)))
+    }
+
+    /// Builds the game, starting with the starting tiles or from the given position (see ```build``` and ```build_resumed```).
+    /// # Arguments
+    /// * ```position``` - The board, the score and the number of the turns to continue from, ```None``` for a new game.
+    /// # Returns
+    /// * ```Ok(Game)``` - The game was created successfully.
+    /// * ```Err(Error)``` - The options or the board are invalid.
+    fn build_game(self, position: Option<(&[[u64; SIZE]; SIZE], u64, u64)>) -> Result<Game<SIZE>, Error> {
+        if SIZE < MIN_BOARD_SIZE {
+            return Err(Error::InvalidSize { size: SIZE });
+        }
+
+        let rule: &dyn MergeRule = self.merge_rule.as_deref().unwrap_or(&ClassicRule);
+        let spawn_config = self.spawn_config.unwrap_or_else(|| rule.spawn_config());
+        if !spawn_config.values().iter().all(|&(value, _)| rule.is_valid_tile(value)) {
+            return Err(Error::InvalidConfig);
+        }
+        if !(1..=SIZE * SIZE).contains(&self.starting_tiles) || self.max_turns == Some(0) || self.junk_every == Some(0) {
+            return Err(Error::InvalidConfig);
+        }
+
+        let mut game = Game::from_board_unchecked(&[[0; SIZE]; SIZE], 0)
+            .with_spawn_config(spawn_config)
+            .with_merge_rule_unchecked(self.merge_rule.clone());
+        match (self.target, self.endless) {
+            (Some(_), true) => return Err(Error::InvalidConfig),
+            (Some(target), false) if target <= game.spawn_config.max_value() => return Err(Error::InvalidConfig),
+            (Some(target), false) => game = game.with_target(target)?,
+            (None, true) => game = game.with_endless_mode(),
+            (None, false) => {}
+        }
+        game.rng = self.seed.map(StdRng::seed_from_u64);
+        game = game.with_history(self.history);
+        game.max_turns = self.max_turns;
+        game.spawn_weight = self.spawn_weight;
+        game.junk_every = self.junk_every;
+        game.puzzle = self.puzzle;
+        #[cfg(feature = "std")]
+        if self.track_time {
+            game = game.with_clock(self.clock.unwrap_or_else(|| Arc::new(SystemClock::default())));
+        }
+
+        match position {
+            None if !game.can_spawn() => return Err(Error::InvalidConfig),
+            None => game.spawn_starting_tiles(self.starting_tiles),
+            Some((board, score, turns)) => {
+                let rule = game.merge_rule();
+                for (row, tiles) in board.iter().enumerate() {
+                    for (col, &value) in tiles.iter().enumerate() {
+                        if value != 0 && value != BLOCKED_CELL && value != JUNK_TILE && !rule.is_valid_tile(value) {
+                            return Err(Error::InvalidValue { row, col, value });
                         }
-                    };
-                    merge = true;
+                    }
                 }
+                game.reset_to(board, score);
+                // the state and the result are derived again with the turns, for the limit of the turns and the turn of the victory
+                game.turns = turns;
+                game.state = GameState::InProgress;
+                game.result = GameResult::Pending;
+                game.victory_turn = None;
+                game.update();
+            }
+        }
+        if let Some(config) = self.repetitions {
+            game = game.with_repetition_tracking(config);
+        }
+
+        Ok(game)
+    }
+}
+
+/// The timing of a game, measured with its clock (see ```GameBuilder::track_time```).
+#[cfg(feature = "std")]
+#[derive(Debug)]
+struct Timing {
+    /// The source of the time.
+    clock: Arc<dyn Clock>,
+    /// The start of the game.
+    started: Duration,
+    /// The end of the previous move, or the start of the game before the first move.
+    last_move: Duration,
+    /// The end of the game, if it is over.
+    finished: Option<Duration>,
+    /// The number of the timed moves.
+    moves: u64,
+    /// The turn of the slowest move and its time.
+    slowest: Option<(u64, Duration)>,
+}
+#[cfg(feature = "std")]
+impl Timing {
+    /// Starts the timing now.
+    /// # Arguments
+    /// * ```clock``` - The source of the time.
+    fn new(clock: Arc<dyn Clock>) -> Self {
+        let now = clock.now();
+        Self {
+            clock,
+            started: now,
+            last_move: now,
+            finished: None,
+            moves: 0,
+            slowest: None,
+        }
+    }
+
+    /// Records the time of a move, since the previous move.
+    /// # Arguments
+    /// * ```turn``` - The turn of the move.
+    /// * ```over``` - Whether the move ended the game.
+    fn record_move(&mut self, turn: u64, over: bool) {
+        let now = self.clock.now();
+        let time = now.saturating_sub(self.last_move);
+        self.last_move = now;
+        self.moves += 1;
+        if self.slowest.is_none_or(|(_, slowest)| time > slowest) {
+            self.slowest = Some((turn, time));
+        }
+        if over {
+            self.finished = Some(now);
+        }
+    }
+
+    /// Returns the time from the start of the game to its end, or to now if the game is not over yet.
+    fn elapsed(&self) -> Duration {
+        self.finished.unwrap_or_else(|| self.clock.now()).saturating_sub(self.started)
+    }
+
+    /// Returns the average time of the moves, ```None``` before the first move.
+    fn average_move_time(&self) -> Option<Duration> {
+        // the times of the moves add up to the time from the start to the last move
+        (self.moves > 0).then(|| (self.last_move - self.started) / u32::try_from(self.moves).unwrap_or(u32::MAX))
+    }
+}
+
+/// The state of the game before a move, restored by ```Game::undo```.
+/// The empty cells, the possible moves and the boards after them are computed again from the board.
+#[derive(Debug)]
+struct Snapshot<const SIZE: usize> {
+    /// Game tiles.
+    board: [[u64; SIZE]; SIZE],
+    /// Game score.
+    score: u64,
+    /// Number of moves made.
+    turns: u64,
+    /// Number of merges made.
+    merges: u64,
+    /// The number of merges that created each tile value.
+    merge_histogram: BTreeMap<u64, u64>,
+    /// The largest merged tile so far.
+    best_merge: u64,
+    /// The score gained by the last move.
+    last_move_gain: u64,
+    /// The turn on which the target tile was reached.
+    victory_turn: Option<u64>,
+    /// Identities of the tiles, present only if tile tracking is enabled.
+    tile_ids: Option<[[Option<NonZeroU64>; SIZE]; SIZE]>,
+    /// The identity that will be assigned to the next created tile.
+    next_tile_id: u64,
+    /// The random number generator of the spawned tiles, if the game is seeded.
+    rng: Option<StdRng>,
+    /// The state of the game.
+    state: GameState,
+    /// The result of the game.
+    result: GameResult,
+}
+
+#[derive(Debug)]
+/// A struct that represents the 2048 game.
+/// The default size of the board is 4x4.
+pub struct Game<const SIZE: usize = 4> {
+    /// Game tiles, stored as their values and not as the exponents: the tiles of other merge rules (e.g. 3 or 5) are not powers of 2,
+    /// and the blocked cells and the junk tiles are marked with values no exponent represents.
+    /// The 4x4 AI search packs the exponents of the classic tiles into nibbles instead (see ```BitBoard```).
+    board: [[u64; SIZE]; SIZE],
+    /// The number of empty cells of the board.
+    empty_cells: usize,
+    /// The number of tiles spawned at the start of the game.
+    starting_tiles: usize,
+    /// Game score.
+    score: u64,
+    /// Additional score for each move.
+    score_next: [u64; 4],
+    /// The largest merged tile of each move (0 if the move doesn't merge any tiles).
+    best_merge_next: [u64; 4],
+    /// Availability of moves.
+    moves: [bool; 4],
+    /// Number of moves made.
+    turns: u64,
+    /// Board after each of the moves.
+    moves_next: [[[u64; SIZE]; SIZE]; 4],
+    /// Whether the board, the score and the merged tile after each of the moves are built (always for the valid moves, unless the moves are lazy).
+    moves_built: [bool; 4],
+    /// Whether the boards after the moves are built only when they are needed (see ```with_lazy_moves```).
+    lazy_moves: bool,
+    /// The state of the game.
+    state: GameState,
+    /// The result of the game.
+    result: GameResult,
+    /// Identities of the tiles, present only if tile tracking is enabled.
+    tile_ids: Option<[[Option<NonZeroU64>; SIZE]; SIZE]>,
+    /// The identity that will be assigned to the next created tile.
+    next_tile_id: u64,
+    /// The observer notified about the events in the game.
+    observer: Option<Box<dyn GameObserver>>,
+    /// The channels of the subscribers of the events.
+    #[cfg(feature = "std")]
+    subscribers: Vec<Sender<GameEvent>>,
+    /// The distribution of the values of the spawned tiles.
+    spawn_config: SpawnConfig,
+    /// The spawner placing the new tiles, if the tiles are not placed randomly.
+    spawner: Option<Box<dyn TileSpawner<SIZE>>>,
+    /// Whether the game waits for a tile to be placed (in the two-player mode).
+    awaiting_tile: bool,
+    /// Whether the board contains blocked cells.
+    has_blocked_cells: bool,
+    /// The rule deciding which tiles merge, ```None``` for the classic rules.
+    merge_rule: Option<Arc<dyn MergeRule>>,
+    /// The policy weighting the cells of the spawned tiles, ```None``` for the uniform choice.
+    spawn_weight: Option<Arc<dyn SpawnPositionWeight<SIZE>>>,
+    /// The tile that wins the game, ```None``` in the endless mode.
+    target: Option<u64>,
+    /// The number of turns after which the game is over, ```None``` for no limit.
+    max_turns: Option<u64>,
+    /// Whether the game is a puzzle, whose moves don't spawn tiles (see ```Game::puzzle```).
+    puzzle: bool,
+    /// The number of turns between two junk tiles, ```None``` if the junk mode is disabled.
+    junk_every: Option<u64>,
+    /// The number of turns made before the game was derived (see ```derive_game_ahead```), which keeps the junk tiles of the searches in step.
+    junk_offset: u64,
+    /// The random number generator of the spawned tiles, if the game is seeded.
+    rng: Option<StdRng>,
+    /// Number of merges made.
+    merges: u64,
+    /// The number of merges that created each tile value.
+    merge_histogram: BTreeMap<u64, u64>,
+    /// The largest merged tile so far.
+    best_merge: u64,
+    /// The score gained by the last move.
+    last_move_gain: u64,
+    /// The retention of the history.
+    history_config: HistoryConfig,
+    /// The states before the latest moves, for the undo.
+    undo_stack: Ring<Snapshot<SIZE>>,
+    /// The latest moves, kept only if the move log is enabled.
+    move_log: Ring<GameMove>,
+    /// The score gained by the latest moves, kept only if the score log is enabled.
+    score_history: Ring<u64>,
+    /// Whether some older entries of the history were discarded.
+    history_truncated: bool,
+    /// The latest positions, kept only if the repetition tracking is enabled.
+    repetitions: Option<Repetitions>,
+    /// The turn on which the target tile was reached.
+    victory_turn: Option<u64>,
+    /// The timing of the game and its moves, present only if the timing is enabled.
+    #[cfg(feature = "std")]
+    timing: Option<Timing>,
+}
+impl<const SIZE: usize> Game<SIZE> {
+    /// Creates a new game of 2048.
+    /// # Returns
+    /// * ```Ok(Game)```: The game was created successfully.
+    /// * ```Err(Error)```: The game was not created successfully.
+    /// # Errors
+    /// * ```Error::InvalidSize```: The SIZE is invalid. Must be at least 2.
+    /// * ```Error::InvalidConfig```: Without the ```std``` feature, the game has no random number generator, use ```GameBuilder::seed``` instead.
+    pub fn new() -> Result<Self, Error> {
+        if SIZE < MIN_BOARD_SIZE {
+            return Err(Error::InvalidSize { size: SIZE });
+        }
+
+        let board = [[0; SIZE]; SIZE];
+        let score = 0;
+        let score_next = [0; 4];
+        let moves = [true; 4];
+        let moves_next = [[[0; SIZE]; SIZE]; 4];
+        let state = GameState::InProgress;
+        let result = GameResult::Pending;
+
+        let mut game_object: Self = Self {
+            board,
+            empty_cells: SIZE * SIZE,
+            starting_tiles: 1,
+            score,
+            score_next,
+            best_merge_next: [0; 4],
+            moves,
+            moves_next,
+            moves_built: [false; 4],
+            lazy_moves: false,
+            state,
+            result,
+            turns: 0,
+            tile_ids: None,
+            next_tile_id: 1,
+            observer: None,
+            #[cfg(feature = "std")]
+            subscribers: Vec::new(),
+            spawn_config: SpawnConfig::default(),
+            spawner: None,
+            awaiting_tile: false,
+            has_blocked_cells: false,
+            merge_rule: None,
+            spawn_weight: None,
+            target: Some(Self::default_target()),
+            max_turns: None,
+            puzzle: false,
+            junk_every: None,
+            junk_offset: 0,
+            rng: None,
+            merges: 0,
+            merge_histogram: BTreeMap::new(),
+            best_merge: 0,
+            last_move_gain: 0,
+            history_config: HistoryConfig::default(),
+            undo_stack: Ring::new(0),
+            move_log: Ring::new(0),
+            score_history: Ring::new(0),
+            history_truncated: false,
+            repetitions: None,
+            victory_turn: None,
+            #[cfg(feature = "std")]
+            timing: None,
+        };
+
+        if !game_object.can_spawn() {
+            return Err(Error::InvalidConfig);
+        }
+        game_object.new_tile();
+        game_object.update();
+
+        Ok(game_object)
+    }
+
+    /// Creates a game of 2048 from an existing board.
+    /// The board must be a square matrix filled with 0 for empty tiles and powers of 2 for filled tiles.
+    /// An empty board gets the starting tile, like a new game.
+    /// The state and the result of the game are determined from the board, so a board with no possible moves is over.
+    /// # Arguments
+    /// * ```board```: The board to use.
+    /// * ```score```: The score of the game.
+    /// # Returns
+    /// * ```Ok(Game)```: The game was created successfully.
+    /// * ```Err(Error)```: The game was not created successfully.
+    /// # Errors
+    /// * ```Error::InvalidSize```: The SIZE is invalid. Must be at least 2.
+    /// * ```Error::InvalidValue```: The board contains invalid value. Must be 0 or a power of 2, starting from 2 (up to 2^63).
+    /// * ```Error::InvalidConfig```: Without the ```std``` feature, the board is empty and the starting tile can't be spawned (see ```with_seed```).
+    pub fn from_existing(board: &[[u64; SIZE]; SIZE], score: u64) -> Result<Self, Error> {
+        validate_board(board)?;
+
+        let mut game_object = Self::from_board_unchecked(board, score);
+        game_object.spawn_starting_tile()?;
+
+        Ok(game_object)
+    }
+
+    /// Creates a puzzle on the given board, e.g. "reach 256 in 6 moves from this position".
+    /// The moves of a puzzle only slide and merge the tiles, no tiles are spawned.
+    /// The puzzle is solved (```GameResult::Victory```, and the game is over) when a move creates the target tile within the budget of the moves,
+    /// and it is lost (```GameResult::Loss```) when the budget is exhausted or no move is possible.
+    /// The AI (```find_best_move```) tries every sequence of the remaining moves instead of the simulated games, so the budget should stay small.
+    /// # Arguments
+    /// * ```board```: The board of the puzzle.
+    /// * ```max_moves```: The budget of the moves.
+    /// * ```target```: The value of the target tile.
+    /// # Returns
+    /// * ```Ok(Game)```: The puzzle.
+    /// * ```Err(Error)```: The puzzle is invalid.
+    /// # Errors
+    /// * ```Error::InvalidSize```: The SIZE is invalid. Must be at least 2.
+    /// * ```Error::InvalidValue```: The board contains invalid value. Must be 0 or a power of 2, starting from 2 (up to 2^63).
+    /// * ```Error::InvalidConfig```: The budget is 0 or the target is not a valid tile.
+    pub fn puzzle(board: &[[u64; SIZE]; SIZE], max_moves: u64, target: u64) -> Result<Self, Error> {
+        validate_board(board)?;
+        if max_moves == 0 || target == 0 || !ClassicRule.is_valid_tile(target) {
+            return Err(Error::InvalidConfig);
+        }
+
+        let mut game_object = Self::from_board_unchecked(board, 0);
+        game_object.puzzle = true;
+        game_object.max_turns = Some(max_moves);
+        game_object.target = Some(target);
+        game_object.update();
+        Ok(game_object)
+    }
+
+    /// Checks whether the game is a puzzle (see ```Game::puzzle```).
+    /// # Returns
+    /// * ```true``` - The game is a puzzle, its moves don't spawn tiles.
+    /// * ```false``` - The game is not a puzzle.
+    pub fn is_puzzle(&self) -> bool {
+        self.puzzle
+    }
+
+    /// Creates a game of 2048 from the rows of an existing board.
+    /// Works like ```from_existing```, for boards that are built at runtime.
+    /// # Arguments
+    /// * ```rows```: The rows of the board.
+    /// * ```score```: The score of the game.
+    /// # Returns
+    /// * ```Ok(Game)```: The game was created successfully.
+    /// * ```Err(Error)```: The game was not created successfully.
+    /// # Errors
+    /// * ```Error::InvalidSize```: The SIZE is invalid. Must be at least 2.
+    /// * ```Error::InvalidBoard```: The number of rows or the length of some row is not SIZE.
+    /// * ```Error::InvalidValue```: The board contains invalid value. Must be 0 or a power of 2, starting from 2 (up to 2^63).
+    pub fn from_rows(rows: &[Vec<u64>], score: u64) -> Result<Self, Error> {
+        if SIZE < MIN_BOARD_SIZE {
+            return Err(Error::InvalidSize { size: SIZE });
+        }
+
+        let mut board = [[0; SIZE]; SIZE];
+        if rows.len() != SIZE {
+            return Err(Error::InvalidBoard {
+                expected: SIZE,
+                actual: rows.len(),
+            });
+        }
+        for (board_row, row) in board.iter_mut().zip(rows) {
+            if row.len() != SIZE {
+                return Err(Error::InvalidBoard {
+                    expected: SIZE,
+                    actual: row.len(),
+                });
+            }
+            board_row.copy_from_slice(row);
+        }
+
+        Self::from_existing(&board, score)
+    }
+
+    /// Generates a random position with the given number of tiles, for the fixtures of the tests and the benchmarks.
+    /// The position is determined by the seed. The exponents of the tiles have a geometric distribution
+    /// (every larger exponent is half as likely) capped at the maximum exponent,
+    /// and the score is the score of creating all the tiles by merging twos.
+    /// A board that is not full always has a possible move, a full board may be over.
+    /// The returned game is seeded with the same seed, so the spawned tiles are reproducible too.
+    /// # Arguments
+    /// * ```filled```: The number of tiles on the board, from 1 to SIZE * SIZE.
+    /// * ```max_exponent```: The largest exponent of the tiles, from 1 to ```MAX_SUPPORTED_EXPONENT```.
+    /// * ```seed```: The seed of the position.
+    /// # Returns
+    /// * ```Ok(Game)```: The generated game.
+    /// * ```Err(Error)```: The game was not generated.
+    /// # Errors
+    /// * ```Error::InvalidSize```: The SIZE is invalid. Must be at least 2.
+    /// * ```Error::InvalidConfig```: The number of tiles or the maximum exponent is out of range.
+    pub fn random_position(filled: usize, max_exponent: u32, seed: u64) -> Result<Self, Error> {
+        if SIZE < MIN_BOARD_SIZE {
+            return Err(Error::InvalidSize { size: SIZE });
+        }
+        if !(1..=SIZE * SIZE).contains(&filled) || !(1..=MAX_SUPPORTED_EXPONENT).contains(&max_exponent) {
+            return Err(Error::InvalidConfig);
+        }
+
+        let mut rng = StdRng::seed_from_u64(seed);
+        // the first cells of a partial shuffle are the filled ones
+        let mut cells: Vec<(usize, usize)> = (0..SIZE).flat_map(|row| (0..SIZE).map(move |col| (row, col))).collect();
+        let mut board = [[0; SIZE]; SIZE];
+        let mut score: u64 = 0;
+        for ind in 0..filled {
+            cells.swap(ind, rng.gen_range(ind..SIZE * SIZE));
+            let mut exponent = 1;
+            while exponent < max_exponent && rng.gen_bool(0.5) {
+                exponent += 1;
+            }
+            let (row, col) = cells[ind];
+            board[row][col] = 1 << exponent;
+            score = score.saturating_add(u64::from(exponent - 1).saturating_mul(1 << exponent));
+        }
+
+        let mut game = Self::from_existing(&board, score)?;
+        game.rng = Some(rng);
+        Ok(game)
+    }
+
+    /// Creates a game of 2048 from the state saved by the original web game (gabrielecirulli/2048) in ```localStorage```.
+    /// The state of the game is determined from the board like in ```from_existing```,
+    /// except that the ```won``` flag makes the game won and the ```over``` flag makes the game over.
+    /// Available with the ```serde``` feature.
+    /// # Arguments
+    /// * ```json``` - The JSON of the saved state.
+    /// # Returns
+    /// * ```Ok(Game)```: The game was created successfully.
+    /// * ```Err(Error)```: The game was not created successfully.
+    /// # Errors
+    /// * ```Error::InvalidSize```: The SIZE is invalid. Must be at least 2.
+    /// * ```Error::InvalidEncoding```: The JSON is malformed or is not a state of the web game.
+    /// * ```Error::InvalidBoard```: The size of the saved grid is not SIZE.
+    /// * ```Error::InvalidValue```: The saved grid contains invalid value. Must be a power of 2, starting from 2 (up to 2^63).
+    #[cfg(feature = "serde")]
+    pub fn from_web_json(json: &str) -> Result<Self, Error> {
+        if SIZE < MIN_BOARD_SIZE {
+            return Err(Error::InvalidSize { size: SIZE });
+        }
+
+        let save = WebSave::<SIZE>::from_json(json)?;
+        let mut game_object = Self::from_existing(&save.board, save.score)?;
+        game_object.apply_saved_flags(save.won, save.over);
+
+        Ok(game_object)
+    }
+
+    /// Saves the game in the format of the original web game (gabrielecirulli/2048), which can be put into its ```localStorage```.
+    /// The won games are saved as continued (```keepPlaying```), the blocked cells are saved as empty cells.
+    /// Available with the ```serde``` feature.
+    /// # Returns
+    /// * ```String``` - The JSON of the saved state.
+    #[cfg(feature = "serde")]
+    pub fn to_web_json(&self) -> String {
+        WebSave {
+            board: self.board,
+            score: self.score,
+            won: self.result.is_victory(),
+            over: self.state == GameState::GameOver,
+        }
+        .to_json()
+    }
+
+    /// Applies the flags of a saved game to the game loaded from the board of the save.
+    /// # Arguments
+    /// * ```won``` - Whether the saved game was won, even if the target tile is not on the board.
+    /// * ```over``` - Whether the saved game was over, even if there are valid moves left.
+    fn apply_saved_flags(&mut self, won: bool, over: bool) {
+        if won && !self.result.is_victory() {
+            self.result = GameResult::Victory;
+            self.victory_turn = Some(0);
+        }
+        if over {
+            // the saved game ended, so no move can be made even if the board would allow one
+            self.moves = [false; 4];
+            self.state = GameState::GameOver;
+            self.result = if self.result.is_victory() { GameResult::VictoryThenLoss } else { GameResult::Loss };
+        }
+    }
+
+    /// Encodes the game into the compact binary encoding:
+    /// the size (1 byte), the exponents of the tiles row by row (1 byte each, see ```board_exponents```),
+    /// the score (8 bytes, little endian) and the flags (1 byte, the lowest bit is set for the won games).
+    /// # Returns
+    /// * ```Some(Vec<u8>)``` - The encoded game.
+    /// * ```None``` - The game can't be encoded, the size is larger than 255 or the board contains a tile that is not a power of 2.
+    pub(crate) fn to_compact_bytes(&self) -> Option<Vec<u8>> {
+        let size = u8::try_from(SIZE).ok()?;
+        let exponents = self.board_exponents()?;
+
+        let mut bytes = Vec::with_capacity(SIZE * SIZE + 10);
+        bytes.push(size);
+        bytes.extend(exponents.iter().flatten());
+        bytes.extend(self.score.to_le_bytes());
+        bytes.push(u8::from(self.result.is_victory()));
+        Some(bytes)
+    }
+
+    /// Decodes the game from the compact binary encoding (see ```to_compact_bytes```).
+    /// The board is validated and the state of the game is determined from it, like in ```from_existing```.
+    /// # Arguments
+    /// * ```bytes``` - The encoded game.
+    /// # Returns
+    /// * ```Ok(Game)``` - The decoded game.
+    /// * ```Err(Error)``` - The encoding is invalid.
+    /// # Errors
+    /// * ```Error::InvalidSize```: The SIZE is invalid. Must be at least 2.
+    /// * ```Error::InvalidBoard```: The size of the encoded game is not SIZE.
+    /// * ```Error::InvalidEncoding```: The encoding is truncated, too long or contains an invalid exponent or flag.
+    pub(crate) fn from_compact_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        if SIZE < MIN_BOARD_SIZE {
+            return Err(Error::InvalidSize { size: SIZE });
+        }
+        let (&size, rest) = bytes.split_first().ok_or(Error::InvalidEncoding)?;
+        if usize::from(size) != SIZE {
+            return Err(Error::InvalidBoard {
+                expected: SIZE,
+                actual: usize::from(size),
+            });
+        }
+        if rest.len() != SIZE * SIZE + 9 {
+            return Err(Error::InvalidEncoding);
+        }
+        let (exponents, rest) = rest.split_at(SIZE * SIZE);
+        let (score, flags) = rest.split_at(8);
+        if flags[0] > 1 {
+            return Err(Error::InvalidEncoding);
+        }
+
+        let mut board = [[0; SIZE]; SIZE];
+        for (tile, &exponent) in board.iter_mut().flatten().zip(exponents) {
+            *tile = match exponent {
+                0 => 0,
+                exponent if (1..=MAX_SUPPORTED_EXPONENT).contains(&u32::from(exponent)) => 1 << exponent,
+                _ => return Err(Error::InvalidEncoding),
+            };
+        }
+        let score = u64::from_le_bytes(score.try_into().unwrap());
+
+        let mut game_object = Self::from_existing(&board, score)?;
+        game_object.apply_saved_flags(flags[0] == 1, false);
+        Ok(game_object)
+    }
+
+    /// Creates the code of the position, for sharing it as a short string.
+    /// The code is the URL-safe base64 (without padding) of the compact encoding of the game
+    /// (the size, the exponents of the tiles, the score and whether the game is won) followed by a checksum.
+    /// The code of a 4x4 game has 36 characters.
+    /// # Returns
+    /// * ```Ok(String)``` - The code of the position.
+    /// * ```Err(Error)``` - The game can't be shared.
+    /// # Errors
+    /// * ```Error::InvalidConfig```: The game uses other merge rules than the classic ones, or the size is larger than 255.
+    /// * ```Error::InvalidValue```: The board contains a blocked cell or a junk tile.
+    pub fn share_code(&self) -> Result<String, Error> {
+        if self.merge_rule().name() != Some("classic") || SIZE > usize::from(u8::MAX) {
+            return Err(Error::InvalidConfig);
+        }
+        for (row, tiles) in self.board.iter().enumerate() {
+            for (col, &value) in tiles.iter().enumerate() {
+                if value != 0 && !ClassicRule.is_valid_tile(value) {
+                    return Err(Error::InvalidValue { row, col, value });
+                }
+            }
+        }
+        // the classic tiles of the boards up to the size of 255 always encode
+        let bytes = self.to_compact_bytes().ok_or(Error::InvalidConfig)?;
+        Ok(share::encode(&bytes))
+    }
+
+    /// Creates a game from the code of a position created by ```share_code```.
+    /// The board is validated and the state of the game is determined from it, like in ```from_existing```.
+    /// # Arguments
+    /// * ```code``` - The code of the position.
+    /// # Returns
+    /// * ```Ok(Game)``` - The game of the position.
+    /// * ```Err(Error)``` - The code is invalid.
+    /// # Errors
+    /// * ```Error::InvalidSize```: The SIZE is invalid. Must be at least 2.
+    /// * ```Error::InvalidEncoding```: The code is not valid base64, its checksum doesn't match or the encoded game is malformed.
+    /// * ```Error::InvalidBoard```: The size of the shared game is not SIZE.
+    pub fn from_share_code(code: &str) -> Result<Self, Error> {
+        Self::from_compact_bytes(&share::decode(code)?)
+    }
+
+    /// Renders the board like ```Display```, but with the exponents of the tiles (e.g. 11 for the tile of 2048, 0 for the empty cells).
+    /// The tiles that are not powers of 2 starting from 2 (the tiles of other merge rules) are shown as ```?```, the blocked cells as ```#```
+    /// and the junk tiles as ```X```.
+    /// # Returns
+    /// * ```String``` - The rendered board and the score.
+    pub fn to_exponent_string(&self) -> String {
+        self.render(|tile| match tile {
+            0 => String::from("0"),
+            BLOCKED_CELL => String::from("#"),
+            JUNK_TILE => String::from("X"),
+            tile if tile.is_power_of_two() && tile > 1 => tile.trailing_zeros().to_string(),
+            _ => String::from("?"),
+        })
+    }
+
+    /// Writes the board and the score on a single line: the size, the rows separated by ```/``` with the tiles separated by ```,```
+    /// (```#``` for the blocked cells, ```X``` for the junk tiles) and the score, e.g. ```4|2,0,0,4/0,8,16,0/0,0,0,0/0,0,0,2/score=1234```.
+    /// The games with other than the classic merge rule end with its name (see ```MergeRule::name```, ```custom``` for the others),
+    /// e.g. ```3|1,2,3/0,0,0/0,0,6/score=0/rule=threes```.
+    /// The line is parsed back by the ```FromStr``` implementation of the game.
+    /// # Returns
+    /// * ```String``` - The compact form of the game.
+    pub fn to_compact_string(&self) -> String {
+        let mut output = SIZE.to_string();
+        output.push('|');
+        for row in &self.board {
+            for (j, &tile) in row.iter().enumerate() {
+                if j > 0 {
+                    output.push(',');
+                }
+                match tile {
+                    BLOCKED_CELL => output.push('#'),
+                    JUNK_TILE => output.push('X'),
+                    tile => write!(&mut output, "{}", tile).unwrap(),
+                }
+            }
+            output.push('/');
+        }
+        write!(&mut output, "score={}", self.score).unwrap();
+        if let Some(rule) = &self.merge_rule {
+            write!(&mut output, "/rule={}", rule.name().unwrap_or("custom")).unwrap();
+        }
+        output
+    }
+
+    /// Renders the board row by row with the labels of the tiles aligned to the right, followed by the score.
+    /// # Arguments
+    /// * ```label``` - The label of a tile.
+    /// # Returns
+    /// * ```String``` - The rendered board.
+    fn render(&self, label: impl Fn(u64) -> String) -> String {
+        let labels = self.board.map(|row| row.map(&label));
+        // one more space separates the tiles
+        let width = labels.iter().flatten().map(String::len).max().unwrap_or(1) + 1;
+
+        let mut output = String::from("Board:\n");
+        for row in &labels {
+            for label in row {
+                write!(&mut output, "{:>width$}", label, width = width).unwrap();
+            }
+            output.push('\n');
+        }
+        writeln!(&mut output, "Score: {}", self.score).unwrap();
+        output
+    }
+
+    /// Saves the game into the file, in the compact binary encoding preceded by the magic header and the version of the format.
+    /// Available with the ```std``` feature.
+    /// # Arguments
+    /// * ```path``` - The path of the file, the existing file is overwritten.
+    /// # Returns
+    /// * ```Ok(())``` - The game was saved.
+    /// * ```Err(io::Error)``` - The file could not be written,
+    ///   or the game can't be encoded (```io::ErrorKind::InvalidInput```, the board contains a tile that is not a power of 2).
+    #[cfg(feature = "std")]
+    pub fn save_to(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let encoded = self
+            .to_compact_bytes()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "the board contains a tile that is not a power of 2"))?;
+
+        let mut bytes = Vec::with_capacity(SAVE_MAGIC.len() + 1 + encoded.len());
+        bytes.extend(SAVE_MAGIC);
+        bytes.push(SAVE_VERSION);
+        bytes.extend(encoded);
+        fs::write(path, bytes)
+    }
+
+    /// Loads the game saved by ```save_to```.
+    /// The board is validated and the state of the game is determined from it, like in ```from_existing```.
+    /// Available with the ```std``` feature.
+    /// # Arguments
+    /// * ```path``` - The path of the file.
+    /// # Returns
+    /// * ```Ok(Game)``` - The loaded game.
+    /// * ```Err(LoadError)``` - The game could not be loaded.
+    /// # Errors
+    /// * ```LoadError::Io``` - The file could not be read.
+    /// * ```LoadError::UnsupportedVersion``` - The file was saved in a newer version of the format.
+    /// * ```LoadError::Game``` - The file is not a saved game (```Error::InvalidEncoding```), the size of the saved game is not SIZE
+    ///   (```Error::InvalidBoard```) or the saved board is invalid.
+    #[cfg(feature = "std")]
+    pub fn load_from(path: impl AsRef<Path>) -> Result<Self, LoadError> {
+        let bytes = fs::read(path)?;
+        let rest = bytes.strip_prefix(&SAVE_MAGIC).ok_or(Error::InvalidEncoding)?;
+        let (&version, encoded) = rest.split_first().ok_or(Error::InvalidEncoding)?;
+        match version {
+            SAVE_VERSION => Ok(Self::from_compact_bytes(encoded)?),
+            0 => Err(Error::InvalidEncoding.into()),
+            _ => Err(LoadError::UnsupportedVersion { version }),
+        }
+    }
+
+    /// Creates a new game of 2048 played with the given merge rule.
+    /// The spawned tiles come from the distribution of the rule (see ```MergeRule::spawn_config```).
+    /// # Arguments
+    /// * ```rule``` - The rule deciding which tiles merge.
+    /// # Returns
+    /// * ```Ok(Game)```: The game was created successfully.
+    /// * ```Err(Error)```: The game was not created successfully.
+    /// # Errors
+    /// * ```Error::InvalidSize```: The SIZE is invalid. Must be at least 2.
+    pub fn new_with_rule(rule: Arc<dyn MergeRule>) -> Result<Self, Error> {
+        Self::from_existing_with_rule(&[[0; SIZE]; SIZE], 0, rule)
+    }
+
+    /// Creates a game of 2048 played with the given merge rule from an existing board.
+    /// The board must be a square matrix filled with 0 for empty tiles and valid tiles of the rule for filled tiles.
+    /// An empty board gets the starting tile, like a new game.
+    /// The spawned tiles come from the distribution of the rule (see ```MergeRule::spawn_config```).
+    /// # Arguments
+    /// * ```board```: The board to use.
+    /// * ```score```: The score of the game.
+    /// * ```rule``` - The rule deciding which tiles merge.
+    /// # Returns
+    /// * ```Ok(Game)```: The game was created successfully.
+    /// * ```Err(Error)```: The game was not created successfully.
+    /// # Errors
+    /// * ```Error::InvalidSize```: The SIZE is invalid. Must be at least 2.
+    /// * ```Error::InvalidValue```: The board contains a value that is not a valid tile of the rule.
+    /// * ```Error::InvalidConfig```: Without the ```std``` feature, the board is empty and the starting tile can't be spawned (see ```with_seed```).
+    pub fn from_existing_with_rule(board: &[[u64; SIZE]; SIZE], score: u64, rule: Arc<dyn MergeRule>) -> Result<Self, Error> {
+        validate_board_with_rule(board, rule.as_ref())?;
+
+        let spawn_config = rule.spawn_config();
+        let mut game_object = Self::from_board_unchecked(board, score)
+            .with_spawn_config(spawn_config)
+            .with_merge_rule_unchecked(Some(rule));
+        game_object.spawn_starting_tile()?;
+
+        Ok(game_object)
+    }
+
+    /// Restarts the game in place, reusing the existing game object.
+    /// The board is cleared (except for the blocked cells), the score, the state, the result and the counters are reset
+    /// and the starting tiles are spawned again, like in a freshly built game.
+    /// The configuration (the target, the spawned values, the merge rule, the spawner, the observer) is kept
+    /// and the random number generator of a seeded game continues from its current state.
+    /// The enabled history, tile identities and timing are restarted.
+    pub fn reset(&mut self) {
+        let board = self.board.map(|row| row.map(|tile| if tile == BLOCKED_CELL { BLOCKED_CELL } else { 0 }));
+        self.restart(&board, true);
+    }
+
+    /// Restarts the game in place on the board it had after a restart, instead of spawning the starting tiles,
+    /// e.g. to replay a recorded ```reset```. The board is not validated.
+    /// # Arguments
+    /// * ```board``` - The board after the restart, with ```BLOCKED_CELL``` in the blocked cells.
+    #[cfg(feature = "std")]
+    pub(crate) fn restart_on(&mut self, board: &[[u64; SIZE]; SIZE]) {
+        self.restart(board, false);
+    }
+
+    /// Restarts the game in place (see ```reset```).
+    /// # Arguments
+    /// * ```board``` - The board to restart on, with ```BLOCKED_CELL``` in the blocked cells.
+    /// * ```spawn``` - Whether the starting tiles are spawned on the board.
+    fn restart(&mut self, board: &[[u64; SIZE]; SIZE], spawn: bool) {
+        self.reset_to(board, 0);
+        self.undo_stack.clear();
+        self.move_log.clear();
+        self.score_history.clear();
+        self.history_truncated = false;
+        if let Some(tile_ids) = &mut self.tile_ids {
+            *tile_ids = [[None; SIZE]; SIZE];
+            self.next_tile_id = 1;
+        }
+        #[cfg(feature = "std")]
+        if let Some(timing) = &mut self.timing {
+            *timing = Timing::new(timing.clock.clone());
+        }
+        if spawn {
+            self.spawn_starting_tiles(self.starting_tiles.min(self.empty_cells));
+        }
+        if let Some(repetitions) = &mut self.repetitions {
+            repetitions.clear();
+            repetitions.record(symmetry::canonical_hash(&self.board));
+        }
+    }
+
+    /// Resets the game to the given board, reusing the existing game object.
+    /// Used internally by ```reset``` and by the simulated games of the AI instead of creating a new game for every simulation.
+    /// The board is not validated.
+    /// # Arguments
+    /// * ```board``` - The board to use.
+    /// * ```score``` - The score of the game.
+    fn reset_to(&mut self, board: &[[u64; SIZE]; SIZE], score: u64) {
+        self.board = *board;
+        self.empty_cells = heuristics::empty_cells(board);
+        self.score = score;
+        self.turns = 0;
+        self.merges = 0;
+        self.merge_histogram.clear();
+        self.best_merge = 0;
+        self.last_move_gain = 0;
+        self.victory_turn = None;
+        self.state = GameState::InProgress;
+        self.result = GameResult::Pending;
+        self.awaiting_tile = false;
+        self.has_blocked_cells = board.iter().flatten().any(|&tile| tile == BLOCKED_CELL);
+        self.update();
+    }
+
+    /// Spawns the starting tile if the board is empty.
+    /// # Returns
+    /// * ```Ok(())``` - The starting tile was spawned, or the board is not empty.
+    /// * ```Err(Error)``` - The board is empty, but the game can't spawn tiles (```Error::InvalidConfig```, see ```can_spawn```).
+    fn spawn_starting_tile(&mut self) -> Result<(), Error> {
+        if self.board.iter().flatten().all(|&tile| tile == 0) {
+            if !self.can_spawn() {
+                return Err(Error::InvalidConfig);
+            }
+            self.spawn_starting_tiles(1);
+        }
+        Ok(())
+    }
+
+    /// Spawns the given number of tiles on an empty board and starts the game.
+    /// # Arguments
+    /// * ```count``` - The number of tiles to spawn.
+    fn spawn_starting_tiles(&mut self, count: usize) {
+        self.starting_tiles = count;
+        for _ in 0..count {
+            self.new_tile();
+        }
+        // the empty board had no possible moves
+        self.state = GameState::InProgress;
+        self.result = GameResult::Pending;
+        self.update();
+    }
+
+    /// Sets the merge rule without validating the board against it.
+    /// A game that is not in the endless mode gets the default target of the rule (see ```MergeRule::default_target```).
+    /// # Arguments
+    /// * ```rule``` - The rule to use, ```None``` for the classic rules.
+    /// # Returns
+    /// * ```Game``` - The game with the rule set.
+    fn with_merge_rule_unchecked(mut self, rule: Option<Arc<dyn MergeRule>>) -> Self {
+        self.merge_rule = rule;
+        if self.target.is_some() {
+            self.target = Some(self.merge_rule().default_target(SIZE));
+        }
+        self.update();
+        self
+    }
+
+    /// Returns the default target tile of the classic rules for the size of the board.
+    /// The target is 2048 for boards of 4x4 and larger, 512 for 3x3 boards and 32 for 2x2 boards,
+    /// as 2048 can't be reached on the smaller boards.
+    fn default_target() -> u64 {
+        ClassicRule.default_target(SIZE)
+    }
+
+    /// Sets the tile that wins the game.
+    /// # Arguments
+    /// * ```target``` - The value of the target tile.
+    /// # Returns
+    /// * ```Ok(Game)``` - The game with the target set.
+    /// * ```Err(Error)``` - The target is invalid.
+    /// # Errors
+    /// * ```Error::InvalidConfig``` - The target is not a valid tile of the merge rule of the game.
+    pub fn with_target(mut self, target: u64) -> Result<Self, Error> {
+        if target == 0 || !self.merge_rule().is_valid_tile(target) {
+            return Err(Error::InvalidConfig);
+        }
+
+        self.target = Some(target);
+        self.result = GameResult::Pending;
+        self.update();
+        Ok(self)
+    }
+
+    /// Switches the game to the endless mode, in which there is no target tile and the game can't be won.
+    /// # Returns
+    /// * ```Game``` - The game in the endless mode.
+    pub fn with_endless_mode(mut self) -> Self {
+        self.target = None;
+        self.result = GameResult::Pending;
+        self.update();
+        self
+    }
+
+    /// Returns the tile that wins the game.
+    /// The default target is 2048 for boards of 4x4 and larger, 512 for 3x3 boards and 32 for 2x2 boards,
+    /// the games of other merge rules default to the target of the rule (see ```MergeRule::default_target```).
+    /// # Returns
+    /// * ```Some(u64)``` - The value of the target tile.
+    /// * ```None``` - The game is in the endless mode.
+    pub fn target(&self) -> Option<u64> {
+        self.target
+    }
+
+    /// Returns the number of turns after which the game is over (see ```GameBuilder::max_turns```).
+    /// # Returns
+    /// * ```Some(u64)``` - The limit of the turns.
+    /// * ```None``` - The number of turns is not limited.
+    pub fn max_turns(&self) -> Option<u64> {
+        self.max_turns
+    }
+
+    /// Returns the number of turns between two junk tiles (see ```GameBuilder::junk_every```).
+    /// # Returns
+    /// * ```Some(u64)``` - The period of the junk tiles.
+    /// * ```None``` - The junk mode is disabled.
+    pub fn junk_every(&self) -> Option<u64> {
+        self.junk_every
+    }
+
+    /// Checks whether the tile spawned after the given turn is a junk tile.
+    /// # Arguments
+    /// * ```turn``` - The turn, counted in this game.
+    /// # Returns
+    /// * ```true``` - The spawned tile is a junk tile.
+    /// * ```false``` - The spawned tile comes from the spawn distribution (the starting tiles always do).
+    fn junk_due(&self, turn: u64) -> bool {
+        let turn = self.junk_offset + turn;
+        self.junk_every.is_some_and(|every| turn > 0 && turn.is_multiple_of(every))
+    }
+
+    /// Creates a builder of a game with the given options.
+    /// # Returns
+    /// * ```GameBuilder``` - The builder with the default options, which build the same game as ```Game::new```.
+    pub fn builder() -> GameBuilder<SIZE> {
+        GameBuilder::default()
+    }
+
+    /// Returns the rule deciding which tiles merge.
+    pub fn merge_rule(&self) -> &dyn MergeRule {
+        self.merge_rule.as_deref().unwrap_or(&ClassicRule)
+    }
+
+    /// Checks whether the game uses the classic rules (the classic merges, the uniform spawn positions, the default spawn distribution and no junk tiles).
+    pub(crate) fn has_classic_rules(&self) -> bool {
+        self.merge_rule.is_none() && self.spawn_weight.is_none() && self.junk_every.is_none() && self.spawn_config == SpawnConfig::default()
+    }
+
+    /// Creates a game from an existing board without validating it.
+    /// Used internally by the searches and the spawners, which work with boards of already validated games
+    /// (these can also contain blocked cells, which ```from_existing``` rejects).
+    /// # Arguments
+    /// * ```board```: The board to use.
+    /// * ```score```: The score of the game.
+    /// # Returns
+    /// * ```Game```: The created game.
+    pub(crate) fn from_board_unchecked(board: &[[u64; SIZE]; SIZE], score: u64) -> Self {
+        let board = *board;
+        let score_next = [0; 4];
+        let moves = [true; 4];
+        let moves_next = [[[0; SIZE]; SIZE]; 4];
+        let state = GameState::InProgress;
+        let result = GameResult::Pending;
+
+        let mut game_object = Self {
+            board,
+            empty_cells: heuristics::empty_cells(&board),
+            starting_tiles: 1,
+            score,
+            score_next,
+            best_merge_next: [0; 4],
+            moves,
+            moves_next,
+            moves_built: [false; 4],
+            lazy_moves: false,
+            state,
+            result,
+            turns: 0,
+            tile_ids: None,
+            next_tile_id: 1,
+            observer: None,
+            #[cfg(feature = "std")]
+            subscribers: Vec::new(),
+            spawn_config: SpawnConfig::default(),
+            spawner: None,
+            awaiting_tile: false,
+            has_blocked_cells: board.iter().flatten().any(|&tile| tile == BLOCKED_CELL),
+            merge_rule: None,
+            spawn_weight: None,
+            target: Some(Self::default_target()),
+            max_turns: None,
+            puzzle: false,
+            junk_every: None,
+            junk_offset: 0,
+            rng: None,
+            merges: 0,
+            merge_histogram: BTreeMap::new(),
+            best_merge: 0,
+            last_move_gain: 0,
+            history_config: HistoryConfig::default(),
+            undo_stack: Ring::new(0),
+            move_log: Ring::new(0),
+            score_history: Ring::new(0),
+            history_truncated: false,
+            repetitions: None,
+            victory_turn: None,
+            #[cfg(feature = "std")]
+            timing: None,
+        };
+        game_object.update();
+
+        game_object
+    }
+
+    /// Returns the reference to the board.
+    /// The board is a square matrix filled with 0 for empty tiles and powers of 2 for filled tiles.
+    /// Blocked cells contain ```BLOCKED_CELL```.
+    /// # Returns
+    /// * ```&[[u64; SIZE]; SIZE]```: The board.
+    pub fn board(&self) -> &[[u64; SIZE]; SIZE] {
+        &self.board
+    }
+
+    /// Finds the difference between the board of the other game (the earlier position) and the board of this game.
+    /// # Arguments
+    /// * ```other``` - The game with the earlier position.
+    /// # Returns
+    /// * ```BoardDiff``` - The difference between the boards (see ```diff```).
+    pub fn diff_from(&self, other: &Game<SIZE>) -> BoardDiff {
+        let rows = |board: &[[u64; SIZE]; SIZE]| board.iter().map(|row| row.to_vec()).collect::<Vec<_>>();
+        diff(&rows(&other.board), &rows(&self.board)).expect("the boards of the same size have the same shape")
+    }
+
+    /// Returns a row of the board.
+    /// # Arguments
+    /// * ```i``` - The index of the row (from the top).
+    /// # Returns
+    /// * ```Some(&[u64])``` - The tiles of the row, from the left.
+    /// * ```None``` - The index is outside the board.
+    pub fn row(&self, i: usize) -> Option<&[u64]> {
+        self.board.get(i).map(|row| row.as_slice())
+    }
+
+    /// Returns a column of the board.
+    /// # Arguments
+    /// * ```j``` - The index of the column (from the left).
+    /// # Returns
+    /// * ```Some(Vec<u64>)``` - The tiles of the column, from the top.
+    /// * ```None``` - The index is outside the board.
+    pub fn column(&self, j: usize) -> Option<Vec<u64>> {
+        (j < SIZE).then(|| Self::column_tiles(&self.board, j).collect())
+    }
+
+    /// Returns the number of rows of the board.
+    /// # Returns
+    /// * ```usize``` - The number of rows (SIZE).
+    pub fn row_count(&self) -> usize {
+        SIZE
+    }
+
+    /// Returns the number of columns of the board.
+    /// # Returns
+    /// * ```usize``` - The number of columns (SIZE).
+    pub fn col_count(&self) -> usize {
+        SIZE
+    }
+
+    /// Iterates over the tiles of a column of the board, from the top.
+    /// # Arguments
+    /// * ```board``` - The board.
+    /// * ```col``` - The index of the column, must be inside the board.
+    /// # Returns
+    /// * ```impl DoubleEndedIterator<Item = u64>``` - The tiles of the column.
+    fn column_tiles(board: &[[u64; SIZE]; SIZE], col: usize) -> impl DoubleEndedIterator<Item = u64> + '_ {
+        board.iter().map(move |row| row[col])
+    }
+
+    /// Returns the board as the exponents of the tiles (0 for empty tiles, n for the tile of the value 2^n).
+    /// The compact form fits e.g. the storage of many positions, the board of a 4x4 game fits into 16 bytes.
+    /// The exponents are computed from the values on every call, the game doesn't store them.
+    /// # Returns
+    /// * ```Some([[u8; SIZE]; SIZE])``` - The exponents of the tiles.
+    /// * ```None``` - The board contains a tile that is not a power of 2 (a blocked cell or a tile of other merge rules).
+    pub fn board_exponents(&self) -> Option<[[u8; SIZE]; SIZE]> {
+        let mut exponents = [[0; SIZE]; SIZE];
+        for (exponent, &tile) in exponents.iter_mut().flatten().zip(self.board.iter().flatten()) {
+            if tile != 0 {
+                if !tile.is_power_of_two() {
+                    return None;
+                }
+                *exponent = tile.trailing_zeros() as u8;
+            }
+        }
+        Some(exponents)
+    }
+
+    /// Returns the result of the game.
+    /// The game can continue after a victory, so the state of a won game is ```GameState::InProgress```
+    /// until there are no valid moves left. Once the state is ```GameState::GameOver```, all moves are rejected.
+    /// # Returns
+    /// * ```Result::Victory```: The game is won, the target tile was reached.
+    /// * ```Result::Pending```: The game is in progress, the target tile is not reached yet.
+    /// * ```Result::Loss```: The game is over, the target tile was not reached.
+    /// * ```Result::VictoryThenLoss```: The game was won, continued, and is now over.
+    pub fn result(&self) -> GameResult {
+        self.result
+    }
+
+    /// Returns the score of the game.
+    /// # Returns
+    /// * ```u64```: The score of the game.
+    pub fn score(&self) -> u64 {
+        self.score
+    }
+
+    /// Returns the value of the highest tile on the board.
+    /// # Returns
+    /// * ```u64```: The value of the highest tile, 0 if the board is empty. Junk tiles are not counted.
+    pub fn highest_tile(&self) -> u64 {
+        self.board
+            .iter()
+            .flatten()
+            .copied()
+            .filter(|&tile| tile != BLOCKED_CELL && tile != JUNK_TILE)
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Returns the number of empty cells of the board. The blocked cells are not empty.
+    /// # Returns
+    /// * ```usize```: The number of empty cells.
+    pub fn empty_count(&self) -> usize {
+        self.empty_cells
+    }
+
+    /// Checks whether the board has no empty cells.
+    /// A full board may still have possible moves, if some tiles can merge.
+    /// # Returns
+    /// * ```true```: The board is full.
+    /// * ```false```: Some cell is empty.
+    pub fn is_full(&self) -> bool {
+        self.empty_cells == 0
+    }
+
+    /// Evaluates the board with the heuristics (see ```heuristics::composite```), e.g. for the leaves of an external search.
+    /// The value only compares the boards, it has no unit and doesn't include the score.
+    /// # Arguments
+    /// * ```weights``` - The weights of the heuristics, ```HeuristicWeights::default``` for the tuned ones.
+    /// # Returns
+    /// * ```f64```: The weighted sum of the heuristics, higher for the better boards.
+    pub fn evaluate(&self, weights: &HeuristicWeights) -> f64 {
+        heuristics::composite(&self.board, weights)
+    }
+
+    /// Blocks the given cells of the board.
+    /// Blocked cells never move, tiles can't pass through them or merge with them, and no tile is spawned on them.
+    /// On the board, they contain ```BLOCKED_CELL```.
+    /// # Arguments
+    /// * ```cells```: The positions (row, column) of the cells to block.
+    /// # Returns
+    /// * ```Ok(Game)```: The game with the cells blocked.
+    /// * ```Err(Error)```: Some cell can't be blocked.
+    /// # Errors
+    /// * ```Error::InvalidPosition```: Some cell is outside the board or not empty.
+    pub fn with_blocked_cells(mut self, cells: &[(usize, usize)]) -> Result<Self, Error> {
+        if cells.iter().any(|&(row, col)| row >= SIZE || col >= SIZE || self.board[row][col] != 0) {
+            return Err(Error::InvalidPosition);
+        }
+
+        for &(row, col) in cells {
+            self.board[row][col] = BLOCKED_CELL;
+        }
+        self.empty_cells = heuristics::empty_cells(&self.board);
+        self.has_blocked_cells |= !cells.is_empty();
+        self.update();
+        Ok(self)
+    }
+
+    /// Rotates the board (with the tile identities and the blocked cells) clockwise by 90 degrees.
+    pub fn rotate_cw(&mut self) {
+        self.transform(symmetry::rotate_cw, symmetry::rotate_cw);
+    }
+
+    /// Rotates the board (with the tile identities and the blocked cells) counterclockwise by 90 degrees.
+    pub fn rotate_ccw(&mut self) {
+        self.transform(symmetry::rotate_ccw, symmetry::rotate_ccw);
+    }
+
+    /// Mirrors the board (with the tile identities and the blocked cells) horizontally.
+    pub fn flip_horizontal(&mut self) {
+        self.transform(symmetry::flip_horizontal, symmetry::flip_horizontal);
+    }
+
+    /// Mirrors the board (with the tile identities and the blocked cells) vertically.
+    pub fn flip_vertical(&mut self) {
+        self.transform(symmetry::flip_vertical, symmetry::flip_vertical);
+    }
+
+    /// Hashes the board so that all its symmetric variants (rotations and mirror images) have the same hash.
+    /// The hash is the same on every platform and in every run (see ```symmetry::canonical_hash```).
+    /// # Returns
+    /// * ```u64``` - The hash of the board.
+    pub fn canonical_hash(&self) -> u64 {
+        symmetry::canonical_hash(&self.board)
+    }
+
+    /// Transforms the board and the tile identities and refreshes the possible moves.
+    /// # Arguments
+    /// * ```board_transform``` - The transformation of the board.
+    /// * ```ids_transform``` - The same transformation of the tile identities.
+    fn transform(&mut self, board_transform: fn(&[[u64; SIZE]; SIZE]) -> [[u64; SIZE]; SIZE], ids_transform: fn(&TileIds<SIZE>) -> TileIds<SIZE>) {
+        self.board = board_transform(&self.board);
+        if let Some(tile_ids) = &mut self.tile_ids {
+            *tile_ids = ids_transform(tile_ids);
+        }
+        self.update();
+        if self.awaiting_tile {
+            // the tile has to be placed before the next move
+            self.moves = [false; 4];
+        }
+    }
+
+    /// Sets the value of a cell, e.g. in a puzzle editor, and recomputes the state of the game (see ```recompute```).
+    /// The edit is not a move, so the turns, the score and the history are kept.
+    /// # Arguments
+    /// * ```row```: The row of the cell.
+    /// * ```col```: The column of the cell.
+    /// * ```value```: The new value of the cell, 0 for an empty cell.
+    /// # Returns
+    /// * ```Ok(())``` - The cell was set.
+    /// * ```Err(Error)``` - The cell was not set, the game is left untouched.
+    /// # Errors
+    /// * ```Error::WrongTurn```: A tile has to be placed first (see ```make_move_without_spawn```).
+    /// * ```Error::InvalidPosition```: The position is outside the board or the cell is blocked.
+    /// * ```Error::InvalidValue```: The value is not 0, ```JUNK_TILE``` or a valid tile of the merge rule.
+    pub fn set_tile(&mut self, row: usize, col: usize, value: u64) -> Result<(), Error> {
+        if row >= SIZE || col >= SIZE || self.board[row][col] == BLOCKED_CELL {
+            return Err(Error::InvalidPosition);
+        }
+        if value == BLOCKED_CELL {
+            return Err(Error::InvalidValue { row, col, value });
+        }
+        let mut board = self.board;
+        board[row][col] = value;
+        self.apply_board(&board)
+    }
+
+    /// Recomputes everything derived from the board after it was edited:
+    /// the empty cells, the possible moves, the state, the result (and the turn of the victory) and the tile identities.
+    /// A game that was over continues if the board allows a move again, but a reached victory is kept.
+    /// # Returns
+    /// * ```Ok(())``` - The state was recomputed.
+    /// * ```Err(Error)``` - The board is invalid, the game is left untouched.
+    /// # Errors
+    /// * ```Error::WrongTurn```: A tile has to be placed first (see ```make_move_without_spawn```).
+    /// * ```Error::InvalidValue```: The board contains a value that is not 0, a blocked cell, a junk tile or a valid tile of the merge rule.
+    ///   The error contains the first invalid tile (row by row).
+    pub fn recompute(&mut self) -> Result<(), Error> {
+        let board = self.board;
+        self.apply_board(&board)
+    }
+
+    /// Validates the edited board and, if it is valid, replaces the board with it and recomputes the state of the game.
+    /// # Arguments
+    /// * ```board``` - The edited board.
+    /// # Returns
+    /// * ```Ok(())``` - The board was applied.
+    /// * ```Err(Error)``` - The board is invalid or a tile has to be placed first, the game is left untouched.
+    fn apply_board(&mut self, board: &[[u64; SIZE]; SIZE]) -> Result<(), Error> {
+        if self.awaiting_tile {
+            return Err(Error::WrongTurn);
+        }
+        let rule = self.merge_rule();
+        for (row, tiles) in board.iter().enumerate() {
+            for (col, &value) in tiles.iter().enumerate() {
+                if value != 0 && value != BLOCKED_CELL && value != JUNK_TILE && !rule.is_valid_tile(value) {
+                    return Err(Error::InvalidValue { row, col, value });
+                }
+            }
+        }
+
+        self.board = *board;
+        self.empty_cells = heuristics::empty_cells(board);
+        self.has_blocked_cells = board.iter().flatten().any(|&tile| tile == BLOCKED_CELL);
+        if self.tile_ids.is_some() {
+            for cell in 0..SIZE * SIZE {
+                let (row, col) = (cell / SIZE, cell % SIZE);
+                let occupied = board[row][col] != 0 && board[row][col] != BLOCKED_CELL;
+                let missing = self.tile_ids.as_ref().is_some_and(|tile_ids| tile_ids[row][col].is_none());
+                let tile_id = match (occupied, missing) {
+                    (true, true) => Some(self.new_tile_id()),
+                    (true, false) => continue,
+                    (false, _) => None,
+                };
+                if let Some(tile_ids) = &mut self.tile_ids {
+                    tile_ids[row][col] = tile_id;
+                }
+            }
+        }
+
+        // the state and the result are derived again, only a reached victory stays
+        let result_before = self.result;
+        self.state = GameState::InProgress;
+        self.result = if self.victory_turn.is_some() { GameResult::Victory } else { GameResult::Pending };
+        self.update();
+        // an edit is not a move, so a won game that is still over keeps its previous result
+        if result_before == GameResult::VictoryThenLoss && self.state == GameState::GameOver {
+            self.result = GameResult::VictoryThenLoss;
+        }
+        Ok(())
+    }
+
+    /// Returns the positions of the blocked cells.
+    /// # Returns
+    /// * ```Vec<(usize, usize)>```: The positions (row, column) of the blocked cells.
+    pub fn blocked_cells(&self) -> Vec<(usize, usize)> {
+        (0..SIZE * SIZE)
+            .map(|cell| (cell / SIZE, cell % SIZE))
+            .filter(|&(row, col)| self.board[row][col] == BLOCKED_CELL)
+            .collect()
+    }
+
+    /// Returns the number of moves made in the game.
+    /// # Returns
+    /// * ```u64```: The number of moves made.
+    pub fn turns(&self) -> u64 {
+        self.turns
+    }
+
+    /// Checks whether a move can be made.
+    /// # Arguments
+    /// * ```direction```: The direction to check.
+    /// # Returns
+    /// * ```true``` - The move is possible.
+    /// * ```false``` - The move is impossible, it would not change the board.
+    pub fn can_move(&self, direction: GameMove) -> bool {
+        self.moves[direction.index()]
+    }
+
+    /// Checks why a move can't be made, e.g. to tell a player that the game is over instead of that the move does nothing.
+    /// # Arguments
+    /// * ```direction```: The direction to check.
+    /// # Returns
+    /// * ```None``` - The move can be made.
+    /// * ```Some(InvalidReason)``` - The reason why the move can't be made.
+    pub fn why_invalid(&self, direction: GameMove) -> Option<InvalidReason> {
+        if self.awaiting_tile {
+            Some(InvalidReason::AwaitingTile)
+        } else if self.state == GameState::GameOver {
+            Some(InvalidReason::GameFinished)
+        } else if !self.puzzle && !self.can_spawn() {
+            Some(InvalidReason::Unseeded)
+        } else if !self.can_move(direction) {
+            Some(InvalidReason::NoEffect)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the result of a move without making it.
+    /// The returned board is the board after sliding and merging the tiles, before the new tile is spawned.
+    /// # Arguments
+    /// * ```direction```: The direction of the move.
+    /// # Returns
+    /// * ```Some(([[u64; SIZE]; SIZE], u64))``` - The board after the move and the score the move would gain.
+    /// * ```None``` - The move is impossible.
+    pub fn peek_move(&self, direction: GameMove) -> Option<([[u64; SIZE]; SIZE], u64)> {
+        let ind = direction.index();
+        if !self.moves[ind] {
+            None
+        } else if self.moves_built[ind] {
+            Some((self.moves_next[ind], self.score_next[ind]))
+        } else {
+            let (board, gained, _) = self.build_move(direction);
+            Some((board, gained))
+        }
+    }
+
+    /// Returns the size of the board.
+    /// # Returns
+    /// * ```usize```: The size of the board. The board is ```usize```x```usize```.
+    pub fn size(&self) -> usize {
+        SIZE
+    }
+
+    /// Returns the state of the game.
+    /// # Returns
+    /// * ```State::InProgress```: The game is in progress.
+    /// * ```State::GameOver```: The game is over.
+    pub fn state(&self) -> GameState {
+        self.state
+    }
+
+    /// Sets the distribution of the values of the spawned tiles.
+    /// The tiles already on the board are kept, the distribution applies to the following spawns
+    /// and to the simulated games of the AI.
+    /// The values should be valid tiles of the merge rule of the game.
+    /// # Arguments
+    /// * ```spawn_config``` - The distribution to use.
+    /// # Returns
+    /// * ```Game``` - The game with the distribution set.
+    pub fn with_spawn_config(mut self, spawn_config: SpawnConfig) -> Self {
+        self.spawn_config = spawn_config;
+        self
+    }
+
+    /// Returns the distribution of the values of the spawned tiles.
+    pub fn spawn_config(&self) -> &SpawnConfig {
+        &self.spawn_config
+    }
+
+    /// Sets the policy weighting the empty cells where the new tiles are spawned (see ```GameBuilder::spawn_weight```).
+    /// # Arguments
+    /// * ```policy``` - The policy.
+    /// # Returns
+    /// * ```Game``` - The game with the policy set.
+    pub fn with_spawn_weight(mut self, policy: Arc<dyn SpawnPositionWeight<SIZE>>) -> Self {
+        self.spawn_weight = Some(policy);
+        self
+    }
+
+    /// Seeds the random number generator of the spawned tiles, replacing the current one.
+    /// Without the ```std``` feature, the games that don't have a spawner have to be seeded before making the moves,
+    /// e.g. the games created with ```from_existing```.
+    /// # Arguments
+    /// * ```seed``` - The seed.
+    /// # Returns
+    /// * ```Game``` - The seeded game.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.rng = Some(StdRng::seed_from_u64(seed));
+        self
+    }
+
+    /// Checks whether the game has a source of the spawned tiles.
+    /// Without the ```std``` feature, only the seeded games and the games with a spawner have one.
+    /// # Returns
+    /// * ```true``` - The game can spawn tiles.
+    /// * ```false``` - The game can't spawn tiles until it is seeded (see ```with_seed```).
+    fn can_spawn(&self) -> bool {
+        cfg!(feature = "std") || self.rng.is_some() || self.spawner.is_some()
+    }
+
+    /// Sets the spawner that places the new tiles after every move.
+    /// The tiles already on the board are kept.
+    /// The simulated games of the AI don't use the spawner, they spawn the tiles randomly from the spawn distribution.
+    /// # Arguments
+    /// * ```spawner``` - The spawner to use.
+    /// # Returns
+    /// * ```Game``` - The game with the spawner set.
+    pub fn with_spawner(mut self, spawner: Box<dyn TileSpawner<SIZE>>) -> Self {
+        self.spawner = Some(spawner);
+        self
+    }
+
+    /// Removes the spawner, the following tiles are spawned randomly.
+    /// # Returns
+    /// * ```Some(Box<dyn TileSpawner<SIZE>>)``` - The removed spawner.
+    /// * ```None``` - No spawner was set.
+    pub fn remove_spawner(&mut self) -> Option<Box<dyn TileSpawner<SIZE>>> {
+        self.spawner.take()
+    }
+
+    /// Enables tile identity tracking.
+    /// Every tile on the board gets an identity which it keeps while it slides around the board.
+    /// A merge consumes the identities of both merged tiles and produces a new one.
+    /// Identities are never reused within a game.
+    /// # Returns
+    /// * ```Game``` - The game with tile tracking enabled.
+    pub fn with_tile_ids(mut self) -> Self {
+        if self.tile_ids.is_none() {
+            let mut tile_ids = [[None; SIZE]; SIZE];
+            for (row, ids_row) in self.board.iter().zip(tile_ids.iter_mut()) {
+                for (&tile, tile_id) in row.iter().zip(ids_row.iter_mut()) {
+                    if tile != 0 && tile != BLOCKED_CELL {
+                        *tile_id = NonZeroU64::new(self.next_tile_id);
+                        self.next_tile_id += 1;
+                    }
+                }
+            }
+            self.tile_ids = Some(tile_ids);
+        }
+        self
+    }
+
+    /// Returns the identities of the tiles.
+    /// The returned matrix is parallel to the board, every non-empty tile has an identity.
+    /// # Returns
+    /// * ```Some(&[[Option<NonZeroU64>; SIZE]; SIZE])``` - The identities of the tiles.
+    /// * ```None``` - Tile tracking is not enabled.
+    pub fn tile_ids(&self) -> Option<&[[Option<NonZeroU64>; SIZE]; SIZE]> {
+        self.tile_ids.as_ref()
+    }
+
+    /// Returns the largest tile created by a merge so far.
+    /// Only the merges made in this game object are counted, the games created from an existing board start with 0.
+    /// # Returns
+    /// * ```u64``` - The value of the largest merged tile, 0 if no tiles were merged yet.
+    pub fn best_merge(&self) -> u64 {
+        self.best_merge
+    }
+
+    /// Counts the tiles of each value on the board. The empty and the blocked cells are not counted.
+    /// # Returns
+    /// * ```BTreeMap<u64, usize>``` - The number of tiles of each value.
+    pub fn tile_histogram(&self) -> BTreeMap<u64, usize> {
+        let mut histogram = BTreeMap::new();
+        for &tile in self.board.iter().flatten().filter(|&&tile| tile != 0 && tile != BLOCKED_CELL) {
+            *histogram.entry(tile).or_insert(0) += 1;
+        }
+        histogram
+    }
+
+    /// Returns how many times each tile value was created by a merge.
+    /// Like ```best_merge```, only the merges made in this game object are counted.
+    /// # Returns
+    /// * ```&BTreeMap<u64, u64>``` - The number of merges that created each value.
+    pub fn merge_histogram(&self) -> &BTreeMap<u64, u64> {
+        &self.merge_histogram
+    }
+
+    /// Returns the score gained by the last move.
+    /// # Returns
+    /// * ```u64``` - The score gained by the last move, 0 if no move was made yet.
+    pub fn last_move_gain(&self) -> u64 {
+        self.last_move_gain
+    }
+
+    /// Enables the score history, which records the score gained by every following move.
+    /// Only the latest ```HistoryConfig::max_log``` moves are kept (see ```with_history```).
+    /// # Returns
+    /// * ```Game``` - The game with the score history enabled.
+    pub fn with_score_history(self) -> Self {
+        let history = HistoryConfig {
+            keep_score_log: true,
+            ..self.history_config
+        };
+        self.with_history(history)
+    }
+
+    /// Sets the retention of the history: the number of the latest moves that can be undone (see ```undo```)
+    /// and whether the moves and the scores gained by them are logged (see ```move_log``` and ```score_history```).
+    /// The history is kept in ring buffers, so the oldest entries are discarded once the configured sizes are reached (see ```history_truncated```).
+    /// The entries already in the history are kept, unless they don't fit into the new sizes.
+    /// # Arguments
+    /// * ```history``` - The retention of the history.
+    /// # Returns
+    /// * ```Game``` - The game with the history configured.
+    pub fn with_history(mut self, history: HistoryConfig) -> Self {
+        self.history_config = history;
+        let discarded = [
+            self.undo_stack.set_capacity(history.max_undo),
+            self.move_log.set_capacity(if history.keep_move_log { history.max_log } else { 0 }),
+            self.score_history.set_capacity(if history.keep_score_log { history.max_log } else { 0 }),
+        ];
+        self.history_truncated |= discarded.contains(&true);
+        self
+    }
+
+    /// Returns the retention of the history (see ```with_history```).
+    /// # Returns
+    /// * ```HistoryConfig``` - The retention of the history.
+    pub fn history_config(&self) -> HistoryConfig {
+        self.history_config
+    }
+
+    /// Enables the tracking of the repeated positions, e.g. for the bots detecting that a strategy loops with the undo.
+    /// The game remembers the canonical hashes (see ```symmetry::canonical_hash```) of the latest positions reached by the moves,
+    /// starting with the current position, and reports every move that reaches a position for at least the threshold-th time
+    /// (```GameObserver::on_repetition``` and ```GameEvent::Repetition```). The oldest positions are forgotten once
+    /// the configured number of positions is remembered. Undoing a move doesn't forget its position, so making it again repeats it.
+    /// Enabling the tracking again starts it over.
+    /// # Arguments
+    /// * ```config``` - The number of the remembered positions and the threshold of the reported repetitions.
+    /// # Returns
+    /// * ```Game``` - The game with the repetition tracking.
+    pub fn with_repetition_tracking(mut self, config: RepetitionConfig) -> Self {
+        let mut repetitions = Repetitions::new(config);
+        repetitions.record(symmetry::canonical_hash(&self.board));
+        self.repetitions = Some(repetitions);
+        self
+    }
+
+    /// Returns how many times the current position occurred among the remembered positions (see ```with_repetition_tracking```).
+    /// # Returns
+    /// * ```usize``` - The number of occurrences of the current position, 0 if the repetition tracking is not enabled.
+    pub fn repetitions(&self) -> usize {
+        self.repetitions.as_ref().map_or(0, |repetitions| repetitions.count(symmetry::canonical_hash(&self.board)))
+    }
+
+    /// Checks whether some older entries of the history were discarded, because the undo or the logs reached their configured sizes.
+    /// The moves before the discarded entries can't be undone and the logs don't start with the first move of the game.
+    /// # Returns
+    /// * ```true``` - Some entries were discarded.
+    /// * ```false``` - The history is complete (since it was enabled or since the game was reset).
+    pub fn history_truncated(&self) -> bool {
+        self.history_truncated
+    }
+
+    /// Returns the latest moves, if the move log is enabled (see ```with_history```).
+    /// # Returns
+    /// * ```impl ExactSizeIterator<Item = GameMove>``` - The moves, from the oldest to the latest kept one. Empty if the move log is not enabled.
+    pub fn move_log(&self) -> impl ExactSizeIterator<Item = GameMove> + '_ {
+        self.move_log.iter().copied()
+    }
+
+    /// Returns the number of moves that can be undone (see ```undo```).
+    /// # Returns
+    /// * ```usize``` - The number of moves, at most ```HistoryConfig::max_undo```.
+    pub fn undo_depth(&self) -> usize {
+        self.undo_stack.len()
+    }
+
+    /// Undoes the latest move, including the spawned tile, and removes it from the logs.
+    /// The game returns to the state before the move: the board, the score, the counters, the tile identities
+    /// and the random number generator of a seeded game, so repeating the move spawns the same tile.
+    /// The observer and the subscribers are not notified.
+    /// # Returns
+    /// * ```true``` - The move was undone.
+    /// * ```false``` - No move can be undone (the undo is disabled, or all kept moves were undone).
+    pub fn undo(&mut self) -> bool {
+        let Some(snapshot) = self.undo_stack.pop() else {
+            return false;
+        };
+        self.move_log.pop();
+        self.score_history.pop();
+
+        self.board = snapshot.board;
+        self.empty_cells = heuristics::empty_cells(&self.board);
+        self.score = snapshot.score;
+        self.turns = snapshot.turns;
+        self.merges = snapshot.merges;
+        self.merge_histogram = snapshot.merge_histogram;
+        self.best_merge = snapshot.best_merge;
+        self.last_move_gain = snapshot.last_move_gain;
+        self.victory_turn = snapshot.victory_turn;
+        self.tile_ids = snapshot.tile_ids;
+        self.next_tile_id = snapshot.next_tile_id;
+        self.rng = snapshot.rng;
+        self.state = snapshot.state;
+        self.result = snapshot.result;
+        self.awaiting_tile = false;
+        self.update();
+
+        #[cfg(feature = "std")]
+        if let Some(timing) = &mut self.timing {
+            timing.finished = None;
+        }
+        true
+    }
+
+    /// Makes the game build the boards after the moves only when they are needed (by ```make_move```, ```peek_move``` and the others),
+    /// instead of after every move. Only the cheap checks of the valid moves run after every move,
+    /// which is faster on the large boards when the game is mostly queried with ```can_move```.
+    /// The boards built by ```peek_move``` are not kept, the ones built by the moves are.
+    /// # Returns
+    /// * ```Game``` - The game with the lazy moves.
+    pub fn with_lazy_moves(mut self) -> Self {
+        self.lazy_moves = true;
+        self
+    }
+
+    /// Returns the score gained by the latest moves made since the score history was enabled (see ```with_score_history```).
+    /// # Returns
+    /// * ```impl ExactSizeIterator<Item = u64>``` - The score gained by the moves, in the order they were made. Empty if the score history is not enabled.
+    pub fn score_history(&self) -> impl ExactSizeIterator<Item = u64> + '_ {
+        self.score_history.iter().copied()
+    }
+
+    /// Returns all possible outcomes of a move, including the spawned tile.
+    /// For every empty cell of the board after the move, the outcome with every value of the spawn distribution (see ```with_spawn_config```) is returned,
+    /// together with its probability (the probability of the value / number of empty cells, e.g. 0.9 / number of empty cells for a 2 by default).
+    /// The outcomes are generated lazily.
+    /// # Arguments
+    /// * ```direction```: The direction of the move.
+    /// # Returns
+    /// * ```Some(impl Iterator<Item = ([[u64; SIZE]; SIZE], f64)>)``` - The possible boards after the move and their probabilities.
+    /// * ```None``` - The move is impossible.
+    pub fn expand(&self, direction: GameMove) -> Option<impl Iterator<Item = ([[u64; SIZE]; SIZE], f64)>> {
+        let (board, _) = self.peek_move(direction)?;
+        let spawned = self.spawns(board).map(move |(i, j, value, probability)| {
+            let mut next_board = board;
+            next_board[i][j] = value;
+            (next_board, probability)
+        });
+        // the moves of a puzzle don't spawn any tile
+        Some(spawned.chain(self.puzzle.then_some((board, 1.0))))
+    }
+
+    /// Returns the distribution of the next spawned tile on the current board.
+    /// For every empty cell, the probability that the next tile is spawned there with every value of the spawn distribution (see ```with_spawn_config```) is returned,
+    /// the probabilities sum to 1. The tiles placed by a spawner (see ```with_spawner```) don't follow this distribution.
+    /// # Returns
+    /// * ```impl Iterator<Item = (usize, usize, u64, f64)>``` - The row and the column of the cell, the value of the tile and its probability.
+    ///   Empty if the board is full.
+    pub fn spawn_distribution(&self) -> impl Iterator<Item = (usize, usize, u64, f64)> {
+        self.spawns(self.board)
+    }
+
+    /// Returns the possible spawned tiles on the board and their probabilities (see ```spawn_distribution```).
+    /// The cells that the spawn position policy never chooses are left out, and the puzzles spawn no tiles.
+    /// # Arguments
+    /// * ```board``` - The board to spawn the tile on.
+    /// # Returns
+    /// * ```impl Iterator<Item = (usize, usize, u64, f64)>``` - The row and the column of the cell, the value of the tile and its probability.
+    fn spawns(&self, board: [[u64; SIZE]; SIZE]) -> impl Iterator<Item = (usize, usize, u64, f64)> {
+        let empty_count = board.iter().flatten().filter(|&&tile| tile == 0).count() as f64;
+        let positions = self.spawn_weight.as_deref().and_then(|policy| position_probabilities(policy, &board));
+        // the tile spawned after the next turn is either junk or from the spawn distribution
+        let values: Vec<(u64, f64)> = if self.puzzle {
+            Vec::new()
+        } else if self.junk_due(self.turns + 1) {
+            Vec::from([(JUNK_TILE, 1.0)])
+        } else {
+            self.spawn_config.values().iter().copied().filter(|&(_, probability)| probability > 0.0).collect()
+        };
+
+        (0..SIZE * SIZE)
+            .map(|cell| (cell / SIZE, cell % SIZE))
+            .filter(move |&(i, j)| board[i][j] == 0)
+            .zip(0..)
+            .map(move |(position, ind)| (position, positions.as_ref().map_or(1.0 / empty_count, |positions| positions[ind])))
+            .filter(|&(_, position_probability)| position_probability > 0.0)
+            .flat_map(move |((i, j), position_probability)| {
+                values
+                    .clone()
+                    .into_iter()
+                    .map(move |(value, probability)| (i, j, value, probability * position_probability))
+            })
+    }
+
+    /// Make a move in the game.
+    /// Tiles of the value 2^63 don't merge, since the merged tile wouldn't fit into a ```u64```,
+    /// and the score saturates at ```u64::MAX```.
+    /// # Arguments
+    /// * ```direction```: The direction to move in.
+    /// # Returns
+    /// * ```true``` - The move was successful.
+    /// * ```false``` - The move was invalid/impossible (or a tile has to be placed first, see ```make_move_without_spawn```).
+    ///   See ```why_invalid``` for the reason.
+    pub fn make_move(&mut self, direction: GameMove) -> bool {
+        if !self.puzzle && !self.can_spawn() {
+            return false;
+        }
+        if self.slide(direction) {
+            let spawned = (!self.puzzle).then(|| self.new_tile());
+            self.finish_turn(spawned);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Replays the recorded moves, e.g. a ```MoveLog``` or the moves of ```move_log```.
+    /// The spawned tiles come from the generator of the game, so a seeded game replays the recorded game exactly.
+    /// # Arguments
+    /// * ```moves``` - The moves, in the order they were made.
+    /// # Returns
+    /// * ```Ok(usize)``` - The number of the replayed moves.
+    /// * ```Err(Error)``` - A move couldn't be made. The moves before it stay made.
+    /// # Errors
+    /// * ```Error::InvalidMove``` - A move is impossible, it doesn't change the board.
+    /// * ```Error::WrongTurn``` - A tile has to be placed first (see ```make_move_without_spawn```).
+    /// * ```Error::InvalidConfig``` - Without the ```std``` feature, the game is not seeded (see ```with_seed```).
+    pub fn replay(&mut self, moves: impl IntoIterator<Item = GameMove>) -> Result<usize, Error> {
+        if self.awaiting_tile {
+            return Err(Error::WrongTurn);
+        }
+        if !self.puzzle && !self.can_spawn() {
+            return Err(Error::InvalidConfig);
+        }
+        let mut count = 0;
+        for direction in moves {
+            if !self.make_move(direction) {
+                return Err(Error::InvalidMove);
+            }
+            count += 1;
+        }
+        Ok(count)
+    }
+
+    /// Begins a move, which is made only when the returned pending move is committed.
+    /// Used by the animated frontends, which show the slide first and spawn the new tile afterwards.
+    /// The pending move borrows the game mutably, so no other move can be made until it is committed or cancelled.
+    /// # Arguments
+    /// * ```direction```: The direction to move in.
+    /// # Returns
+    /// * ```Ok(PendingMove)``` - The pending move, showing the board and the score after the slide.
+    /// * ```Err(Error)``` - The move can't be made.
+    /// # Errors
+    /// * ```Error::NoValidMove```: The game is over.
+    /// * ```Error::InvalidMove```: The move is impossible, it doesn't change the board.
+    /// * ```Error::WrongTurn```: A tile has to be placed first (see ```make_move_without_spawn```).
+    /// * ```Error::InvalidConfig```: The game is a puzzle, whose moves don't spawn tiles (see ```Game::puzzle```),
+    ///   or, without the ```std``` feature, the game is not seeded (see ```with_seed```).
+    pub fn begin_move(&mut self, direction: GameMove) -> Result<PendingMove<'_, SIZE>, Error> {
+        if self.puzzle || !self.can_spawn() {
+            return Err(Error::InvalidConfig);
+        }
+        if self.awaiting_tile {
+            return Err(Error::WrongTurn);
+        }
+        if self.state == GameState::GameOver {
+            return Err(Error::NoValidMove);
+        }
+        if !self.moves[direction.index()] {
+            return Err(Error::InvalidMove);
+        }
+        self.store_move(direction);
+
+        Ok(PendingMove { game: self, direction })
+    }
+
+    /// Make a move in the game without spawning a new tile.
+    /// Used for the two-player mode, where the other player places the new tile with ```place_tile```.
+    /// No moves can be made until the tile is placed.
+    /// # Arguments
+    /// * ```direction```: The direction to move in.
+    /// # Returns
+    /// * ```Ok(true)``` - The move was successful.
+    /// * ```Ok(false)``` - The move was invalid/impossible.
+    /// * ```Err(Error)``` - It is not the turn of the moving player.
+    /// # Errors
+    /// * ```Error::WrongTurn```: The tile from the previous move hasn't been placed yet.
+    pub fn make_move_without_spawn(&mut self, direction: GameMove) -> Result<bool, Error> {
+        if self.awaiting_tile {
+            return Err(Error::WrongTurn);
+        }
+        if !self.slide(direction) {
+            return Ok(false);
+        }
+
+        self.awaiting_tile = true;
+        self.moves = [false; 4];
+        Ok(true)
+    }
+
+    /// Places the tile after a move made with ```make_move_without_spawn```.
+    /// # Arguments
+    /// * ```row```: The row of the tile.
+    /// * ```col```: The column of the tile.
+    /// * ```value```: The value of the tile. Must be 2 or 4.
+    /// # Returns
+    /// * ```Ok(())``` - The tile was placed.
+    /// * ```Err(Error)``` - The tile was not placed.
+    /// # Errors
+    /// * ```Error::WrongTurn```: No tile is expected, a move has to be made first.
+    /// * ```Error::InvalidPosition```: The position is outside the board or not empty.
+    /// * ```Error::InvalidValue```: The value is not 2 or 4.
+    pub fn place_tile(&mut self, row: usize, col: usize, value: u64) -> Result<(), Error> {
+        self.place_checked_tile(row, col, value, false)
+    }
+
+    /// Places the tile after a move made with ```make_move_without_spawn``` like ```place_tile```,
+    /// but accepts every tile a game can spawn: the valid tiles of the merge rule and the junk tiles.
+    /// Used to replay the recorded games.
+    /// # Arguments
+    /// * ```row```: The row of the tile.
+    /// * ```col```: The column of the tile.
+    /// * ```value```: The value of the tile.
+    /// # Returns
+    /// * ```Ok(())``` - The tile was placed.
+    /// * ```Err(Error)``` - The tile was not placed, see ```place_tile```.
+    #[cfg(feature = "std")]
+    pub(crate) fn place_spawned_tile(&mut self, row: usize, col: usize, value: u64) -> Result<(), Error> {
+        self.place_checked_tile(row, col, value, true)
+    }
+
+    /// Places the tile after a move made with ```make_move_without_spawn```.
+    /// # Arguments
+    /// * ```row```: The row of the tile.
+    /// * ```col```: The column of the tile.
+    /// * ```value```: The value of the tile.
+    /// * ```any_tile```: Whether any valid tile of the merge rule or a junk tile is accepted, instead of only 2 and 4.
+    /// # Returns
+    /// * ```Ok(())``` - The tile was placed.
+    /// * ```Err(Error)``` - The tile was not placed, see ```place_tile```.
+    fn place_checked_tile(&mut self, row: usize, col: usize, value: u64, any_tile: bool) -> Result<(), Error> {
+        if !self.awaiting_tile {
+            return Err(Error::WrongTurn);
+        }
+        if row >= SIZE || col >= SIZE || self.board[row][col] != 0 {
+            return Err(Error::InvalidPosition);
+        }
+        let valid = if any_tile {
+            value == JUNK_TILE || self.merge_rule().is_valid_tile(value)
+        } else {
+            value == 2 || value == 4
+        };
+        if !valid {
+            return Err(Error::InvalidValue { row, col, value });
+        }
+
+        self.board[row][col] = value;
+        self.empty_cells -= 1;
+        if self.tile_ids.is_some() {
+            let tile_id = self.new_tile_id();
+            if let Some(tile_ids) = &mut self.tile_ids {
+                tile_ids[row][col] = Some(tile_id);
+            }
+        }
+        self.awaiting_tile = false;
+        self.finish_turn(Some(((row, col), value)));
+        Ok(())
+    }
+
+    /// Checks whether the game is waiting for a tile to be placed with ```place_tile```.
+    /// # Returns
+    /// * ```true``` - The tile has to be placed before the next move.
+    /// * ```false``` - The next move can be made.
+    pub fn awaiting_tile(&self) -> bool {
+        self.awaiting_tile
+    }
+
+    /// Slides and merges the tiles in the given direction and notifies the observer about the move and the merges.
+    /// # Arguments
+    /// * ```direction```: The direction to move in.
+    /// # Returns
+    /// * ```true``` - The move was made.
+    /// * ```false``` - The move was invalid/impossible.
+    fn slide(&mut self, direction: GameMove) -> bool {
+        let next_ind = direction.index();
+        if !self.moves[next_ind] {
+            return false;
+        }
+        self.store_move(direction);
+        if self.history_config.max_undo > 0 {
+            let snapshot = self.snapshot();
+            self.history_truncated |= self.undo_stack.push(snapshot);
+        }
+        if self.history_config.keep_move_log {
+            self.history_truncated |= self.move_log.push(direction);
+        }
+
+        // every merge replaces two tiles with one, leaving one more empty cell
+        let empty_cells = heuristics::empty_cells(&self.moves_next[next_ind]);
+        let merge_count = empty_cells - self.empty_cells;
+        let merged = if merge_count > 0 { self.merged_tiles(direction) } else { Vec::new() };
+        if self.tile_ids.is_some() {
+            self.move_tile_ids(direction);
+        }
+        let gained = self.score_next[next_ind];
+        self.merges += merge_count as u64;
+        self.empty_cells = empty_cells;
+        for &(_, value) in &merged {
+            *self.merge_histogram.entry(value).or_insert(0) += 1;
+        }
+        self.board = self.moves_next[next_ind];
+        self.score = self.score.saturating_add(gained);
+        self.turns += 1;
+        self.best_merge = self.best_merge.max(self.best_merge_next[next_ind]);
+        self.last_move_gain = gained;
+        if self.history_config.keep_score_log {
+            self.history_truncated |= self.score_history.push(gained);
+        }
+
+        if let Some(observer) = &mut self.observer {
+            observer.on_move(direction, gained);
+            for (position, value) in merged {
+                observer.on_merge(position, value);
+            }
+        }
+        self.publish(GameEvent::MoveMade { direction, gained });
+        true
+    }
+
+    /// Captures the state restored by ```undo```.
+    fn snapshot(&self) -> Snapshot<SIZE> {
+        Snapshot {
+            board: self.board,
+            score: self.score,
+            turns: self.turns,
+            merges: self.merges,
+            merge_histogram: self.merge_histogram.clone(),
+            best_merge: self.best_merge,
+            last_move_gain: self.last_move_gain,
+            victory_turn: self.victory_turn,
+            tile_ids: self.tile_ids,
+            next_tile_id: self.next_tile_id,
+            rng: self.rng.clone(),
+            state: self.state,
+            result: self.result,
+        }
+    }
+
+    /// Finishes the turn after the new tile was added: updates the game and notifies the observer.
+    /// # Arguments
+    /// * ```spawned```: The position and the value of the new tile, ```None``` if the move of a puzzle didn't spawn any.
+    fn finish_turn(&mut self, spawned: Option<((usize, usize), u64)>) {
+        let result_before = self.result;
+        self.update();
+        // a game won before this move is lost when the move ends it, unless the limit of the turns or a solved puzzle ended it
+        let turn_limit = self.max_turns.is_some_and(|max_turns| self.turns >= max_turns);
+        if result_before == GameResult::Victory && self.state == GameState::GameOver && !turn_limit && !self.puzzle {
+            self.result = GameResult::VictoryThenLoss;
+        }
+
+        #[cfg(feature = "std")]
+        if let Some(timing) = &mut self.timing {
+            timing.record_move(self.turns, self.state == GameState::GameOver);
+        }
+
+        let victory = result_before == GameResult::Pending && self.result == GameResult::Victory;
+        let repetition = match &mut self.repetitions {
+            Some(repetitions) => {
+                let count = repetitions.record(symmetry::canonical_hash(&self.board));
+                Some(count).filter(|&count| count > 0 && count >= repetitions.config().threshold)
+            }
+            None => None,
+        };
+        if let Some(observer) = &mut self.observer {
+            if let Some((position, value)) = spawned {
+                observer.on_spawn(position, value);
+            }
+            if let Some(count) = repetition {
+                observer.on_repetition(count);
+            }
+            if victory {
+                observer.on_victory();
+            }
+            if self.state == GameState::GameOver {
+                observer.on_game_over();
+            }
+        }
+        if let Some((position, value)) = spawned {
+            self.publish(GameEvent::TileSpawned { position, value });
+        }
+        if let Some(count) = repetition {
+            self.publish(GameEvent::Repetition { count });
+        }
+        if victory {
+            self.publish(GameEvent::Victory);
+        }
+        if self.state == GameState::GameOver {
+            self.publish(GameEvent::GameOver);
+        }
+    }
+
+    /// Sends the event to all subscribers, dropping the ones whose receivers were dropped.
+    /// # Arguments
+    /// * ```event``` - The event to send.
+    fn publish(&mut self, event: GameEvent) {
+        #[cfg(feature = "std")]
+        self.subscribers.retain(|subscriber| subscriber.send(event).is_ok());
+        #[cfg(not(feature = "std"))]
+        let _ = event;
+    }
+
+    /// Plays the game to the end using the given strategy.
+    /// The game stops when it is over, or when the strategy gives up or proposes an invalid move.
+    /// # Arguments
+    /// * ```strategy``` - The strategy choosing the moves.
+    /// # Returns
+    /// * ```GameSummary``` - The summary of the game after playing.
+    pub fn play_with(&mut self, strategy: &mut dyn Strategy<SIZE>) -> GameSummary {
+        while self.state == GameState::InProgress {
+            match strategy.choose(self) {
+                Some(direction) if self.make_move(direction) => {}
+                _ => break,
+            }
+        }
+
+        self.summary()
+    }
+
+    /// Plays the game to the end using the Monte Carlo AI (```Game::find_best_move```).
+    /// # Arguments
+    /// * ```depth``` - The number of simulated games to play to determine every move.
+    /// * ```max_moves``` - The maximum number of moves to make, ```None``` for no limit.
+    /// # Returns
+    /// * ```Ok(GameSummary)``` - The summary of the game after playing.
+    /// * ```Err(Error)``` - The depth is invalid or it is not the turn of the player.
+    /// # Errors
+    /// * ```Error::InvalidDepth``` - The depth is 0.
+    /// * ```Error::WrongTurn``` - The game is awaiting a tile placement (see ```Game::make_move_without_spawn```).
+    #[cfg(feature = "std")]
+    pub fn autoplay(&mut self, depth: usize, max_moves: Option<u64>) -> Result<GameSummary, Error> {
+        if depth == 0 {
+            return Err(Error::InvalidDepth);
+        }
+        self.autoplay_with(&mut MonteCarloStrategy { depth }, max_moves)
+    }
+
+    /// Plays the game to the end using the given strategy.
+    /// The game stops when it is over, when the strategy gives up or when the maximum number of moves is made.
+    /// Unlike ```Game::play_with```, an invalid move is not the end, the strategy is asked again,
+    /// up to 16 consecutive invalid moves.
+    /// # Arguments
+    /// * ```strategy``` - The strategy choosing the moves.
+    /// * ```max_moves``` - The maximum number of moves to make, ```None``` for no limit.
+    /// # Returns
+    /// * ```Ok(GameSummary)``` - The summary of the game after playing.
+    /// * ```Err(Error)``` - The strategy kept proposing invalid moves or it is not the turn of the player.
+    /// # Errors
+    /// * ```Error::InvalidStrategy``` - The strategy proposed 16 invalid moves in a row. The moves made before stay made.
+    /// * ```Error::WrongTurn``` - The game is awaiting a tile placement (see ```Game::make_move_without_spawn```).
+    pub fn autoplay_with(&mut self, strategy: &mut dyn Strategy<SIZE>, max_moves: Option<u64>) -> Result<GameSummary, Error> {
+        if self.awaiting_tile {
+            return Err(Error::WrongTurn);
+        }
+
+        let mut moves_made = 0;
+        let mut invalid_moves = 0;
+        while self.state == GameState::InProgress && max_moves.is_none_or(|max_moves| moves_made < max_moves) {
+            let Some(direction) = strategy.choose(self) else {
+                break;
+            };
+            if self.make_move(direction) {
+                moves_made += 1;
+                invalid_moves = 0;
+            } else {
+                invalid_moves += 1;
+                if invalid_moves == MAX_INVALID_MOVES {
+                    return Err(Error::InvalidStrategy);
+                }
+            }
+        }
+
+        Ok(self.summary())
+    }
+
+    /// Summarizes the game.
+    /// Can be called at any time, not only when the game is over.
+    /// # Returns
+    /// * ```GameSummary``` - The summary of the game.
+    pub fn summary(&self) -> GameSummary {
+        GameSummary {
+            score: self.score,
+            highest_tile: self.highest_tile(),
+            turns: self.turns,
+            state: self.state,
+            result: self.result,
+            merges: self.merges,
+            victory_turn: self.victory_turn,
+            #[cfg(feature = "std")]
+            duration: self.elapsed(),
+            #[cfg(feature = "std")]
+            average_move_time: self.average_move_time(),
+            #[cfg(feature = "std")]
+            slowest_move: self.slowest_move(),
+            #[cfg(not(feature = "std"))]
+            duration: None,
+            #[cfg(not(feature = "std"))]
+            average_move_time: None,
+            #[cfg(not(feature = "std"))]
+            slowest_move: None,
+        }
+    }
+
+    /// Enables the timing of the game with the system clock, which starts now and ends when the game is over.
+    /// The timing is reported in the summary of the game (see ```GameBuilder::track_time```).
+    /// # Returns
+    /// * ```Game``` - The game with the timing enabled.
+    #[cfg(feature = "std")]
+    pub fn with_timing(self) -> Self {
+        self.with_clock(Arc::new(SystemClock::default()))
+    }
+
+    /// Enables the timing of the game with the given clock, which starts now and ends when the game is over.
+    /// # Arguments
+    /// * ```clock``` - The clock.
+    /// # Returns
+    /// * ```Game``` - The game with the timing enabled.
+    #[cfg(feature = "std")]
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.timing = Some(Timing::new(clock));
+        self
+    }
+
+    /// Returns the time from the start of the game to its end, or to now if the game is not over yet.
+    /// # Returns
+    /// * ```Some(Duration)``` - The duration of the game.
+    /// * ```None``` - The timing is not enabled.
+    #[cfg(feature = "std")]
+    pub fn elapsed(&self) -> Option<Duration> {
+        self.timing.as_ref().map(Timing::elapsed)
+    }
+
+    /// Returns the average time of a move, measured from the previous move (or from the start of the game for the first move).
+    /// # Returns
+    /// * ```Some(Duration)``` - The average time of a move.
+    /// * ```None``` - The timing is not enabled or no move was made.
+    #[cfg(feature = "std")]
+    pub fn average_move_time(&self) -> Option<Duration> {
+        self.timing.as_ref().and_then(Timing::average_move_time)
+    }
+
+    /// Returns the slowest move, measured from the previous move (or from the start of the game for the first move).
+    /// The earliest one wins a tie.
+    /// # Returns
+    /// * ```Some((u64, Duration))``` - The turn of the slowest move (the first move is the turn 1) and its time.
+    /// * ```None``` - The timing is not enabled or no move was made.
+    #[cfg(feature = "std")]
+    pub fn slowest_move(&self) -> Option<(u64, Duration)> {
+        self.timing.as_ref().and_then(|timing| timing.slowest)
+    }
+
+    /// Sets the observer of the game.
+    /// The observer is notified about moves, merges, spawns and state changes from within ```make_move```.
+    /// Replaces the previously set observer, if any.
+    /// # Arguments
+    /// * ```observer``` - The observer to notify.
+    pub fn set_observer(&mut self, observer: Box<dyn GameObserver>) {
+        self.observer = Some(observer);
+    }
+
+    /// Removes the observer of the game.
+    /// # Returns
+    /// * ```Some(Box<dyn GameObserver>)``` - The removed observer.
+    /// * ```None``` - No observer was set.
+    pub fn remove_observer(&mut self) -> Option<Box<dyn GameObserver>> {
+        self.observer.take()
+    }
+
+    /// Subscribes to the events of the game.
+    /// The events are sent from within ```make_move``` (and ```make_move_without_spawn``` and ```place_tile```) to every subscriber.
+    /// The receiver can be moved to another thread, e.g. the one rendering the game.
+    /// Dropping the receiver unsubscribes, the channel is removed with the next event.
+    /// # Returns
+    /// * ```Receiver<GameEvent>``` - The receiver of the events.
+    #[cfg(feature = "std")]
+    pub fn subscribe(&mut self) -> Receiver<GameEvent> {
+        let (sender, receiver) = mpsc::channel();
+        self.subscribers.push(sender);
+        receiver
+    }
+
+    /// Add a new tile to the board.
+    /// The tile is placed by the spawner if one is set, otherwise randomly.
+    /// # Returns
+    /// * ```((usize, usize), u64)``` - The position and the value of the new tile.
+    fn new_tile(&mut self) -> ((usize, usize), u64) {
+        // create iterator over all tiles (cartesian product of two ranges)
+        // filter only empty tiles -> get iterator over empty tiles
+        let mut empty_tiles = (0..SIZE)
+            .flat_map(|ind1| (0..SIZE).map(move |ind2| (ind1, ind2)))
+            .filter(|&pos| self.board[pos.0][pos.1] == 0);
+        debug_assert!(self.empty_cells > 0, "a tile can't be spawned on a full board");
+
+        let loc = if let Some(spawner) = &mut self.spawner {
+            let empty_tiles: Vec<(usize, usize)> = empty_tiles.collect();
+            debug_assert_eq!(empty_tiles.len(), self.empty_cells);
+            let (loc, value) = spawner.spawn(&empty_tiles, &self.board);
+            debug_assert!(empty_tiles.contains(&loc), "the spawner chose a cell that is not empty");
+            self.board[loc.0][loc.1] = value;
+            loc
+        } else {
+            // choose one of the empty tiles with rng (weighted by the policy, if any) and add a value from the spawn distribution to it
+            let empty_cells = self.empty_cells;
+            let positions = self.spawn_weight.as_deref().and_then(|policy| position_probabilities(policy, &self.board));
+            let (ind, value) = match &mut self.rng {
+                Some(rng) => (Self::spawn_index(positions.as_deref(), empty_cells, rng), self.spawn_config.sample(rng)),
+                #[cfg(feature = "std")]
+                None => (
+                    Self::spawn_index(positions.as_deref(), empty_cells, &mut thread_rng()),
+                    self.spawn_config.sample(&mut thread_rng()),
+                ),
+                // without std, the games that aren't seeded are rejected before spawning (see can_spawn)
+                #[cfg(not(feature = "std"))]
+                None => unreachable!("the games without std spawn tiles only when seeded"),
+            };
+            let loc = empty_tiles.nth(ind).unwrap();
+            self.board[loc.0][loc.1] = if self.junk_due(self.turns) { JUNK_TILE } else { value };
+            loc
+        };
+        self.empty_cells -= 1;
+
+        if self.tile_ids.is_some() {
+            let tile_id = self.new_tile_id();
+            if let Some(tile_ids) = &mut self.tile_ids {
+                tile_ids[loc.0][loc.1] = Some(tile_id);
+            }
+        }
+
+        (loc, self.board[loc.0][loc.1])
+    }
+
+    /// Chooses the empty cell of the new tile.
+    /// # Arguments
+    /// * ```positions``` - The probabilities of the empty cells under the spawn position policy, ```None``` for the uniform choice.
+    /// * ```empty_cells``` - The number of the empty cells.
+    /// * ```rng``` - The random number generator to use.
+    /// # Returns
+    /// * ```usize``` - The index of the cell among the empty cells (row by row).
+    fn spawn_index<R: Rng>(positions: Option<&[f64]>, empty_cells: usize, rng: &mut R) -> usize {
+        match positions {
+            Some(positions) => sample_position(positions, rng),
+            None => rng.gen_range(0..empty_cells),
+        }
+    }
+
+    /// Returns a fresh tile identity.
+    fn new_tile_id(&mut self) -> NonZeroU64 {
+        let tile_id = NonZeroU64::new(self.next_tile_id).expect("tile identities are never zero");
+        self.next_tile_id += 1;
+        tile_id
+    }
+
+    /// Moves the tile identities along with the tiles for the given move.
+    /// Must be called before the board is replaced with the result of the move.
+    /// # Arguments
+    /// * ```direction``` - The direction of the move.
+    fn move_tile_ids(&mut self, direction: GameMove) {
+        let old_ids = match self.tile_ids {
+            Some(tile_ids) => tile_ids,
+            None => return,
+        };
+        let mut new_ids = [[None; SIZE]; SIZE];
+
+        for line in 0..SIZE {
+            let cells = Self::line_cells(direction, line);
+            let origins = self.trace_line(&cells.map(|(i, j)| self.board[i][j]));
+            for (&(i, j), origin) in cells.iter().zip(origins) {
+                new_ids[i][j] = match origin {
+                    Some((from, None)) => old_ids[cells[from].0][cells[from].1],
+                    Some((_, Some(_))) => Some(self.new_tile_id()),
+                    None => None,
+                };
+            }
+        }
+
+        self.tile_ids = Some(new_ids);
+    }
+
+    /// Finds the merges that the given move would make.
+    /// # Arguments
+    /// * ```direction``` - The direction of the move.
+    /// # Returns
+    /// * ```Vec<((usize, usize), u64)>``` - The positions of the merged tiles after the move and their new values.
+    fn merged_tiles(&self, direction: GameMove) -> Vec<((usize, usize), u64)> {
+        let mut merged = Vec::new();
+        for line in 0..SIZE {
+            let cells = Self::line_cells(direction, line);
+            let values = cells.map(|(i, j)| self.board[i][j]);
+            for (&position, origin) in cells.iter().zip(self.trace_line(&values)) {
+                if let Some((from, Some(other))) = origin {
+                    merged.push((position, self.merge_rule().merge(values[from], values[other]).unwrap().0));
+                }
+            }
+        }
+        merged
+    }
+
+    /// Returns the coordinates of the cells in a line (row or column) of the board.
+    /// The cells are ordered in the direction of the move, the first cell is the one the tiles slide towards.
+    /// # Arguments
+    /// * ```direction``` - The direction of the move.
+    /// * ```line``` - The index of the row (for left and right) or the column (for up and down).
+    /// # Returns
+    /// * ```[(usize, usize); SIZE]``` - The coordinates of the cells.
+    fn line_cells(direction: GameMove, line: usize) -> [(usize, usize); SIZE] {
+        core::array::from_fn(|pos| match direction {
+            GameMove::Left => (line, pos),
+            GameMove::Right => (line, SIZE - 1 - pos),
+            GameMove::Up => (pos, line),
+            GameMove::Down => (SIZE - 1 - pos, line),
+        })
+    }
+
+    /// Slides and merges a single line towards its start and records where each tile came from.
+    /// Uses the merge rule of the game.
+    /// # Arguments
+    /// * ```line``` - The tiles of the line, ordered in the direction of the move.
+    /// # Returns
+    /// * ```[Option<(usize, Option<usize>)>; SIZE]``` - For each cell of the resulting line, the positions of the tiles that ended up there.
+    ///   The second position is present if two tiles were merged. Empty and blocked cells are ```None```.
+    fn trace_line(&self, line: &[u64; SIZE]) -> [Option<(usize, Option<usize>)>; SIZE] {
+        let rule = self.merge_rule();
+        let mut result = [0; SIZE];
+        let mut origins = [None; SIZE];
+        let mut j = 0;
+        let mut merge = false;
+        for (pos, &elem) in line.iter().enumerate().filter(|(_, &x)| x != 0) {
+            if elem == BLOCKED_CELL {
+                // blocked cells stay in place and split the line
+                result[pos] = BLOCKED_CELL;
+                j = pos + 1;
+                merge = false;
+            } else if elem == JUNK_TILE {
+                // junk tiles slide, but nothing merges with them
+                result[j] = elem;
+                origins[j] = Some((pos, None));
+                j += 1;
+                merge = false;
+            } else if let Some((merged, _)) = merge.then(|| rule.merge(result[j - 1], elem)).flatten() {
+                result[j - 1] = merged;
+                if let Some((from, _)) = origins[j - 1] {
+                    origins[j - 1] = Some((from, Some(pos)));
+                }
+                merge = false;
+            } else {
+                result[j] = elem;
+                origins[j] = Some((pos, None));
+                j += 1;
+                merge = true;
+            }
+        }
+        origins
+    }
+
+    /// Slides and merges a single line towards its start.
+    /// # Arguments
+    /// * ```line``` - The tiles of the line, ordered in the direction of the move.
+    /// # Returns
+    /// * ```([u64; SIZE], u64, u64)``` - The resulting line, the score of the merges and the largest merged tile.
+    fn slide_line(&self, line: &[u64; SIZE]) -> ([u64; SIZE], u64, u64) {
+        let mut result = [0; SIZE];
+        let mut score: u64 = 0;
+        let mut best_merge = 0;
+        for (pos, origin) in self.trace_line(line).into_iter().enumerate() {
+            result[pos] = match origin {
+                Some((from, None)) => line[from],
+                Some((from, Some(other))) => {
+                    let (merged, gained) = self.merge_rule().merge(line[from], line[other]).unwrap();
+                    score = score.saturating_add(gained);
+                    best_merge = best_merge.max(merged);
+                    merged
+                }
+                None if line[pos] == BLOCKED_CELL => BLOCKED_CELL,
+                None => 0,
+            };
+        }
+        (result, score, best_merge)
+    }
+
+    /// Update moves, moves_next, score_next, state and result.
+    /// With the lazy moves (see ```with_lazy_moves```), the boards after the moves are built only when they are needed.
+    /// The moves of the 4x4 boards with the classic rules are looked up in the row tables (see ```update_from_tables```).
+    fn update(&mut self) {
+        #[cfg(feature = "std")]
+        let from_tables = self.update_from_tables();
+        #[cfg(not(feature = "std"))]
+        let from_tables = false;
+        if !from_tables {
+            for direction in GameMove::ALL {
+                let ind = direction.index();
+                // a cheap check rules out impossible moves without building their boards
+                self.moves[ind] = self.can_slide(direction);
+                self.moves_built[ind] = false;
+                if !self.moves[ind] {
+                    self.score_next[ind] = 0;
+                    self.best_merge_next[ind] = 0;
+                } else if !self.lazy_moves {
+                    self.store_move(direction);
+                }
+            }
+        }
+
+        // no move can be made once the limit of the turns is reached or the puzzle is solved
+        let turn_limit = self.max_turns.is_some_and(|max_turns| self.turns >= max_turns);
+        let solved = self.puzzle && self.target.is_some_and(|target| self.highest_tile() >= target);
+        if turn_limit || solved {
+            self.moves = [false; 4];
+        }
+
+        // update state
+        if self.moves.iter().all(|&x| !x) {
+            self.state = GameState::GameOver;
+        }
+
+        // update result
+        match self.result {
+            GameResult::Pending => {
+                let victory = self.target.is_some_and(|target| self.highest_tile() >= target);
+                if victory {
+                    self.result = GameResult::Victory;
+                    self.victory_turn = Some(self.turns);
+                } else if turn_limit && !self.puzzle {
+                    self.result = GameResult::TurnLimit;
+                } else if self.state == GameState::GameOver {
+                    self.result = GameResult::Loss;
+                }
+            }
+            // only a move loses a won game (see ```finish_turn```), the other updates (e.g. the rotations) keep the victory
+            GameResult::Victory => {}
+            GameResult::Loss => {}
+            GameResult::VictoryThenLoss => {}
+            GameResult::TurnLimit => {}
+        }
+    }
+
+    /// Updates moves, moves_next and score_next with the precomputed row tables of the bitboard.
+    /// Every move is a few table lookups, so all moves are built, even with the lazy moves.
+    /// # Returns
+    /// * ```true``` - The moves were updated.
+    /// * ```false``` - The board is not 4x4 with the classic rules and tiles up to 32768, or a move would merge two 32768 tiles.
+    ///   The moves are left unchanged, to be updated by the generic algorithm.
+    #[cfg(feature = "std")]
+    fn update_from_tables(&mut self) -> bool {
+        if self.has_blocked_cells || self.merge_rule.is_some() {
+            return false;
+        }
+        let Some(bitboard) = BitBoard::from_board(&self.board) else {
+            return false;
+        };
+        let table_moves = GameMove::ALL.map(|direction| bitboard.table_move(direction));
+        if table_moves.contains(&None) {
+            return false;
+        }
+        for (ind, table_move) in table_moves.into_iter().flatten().enumerate() {
+            self.moves[ind] = table_move.changed;
+            self.moves_built[ind] = table_move.changed;
+            if table_move.changed {
+                self.moves_next[ind] = table_move.board.to_board();
+                self.score_next[ind] = table_move.score;
+                self.best_merge_next[ind] = table_move.best_merge;
+            } else {
+                self.score_next[ind] = 0;
+                self.best_merge_next[ind] = 0;
+            }
+        }
+        true
+    }
+
+    /// Builds the board after a valid move.
+    /// # Arguments
+    /// * ```direction``` - The direction of the move.
+    /// # Returns
+    /// * ```([[u64; SIZE]; SIZE], u64, u64)``` - The board after the move, the gained score and the largest merged tile (0 if no tiles merge).
+    fn build_move(&self, direction: GameMove) -> ([[u64; SIZE]; SIZE], u64, u64) {
+        // the cells that no tile moves to stay empty
+        let mut next = [[0; SIZE]; SIZE];
+        let mut score_next: u64 = 0;
+        let mut best_merge_next = 0;
+        if self.has_blocked_cells || self.merge_rule.is_some() {
+            // the specialised loops below only handle the classic rules without blocked cells
+            // (junk tiles are larger than MAX_TILE, so they never merge in them either)
+            for line in 0..SIZE {
+                let cells = Self::line_cells(direction, line);
+                let (result, score, best_merge) = self.slide_line(&cells.map(|(i, j)| self.board[i][j]));
+                for (&(i, j), value) in cells.iter().zip(result) {
+                    next[i][j] = value;
+                }
+                score_next = score_next.saturating_add(score);
+                best_merge_next = best_merge_next.max(best_merge);
+            }
+            debug_assert_ne!(self.board, next);
+            return (next, score_next, best_merge_next);
+        }
+
+        match direction {
+            GameMove::Left => {
+                for (i, row) in self.board.iter().enumerate() {
+                    let mut j = 0;
+                    let mut merge = false;
+                    for elem in row.iter().filter(|&&x| x != 0) {
+                        if merge && *elem == next[i][j - 1] && *elem < MAX_TILE {
+                            next[i][j - 1] *= 2;
+                            score_next = score_next.saturating_add(next[i][j - 1]);
+                            best_merge_next = best_merge_next.max(next[i][j - 1]);
+                            merge = false;
+                        } else {
+                            next[i][j] = *elem;
+                            j += 1;
+                            merge = true;
+                        }
+                    }
+                }
+            }
+            GameMove::Right => {
+                for (i, row) in self.board.iter().enumerate() {
+                    let mut j = SIZE - 1;
+                    let mut merge = false;
+                    for elem in row.iter().filter(|&&x| x != 0).rev() {
+                        if merge && *elem == next[i][j + 1] && *elem < MAX_TILE {
+                            next[i][j + 1] *= 2;
+                            score_next = score_next.saturating_add(next[i][j + 1]);
+                            best_merge_next = best_merge_next.max(next[i][j + 1]);
+                            merge = false;
+                        } else {
+                            next[i][j] = *elem;
+                            j = match j.checked_sub(1) {
+                                Some(x) => x,
+                                // we processed the whole row, we can safely break
+                                None => break,
+                            };
+                            merge = true;
+                        }
+                    }
+                }
+            }
+            GameMove::Up => {
+                for col in 0..SIZE {
+                    let mut column = [0; SIZE];
+                    let mut i = 0;
+                    let mut merge = false;
+                    for elem in Self::column_tiles(&self.board, col).filter(|&x| x != 0) {
+                        if merge && elem == column[i - 1] && elem < MAX_TILE {
+                            column[i - 1] *= 2;
+                            score_next = score_next.saturating_add(column[i - 1]);
+                            best_merge_next = best_merge_next.max(column[i - 1]);
+                            merge = false;
+                        } else {
+                            column[i] = elem;
+                            i += 1;
+                            merge = true;
+                        }
+                    }
+                    for (row, value) in next.iter_mut().zip(column) {
+                        row[col] = value;
+                    }
+                }
+            }
+            GameMove::Down => {
+                for col in 0..SIZE {
+                    let mut column = [0; SIZE];
+                    let mut i = SIZE - 1;
+                    let mut merge = false;
+                    for elem in Self::column_tiles(&self.board, col).filter(|&x| x != 0).rev() {
+                        if merge && elem == column[i + 1] && elem < MAX_TILE {
+                            column[i + 1] *= 2;
+                            score_next = score_next.saturating_add(column[i + 1]);
+                            best_merge_next = best_merge_next.max(column[i + 1]);
+                            merge = false;
+                        } else {
+                            column[i] = elem;
+                            i = match i.checked_sub(1) {
+                                Some(x) => x,
+                                // we processed whole column, we can safely break
+                                None => break,
+                            };
+                            merge = true;
+                        }
+                    }
+                    for (row, value) in next.iter_mut().zip(column) {
+                        row[col] = value;
+                    }
+                }
+            }
+        }
+        debug_assert_ne!(self.board, next);
+        (next, score_next, best_merge_next)
+    }
+
+    /// Builds and stores the board after a valid move, unless it is already built.
+    /// # Arguments
+    /// * ```direction``` - The direction of the move.
+    fn store_move(&mut self, direction: GameMove) {
+        let ind = direction.index();
+        if self.moves[ind] && !self.moves_built[ind] {
+            (self.moves_next[ind], self.score_next[ind], self.best_merge_next[ind]) = self.build_move(direction);
+            self.moves_built[ind] = true;
+        }
+    }
+
+    /// Checks whether a move would change the board, without building the resulting board.
+    /// A move is possible if some line has a tile with an empty cell in front of it (in the direction of the move),
+    /// or two neighbouring tiles that merge. Junk tiles never merge.
+    /// # Arguments
+    /// * ```direction``` - The direction of the move.
+    /// # Returns
+    /// * ```true``` - The move would change the board.
+    /// * ```false``` - The move would not change the board.
+    fn can_slide(&self, direction: GameMove) -> bool {
+        let board = &self.board;
+        let rule = self.merge_rule();
+        (0..SIZE).any(|line| {
+            let cells = Self::line_cells(direction, line);
+            cells.windows(2).any(|pair| {
+                let front = board[pair[0].0][pair[0].1];
+                let back = board[pair[1].0][pair[1].1];
+                let mergeable = |tile| tile != BLOCKED_CELL && tile != JUNK_TILE;
+                back != 0 && back != BLOCKED_CELL && (front == 0 || (mergeable(front) && mergeable(back) && rule.merge(front, back).is_some()))
+            })
+        })
+    }
+}
+
+#[cfg(feature = "std")]
+impl<const SIZE: usize> Game<SIZE> {
+    /// Find the best move to make based on the current board state.
+    /// Based on Monte Carlo algorithm (randomized guessing).
+    /// Uses multiple threads to speed up the process.
+    /// The puzzles (see ```Game::puzzle```) are solved exactly instead, by trying every sequence of the remaining moves.
+    /// # Arguments
+    /// * ```depth``` - The number of simulated games to play to determine the best move. Recommended value is 1000.
+    /// # Returns
+    /// * ```Ok(GameMove)``` - The best move to make.
+    /// * ```Err(Error)``` - There are no valid moves left or the depth is invalid.
+    /// # Errors
+    /// * ```Error::NoValidMove``` - There are no valid moves left.
+    /// * ```Error::InvalidDepth``` - The depth is 0.
+    pub fn find_best_move(&self, depth: usize) -> Result<GameMove, Error> {
+        self.find_best_move_report(depth).map(|report| report.best)
+    }
+
+    /// Find the best move to make like ```Game::find_best_move```, together with the statistics of the search.
+    /// # Arguments
+    /// * ```depth``` - The number of simulated games to play to determine the best move.
+    /// # Returns
+    /// * ```Ok(SearchReport)``` - The best move and the statistics of the search.
+    /// * ```Err(Error)``` - There are no valid moves left or the depth is invalid.
+    /// # Errors
+    /// * ```Error::NoValidMove``` - There are no valid moves left.
+    /// * ```Error::InvalidDepth``` - The depth is 0.
+    pub fn find_best_move_report(&self, depth: usize) -> Result<SearchReport, Error> {
+        self.search_report(&SearchOptions { depth, ..Default::default() }, None)
+    }
+
+    /// Find the best move to make like ```Game::find_best_move```, together with its result from ```Game::peek_move```,
+    /// so the board after the move can be shown before the new tile is spawned.
+    /// # Arguments
+    /// * ```depth``` - The number of simulated games to play to determine the best move.
+    /// # Returns
+    /// * ```Ok((GameMove, [[u64; SIZE]; SIZE], u64))``` - The best move, the board after sliding and merging the tiles and the score the move would gain.
+    /// * ```Err(Error)``` - There are no valid moves left or the depth is invalid.
+    /// # Errors
+    /// * ```Error::NoValidMove``` - There are no valid moves left.
+    /// * ```Error::InvalidDepth``` - The depth is 0.
+    pub fn preview_best_move(&self, depth: usize) -> Result<(GameMove, [[u64; SIZE]; SIZE], u64), Error> {
+        let best = self.find_best_move(depth)?;
+        let (board, gained) = self.peek_move(best).ok_or(Error::NoValidMove)?;
+        Ok((best, board, gained))
+    }
+
+    /// Find the best move to make with the Monte Carlo tree search (MCTS).
+    /// Unlike the flat Monte Carlo search (```Game::find_best_move```), which splits the simulated games evenly between the moves,
+    /// the tree search grows a tree of the moves and the spawned tiles and spends more playouts on the promising moves,
+    /// choosing the moves in the tree with the UCT formula.
+    /// # Arguments
+    /// * ```iterations``` - The number of playouts, each expanding one position of the tree.
+    /// * ```exploration_c``` - The weight of the exploration in the UCT formula, the values are normalised to [0, 1] (e.g. 1.4).
+    /// # Returns
+    /// * ```Ok(GameMove)``` - The best move to make.
+    /// * ```Err(Error)``` - There are no valid moves left or the number of iterations is invalid.
+    /// # Errors
+    /// * ```Error::NoValidMove``` - There are no valid moves left.
+    /// * ```Error::InvalidDepth``` - The number of iterations is 0.
+    pub fn find_best_move_mcts(&self, iterations: usize, exploration_c: f64) -> Result<GameMove, Error> {
+        self.find_best_move_mcts_report(
+            &SearchOptions {
+                depth: iterations,
+                ..Default::default()
+            },
+            exploration_c,
+        )
+        .map(|report| report.best)
+    }
+
+    /// Find the best move to make with the Monte Carlo tree search (see ```Game::find_best_move_mcts```), together with the statistics of the search.
+    /// The search runs on the current thread. ```options.depth``` is the number of iterations,
+    /// and the objective, the risk horizon, the seed and the cancellation token are used like in the flat search.
+    /// The best move is the most visited one (ties are resolved by the average value), the risk aversion, the loss guard and the opening book are not used.
+    /// # Arguments
+    /// * ```options``` - The options of the search.
+    /// * ```exploration_c``` - The weight of the exploration in the UCT formula.
+    /// # Returns
+    /// * ```Ok(SearchReport)``` - The best move to make and the statistics of the search, with the playouts of every move as its simulated games.
+    /// * ```Err(Error)``` - There are no valid moves left, the number of iterations is invalid or the search was cancelled.
+    /// # Errors
+    /// * ```Error::NoValidMove``` - There are no valid moves left.
+    /// * ```Error::InvalidDepth``` - The number of iterations is 0.
+    /// * ```Error::Cancelled``` - The search was cancelled (see ```SearchOptions::cancel```).
+    pub fn find_best_move_mcts_report(&self, options: &SearchOptions, exploration_c: f64) -> Result<SearchReport, Error> {
+        let started = self.search_time();
+        if options.depth == 0 {
+            return Err(Error::InvalidDepth);
+        }
+        match self.moves.iter().filter(|&&valid| valid).count() {
+            0 => return Err(Error::NoValidMove),
+            1 => return Ok(self.unsearched_report(GameMove::from_index(self.moves.iter().position(|&valid| valid).unwrap()), started)),
+            _ => {}
+        }
+
+        let objective = options.objective;
+        let mut rng = self.search_seed(options).map_or_else(StdRng::from_entropy, StdRng::seed_from_u64);
+        let mut work_game = self.derive_game(&self.board);
+        let mut nodes = vec![MctsNode::new(self.derive_game(&self.board))];
+        let mut bounds = (f64::INFINITY, f64::NEG_INFINITY);
+        let mut early_losses = [0; 4];
+        let mut path = Vec::new();
+        for _ in 0..options.depth {
+            if options.cancel.as_ref().is_some_and(CancellationToken::is_cancelled) {
+                return Err(Error::Cancelled);
+            }
+
+            // selection and expansion
+            path.clear();
+            let mut node = 0;
+            let (mut value, mut turns) = loop {
+                if nodes[node].moves.is_empty() {
+                    break (objective.value(0, nodes[node].game.highest_tile(), 0), 0);
+                }
+                let slot = nodes[node].select(exploration_c, bounds);
+                let (outcome, board) = nodes[node].sample_outcome(slot, &mut rng);
+                path.push((node, slot));
+                if let Some(&(_, child)) = nodes[node].moves[slot].children.iter().find(|&&(child_outcome, _)| child_outcome == outcome) {
+                    node = child;
+                    continue;
+                }
+
+                // playout from the new position, which is as many turns ahead as the length of the path
+                let child_game = self.derive_game_ahead(&board, path.len() as u64);
+                work_game.max_turns = child_game.max_turns;
+                work_game.junk_offset = child_game.junk_offset;
+                let child = MctsNode::new(child_game);
+                let playout = match child.moves.iter().map(|child_move| child_move.direction).choose(&mut rng) {
+                    Some(direction) => work_game.simulate(&board, direction, objective, &mut rng),
+                    None => (objective.value(0, child.game.highest_tile(), 0), 0),
+                };
+                let child_ind = nodes.len();
+                nodes[node].moves[slot].children.push((outcome, child_ind));
+                nodes.push(child);
+                break playout;
+            };
+
+            // backpropagation of the value of the whole game
+            for &(node, slot) in path.iter().rev() {
+                value = objective.before_first_move(value, nodes[node].moves[slot].gain);
+                turns += 1;
+            }
+            let value = value as f64;
+            bounds = (bounds.0.min(value), bounds.1.max(value));
+            for &(node, slot) in &path {
+                nodes[node].visits += 1;
+                nodes[node].moves[slot].visits += 1;
+                nodes[node].moves[slot].total += value;
+            }
+            if turns <= options.risk_horizon as u64 {
+                early_losses[nodes[0].moves[path[0].1].direction.index()] += 1;
+            }
+        }
+
+        let (mut scores, mut early_loss_rate, mut rollouts) = ([None; 4], [None; 4], [0; 4]);
+        for root_move in &nodes[0].moves {
+            let ind = root_move.direction.index();
+            rollouts[ind] = root_move.visits;
+            scores[ind] = Some(root_move.total / root_move.visits.max(1) as f64);
+            early_loss_rate[ind] = Some(early_losses[ind] as f64 / root_move.visits.max(1) as f64);
+        }
+        let best = nodes[0]
+            .moves
+            .iter()
+            .max_by(|a, b| {
+                let (a_average, b_average) = (scores[a.direction.index()].unwrap(), scores[b.direction.index()].unwrap());
+                a.visits.cmp(&b.visits).then(a_average.partial_cmp(&b_average).unwrap_or(Ordering::Equal))
+            })
+            .unwrap()
+            .direction;
+        Ok(SearchReport {
+            best,
+            scores,
+            early_loss_rate,
+            rollouts,
+            elapsed: self.search_time().saturating_sub(started),
+            threads_used: 1,
+        })
+    }
+
+    /// Find the best move to make like ```Game::find_best_move```, running the search on a background thread.
+    /// The search works on a snapshot of the game, so the game can be changed while the search is running.
+    /// Dropping the returned future cancels the search.
+    /// Available with the ```async``` feature.
+    /// # Arguments
+    /// * ```depth``` - The number of simulated games to play to determine the best move.
+    /// # Returns
+    /// * ```SearchFuture``` - The future of the best move, resolving to the same results as ```Game::find_best_move```.
+    #[cfg(feature = "async")]
+    pub fn find_best_move_async(&self, depth: usize) -> crate::task::SearchFuture {
+        let game = self.fork();
+        crate::task::SearchFuture::spawn(move |cancel| {
+            game.find_best_move_with_options(&SearchOptions {
+                depth,
+                cancel: Some(cancel),
+                ..Default::default()
+            })
+        })
+    }
+
+    /// Find the best move to make based on the current board state, using the given search options.
+    /// Based on Monte Carlo algorithm (randomized guessing).
+    /// Uses multiple threads to speed up the process.
+    /// # Arguments
+    /// * ```options``` - The options of the search.
+    /// # Returns
+    /// * ```Ok(GameMove)``` - The best move to make.
+    /// * ```Err(Error)``` - There are no valid moves left or the depth is invalid.
+    /// # Errors
+    /// * ```Error::NoValidMove``` - There are no valid moves left.
+    /// * ```Error::InvalidDepth``` - The depth is 0.
+    /// * ```Error::Cancelled``` - The search was cancelled (see ```SearchOptions::cancel```).
+    pub fn find_best_move_with_options(&self, options: &SearchOptions) -> Result<GameMove, Error> {
+        self.search_report(options, None).map(|report| report.best)
+    }
+
+    /// Find the best move to make with the Monte Carlo search, reusing the evaluations or the simulated games of the previous searches.
+    /// Used by ```SearchEngine```.
+    /// # Arguments
+    /// * ```options``` - The options of the search.
+    /// * ```memory``` - The cache of the evaluations or the statistics of the simulated games, if any.
+    /// # Returns
+    /// * ```Ok(SearchReport)``` - The best move to make and the statistics of the search.
+    /// * ```Err(Error)``` - There are no valid moves left or the depth is invalid.
+    /// # Errors
+    /// * ```Error::NoValidMove``` - There are no valid moves left.
+    /// * ```Error::InvalidDepth``` - The depth is 0.
+    /// * ```Error::Cancelled``` - The search was cancelled (see ```SearchOptions::cancel```).
+    pub(crate) fn search_report(&self, options: &SearchOptions, memory: Option<SearchMemory>) -> Result<SearchReport, Error> {
+        let started = self.search_time();
+        if options.depth == 0 {
+            return Err(Error::InvalidDepth);
+        }
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!("search", depth = options.depth, threads = options.threads()).entered();
+        let possible_moves_count = self.moves.iter().filter(|&&x| x).count();
+        let unsearched = |best| self.unsearched_report(best, started);
+        let seeded_options;
+        let options = match (options.seed, self.search_seed(options)) {
+            (None, Some(seed)) => {
+                seeded_options = SearchOptions {
+                    seed: Some(seed),
+                    ..options.clone()
+                };
+                &seeded_options
+            }
+            _ => options,
+        };
+
+        match possible_moves_count {
+            0 => Err(Error::NoValidMove),
+            1 => Ok(unsearched(GameMove::from_index(self.moves.iter().position(|&val| val).unwrap()))),
+            // the puzzles are deterministic, so every sequence of the remaining moves is tried instead of the simulated games
+            2.. if self.puzzle => Ok(unsearched(self.solve_puzzle().0.expect("a move is possible"))),
+            2.. => {
+                if let Some(direction) = options.use_opening_book.then(|| book::lookup(self)).flatten() {
+                    #[cfg(feature = "tracing")]
+                    tracing::debug!(direction = ?direction, "move found in the opening book");
+                    return Ok(unsearched(direction));
+                }
+                let (moves_values, early_loss_rate, rollouts) = match memory {
+                    Some(SearchMemory::Cache(cache)) => self.evaluate_moves_cached(options, cache)?,
+                    Some(SearchMemory::Rollouts(store)) => self.evaluate_moves_warm(options, store)?,
+                    None => self.evaluate_moves_counted(options)?,
+                };
+                // the values of the risky moves are lowered by the risk aversion
+                let mut ranked: Vec<(GameMove, f64)> = GameMove::ALL
+                    .into_iter()
+                    .zip(moves_values.into_iter().zip(early_loss_rate))
+                    .filter_map(|(direction, (value, loss_rate))| Some((direction, value? * (1.0 - options.risk_aversion * loss_rate?))))
+                    .collect();
+                ranked.sort_by(|(_, a), (_, b)| b.partial_cmp(a).unwrap_or(Ordering::Equal));
+
+                let mut chosen = ranked[0];
+                if options.loss_guard > 0 {
+                    if let Some(&safe) = ranked.iter().find(|&&(direction, _)| !self.loss_forced(direction, options.loss_guard)) {
+                        chosen = safe;
+                    }
+                }
+
+                #[cfg(feature = "tracing")]
+                {
+                    let runner_up = ranked
+                        .iter()
+                        .filter(|&&(direction, _)| direction != chosen.0)
+                        .map(|&(_, value)| value)
+                        .fold(f64::NEG_INFINITY, f64::max);
+                    tracing::debug!(direction = ?chosen.0, value = chosen.1, margin = chosen.1 - runner_up, "move chosen");
+                }
+                let played = rollouts.iter().sum::<usize>();
+                Ok(SearchReport {
+                    best: chosen.0,
+                    scores: moves_values,
+                    early_loss_rate,
+                    rollouts,
+                    elapsed: self.search_time().saturating_sub(started),
+                    // a thread plays games if it gets a share of the games of some move
+                    threads_used: options.threads().min(played.div_ceil(possible_moves_count)),
+                })
+            }
+        }
+    }
+
+    /// Returns the seed of the search: the seed of the options, or the seed derived from the random number generator of a seeded game.
+    /// The generator of the game is not advanced, the derived seed changes with every spawned tile.
+    /// # Arguments
+    /// * ```options``` - The options of the search.
+    /// # Returns
+    /// * ```Some(u64)``` - The seed of the search.
+    /// * ```None``` - Neither the options nor the game are seeded, the search uses the entropy.
+    pub(crate) fn search_seed(&self, options: &SearchOptions) -> Option<u64> {
+        options.seed.or_else(|| self.rng.clone().map(|mut rng| derive_seed(rng.gen(), 0)))
+    }
+
+    /// Finds the best move of a puzzle by trying every sequence of the remaining moves, remembering the positions already tried.
+    /// The lines are ranked by solving the puzzle, then by the number of the moves they leave unused, by the highest tile and by the gained score.
+    /// # Returns
+    /// * ```(Option<GameMove>, PuzzleLine)``` - The first move of the best line (```None``` if no move is possible) and the outcome of the line.
+    fn solve_puzzle(&self) -> (Option<GameMove>, PuzzleLine) {
+        /// Finds the best line from the position.
+        fn best_line<const SIZE: usize>(game: &Game<SIZE>, board: &[[u64; SIZE]; SIZE], moves_left: u64, seen: &mut PuzzleMemo<SIZE>) -> (Option<GameMove>, PuzzleLine) {
+            if let Some(&line) = seen.get(&(*board, moves_left)) {
+                return line;
+            }
+            let position = game.derive_game(board);
+            let highest_tile = position.highest_tile();
+            let mut best = (None, (game.target.is_some_and(|target| highest_tile >= target), moves_left, highest_tile, 0));
+            if !best.1 .0 && moves_left > 0 {
+                for direction in GameMove::ALL {
+                    if let Some((next, gained)) = position.peek_move(direction) {
+                        let (_, (solved, unused, highest_tile, score)) = best_line(game, &next, moves_left - 1, seen);
+                        let line = (solved, unused, highest_tile, score.saturating_add(gained));
+                        if best.0.is_none() || line > best.1 {
+                            best = (Some(direction), line);
+                        }
+                    }
+                }
+            }
+            seen.insert((*board, moves_left), best);
+            best
+        }
+
+        let moves_left = self.max_turns.map_or(0, |max_turns| max_turns.saturating_sub(self.turns));
+        best_line(self, &self.board, moves_left, &mut HashMap::new())
+    }
+
+    /// Reads the time measuring the duration of the searches: the clock of the timing if the game is timed (see ```GameBuilder::clock```),
+    /// otherwise the system clock. WebAssembly has no system clock, so the searches of the untimed games take no time there.
+    /// # Returns
+    /// * ```Duration``` - The current time of the clock.
+    fn search_time(&self) -> Duration {
+        match &self.timing {
+            Some(timing) => timing.clock.now(),
+            #[cfg(not(target_arch = "wasm32"))]
+            None => {
+                static SEARCH_CLOCK: OnceLock<SystemClock> = OnceLock::new();
+                SEARCH_CLOCK.get_or_init(SystemClock::default).now()
+            }
+            #[cfg(target_arch = "wasm32")]
+            None => Duration::ZERO,
+        }
+    }
+
+    /// Creates the report of a move decided without the simulated games.
+    /// # Arguments
+    /// * ```best``` - The decided move.
+    /// * ```started``` - The start of the search (see ```search_time```).
+    /// # Returns
+    /// * ```SearchReport``` - The report with no simulated games.
+    fn unsearched_report(&self, best: GameMove, started: Duration) -> SearchReport {
+        SearchReport {
+            best,
+            scores: self.moves.map(|valid| valid.then_some(0.0)),
+            early_loss_rate: self.moves.map(|valid| valid.then_some(0.0)),
+            rollouts: [0; 4],
+            elapsed: self.search_time().saturating_sub(started),
+            threads_used: 0,
+        }
+    }
+
+    /// Evaluates all valid moves by playing simulated games.
+    /// Uses multiple threads to speed up the process.
+    /// The simulated games are split as evenly as possible between the valid moves,
+    /// so a move can get no simulated games if the depth is lower than the number of valid moves.
+    /// # Arguments
+    /// * ```options``` - The options of the search.
+    /// # Returns
+    /// * ```Ok([Option<f64>; 4])``` - The average values (see ```SearchObjective```) of the simulated games for every move (in the order of ```GameMove::ALL```),
+    ///   ```None``` for invalid moves and 0 for moves that got no simulated games.
+    /// * ```Err(Error)``` - The depth is invalid.
+    /// # Errors
+    /// * ```Error::InvalidDepth``` - The depth is 0.
+    /// * ```Error::Cancelled``` - The search was cancelled (see ```SearchOptions::cancel```).
+    pub fn evaluate_moves(&self, options: &SearchOptions) -> Result<[Option<f64>; 4], Error> {
+        self.evaluate_moves_counted(options).map(|(values, _, _)| values)
+    }
+
+    /// Evaluates all valid moves like ```evaluate_moves```, finds the rates of the early losses and counts the simulated games of every move.
+    /// # Arguments
+    /// * ```options``` - The options of the search.
+    /// # Returns
+    /// * ```Ok(([Option<f64>; 4], [Option<f64>; 4], [usize; 4]))``` - The average values, the rates of the early losses (see ```SearchReport::early_loss_rate```)
+    ///   and the numbers of the simulated games for every move.
+    /// * ```Err(Error)``` - The depth is invalid.
+    /// # Errors
+    /// * ```Error::InvalidDepth``` - The depth is 0.
+    /// * ```Error::Cancelled``` - The search was cancelled (see ```SearchOptions::cancel```).
+    fn evaluate_moves_counted(&self, options: &SearchOptions) -> Result<MoveEvaluation, Error> {
+        let (moves_values, moves_simulations, early_losses) = self.simulate_moves(options)?;
+        #[cfg(feature = "tracing")]
+        for ind in (0..4).filter(|&ind| self.moves[ind]) {
+            tracing::trace!(direction = ?GameMove::from_index(ind), total = moves_values[ind], rollouts = moves_simulations[ind], "move evaluated");
+        }
+        Ok((
+            self.averages(moves_values.map(|value| value as f64), moves_simulations),
+            self.averages(early_losses.map(|losses| losses as f64), moves_simulations),
+            moves_simulations,
+        ))
+    }
+
+    /// Averages the totals of the simulated games of every move.
+    /// # Arguments
+    /// * ```totals``` - The totals for every move.
+    /// * ```simulations``` - The numbers of the simulated games for every move.
+    /// # Returns
+    /// * ```[Option<f64>; 4]``` - The averages, ```None``` for invalid moves and 0 for moves that got no simulated games.
+    fn averages(&self, totals: [f64; 4], simulations: [usize; 4]) -> [Option<f64>; 4] {
+        core::array::from_fn(|ind| match simulations[ind] {
+            _ if !self.moves[ind] => None,
+            0 => Some(0.0),
+            count => Some(totals[ind] / count as f64),
+        })
+    }
+
+    /// Ranks all valid moves from the best to the worst, for showing the hints to the players.
+    /// The moves are evaluated with the Monte Carlo search (see ```evaluate_moves```) and ranked by the average final score of their simulated games.
+    /// # Arguments
+    /// * ```depth``` - The number of simulated games to play, split between the valid moves.
+    /// # Returns
+    /// * ```Ok(Vec<MoveHint>)``` - The valid moves, from the best to the worst.
+    /// * ```Err(Error)``` - There are no valid moves left or the depth is invalid.
+    /// # Errors
+    /// * ```Error::NoValidMove``` - There are no valid moves left.
+    /// * ```Error::InvalidDepth``` - The depth is 0.
+    pub fn hint(&self, depth: usize) -> Result<Vec<MoveHint>, Error> {
+        if self.state == GameState::GameOver || self.moves.iter().all(|&valid| !valid) {
+            return Err(Error::NoValidMove);
+        }
+        let values = self.evaluate_moves(&SearchOptions { depth, ..Default::default() })?;
+
+        let mut hints: Vec<MoveHint> = GameMove::ALL
+            .into_iter()
+            .filter_map(|direction| {
+                let ind = direction.index();
+                let (board, gain) = self.peek_move(direction)?;
+                values[ind].map(|value| MoveHint {
+                    direction,
+                    value,
+                    gain,
+                    empty_cells: heuristics::empty_cells(&board),
+                })
+            })
+            .collect();
+        hints.sort_by(|first, second| second.value.total_cmp(&first.value));
+        Ok(hints)
+    }
+
+    /// Evaluates all valid moves like ```evaluate_moves_counted```, unless the cache already holds an evaluation of a symmetric board
+    /// with at least as many simulated games.
+    /// # Arguments
+    /// * ```options``` - The options of the search.
+    /// * ```cache``` - The cache of the evaluations.
+    /// # Returns
+    /// * ```Ok(([Option<f64>; 4], [Option<f64>; 4], [usize; 4]))``` - The average values, the rates of the early losses
+    ///   and the numbers of the simulated games for every move (no simulated games if the values were found in the cache).
+    /// * ```Err(Error)``` - The depth is invalid.
+    /// # Errors
+    /// * ```Error::InvalidDepth``` - The depth is 0.
+    /// * ```Error::Cancelled``` - The search was cancelled (see ```SearchOptions::cancel```).
+    fn evaluate_moves_cached(&self, options: &SearchOptions, cache: &mut EvaluationCache) -> Result<MoveEvaluation, Error> {
+        // the cached values are stored in the orientation of the canonical board
+        let variant = symmetry::canonical_index(&self.board);
+        let key = EvaluationCache::moves_key(self.canonical_hash(), options.objective);
+        if let Some(CachedEval::Moves {
+            values,
+            early_loss_rate,
+            risk_horizon,
+            depth,
+        }) = cache.get(key)
+        {
+            if depth >= options.depth && risk_horizon == options.risk_horizon {
+                let actual = |canonical: [Option<f64>; 4]| core::array::from_fn(|ind| canonical[symmetry::transform_move(GameMove::from_index(ind), variant).index()]);
+                return Ok((actual(values), actual(early_loss_rate), [0; 4]));
+            }
+        }
+
+        let (moves_values, moves_loss_rates, rollouts) = self.evaluate_moves_counted(options)?;
+        let (mut values, mut early_loss_rate) = ([None; 4], [None; 4]);
+        for direction in GameMove::ALL {
+            let canonical = symmetry::transform_move(direction, variant).index();
+            values[canonical] = moves_values[direction.index()];
+            early_loss_rate[canonical] = moves_loss_rates[direction.index()];
+        }
+        cache.insert(
+            key,
+            CachedEval::Moves {
+                values,
+                early_loss_rate,
+                risk_horizon: options.risk_horizon,
+                depth: options.depth,
+            },
+        );
+        Ok((moves_values, moves_loss_rates, rollouts))
+    }
+
+    /// Evaluates all valid moves like ```evaluate_moves_counted```, counting the simulated games stored for a symmetric board
+    /// by the previous searches as already played. Only the missing simulated games are played,
+    /// and the positions they reach after the first move are stored for the next searches.
+    /// # Arguments
+    /// * ```options``` - The options of the search.
+    /// * ```store``` - The statistics of the simulated games of the previous searches.
+    /// # Returns
+    /// * ```Ok(([Option<f64>; 4], [Option<f64>; 4], [usize; 4]))``` - The average values and the rates of the early losses
+    ///   of the stored and the new simulated games, and the numbers of the new simulated games for every move.
+    /// * ```Err(Error)``` - The search was cancelled.
+    /// # Errors
+    /// * ```Error::Cancelled``` - The search was cancelled (see ```SearchOptions::cancel```).
+    fn evaluate_moves_warm(&self, options: &SearchOptions, store: &mut RolloutStore) -> Result<MoveEvaluation, Error> {
+        // the statistics are stored in the orientation of the canonical board
+        let variant = symmetry::canonical_index(&self.board);
+        let key = EvaluationCache::moves_key(self.canonical_hash(), options.objective);
+        let stored = store.get(key).unwrap_or([(0, 0, 0); 4]);
+        let seeded: MoveStats = core::array::from_fn(|ind| {
+            if self.moves[ind] {
+                stored[symmetry::transform_move(GameMove::from_index(ind), variant).index()]
+            } else {
+                (0, 0, 0)
+            }
+        });
+
+        let targets = self.move_depths(options.depth);
+        let fresh_depths = core::array::from_fn(|ind| targets[ind].saturating_sub(seeded[ind].1));
+        let mut children = HashMap::new();
+        let (moves_values, rollouts, early_losses) = self.simulate_move_games(options, fresh_depths, Some(&mut children))?;
+        store.merge(children);
+
+        let totals: MoveStats = core::array::from_fn(|ind| {
+            let (value, count, losses) = seeded[ind];
+            (value + moves_values[ind], count + rollouts[ind], losses + early_losses[ind])
+        });
+        let mut canonical_totals = [(0, 0, 0); 4];
+        for direction in GameMove::ALL {
+            canonical_totals[symmetry::transform_move(direction, variant).index()] = totals[direction.index()];
+        }
+        store.replace(key, canonical_totals);
+
+        let simulations = totals.map(|(_, count, _)| count);
+        Ok((
+            self.averages(totals.map(|(value, _, _)| value as f64), simulations),
+            self.averages(totals.map(|(_, _, losses)| losses as f64), simulations),
+            rollouts,
+        ))
+    }
+
+    /// Plays batches of simulated games on the current thread until the search is cancelled,
+    /// adding the results of every finished batch to the statistics. Used by ```SearchEngine::start_pondering```.
+    /// The depth and the backend of the options are ignored, the unfinished batch is discarded.
+    /// # Arguments
+    /// * ```options``` - The options of the search. The search never stops without ```options.cancel```.
+    /// * ```stats``` - The summed values and the numbers of the simulated games for every move.
+    /// * ```batch_done``` - The signal notified after every finished batch.
+    pub(crate) fn ponder(&self, options: &SearchOptions, stats: &Mutex<([u128; 4], [usize; 4])>, batch_done: &Condvar) {
+        let possible_moves_count = self.moves.iter().filter(|&&valid| valid).count();
+        if possible_moves_count == 0 {
+            return;
+        }
+        let mut batch_options = SearchOptions {
+            depth: PONDER_BATCH * possible_moves_count,
+            backend: SearchBackend::CurrentThread,
+            progress: None,
+            ..options.clone()
+        };
+        for batch in 0_u64.. {
+            // the batches are seeded differently, so they don't repeat the same games
+            batch_options.seed = options.seed.map(|seed| derive_seed(seed, batch));
+            let Ok((moves_values, moves_simulations, _)) = self.simulate_moves(&batch_options) else {
+                return;
+            };
+            let mut stats = stats.lock().unwrap();
+            for ind in 0..4 {
+                stats.0[ind] += moves_values[ind];
+                stats.1[ind] += moves_simulations[ind];
+            }
+            batch_done.notify_all();
+        }
+    }
+
+    /// Plays exactly ```options.depth``` simulated games split between the valid moves and the threads.
+    /// # Arguments
+    /// * ```options``` - The options of the search.
+    /// # Returns
+    /// * ```Ok(([u128; 4], [usize; 4], [usize; 4]))``` - The summed values of the simulated games, the number of simulated games
+    ///   and the number of the simulated games lost within ```options.risk_horizon``` moves for every move.
+    /// * ```Err(Error)``` - The depth is invalid.
+    /// # Errors
+    /// * ```Error::InvalidDepth``` - The depth is 0.
+    /// * ```Error::Cancelled``` - The search was cancelled (see ```SearchOptions::cancel```).
+    fn simulate_moves(&self, options: &SearchOptions) -> Result<MoveTotals, Error> {
+        if options.depth == 0 {
+            return Err(Error::InvalidDepth);
+        }
+        self.simulate_move_games(options, self.move_depths(options.depth), None)
+    }
+
+    /// Splits the simulated games as evenly as possible between the valid moves.
+    /// # Arguments
+    /// * ```depth``` - The number of simulated games.
+    /// # Returns
+    /// * ```[usize; 4]``` - The number of simulated games of every move, 0 for invalid moves.
+    fn move_depths(&self, depth: usize) -> [usize; 4] {
+        let possible_moves: Vec<usize> = (0..4).filter(|&ind| self.moves[ind]).collect();
+        let mut move_depths = [0; 4];
+        for (nth_move, &move_ind) in possible_moves.iter().enumerate() {
+            move_depths[move_ind] = Self::split_evenly(depth, possible_moves.len(), nth_move);
+        }
+        move_depths
+    }
+
+    /// Plays the given number of simulated games for every move, split between the threads.
+    /// # Arguments
+    /// * ```options``` - The options of the search (the depth is given by the numbers of the games).
+    /// * ```move_depths``` - The number of simulated games of every move, 0 for invalid moves.
+    /// * ```children``` - The statistics of the positions after the first move and the spawned tile, if they are collected.
+    /// # Returns
+    /// * ```Ok(([u128; 4], [usize; 4], [usize; 4]))``` - The summed values of the simulated games, the number of simulated games
+    ///   and the number of the simulated games lost early for every move.
+    /// * ```Err(Error)``` - The search was cancelled.
+    /// # Errors
+    /// * ```Error::Cancelled``` - The search was cancelled (see ```SearchOptions::cancel```).
+    fn simulate_move_games(&self, options: &SearchOptions, move_depths: [usize; 4], children: Option<&mut HashMap<u64, MoveStats>>) -> Result<MoveTotals, Error> {
+        let objective = options.objective;
+        let depth = move_depths.iter().sum();
+        if depth == 0 {
+            return Ok(([0; 4], [0; 4], [0; 4]));
+        }
+
+        let threads = options.threads();
+
+        // split the games of every move between the threads
+        let mut chunks = vec![Vec::new(); threads];
+        for (move_ind, &move_depth) in move_depths.iter().enumerate() {
+            for (nth_thread, thread_chunks) in chunks.iter_mut().enumerate() {
+                let thread_depth = Self::split_evenly(move_depth, threads, nth_thread);
+                if thread_depth != 0 {
+                    let seed = options.seed.map(|seed| derive_seed(seed, (nth_thread * 4 + move_ind) as u64));
+                    thread_chunks.push((move_ind, thread_depth, seed));
+                }
+            }
+        }
+
+        let shared = Arc::new(SharedSearch {
+            moves_values: Default::default(),
+            moves_rollouts: Default::default(),
+            rollouts_done: AtomicUsize::new(0),
+            depth,
+            objective,
+            risk_horizon: options.risk_horizon as u64,
+            early_losses: Default::default(),
+            progress: options.progress.clone(),
+            progress_interval: options.progress_interval.max(1),
+            spawn_config: self.spawn_config.clone(),
+            merge_rule: self.merge_rule.clone(),
+            spawn_weight: self.spawn_weight.clone(),
+            max_turns: self.max_turns.map(|max_turns| max_turns.saturating_sub(self.turns)),
+            junk_every: self.junk_every,
+            junk_offset: self.junk_offset + self.turns,
+            cancel: options.cancel.clone(),
+            children: children.as_ref().map(|_| Mutex::new(HashMap::new())),
+        });
+        let board = &self.board;
+
+        match (&options.executor, options.backend) {
+            (Some(executor), _) => {
+                let (sender, receiver) = mpsc::channel();
+                let mut jobs = 0;
+                for thread_chunks in chunks.into_iter().filter(|thread_chunks| !thread_chunks.is_empty()) {
+                    let (shared, sender, board) = (Arc::clone(&shared), sender.clone(), *board);
+                    executor.execute(Box::new(move || {
+                        for (move_ind, count, seed) in thread_chunks {
+                            shared.run_chunk(&board, move_ind, count, seed);
+                        }
+                        // the shared state is released before the search learns that the job is finished
+                        drop(shared);
+                        let _ = sender.send(());
+                    }));
+                    jobs += 1;
+                }
+                drop(sender);
+                for _ in 0..jobs {
+                    receiver.recv().expect("a job of the search panicked");
+                }
+            }
+            (None, SearchBackend::Threads) => thread::scope(|scope| {
+                let shared = &shared;
+                for thread_chunks in chunks.into_iter().filter(|thread_chunks| !thread_chunks.is_empty()) {
+                    scope.spawn(move || {
+                        for (move_ind, count, seed) in thread_chunks {
+                            shared.run_chunk(board, move_ind, count, seed);
+                        }
+                    });
+                }
+            }),
+            (None, SearchBackend::CurrentThread) => {
+                for (move_ind, count, seed) in chunks.into_iter().flatten() {
+                    shared.run_chunk(board, move_ind, count, seed);
+                }
+            }
+            #[cfg(feature = "rayon")]
+            (None, SearchBackend::Rayon) => {
+                use rayon::prelude::*;
+                chunks
+                    .into_par_iter()
+                    .flatten()
+                    .for_each(|(move_ind, count, seed)| shared.run_chunk(board, move_ind, count, seed));
+            }
+        }
+
+        if options.cancel.as_ref().is_some_and(CancellationToken::is_cancelled) {
+            return Err(Error::Cancelled);
+        }
+        let shared = Arc::into_inner(shared).expect("the jobs of the search release the shared state when they finish");
+        let moves_values = shared.published();
+        if let (Some(children), Some(shared_children)) = (children, shared.children) {
+            *children = shared_children.into_inner().unwrap();
+        }
+        Ok((moves_values.0, moves_values.1, shared.early_losses.map(AtomicUsize::into_inner)))
+    }
+
+    /// Finds the move with the highest average value of the simulated games.
+    /// # Arguments
+    /// * ```values``` - The summed values of the simulated games for every move.
+    /// * ```simulations``` - The number of simulated games for every move.
+    /// # Returns
+    /// * ```Some(GameMove)``` - The move with the highest average value.
+    /// * ```None``` - No move has any simulated games.
+    fn best_average(values: &[u128; 4], simulations: &[usize; 4]) -> Option<GameMove> {
+        (0..4)
+            .filter(|&ind| simulations[ind] != 0)
+            .map(|ind| (ind, values[ind] as f64 / simulations[ind] as f64))
+            .max_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap_or(Ordering::Equal))
+            .map(|(ind, _)| GameMove::from_index(ind))
+    }
+
+    /// Calculates the size of a part when splitting the total as evenly as possible, the first parts getting the remainder.
+    /// # Arguments
+    /// * ```total``` - The total to split.
+    /// * ```parts``` - The number of parts.
+    /// * ```part``` - The index of the part.
+    /// # Returns
+    /// * ```usize``` - The size of the part.
+    fn split_evenly(total: usize, parts: usize, part: usize) -> usize {
+        total / parts + usize::from(part < total % parts)
+    }
+
+    /// Checks whether the spawned tiles can force the game over after making the given move.
+    /// # Arguments
+    /// * ```direction``` - The move to make. Must be a valid move.
+    /// * ```plies``` - The number of spawned tiles to consider.
+    /// # Returns
+    /// * ```true``` - Some sequence of spawns ends the game within the given number of spawns, whatever moves are made.
+    /// * ```false``` - The game can be kept alive.
+    fn loss_forced(&self, direction: GameMove, plies: usize) -> bool {
+        plies > 0
+            && self.expand(direction).into_iter().flatten().any(|(board, _)| {
+                let game = self.derive_game_ahead(&board, 1);
+                GameMove::ALL.into_iter().all(|next| !game.can_move(next) || game.loss_forced(next, plies - 1))
+            })
+    }
+
+    /// Plays a simulated game with random moves from the current position, the same way as the simulated games of ```find_best_move```,
+    /// e.g. for the searches built outside of the crate. The game is not changed.
+    /// The simulated game keeps the rules of the game (the target, the remaining turns, the spawn distribution, the junk tiles and the merge rule).
+    /// 4x4 boards with the classic rules are simulated on a bitboard, other boards on a single game reused for all the moves, without validating the board.
+    /// # Arguments
+    /// * ```first_move``` - The first move to make, ```None``` for a random valid move. An impossible move ends the simulated game without any move.
+    /// * ```rng``` - The random number generator choosing the moves and the spawned tiles.
+    /// * ```max_moves``` - The maximum number of moves, ```None``` for playing to the end.
+    /// # Returns
+    /// * ```RolloutResult``` - The end of the simulated game. The game over or a simulated game without any move ends with the score and the result of the game.
+    pub fn rollout<R: Rng>(&self, first_move: Option<GameMove>, rng: &mut R, max_moves: Option<u64>) -> RolloutResult {
+        let first_move = first_move.or_else(|| GameMove::ALL.into_iter().filter(|&direction| self.can_move(direction)).choose(rng));
+        match first_move.filter(|&direction| self.can_move(direction) && max_moves != Some(0)) {
+            Some(direction) => self.derive_game(&self.board).play_rollout(&self.board, self.score, direction, max_moves, rng),
+            None => RolloutResult {
+                score: self.score,
+                moves: 0,
+                highest_tile: self.highest_tile(),
+                final_result: self.result,
+            },
+        }
+    }
+
+    /// Find the best move to make, scaling the number of simulated games by the fullness of the board.
+    /// See ```AdaptivePolicy``` for the details of the scaling.
+    /// # Arguments
+    /// * ```base_depth``` - The number of simulated games to play on a nearly empty board.
+    /// * ```policy``` - The policy scaling the depth.
+    /// # Returns
+    /// * ```Ok(GameMove)``` - The best move to make.
+    /// * ```Err(Error)``` - There are no valid moves left or the scaled depth is invalid.
+    /// # Errors
+    /// * ```Error::NoValidMove``` - There are no valid moves left.
+    /// * ```Error::InvalidDepth``` - The scaled depth is 0.
+    pub fn find_best_move_adaptive(&self, base_depth: usize, policy: &AdaptivePolicy) -> Result<GameMove, Error> {
+        self.find_best_move(policy.scaled_depth::<SIZE>(base_depth, self.empty_cells))
+    }
+
+    /// Find the best move to make by exhaustively expanding all moves and spawns to the given number of moves.
+    /// Returns the move that maximises the probability of surviving the given number of moves,
+    /// ties are resolved by the expected score.
+    /// The number of expanded positions grows exponentially with the number of moves and empty cells,
+    /// so this is meant for nearly full boards (e.g. 3 or fewer empty cells), where the Monte Carlo search is unreliable.
+    /// # Arguments
+    /// * ```plies``` - The number of moves to look ahead (at least 1).
+    /// # Returns
+    /// * ```Ok(GameMove)``` - The best move to make.
+    /// * ```Err(Error)``` - There are no valid moves left.
+    /// # Errors
+    /// * ```Error::NoValidMove``` - There are no valid moves left.
+    pub fn find_best_move_exact(&self, plies: usize) -> Result<GameMove, Error> {
+        self.find_best_move_exact_cached(plies, None)
+    }
+
+    /// Find the best move to make with the exhaustive search, reusing the values of the positions stored in the cache.
+    /// Used by ```SearchEngine```.
+    /// # Arguments
+    /// * ```plies``` - The number of moves to look ahead (at least 1).
+    /// * ```cache``` - The cache of the evaluations, if any.
+    /// # Returns
+    /// * ```Ok(GameMove)``` - The best move to make.
+    /// * ```Err(Error)``` - There are no valid moves left.
+    /// # Errors
+    /// * ```Error::NoValidMove``` - There are no valid moves left.
+    pub(crate) fn find_best_move_exact_cached(&self, plies: usize, mut cache: Option<&mut EvaluationCache>) -> Result<GameMove, Error> {
+        GameMove::ALL
+            .into_iter()
+            .filter_map(|direction| Some((direction, self.expectimax_move(direction, plies.max(1), cache.as_deref_mut())?)))
+            .max_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap_or(Ordering::Equal))
+            .map(|(direction, _)| direction)
+            .ok_or(Error::NoValidMove)
+    }
+
+    /// Calculates the value of making the given move and then playing optimally.
+    /// # Arguments
+    /// * ```direction``` - The move to make.
+    /// * ```plies``` - The number of moves to look ahead, including this move.
+    /// * ```cache``` - The cache of the values of the positions, if any.
+    /// # Returns
+    /// * ```Some((f64, f64))``` - The probability of surviving and the expected score.
+    /// * ```None``` - The move is invalid.
+    fn expectimax_move(&self, direction: GameMove, plies: usize, mut cache: Option<&mut EvaluationCache>) -> Option<(f64, f64)> {
+        let (_, gained) = self.peek_move(direction)?;
+        let mut value = (0.0, gained as f64);
+        for (board, probability) in self.expand(direction)? {
+            let (survival, score) = self.expectimax(&board, plies - 1, cache.as_deref_mut());
+            value.0 += probability * survival;
+            value.1 += probability * score;
+        }
+        Some(value)
+    }
+
+    /// Calculates the value of the given board when playing optimally.
+    /// # Arguments
+    /// * ```board``` - The board to evaluate, after a move and the spawned tile.
+    /// * ```plies``` - The number of moves to look ahead.
+    /// * ```cache``` - The cache of the values of the positions, if any.
+    /// # Returns
+    /// * ```(f64, f64)``` - The probability of surviving and the expected score.
+    fn expectimax(&self, board: &[[u64; SIZE]; SIZE], plies: usize, mut cache: Option<&mut EvaluationCache>) -> (f64, f64) {
+        // the board is one turn ahead of this game
+        let game = self.derive_game_ahead(board, 1);
+        if game.state == GameState::GameOver {
+            return (0.0, 0.0);
+        }
+        if plies == 0 {
+            return (1.0, 0.0);
+        }
+
+        // the value of a position doesn't change under the symmetries, so the symmetric positions share the entry
+        let key = cache.is_some().then(|| EvaluationCache::node_key(symmetry::canonical_hash(board), plies));
+        if let (Some(cache), Some(key)) = (cache.as_deref_mut(), key) {
+            if let Some(CachedEval::Node { survival, score }) = cache.get(key) {
+                return (survival, score);
+            }
+        }
+        let value = GameMove::ALL
+            .into_iter()
+            .filter_map(|direction| game.expectimax_move(direction, plies, cache.as_deref_mut()))
+            .max_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal))
+            .unwrap();
+        if let (Some(cache), Some(key)) = (cache, key) {
+            cache.insert(
+                key,
+                CachedEval::Node {
+                    survival: value.0,
+                    score: value.1,
+                },
+            );
+        }
+        value
+    }
+
+    /// Creates a copy of the game for the simulations, used by the searches of the AI.
+    /// Only the board, the score, the state, the result and the configuration (the target, the remaining turns, the spawn distribution,
+    /// the merge rule and the lazy moves) are copied. The fork has no observer, no subscribers, no spawner (the tiles spawn
+    /// from the spawn distribution), no tile identities, no history and no timing, so the moves made on the fork never notify
+    /// the observers of this game and copy no logs.
+    /// The fork of a seeded game gets a fresh generator seeded from the generator of the game, without advancing it,
+    /// so the forks of the same position spawn the same tiles. The fork of an unseeded game spawns from the thread-local generator.
+    /// # Returns
+    /// * ```Game``` - The fork.
+    pub fn fork(&self) -> Self {
+        let mut fork = self.derive_game(&self.board);
+        fork.score = self.score;
+        fork.state = self.state;
+        fork.result = self.result;
+        fork.lazy_moves = self.lazy_moves;
+        fork.rng = self.rng.clone().map(|mut rng| StdRng::seed_from_u64(rng.gen()));
+        if self.awaiting_tile {
+            fork.awaiting_tile = true;
+            fork.moves = [false; 4];
+        }
+        fork
+    }
+
+    /// Creates a game on the given board with the same rules (the target, the remaining turns, the spawn distribution, the junk tiles and the merge rule) as this game.
+    /// Used internally by the searches. The board is not validated.
+    /// # Arguments
+    /// * ```board``` - The board to use.
+    /// # Returns
+    /// * ```Game``` - The new game, with the score of 0.
+    pub(crate) fn derive_game(&self, board: &[[u64; SIZE]; SIZE]) -> Self {
+        self.derive_game_ahead(board, 0)
+    }
+
+    /// Creates a game on the given board like ```derive_game```, for a position the given number of turns ahead of this game.
+    /// # Arguments
+    /// * ```board``` - The board to use.
+    /// * ```turns_ahead``` - The number of turns between this game and the board, which are not left before the limit of the turns
+    ///   and which move the schedule of the junk tiles.
+    /// # Returns
+    /// * ```Game``` - The new game, with the score of 0.
+    fn derive_game_ahead(&self, board: &[[u64; SIZE]; SIZE], turns_ahead: u64) -> Self {
+        let mut game = Self::from_board_unchecked(board, 0)
+            .with_spawn_config(self.spawn_config.clone())
+            .with_merge_rule_unchecked(self.merge_rule.clone());
+        game.target = self.target;
+        game.spawn_weight = self.spawn_weight.clone();
+        game.junk_every = self.junk_every;
+        game.junk_offset = self.junk_offset + self.turns + turns_ahead;
+        game.puzzle = self.puzzle;
+        // the new game starts at the turn 0, so only the remaining turns are left
+        game.max_turns = self.max_turns.map(|max_turns| max_turns.saturating_sub(self.turns + turns_ahead));
+        if game.max_turns.is_some() {
+            game.update();
+        }
+        game
+    }
+
+    /// Plays a simulated game with random moves to the end, starting with the given move (see ```play_rollout```).
+    /// # Arguments
+    /// * ```board``` - The board to start from.
+    /// * ```first_move``` - The first move to make. Must be a valid move.
+    /// * ```objective``` - The value of the simulated game to return.
+    /// * ```rng``` - The random number generator to use.
+    /// # Returns
+    /// * ```(u64, u64)``` - The value of the simulated game and the number of moves it lasted.
+    fn simulate<R: Rng>(&mut self, board: &[[u64; SIZE]; SIZE], first_move: GameMove, objective: SearchObjective, rng: &mut R) -> (u64, u64) {
+        let end = self.play_rollout(board, 0, first_move, None, rng);
+        (objective.value(end.score, end.highest_tile, end.moves), end.moves)
+    }
+
+    /// Plays a simulated game with random moves, starting with the given move.
+    /// 4x4 boards with the classic rules are simulated on a bitboard, which is much faster,
+    /// other boards are simulated on this game, which is reset to the given board first.
+    /// # Arguments
+    /// * ```board``` - The board to start from.
+    /// * ```score``` - The score to start from.
+    /// * ```first_move``` - The first move to make. Must be a valid move.
+    /// * ```max_moves``` - The maximum number of moves (at least 1), ```None``` for playing to the end.
+    /// * ```rng``` - The random number generator to use.
+    /// # Returns
+    /// * ```RolloutResult``` - The end of the simulated game.
+    fn play_rollout<R: Rng>(&mut self, board: &[[u64; SIZE]; SIZE], score: u64, first_move: GameMove, max_moves: Option<u64>, rng: &mut R) -> RolloutResult {
+        // the rollouts on the bitboard don't stop at the limit of the turns, spawn the tiles uniformly and never spawn junk
+        let bitboard = BitBoard::from_board(board)
+            .filter(|_| self.merge_rule.is_none() && self.spawn_weight.is_none() && self.junk_every.is_none() && self.max_turns.is_none())
+            .filter(|_| self.spawn_config.max_value() <= BitBoard::MAX_TILE);
+        let (end, over) = match bitboard.map(|bitboard| bitboard.rollout(first_move, &self.spawn_config, max_moves, rng)) {
+            Some(RolloutEnd::Finished(bitboard, gained, turns)) => ((bitboard, gained, turns), true),
+            Some(RolloutEnd::Stopped(bitboard, gained, turns)) => ((bitboard, gained, turns), false),
+            Some(RolloutEnd::Overflow(bitboard, gained, turns, next_move)) => {
+                self.reset_to(&bitboard.to_board(), score.saturating_add(gained));
+                self.turns = turns;
+                return self.finish_rollout(next_move, max_moves, rng);
+            }
+            None => {
+                self.reset_to(board, score);
+                return self.finish_rollout(first_move, max_moves, rng);
+            }
+        };
+
+        let (bitboard, gained, turns) = end;
+        let highest_tile = bitboard.highest_tile();
+        let victory = self.target.is_some_and(|target| highest_tile >= target);
+        RolloutResult {
+            score: score.saturating_add(gained),
+            moves: turns,
+            highest_tile,
+            final_result: match (victory, over) {
+                (true, true) => GameResult::VictoryThenLoss,
+                (true, false) => GameResult::Victory,
+                (false, true) => GameResult::Loss,
+                (false, false) => GameResult::Pending,
+            },
+        }
+    }
+
+    /// Continues the simulated game of ```play_rollout``` on this game with random moves.
+    /// # Arguments
+    /// * ```next_move``` - The next move to make. Must be a valid move.
+    /// * ```max_moves``` - The maximum number of moves, including the moves made before, ```None``` for playing to the end.
+    /// * ```rng``` - The random number generator to use.
+    /// # Returns
+    /// * ```RolloutResult``` - The end of the simulated game.
+    fn finish_rollout<R: Rng>(&mut self, next_move: GameMove, max_moves: Option<u64>, rng: &mut R) -> RolloutResult {
+        self.make_move(next_move);
+        while self.state == GameState::InProgress && max_moves.is_none_or(|max_moves| self.turns < max_moves) {
+            self.make_move(
+                self.moves
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(i, &b)| if b { Some(GameMove::from_index(i)) } else { None })
+                    .choose(rng)
+                    .unwrap(),
+            );
+        }
+
+        RolloutResult {
+            score: self.score,
+            moves: self.turns,
+            highest_tile: self.highest_tile(),
+            final_result: self.result,
+        }
+    }
+
+    /// Plays a simulated game like ```simulate```, but makes the first move and spawns the tile on this game,
+    /// so that the value of the rest of the game can be recorded for the reached position and the random second move.
+    /// # Arguments
+    /// * ```board``` - The board to start from.
+    /// * ```first_move``` - The first move to make. Must be a valid move.
+    /// * ```objective``` - The value of the simulated game to return.
+    /// * ```risk_horizon``` - The number of moves within which a lost simulated game counts as an early loss.
+    /// * ```rng``` - The random number generator to use.
+    /// * ```children``` - The statistics of the reached positions, keyed like ```SearchEngine``` keys them,
+    ///   with the second moves in the orientation of the canonical board.
+    /// # Returns
+    /// * ```(u64, u64)``` - The value of the simulated game and the number of moves it lasted.
+    fn simulate_recorded<R: Rng>(
+        &mut self,
+        board: &[[u64; SIZE]; SIZE],
+        first_move: GameMove,
+        objective: SearchObjective,
+        risk_horizon: u64,
+        rng: &mut R,
+        children: &mut HashMap<u64, MoveStats>,
+    ) -> (u64, u64) {
+        self.reset_to(board, 0);
+        self.make_move(first_move);
+        let gain = self.score;
+        let child = self.board;
+        let Some(second_move) = (0..4).filter(|&ind| self.moves[ind]).map(GameMove::from_index).choose(rng) else {
+            return (objective.value(gain, self.highest_tile(), 1), 1);
+        };
+
+        let (value, turns) = self.simulate(&child, second_move, objective, rng);
+        let key = EvaluationCache::moves_key(symmetry::canonical_hash(&child), objective);
+        let canonical_move = symmetry::transform_move(second_move, symmetry::canonical_index(&child));
+        let stats = &mut children.entry(key).or_insert([(0, 0, 0); 4])[canonical_move.index()];
+        *stats = (stats.0 + u128::from(value), stats.1 + 1, stats.2 + usize::from(turns <= risk_horizon));
+        (objective.before_first_move(value, gain), turns + 1)
+    }
+}
+impl Default for Game {
+    /// Creates a new game of 2048 with a 4x4 board.
+    fn default() -> Self {
+        Self::new().expect("4x4 is a valid size")
+    }
+}
+/// The games are compared by the observable state: the board, the score, the state and the result.
+/// The random number generator and the rest of the configuration are not compared,
+/// so two equal games may still diverge because of the spawned tiles.
+impl<const SIZE: usize> PartialEq for Game<SIZE> {
+    fn eq(&self, other: &Self) -> bool {
+        self.board == other.board && self.score == other.score && self.state == other.state && self.result == other.result
+    }
+}
+impl<const SIZE: usize> Eq for Game<SIZE> {}
+/// Hashes the same observable state that is compared by ```PartialEq```.
+impl<const SIZE: usize> Hash for Game<SIZE> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.board.hash(state);
+        self.score.hash(state);
+        self.state.hash(state);
+        self.result.hash(state);
+    }
+}
+impl<const SIZE: usize> TryFrom<Vec<Vec<u64>>> for Game<SIZE> {
+    type Error = Error;
+
+    /// Creates a game from the rows of an existing board with the score of 0 (see ```Game::from_rows```).
+    fn try_from(rows: Vec<Vec<u64>>) -> Result<Self, Self::Error> {
+        Self::from_rows(&rows, 0)
+    }
+}
+impl<const SIZE: usize> TryFrom<[[u64; SIZE]; SIZE]> for Game<SIZE> {
+    type Error = Error;
+
+    /// Creates a game from an existing board with the score of 0 (see ```Game::from_existing```).
+    fn try_from(board: [[u64; SIZE]; SIZE]) -> Result<Self, Self::Error> {
+        Self::from_existing(&board, 0)
+    }
+}
+/// Generates valid games for fuzzing. Available with the ```arbitrary``` feature.
+/// The tiles are empty or powers of 2 up to 2^17, the score is at most the score of creating all the tiles by merging twos,
+/// and the game is created with ```Game::from_existing```, so it passes the same validation as any other board.
+/// An empty board gets a tile at a generated position and the game is seeded, so the generated game is reproducible.
+#[cfg(feature = "arbitrary")]
+impl<'a, const SIZE: usize> arbitrary::Arbitrary<'a> for Game<SIZE> {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        let mut board = [[0; SIZE]; SIZE];
+        let mut max_score: u64 = 0;
+        for tile in board.iter_mut().flatten() {
+            let exponent: u32 = u.int_in_range(0..=17)?;
+            if exponent > 0 {
+                *tile = 1 << exponent;
+                max_score += u64::from(exponent - 1) << exponent;
+            }
+        }
+        if board.iter().flatten().all(|&tile| tile == 0) {
+            board[u.choose_index(SIZE)?][u.choose_index(SIZE)?] = 2;
+        }
+        let score = u.int_in_range(0..=max_score)?;
+
+        let mut game = Self::from_existing(&board, score).map_err(|_| arbitrary::Error::IncorrectFormat)?;
+        game.rng = Some(StdRng::seed_from_u64(u.arbitrary()?));
+        Ok(game)
+    }
+}
+impl<'a, const SIZE: usize> IntoIterator for &'a Game<SIZE> {
+    type Item = &'a [u64; SIZE];
+    type IntoIter = core::slice::Iter<'a, [u64; SIZE]>;
+
+    /// Iterates over the rows of the board, from the top.
+    fn into_iter(self) -> Self::IntoIter {
+        self.board.iter()
+    }
+}
+impl<const SIZE: usize> Display for Game<SIZE> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let output = self.render(|tile| match tile {
+            BLOCKED_CELL => String::from("#"),
+            JUNK_TILE => String::from("X"),
+            tile => tile.to_string(),
+        });
+        write!(f, "{}", output)
+    }
+}
+impl<const SIZE: usize> FromStr for Game<SIZE> {
+    type Err = Error;
+
+    /// Parses the compact form of the game written by ```Game::to_compact_string```.
+    /// The board is validated and the state of the game is determined from it, like in ```Game::from_existing```,
+    /// or in ```Game::from_existing_with_rule``` with the built-in merge rule named in the line.
+    /// # Errors
+    /// * ```Error::InvalidSize```: The SIZE is invalid. Must be at least 2.
+    /// * ```Error::InvalidEncoding```: The line is malformed.
+    /// * ```Error::InvalidBoard```: The size of the game, the number of the rows or the length of a row is not SIZE.
+    /// * ```Error::InvalidValue```: The board contains a value that is not 0, a junk tile or a valid tile of the merge rule.
+    /// * ```Error::InvalidConfig```: The merge rule is not built in (see ```rules::from_name```).
+    fn from_str(line: &str) -> Result<Self, Self::Err> {
+        let (board, score, rule) = Self::parse_compact_string(line)?;
+        let blocked: Vec<(usize, usize)> = (0..SIZE * SIZE)
+            .map(|cell| (cell / SIZE, cell % SIZE))
+            .filter(|&(row, col)| board[row][col] == BLOCKED_CELL)
+            .collect();
+        let board = board.map(|row| row.map(|tile| if tile == BLOCKED_CELL { 0 } else { tile }));
+        let game = match rule {
+            "classic" => Self::from_existing(&board, score)?,
+            rule => Self::from_existing_with_rule(&board, score, rules::from_name(rule).ok_or(Error::InvalidConfig)?)?,
+        };
+        if blocked.is_empty() {
+            Ok(game)
+        } else {
+            game.with_blocked_cells(&blocked)
+        }
+    }
+}
+impl<const SIZE: usize> Game<SIZE> {
+    /// Parses the board, the score and the name of the merge rule from the compact form of the game written by ```Game::to_compact_string```,
+    /// without validating the tiles.
+    /// # Arguments
+    /// * ```line``` - The compact form.
+    /// # Returns
+    /// * ```Ok(([[u64; SIZE]; SIZE], u64, &str))``` - The board, with ```BLOCKED_CELL``` in the blocked cells, the score
+    ///   and the name of the merge rule (```classic``` if the line doesn't name one).
+    /// * ```Err(Error)``` - The line is malformed.
+    /// # Errors
+    /// * ```Error::InvalidEncoding```: The line is malformed.
+    /// * ```Error::InvalidBoard```: The size of the game, the number of the rows or the length of a row is not SIZE.
+    pub(crate) fn parse_compact_string(line: &str) -> Result<([[u64; SIZE]; SIZE], u64, &str), Error> {
+        let (size, rest) = line.split_once('|').ok_or(Error::InvalidEncoding)?;
+        let size: usize = size.parse().map_err(|_| Error::InvalidEncoding)?;
+        if size != SIZE {
+            return Err(Error::InvalidBoard { expected: SIZE, actual: size });
+        }
+        let (rest, rule) = rest.rsplit_once("/rule=").unwrap_or((rest, "classic"));
+        let (rows, score) = rest.rsplit_once('/').ok_or(Error::InvalidEncoding)?;
+        let score = score.strip_prefix("score=").and_then(|score| score.parse().ok()).ok_or(Error::InvalidEncoding)?;
+
+        let rows: Vec<&str> = rows.split('/').collect();
+        if rows.len() != SIZE {
+            return Err(Error::InvalidBoard {
+                expected: SIZE,
+                actual: rows.len(),
+            });
+        }
+        let mut board = [[0; SIZE]; SIZE];
+        for (row, board_row) in rows.into_iter().zip(board.iter_mut()) {
+            let tiles: Vec<&str> = row.split(',').collect();
+            if tiles.len() != SIZE {
+                return Err(Error::InvalidBoard {
+                    expected: SIZE,
+                    actual: tiles.len(),
+                });
+            }
+            for (tile, cell) in tiles.into_iter().zip(board_row.iter_mut()) {
+                *cell = match tile {
+                    "#" => BLOCKED_CELL,
+                    "X" => JUNK_TILE,
+                    tile => tile.parse().map_err(|_| Error::InvalidEncoding)?,
+                };
+            }
+        }
+        Ok((board, score, rule))
+    }
+}
+
+/// A move begun with ```Game::begin_move```, whose slide is shown before the new tile is spawned.
+/// The game is changed only when the move is committed, so cancelling (or dropping) the pending move leaves the game exactly as it was.
+#[derive(Debug)]
+pub struct PendingMove<'a, const SIZE: usize> {
+    /// The game the move is made in.
+    game: &'a mut Game<SIZE>,
+    /// The direction of the move.
+    direction: GameMove,
+}
+impl<const SIZE: usize> PendingMove<'_, SIZE> {
+    /// Returns the direction of the move.
+    /// # Returns
+    /// * ```GameMove``` - The direction.
+    pub fn direction(&self) -> GameMove {
+        self.direction
+    }
+
+    /// Returns the board after the slide, before the new tile is spawned.
+    /// # Returns
+    /// * ```&[[u64; SIZE]; SIZE]``` - The board after the slide.
+    pub fn board(&self) -> &[[u64; SIZE]; SIZE] {
+        &self.game.moves_next[self.direction.index()]
+    }
+
+    /// Returns the score gained by the move.
+    /// # Returns
+    /// * ```u64``` - The gained score.
+    pub fn gained(&self) -> u64 {
+        self.game.score_next[self.direction.index()]
+    }
+
+    /// Returns the score of the game after the move.
+    /// # Returns
+    /// * ```u64``` - The score after the move.
+    pub fn score(&self) -> u64 {
+        self.game.score.saturating_add(self.gained())
+    }
+
+    /// Makes the move: slides the tiles, spawns the new tile and updates the game, like ```Game::make_move```.
+    /// # Returns
+    /// * ```((usize, usize), u64)``` - The position and the value of the spawned tile.
+    pub fn commit(self) -> ((usize, usize), u64) {
+        let moved = self.game.slide(self.direction);
+        debug_assert!(moved, "begin_move only accepts the valid moves");
+        let spawned = self.game.new_tile();
+        self.game.finish_turn(Some(spawned));
+        spawned
+    }
+
+    /// Cancels the move, the game stays as it was before ```Game::begin_move```.
+    pub fn cancel(self) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::ManualClock;
+    use crate::engine::SearchEngine;
+    use crate::executor::{InlineExecutor, Job, ThreadExecutor};
+    use crate::history::{MoveLog, RepetitionConfig};
+    use crate::rules::{FibonacciRule, ThreesRule};
+    use crate::spawn::{Corners, NearMaxTile, ScriptedSpawner};
+    use crate::strategy::RandomStrategy;
+    use std::collections::HashSet;
+
+    #[test]
+    fn create_game_4() {
+        //! Test the creation of a new game with the default size (4x4)
+
+        let game: Game<4> = Game::new().unwrap();
+        let game_from = Game::from_existing(game.board(), 0).unwrap();
+
+        assert_eq!(game.size(), 4);
+        assert_eq!(game_from.size(), 4);
+
+        assert_eq!(game.score(), 0);
+        assert_eq!(game_from.score(), 0);
+
+        assert_eq!(game.state(), GameState::InProgress);
+        assert_eq!(game_from.state(), GameState::InProgress);
+
+        assert_eq!(game.result(), GameResult::Pending);
+        assert_eq!(game_from.result(), GameResult::Pending);
+    }
+
+    #[test]
+    fn create_game_5() {
+        //! Test the creation of a new game with a bigger size (5x5)
+
+        let game: Game<5> = Game::new().unwrap();
+        let game_from = Game::from_existing(game.board(), 0).unwrap();
+
+        assert_eq!(game.size(), 5);
+        assert_eq!(game_from.size(), 5);
+
+        assert_eq!(game.score(), 0);
+        assert_eq!(game_from.score(), 0);
+
+        assert_eq!(game.state(), GameState::InProgress);
+        assert_eq!(game_from.state(), GameState::InProgress);
+
+        assert_eq!(game.result(), GameResult::Pending);
+        assert_eq!(game_from.result(), GameResult::Pending);
+    }
+
+    #[test]
+    fn tile_ids_tracking() {
+        //! Test that tile identities follow the tiles and are never reused
+
+        let moves = [GameMove::Left, GameMove::Right, GameMove::Up, GameMove::Down];
+        let mut game: Game<4> = Game::new().unwrap().with_tile_ids();
+        let mut seen = HashSet::new();
+        let mut moves_made = 0;
+
+        while moves_made < 500 {
+            if game.state() == GameState::GameOver {
+                game = Game::new().unwrap().with_tile_ids();
+                seen.clear();
+            }
+
+            let ids_before: HashSet<NonZeroU64> = game.tile_ids().unwrap().iter().flatten().flatten().copied().collect();
+            seen.extend(ids_before.iter().copied());
+            let tiles_before = game.board().iter().flatten().filter(|&&x| x != 0).count();
+
+            if !game.make_move(*moves.iter().choose(&mut thread_rng()).unwrap()) {
+                continue;
+            }
+            moves_made += 1;
+
+            let tile_ids = game.tile_ids().unwrap();
+            for (row, ids_row) in game.board().iter().zip(tile_ids.iter()) {
+                for (&tile, tile_id) in row.iter().zip(ids_row.iter()) {
+                    assert_eq!(tile != 0, tile_id.is_some());
+                }
+            }
+
+            let ids_after: HashSet<NonZeroU64> = tile_ids.iter().flatten().flatten().copied().collect();
+            let tiles_after = game.board().iter().flatten().filter(|&&x| x != 0).count();
+            assert_eq!(ids_after.len(), tiles_after);
+
+            // every merge and the spawned tile produce a new identity, which must never have been used before
+            let new_ids: Vec<NonZeroU64> = ids_after.difference(&ids_before).copied().collect();
+            assert_eq!(new_ids.len(), tiles_before + 1 - tiles_after + 1);
+            assert!(new_ids.iter().all(|tile_id| !seen.contains(tile_id)));
+        }
+    }
+
+    #[test]
+    fn observer_events() {
+        //! Test that the observer is notified about all events in the correct order
+
+        #[derive(Debug, PartialEq)]
+        enum Event {
+            Move(GameMove, u64),
+            Merge((usize, usize), u64),
+            Spawn((usize, usize), u64),
+            Victory,
+            GameOver,
+        }
+
+        struct Recorder(Arc<Mutex<Vec<Event>>>);
+        impl GameObserver for Recorder {
+            fn on_move(&mut self, direction: GameMove, gained: u64) {
+                self.0.lock().unwrap().push(Event::Move(direction, gained));
+            }
+            fn on_merge(&mut self, position: (usize, usize), value: u64) {
+                self.0.lock().unwrap().push(Event::Merge(position, value));
+            }
+            fn on_spawn(&mut self, position: (usize, usize), value: u64) {
+                self.0.lock().unwrap().push(Event::Spawn(position, value));
+            }
+            fn on_victory(&mut self) {
+                self.0.lock().unwrap().push(Event::Victory);
+            }
+            fn on_game_over(&mut self) {
+                self.0.lock().unwrap().push(Event::GameOver);
+            }
+        }
+
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let mut game = Game::from_existing(&[[1024, 1024, 4, 4], [0, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0]], 0).unwrap();
+        game.set_observer(Box::new(Recorder(Arc::clone(&events))));
+
+        assert!(!game.make_move(GameMove::Up));
+        assert!(events.lock().unwrap().is_empty());
+
+        assert!(game.make_move(GameMove::Left));
+        {
+            let events = events.lock().unwrap();
+            assert_eq!(events.len(), 5);
+            assert_eq!(events[0], Event::Move(GameMove::Left, 2056));
+            assert_eq!(events[1], Event::Merge((0, 0), 2048));
+            assert_eq!(events[2], Event::Merge((0, 1), 8));
+            assert!(matches!(events[3], Event::Spawn(position, value) if game.board()[position.0][position.1] == value));
+            assert_eq!(events[4], Event::Victory);
+        }
+
+        let mut game = Game::from_existing(&[[2, 4, 2, 4], [4, 2, 4, 2], [2, 4, 2, 4], [4, 2, 0, 0]], 0).unwrap();
+        events.lock().unwrap().clear();
+        game.set_observer(Box::new(Recorder(Arc::clone(&events))));
+        while game.state() == GameState::InProgress {
+            let direction = [GameMove::Left, GameMove::Right, GameMove::Up, GameMove::Down]
+                .into_iter()
+                .find(|&direction| game.make_move(direction));
+            assert!(direction.is_some());
+        }
+        let events = events.lock().unwrap();
+        assert_eq!(events.last(), Some(&Event::GameOver));
+        assert_eq!(events.iter().filter(|event| **event == Event::GameOver).count(), 1);
+    }
+
+    #[test]
+    fn reset_to_existing() {
+        //! Test that resetting a game gives the same game as creating it from the board
+
+        let board = [[2, 2, 0, 0, 0], [4, 0, 0, 0, 8], [0, 0, 16, 0, 0], [0, 0, 0, 0, 0], [2, 0, 0, 0, 0]];
+        let expected = Game::from_existing(&board, 12).unwrap();
+
+        let mut game: Game<5> = Game::new().unwrap();
+        game.make_move(GameMove::Left);
+        game.reset_to(&board, 12);
+
+        assert_eq!(game.board, expected.board);
+        assert_eq!(game.score, expected.score);
+        assert_eq!(game.turns, expected.turns);
+        assert_eq!(game.moves, expected.moves);
+        assert_eq!(game.score_next, expected.score_next);
+        for direction in GameMove::ALL {
+            assert_eq!(game.peek_move(direction), expected.peek_move(direction));
+        }
+        assert_eq!(game.state, expected.state);
+        assert_eq!(game.result, expected.result);
+    }
+
+    #[test]
+    fn move_precheck() {
+        //! Test the cheap move check and the move results against a straightforward implementation of the moves
+
+        fn reference<const SIZE: usize>(game: &Game<SIZE>, direction: GameMove) -> ([[u64; SIZE]; SIZE], u64) {
+            let board = game.board();
+            let mut result = [[0; SIZE]; SIZE];
+            let mut score = 0;
+            for line in 0..SIZE {
+                let cells = Game::<SIZE>::line_cells(direction, line);
+                let values = cells.map(|(i, j)| board[i][j]);
+                for (&(i, j), origin) in cells.iter().zip(game.trace_line(&values)) {
+                    result[i][j] = match origin {
+                        Some((from, None)) => values[from],
+                        Some((from, Some(_))) => {
+                            score += values[from] * 2;
+                            values[from] * 2
+                        }
+                        None => 0,
+                    };
+                }
+            }
+            (result, score)
+        }
+
+        let mut rng = thread_rng();
+        for _ in 0..2_000 {
+            let mut board = [[0; 6]; 6];
+            let density = rng.gen_range(0.0..=1.0);
+            for tile in board.iter_mut().flatten() {
+                if rng.gen_bool(density) {
+                    *tile = 1 << rng.gen_range(1..=4);
+                }
+            }
+
+            if board == [[0; 6]; 6] {
+                // the empty board gets the starting tile
+                continue;
+            }
+            let game = Game::from_existing(&board, 0).unwrap();
+            for direction in GameMove::ALL {
+                let (expected, expected_score) = reference(&game, direction);
+                assert_eq!(game.can_move(direction), expected != board);
+                if expected != board {
+                    assert_eq!(game.peek_move(direction), Some((expected, expected_score)));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn lazy_moves() {
+        //! Test that the games with the lazy moves play exactly like the games building the moves after every move
+
+        fn compare<const SIZE: usize>(mut eager: Game<SIZE>, lazy: Game<SIZE>, seed: u64) {
+            let mut lazy = lazy.with_lazy_moves();
+            let mut rng = StdRng::seed_from_u64(seed);
+            while eager.state() == GameState::InProgress && eager.turns() < 500 {
+                for direction in GameMove::ALL {
+                    assert_eq!(lazy.can_move(direction), eager.can_move(direction));
+                    assert_eq!(lazy.peek_move(direction), eager.peek_move(direction));
+                }
+                let direction = GameMove::ALL.into_iter().filter(|&direction| eager.can_move(direction)).choose(&mut rng).unwrap();
+                if rng.gen_bool(0.5) {
+                    assert!(eager.make_move(direction) && lazy.make_move(direction));
+                } else {
+                    let (eager_move, lazy_move) = (eager.begin_move(direction).unwrap(), lazy.begin_move(direction).unwrap());
+                    assert_eq!((lazy_move.board(), lazy_move.gained()), (eager_move.board(), eager_move.gained()));
+                    assert_eq!(lazy_move.commit(), eager_move.commit());
+                }
+                assert_eq!(lazy, eager);
+                assert_eq!((lazy.merges, lazy.best_merge(), lazy.turns()), (eager.merges, eager.best_merge(), eager.turns()));
+            }
+        }
+
+        for seed in 0..5 {
+            let build = || Game::<4>::builder().seed(seed).build().unwrap();
+            compare(build(), build(), seed);
+            let build = || Game::<16>::builder().seed(seed).build().unwrap();
+            compare(build(), build(), seed);
+            let build = || Game::<5>::builder().seed(seed).build().unwrap().with_blocked_cells(&[(2, 2)]).unwrap();
+            compare(build(), build(), seed);
+        }
+    }
+
+    #[test]
+    fn row_tables() {
+        //! Test that the moves of the 4x4 boards looked up in the row tables match the generic algorithm, including the 32768 tiles
+
+        let mut rng = StdRng::seed_from_u64(612);
+        for ind in 0..20_000 {
+            let mut board = [[0; 4]; 4];
+            for tile in board.iter_mut().flatten() {
+                // every fourth tile is a 32768 on some boards, so the overflowing merges are common
+                let exp = if ind % 2 == 0 && rng.gen_bool(0.25) { 15 } else { rng.gen_range(0..=15) };
+                if exp != 0 {
+                    *tile = 1 << exp;
+                }
+            }
+
+            let tables = Game::from_existing(&board, 0).unwrap();
+            let generic = Game::from_existing_with_rule(&board, 0, Arc::new(ClassicRule)).unwrap();
+            assert_eq!(tables.state(), generic.state());
+            for direction in GameMove::ALL {
+                assert_eq!(tables.can_move(direction), generic.can_move(direction));
+                assert_eq!(tables.peek_move(direction), generic.peek_move(direction));
+                assert_eq!(tables.best_merge_next[direction.index()], generic.best_merge_next[direction.index()]);
+            }
+        }
+    }
+
+    #[test]
+    fn expand_move() {
+        //! Test the expansion of a move into all possible spawns
+
+        let game = Game::from_existing(&[[2, 2, 4, 8], [4, 8, 16, 32], [2, 4, 8, 16], [0, 0, 0, 4]], 0).unwrap();
+        assert!(game.expand(GameMove::Up).is_none());
+
+        let (after_move, _) = game.peek_move(GameMove::Left).unwrap();
+        let outcomes: Vec<([[u64; 4]; 4], f64)> = game.expand(GameMove::Left).unwrap().collect();
+        assert_eq!(outcomes.len(), 2 * 4);
+        assert!((outcomes.iter().map(|(_, probability)| probability).sum::<f64>() - 1.0).abs() < 1e-9);
+
+        for (board, probability) in outcomes {
+            let changed: Vec<u64> = board.iter().flatten().zip(after_move.iter().flatten()).filter(|(a, b)| a != b).map(|(&a, _)| a).collect();
+            assert_eq!(changed.len(), 1);
+            match changed[0] {
+                2 => assert!((probability - 0.9 / 4.0).abs() < 1e-9),
+                4 => assert!((probability - 0.1 / 4.0).abs() < 1e-9),
+                _ => panic!("unexpected spawned tile"),
+            }
+        }
+    }
+
+    #[test]
+    fn board_edits() {
+        //! Test that random edits either leave the game untouched or keep it consistent with a game created from the edited board
+
+        let mut game = Game::<4>::builder()
+            .seed(620)
+            .target(64)
+            .build()
+            .unwrap()
+            .with_tile_ids()
+            .with_blocked_cells(&[(3, 3)])
+            .unwrap();
+        let mut rng = StdRng::seed_from_u64(620);
+        let values = [0, 0, 0, 2, 4, 8, 16, 32, 64, 128, 3, 6, 1, 1 << 63, BLOCKED_CELL];
+        for _ in 0..5_000 {
+            let (row, col, value) = (rng.gen_range(0..5), rng.gen_range(0..5), values[rng.gen_range(0..values.len())]);
+            let before = (*game.board(), game.score(), game.state(), game.result(), *game.tile_ids().unwrap());
+            match game.set_tile(row, col, value) {
+                Ok(()) => {
+                    assert_eq!(game.board()[row][col], value);
+                    assert_eq!(game.score(), before.1);
+                }
+                Err(error) => {
+                    let expected = if row >= 4 || col >= 4 || (row, col) == (3, 3) {
+                        Error::InvalidPosition
+                    } else {
+                        Error::InvalidValue { row, col, value }
+                    };
+                    assert_eq!(error, expected);
+                    assert_eq!((*game.board(), game.score(), game.state(), game.result(), *game.tile_ids().unwrap()), before);
+                    continue;
+                }
+            }
+
+            let board = *game.board();
+            let expected = Game::from_existing(&board.map(|row| row.map(|tile| if tile == BLOCKED_CELL { 0 } else { tile })), 0)
+                .unwrap()
+                .with_blocked_cells(&[(3, 3)])
+                .unwrap();
+            for direction in GameMove::ALL {
+                assert_eq!(game.peek_move(direction), expected.peek_move(direction));
+            }
+            assert_eq!(
+                game.state(),
+                if GameMove::ALL.into_iter().any(|direction| game.can_move(direction)) {
+                    GameState::InProgress
+                } else {
+                    GameState::GameOver
+                }
+            );
+            let won = game.summary().victory_turn.is_some();
+            assert!(!won || matches!(game.result(), GameResult::Victory | GameResult::VictoryThenLoss));
+            assert_eq!(game.result() == GameResult::Loss, !won && game.state() == GameState::GameOver);
+            let tile_ids = game.tile_ids().unwrap();
+            for cell in 0..16 {
+                let tile = board[cell / 4][cell % 4];
+                assert_eq!(tile_ids[cell / 4][cell % 4].is_some(), tile != 0 && tile != BLOCKED_CELL);
+            }
+
+            if game.state() == GameState::InProgress && rng.gen_bool(0.3) {
+                let direction = GameMove::ALL.into_iter().find(|&direction| game.can_move(direction)).unwrap();
+                assert!(game.make_move(direction));
+            }
+        }
+        assert_eq!(game.recompute(), Ok(()));
+
+        // the edits of a won game that is over keep its result, whether it was won by the last move or later lost
+        let mut won = Game::from_existing(&[[16, 16], [2, 4]], 0)
+            .unwrap()
+            .with_spawner(Box::new(ScriptedSpawner::new([((0, 1), 2)])));
+        assert!(won.make_move(GameMove::Left));
+        let mut lost = Game::from_existing(&[[16, 16], [2, 4]], 0)
+            .unwrap()
+            .with_spawner(Box::new(ScriptedSpawner::new([((0, 1), 4), ((1, 1), 4)])));
+        assert!(lost.make_move(GameMove::Left) && lost.make_move(GameMove::Up));
+        for (game, result) in [(&mut won, GameResult::Victory), (&mut lost, GameResult::VictoryThenLoss)] {
+            assert_eq!((game.state(), game.result()), (GameState::GameOver, result));
+            assert_eq!(game.recompute(), Ok(()));
+            assert_eq!(game.set_tile(1, 0, game.board()[1][0]), Ok(()));
+            assert_eq!((game.state(), game.result()), (GameState::GameOver, result));
+        }
+        // the game continues as won when an edit allows a move again
+        assert_eq!(lost.set_tile(1, 1, 0), Ok(()));
+        assert_eq!((lost.state(), lost.result()), (GameState::InProgress, GameResult::Victory));
+    }
+
+    #[test]
+    fn preview_best_move() {
+        //! Test that the previewed best move matches the board and the score of the move once it is made
+
+        let mut game = Game::<4>::builder().seed(619).build().unwrap();
+        for _ in 0..20 {
+            let (best, board, gained) = game.preview_best_move(50).unwrap();
+            assert_eq!(game.peek_move(best), Some((board, gained)));
+            let score = game.score();
+            assert_eq!(game.make_move_without_spawn(best), Ok(true));
+            assert_eq!((*game.board(), game.score()), (board, score + gained));
+            let cell = (0..16).find(|&cell| board[cell / 4][cell % 4] == 0).unwrap();
+            game.place_tile(cell / 4, cell % 4, 2).unwrap();
+        }
+
+        let game = Game::from_existing(&[[2, 4, 2, 4], [4, 2, 4, 2], [2, 4, 2, 4], [4, 2, 4, 2]], 0).unwrap();
+        assert_eq!(game.preview_best_move(50), Err(Error::NoValidMove));
+    }
+
+    #[test]
+    fn spawn_distribution() {
+        //! Test the distribution of the next spawned tile with the default and a custom spawn distribution
+
+        let game = Game::from_existing(&[[2, 2, 4, 8], [4, 8, 16, 32], [2, 4, 8, 16], [0, 0, 0, 4]], 0).unwrap();
+        let spawns: Vec<(usize, usize, u64, f64)> = game.spawn_distribution().collect();
+        assert_eq!(spawns.len(), 2 * 3);
+        assert!(spawns.iter().all(|&(i, j, _, _)| i == 3 && j < 3));
+        assert!((spawns.iter().map(|&(_, _, _, probability)| probability).sum::<f64>() - 1.0).abs() < 1e-9);
+        assert!(spawns.contains(&(3, 1, 4, 0.1 / 3.0)));
+
+        let game = game.with_spawn_config(SpawnConfig::from_values(&[(2, 0.75), (4, 0.2), (8, 0.05)]).unwrap());
+        let spawns: Vec<(usize, usize, u64, f64)> = game.spawn_distribution().collect();
+        assert_eq!(spawns.len(), 3 * 3);
+        assert!((spawns.iter().map(|&(_, _, _, probability)| probability).sum::<f64>() - 1.0).abs() < 1e-9);
+        assert!(spawns.contains(&(3, 2, 8, 0.05 / 3.0)));
+
+        let game = Game::from_existing(&[[2, 4], [4, 2]], 0).unwrap();
+        assert_eq!(game.spawn_distribution().count(), 0);
+    }
+
+    #[test]
+    fn spawn_position_weights() {
+        //! Test that the spawned tiles, the expansion and the search follow the spawn position policy
+
+        let mut game = Game::<4>::builder().seed(11).spawn_weight(Arc::new(Corners)).build().unwrap();
+        assert!(!game.has_classic_rules());
+        let corners = [(0, 0), (0, 3), (3, 0), (3, 3)];
+        for _ in 0..30 {
+            let Some(direction) = GameMove::ALL.into_iter().find(|&direction| game.can_move(direction)) else {
+                break;
+            };
+            let (moved, _) = game.peek_move(direction).unwrap();
+            let corner_empty = corners.iter().any(|&(i, j)| moved[i][j] == 0);
+            game.make_move(direction);
+            let spawned: Vec<(usize, usize)> = (0..16).map(|cell| (cell / 4, cell % 4)).filter(|&(i, j)| moved[i][j] != game.board()[i][j]).collect();
+            assert_eq!(spawned.len(), 1);
+            assert!(!corner_empty || corners.contains(&spawned[0]));
+        }
+
+        let game = Game::from_existing(&[[0, 0, 0, 0], [0, 0, 0, 0], [0, 0, 64, 0], [0, 0, 0, 0]], 0)
+            .unwrap()
+            .with_spawn_weight(Arc::new(NearMaxTile));
+        let spawns: Vec<(usize, usize, u64, f64)> = game.spawn_distribution().collect();
+        assert_eq!(spawns.len(), 2 * 4);
+        assert!(spawns.iter().all(|&(i, j, _, _)| [(1, 2), (2, 1), (2, 3), (3, 2)].contains(&(i, j))));
+        assert!((spawns.iter().map(|&(_, _, _, probability)| probability).sum::<f64>() - 1.0).abs() < 1e-9);
+        let outcomes: Vec<_> = game.expand(GameMove::Left).unwrap().collect();
+        assert_eq!(outcomes.len(), 2 * 3);
+        assert!((outcomes.iter().map(|&(_, probability)| probability).sum::<f64>() - 1.0).abs() < 1e-9);
+        assert!(outcomes.iter().all(|&(board, _)| board[1][0] != 0 || board[2][1] != 0 || board[3][0] != 0));
+        assert!(game.find_best_move(1).is_ok());
+    }
+
+    #[test]
+    fn public_constants() {
+        //! Test that the games follow the public constants
+
+        assert_eq!(Game::<MIN_BOARD_SIZE>::new().unwrap().size(), MIN_BOARD_SIZE);
+        assert_eq!(Game::<{ MIN_BOARD_SIZE - 1 }>::new().unwrap_err(), Error::InvalidSize { size: MIN_BOARD_SIZE - 1 });
+        assert_eq!(
+            Game::<{ MIN_BOARD_SIZE - 1 }>::builder().build().unwrap_err(),
+            Error::InvalidSize { size: MIN_BOARD_SIZE - 1 }
+        );
+        assert_eq!(
+            validate_board(&[[0; MIN_BOARD_SIZE - 1]; MIN_BOARD_SIZE - 1]),
+            Err(Error::InvalidSize { size: MIN_BOARD_SIZE - 1 })
+        );
+
+        assert_eq!(Game::<4>::new().unwrap().target(), Some(DEFAULT_WIN_TARGET));
+        assert_eq!(Game::<6>::builder().build().unwrap().target(), Some(DEFAULT_WIN_TARGET));
+        assert_eq!(SpawnConfig::default(), SpawnConfig::new(DEFAULT_FOUR_PROBABILITY).unwrap());
+        assert_eq!(Game::<4>::new().unwrap().spawn_config().values()[1], (4, DEFAULT_FOUR_PROBABILITY));
+
+        let (highest, below) = (1 << MAX_SUPPORTED_EXPONENT, 1 << (MAX_SUPPORTED_EXPONENT - 1));
+        assert_eq!(MAX_TILE, highest);
+        let game = Game::from_existing(&[[highest, highest], [below, below]], 0).unwrap();
+        assert_eq!(game.peek_move(GameMove::Left), Some(([[highest, highest], [highest, 0]], highest)));
+        assert!(Game::<4>::random_position(4, MAX_SUPPORTED_EXPONENT, 1).is_ok());
+        assert_eq!(Game::<4>::random_position(4, MAX_SUPPORTED_EXPONENT + 1, 1).unwrap_err(), Error::InvalidConfig);
+    }
+
+    #[test]
+    fn merge_overflow() {
+        //! Test that the merges and the score don't overflow with huge tiles
+
+        let mut game = Game::from_existing(&[[1 << 62, 1 << 62, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0]], 0).unwrap();
+        assert_eq!(game.peek_move(GameMove::Left).unwrap().1, 1 << 63);
+        assert!(game.make_move(GameMove::Left));
+        assert_eq!(game.board()[0][0], 1 << 63);
+        assert_eq!(game.score(), 1 << 63);
+
+        // the 2^63 tiles can't merge, so the only valid moves slide them
+        let mut game = Game::from_existing(&[[1 << 63, 1 << 63, 0, 0], [1 << 62, 1 << 62, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0]], u64::MAX - 5).unwrap();
+        assert_eq!(game.peek_move(GameMove::Left).unwrap().0[0], [1 << 63, 1 << 63, 0, 0]);
+        assert_eq!(game.peek_move(GameMove::Right).unwrap().0[0], [0, 0, 1 << 63, 1 << 63]);
+        assert!(game.make_move(GameMove::Left));
+        assert_eq!(game.score(), u64::MAX);
+        assert_eq!(game.board()[1][0], 1 << 63);
+
+        // a full board where only the 2^63 tiles are equal neighbours is over
+        let (a, b) = (1 << 63, 1 << 62);
+        let game = Game::from_existing(&[[a, a, b, a], [b, a, a, b], [a, b, b / 2, a], [b, a, a, b]], 0).unwrap();
+        assert_eq!(game.state(), GameState::GameOver);
+        assert_eq!(
+            Game::from_existing(&[[0, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 3 << 62]], 0).unwrap_err(),
+            Error::InvalidValue { row: 3, col: 3, value: 3 << 62 }
+        );
+    }
+
+    #[test]
+    fn custom_spawn_config() {
+        //! Test that the spawned tiles, the expansion and the simulated games follow the spawn distribution
+
+        let config = SpawnConfig::new(1.0).unwrap();
+        let mut game: Game<4> = Game::new().unwrap().with_spawn_config(config.clone());
+        assert_eq!(game.spawn_config(), &config);
+        for _ in 0..10 {
+            let direction = GameMove::ALL.into_iter().find(|&direction| game.can_move(direction)).unwrap();
+            let (moved, _) = game.peek_move(direction).unwrap();
+            game.make_move(direction);
+            let spawned: Vec<u64> = (0..16)
+                .filter(|&cell| moved[cell / 4][cell % 4] != game.board()[cell / 4][cell % 4])
+                .map(|cell| game.board()[cell / 4][cell % 4])
+                .collect();
+            assert_eq!(spawned, [4]);
+        }
+
+        let game = Game::from_existing(&[[2, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0]], 0)
+            .unwrap()
+            .with_spawn_config(SpawnConfig::from_values(&[(2, 0.5), (8, 0.5)]).unwrap());
+        let outcomes: Vec<_> = game.expand(GameMove::Right).unwrap().collect();
+        assert_eq!(outcomes.len(), 30);
+        assert!(outcomes
+            .iter()
+            .all(|&(board, probability)| probability == 0.5 / 15.0 && board.iter().flatten().any(|&tile| tile == 2 || tile == 8)));
+
+        // the simulated games on the bitboard and on the game itself spawn only 4s, so no 2 is ever on the final board
+        let mut rng = thread_rng();
+        for _ in 0..20 {
+            match BitBoard::from_board(&[[4, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0]])
+                .unwrap()
+                .rollout(GameMove::Right, &config, None, &mut rng)
+            {
+                RolloutEnd::Finished(board, _, _) => assert!(board.to_board::<4>().iter().flatten().all(|&tile| tile != 2)),
+                RolloutEnd::Stopped(..) | RolloutEnd::Overflow(..) => unreachable!(),
+            }
+
+            let board = [[4, 0, 0, 0, 0], [0, 0, 0, 0, 0], [0, 0, 0, 0, 0], [0, 0, 0, 0, 0], [0, 0, 0, 0, 0]];
+            let mut work_game = Game::from_existing(&board, 0).unwrap().with_spawn_config(config.clone());
+            work_game.simulate(&board, GameMove::Right, SearchObjective::Score, &mut rng);
+            assert!(work_game.board().iter().flatten().all(|&tile| tile != 2));
+        }
+    }
+
+    #[test]
+    fn two_player_mode() {
+        //! Test the alternating moves and tile placements of the two-player mode
+
+        let mut game = Game::from_existing(&[[0, 0, 0, 2], [0, 0, 0, 2], [0, 0, 0, 0], [0, 0, 0, 0]], 0).unwrap();
+        assert_eq!(game.place_tile(0, 0, 2), Err(Error::WrongTurn));
+        assert_eq!(game.make_move_without_spawn(GameMove::Right), Ok(false));
+        assert_eq!(game.make_move_without_spawn(GameMove::Up), Ok(true));
+        assert_eq!(game.board(), &[[0, 0, 0, 4], [0, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0]]);
+        assert_eq!(game.score(), 4);
+        assert!(game.awaiting_tile());
+
+        assert_eq!(game.make_move_without_spawn(GameMove::Left), Err(Error::WrongTurn));
+        assert!(!game.make_move(GameMove::Left));
+        assert!(!game.can_move(GameMove::Left));
+        assert_eq!(game.place_tile(0, 3, 2), Err(Error::InvalidPosition));
+        assert_eq!(game.place_tile(4, 0, 2), Err(Error::InvalidPosition));
+        assert_eq!(game.place_tile(0, 0, 8), Err(Error::InvalidValue { row: 0, col: 0, value: 8 }));
+        assert_eq!(game.place_tile(0, 0, 4), Ok(()));
+        assert!(!game.awaiting_tile());
+        assert_eq!(game.board(), &[[4, 0, 0, 4], [0, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0]]);
+        assert_eq!(game.turns(), 1);
+
+        assert_eq!(game.place_tile(1, 0, 2), Err(Error::WrongTurn));
+        assert!(game.can_move(GameMove::Left));
+        assert_eq!(game.make_move_without_spawn(GameMove::Left), Ok(true));
+        assert_eq!(game.board(), &[[8, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0]]);
+
+        // the placing player can end the game
+        let mut game = Game::from_existing(&[[2, 4, 2, 4], [4, 2, 4, 2], [2, 4, 2, 4], [4, 2, 8, 8]], 0).unwrap();
+        assert_eq!(game.make_move_without_spawn(GameMove::Left), Ok(true));
+        assert_eq!(game.place_tile(3, 3, 2), Ok(()));
+        assert_eq!(game.state(), GameState::GameOver);
+    }
+
+    #[test]
+    fn blocked_cells() {
+        //! Test sliding and merging around a cross of blocked cells in all four directions
+
+        const B: u64 = BLOCKED_CELL;
+        let cross = [(1, 2), (2, 1), (2, 2), (2, 3), (3, 2)];
+        let board = [
+            [2, 2, 0, 4, 0, 4],
+            [0, 4, 0, 0, 0, 4],
+            [4, 0, 0, 0, 2, 2],
+            [2, 0, 0, 2, 0, 0],
+            [0, 0, 8, 0, 0, 0],
+            [0, 0, 0, 0, 0, 2],
+        ];
+        let game = Game::from_existing(&board, 0).unwrap().with_blocked_cells(&cross).unwrap();
+        assert_eq!(game.blocked_cells(), cross);
+        assert_eq!(game.highest_tile(), 8);
+
+        let expected = [
+            (
+                GameMove::Left,
+                [
+                    [4, 8, 0, 0, 0, 0],
+                    [4, 0, B, 4, 0, 0],
+                    [4, B, B, B, 4, 0],
+                    [2, 0, B, 2, 0, 0],
+                    [8, 0, 0, 0, 0, 0],
+                    [2, 0, 0, 0, 0, 0],
+                ],
+                16,
+            ),
+            (
+                GameMove::Right,
+                [
+                    [0, 0, 0, 0, 4, 8],
+                    [0, 4, B, 0, 0, 4],
+                    [4, B, B, B, 0, 4],
+                    [0, 2, B, 0, 0, 2],
+                    [0, 0, 0, 0, 0, 8],
+                    [0, 0, 0, 0, 0, 2],
+                ],
+                16,
+            ),
+            (
+                GameMove::Up,
+                [
+                    [2, 2, 0, 4, 2, 8],
+                    [4, 4, B, 0, 0, 4],
+                    [2, B, B, B, 0, 0],
+                    [0, 0, B, 2, 0, 0],
+                    [0, 0, 8, 0, 0, 0],
+                    [0, 0, 0, 0, 0, 0],
+                ],
+                12,
+            ),
+            (
+                GameMove::Down,
+                [
+                    [0, 2, 0, 0, 0, 0],
+                    [0, 4, B, 4, 0, 0],
+                    [0, B, B, B, 0, 0],
+                    [2, 0, B, 0, 0, 0],
+                    [4, 0, 0, 0, 0, 8],
+                    [2, 0, 8, 2, 2, 4],
+                ],
+                12,
+            ),
+        ];
+        for (direction, board, score) in expected {
+            assert_eq!(game.peek_move(direction), Some((board, score)), "{direction:?}");
+        }
+
+        // tiles are never spawned on the blocked cells and the blocked cells never move
+        let mut game = game.with_tile_ids();
+        while let Some(direction) = GameMove::ALL.into_iter().find(|&direction| game.can_move(direction)) {
+            game.make_move(direction);
+            assert_eq!(game.blocked_cells(), cross);
+            assert!(cross.iter().all(|&(i, j)| game.tile_ids().unwrap()[i][j].is_none()));
+        }
+        assert_eq!(game.state(), GameState::GameOver);
+        assert!(game.to_string().contains('#'));
+
+        let game = Game::<4>::from_existing(&[[2, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0]], 0).unwrap();
+        assert_eq!(game.with_blocked_cells(&[(0, 0)]).unwrap_err(), Error::InvalidPosition);
+        let game = Game::<4>::from_existing(&[[2, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0]], 0).unwrap();
+        assert_eq!(game.with_blocked_cells(&[(0, 4)]).unwrap_err(), Error::InvalidPosition);
+        let game = Game::<4>::from_existing(&[[2, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0]], 0).unwrap();
+        assert_eq!(game.with_blocked_cells(&[(0, 1), (1, 0)]).unwrap().state(), GameState::GameOver);
+    }
+
+    #[test]
+    fn junk_tiles() {
+        //! Test that the junk tiles slide without merging, block the merges across them and count for the game over
+
+        const J: u64 = JUNK_TILE;
+        assert_eq!(validate_board(&[[2, J], [0, 4]]), Ok(()));
+        let game = Game::from_existing(&[[0, 2, J, 2], [J, J, 0, 0], [4, 4, J, 0], [2, 0, 0, 0]], 0)
+            .unwrap()
+            .with_target(8)
+            .unwrap();
+        assert_eq!((game.highest_tile(), game.result()), (4, GameResult::Pending));
+        assert_eq!(game.to_string().matches('X').count(), 4);
+        assert_eq!(game.to_compact_string().parse::<Game>().unwrap().board(), game.board());
+        assert_eq!(game.peek_move(GameMove::Left), Some(([[2, J, 2, 0], [J, J, 0, 0], [8, J, 0, 0], [2, 0, 0, 0]], 8)));
+        assert_eq!(game.peek_move(GameMove::Right), Some(([[0, 2, J, 2], [0, 0, J, J], [0, 0, 8, J], [0, 0, 0, 2]], 8)));
+
+        // the generic slides around the blocked cells follow the same rules
+        let game = Game::from_existing(&[[J, 2, 2, 0], [2, J, 2, 0], [0, 0, 0, 0], [0, 0, 0, 0]], 0)
+            .unwrap()
+            .with_blocked_cells(&[(0, 3), (1, 3)])
+            .unwrap();
+        assert_eq!(game.peek_move(GameMove::Left).unwrap().0[..2], [[J, 4, 0, BLOCKED_CELL], [2, J, 2, BLOCKED_CELL]]);
+
+        // a full board whose only equal neighbours are junk tiles is lost
+        let game = Game::from_existing(&[[J, J], [2, 4]], 0).unwrap();
+        assert_eq!((game.state(), game.result()), (GameState::GameOver, GameResult::Loss));
+        let game = Game::from_existing(&[[2, J, 2], [4, 8, 4], [2, 4, 2]], 0).unwrap();
+        assert_eq!(game.state(), GameState::GameOver);
+
+        // every third spawned tile is junk and stays on the board
+        assert_eq!(Game::<4>::builder().junk_every(0).build().err(), Some(Error::InvalidConfig));
+        let mut game = Game::<4>::builder().seed(5).junk_every(3).build().unwrap();
+        assert_eq!(game.junk_every(), Some(3));
+        while game.turns() < 30 && game.state() == GameState::InProgress {
+            let direction = GameMove::ALL.into_iter().find(|&direction| game.can_move(direction)).unwrap();
+            assert!(game.make_move(direction));
+            let junk = game.board().iter().flatten().filter(|&&tile| tile == J).count() as u64;
+            assert_eq!(junk, game.turns() / 3);
+            assert_ne!(game.highest_tile(), J);
+        }
+
+        // the expansion and the searches know when the next tile is junk
+        let game = Game::<4>::builder().seed(5).junk_every(1).build().unwrap();
+        assert!(game.spawn_distribution().all(|(_, _, value, _)| value == J));
+        assert!((game.spawn_distribution().map(|(_, _, _, probability)| probability).sum::<f64>() - 1.0).abs() < 1e-9);
+        assert!(game.find_best_move(100).is_ok());
+        assert!(game.find_best_move_mcts(50, 1.4).is_ok());
+        assert!(game.find_best_move_exact(2).is_ok());
+    }
+
+    #[test]
+    fn merge_rules() {
+        //! Test that the moves in all four directions follow the merge rule of the game
+
+        let board = [[1, 2, 3, 3], [2, 2, 1, 0], [3, 0, 6, 6], [1, 1, 2, 2]];
+        assert_eq!(Game::from_existing(&board, 0).unwrap_err(), Error::InvalidValue { row: 0, col: 0, value: 1 });
+        assert_eq!(
+            Game::from_existing_with_rule(&[[4; 4]; 4], 0, Arc::new(ThreesRule)).unwrap_err(),
+            Error::InvalidValue { row: 0, col: 0, value: 4 }
+        );
+
+        let game = Game::from_existing_with_rule(&board, 0, Arc::new(ThreesRule)).unwrap();
+        let expected = [
+            (GameMove::Left, [[3, 6, 0, 0], [2, 3, 0, 0], [3, 12, 0, 0], [1, 3, 2, 0]], 27),
+            (GameMove::Right, [[0, 0, 3, 6], [0, 0, 2, 3], [0, 0, 3, 12], [0, 1, 3, 2]], 27),
+            (GameMove::Up, [[3, 2, 3, 3], [3, 3, 1, 6], [1, 0, 6, 2], [0, 0, 2, 0]], 6),
+            (GameMove::Down, [[0, 0, 3, 0], [3, 0, 1, 3], [3, 2, 6, 6], [1, 3, 2, 2]], 6),
+        ];
+        for (direction, board, score) in expected {
+            assert_eq!(game.peek_move(direction), Some((board, score)), "{direction:?}");
+        }
+
+        // the spawned tiles and the merged tiles are always valid tiles of the rule
+        let mut game = Game::<4>::new_with_rule(Arc::new(ThreesRule)).unwrap();
+        while game.state() == GameState::InProgress {
+            game.make_move(game.find_best_move(10).unwrap());
+            assert!(game.board().iter().flatten().all(|&tile| tile == 0 || ThreesRule.is_valid_tile(tile)));
+        }
+
+        // the Fibonacci rules merge the neighbouring Fibonacci numbers
+        let board = [[1, 1, 2, 3], [2, 3, 5, 0], [1, 2, 2, 0], [8, 5, 3, 0]];
+        let game = Game::from_existing_with_rule(&board, 0, Arc::new(FibonacciRule)).unwrap();
+        let expected = [
+            (GameMove::Left, [[2, 5, 0, 0], [5, 5, 0, 0], [3, 2, 0, 0], [13, 3, 0, 0]], 28),
+            (GameMove::Right, [[0, 0, 2, 5], [0, 0, 2, 8], [0, 0, 3, 2], [0, 0, 8, 8]], 26),
+            (GameMove::Up, [[3, 1, 2, 3], [1, 5, 5, 0], [8, 5, 5, 0], [0, 0, 0, 0]], 13),
+        ];
+        for (direction, board, score) in expected {
+            assert_eq!(game.peek_move(direction), Some((board, score)), "{direction:?}");
+        }
+        assert_eq!(
+            Game::from_existing_with_rule(&[[1, 4], [0, 0]], 0, Arc::new(FibonacciRule)).unwrap_err(),
+            Error::InvalidValue { row: 0, col: 1, value: 4 }
+        );
+
+        // the games of the rules default to the targets of the rules
+        assert_eq!(game.target(), Some(2584));
+        assert_eq!(Game::<3>::new_with_rule(Arc::new(ThreesRule)).unwrap().target(), Some(768));
+        assert_eq!(Game::<4>::builder().merge_rule(Arc::new(FibonacciRule)).build().unwrap().target(), Some(2584));
+        assert_eq!(Game::<4>::builder().merge_rule(Arc::new(FibonacciRule)).endless(true).build().unwrap().target(), None);
+        let mut game = Game::<4>::new_with_rule(Arc::new(FibonacciRule)).unwrap();
+        while game.state() == GameState::InProgress {
+            game.make_move(game.find_best_move(10).unwrap());
+            assert!(game.board().iter().flatten().all(|&tile| tile == 0 || FibonacciRule.is_valid_tile(tile)));
+        }
+
+        // the classic rule set explicitly behaves like the built-in moves
+        let mut rng = thread_rng();
+        for _ in 0..200 {
+            let mut board = [[0; 5]; 5];
+            for tile in board.iter_mut().flatten() {
+                if rng.gen_bool(0.7) {
+                    *tile = 1 << rng.gen_range(1..=3);
+                }
+            }
+            let classic = Game::from_existing(&board, 0).unwrap();
+            let ruled = Game::from_existing_with_rule(&board, 0, Arc::new(ClassicRule)).unwrap();
+            for direction in GameMove::ALL {
+                assert_eq!(classic.peek_move(direction), ruled.peek_move(direction));
+            }
+        }
+    }
+
+    #[test]
+    fn small_boards() {
+        //! Test the sizes, the default targets and the targets set explicitly
+
+        assert_eq!(Game::<1>::new().unwrap_err(), Error::InvalidSize { size: 1 });
+        assert_eq!(Game::<1>::from_existing(&[[2]], 0).unwrap_err(), Error::InvalidSize { size: 1 });
+        assert_eq!(Game::<0>::from_existing(&[], 0).unwrap_err().to_string(), "Invalid game size 0. Must be at least 2.");
+        assert_eq!(Game::<2>::new().unwrap().target(), Some(32));
+        assert_eq!(Game::<3>::new().unwrap().target(), Some(512));
+        assert_eq!(Game::<5>::new().unwrap().target(), Some(2048));
+
+        let game = Game::default();
+        assert_eq!(game.size(), 4);
+        assert_eq!(game.target(), Some(2048));
+
+        let game = Game::from_existing(&[[16, 16], [0, 0]], 0).unwrap();
+        assert_eq!(game.result(), GameResult::Pending);
+        let mut game = game.with_target(16).unwrap();
+        assert_eq!(game.result(), GameResult::Victory);
+        assert_eq!(game.with_target(24).unwrap_err(), Error::InvalidConfig);
+        game = Game::from_existing(&[[16, 16], [0, 0]], 0).unwrap();
+        assert!(game.make_move(GameMove::Left));
+        assert_eq!(game.result(), GameResult::Victory);
+
+        // random games on the small boards end without panicking
+        for _ in 0..100 {
+            let mut game = Game::<2>::new().unwrap();
+            while let Some(direction) = GameMove::ALL.into_iter().filter(|&direction| game.can_move(direction)).choose(&mut thread_rng()) {
+                game.make_move(direction);
+            }
+            assert_eq!(game.state(), GameState::GameOver);
+            assert!(game.highest_tile() <= 32);
+
+            let mut game = Game::<3>::new().unwrap();
+            game.play_with(&mut RandomStrategy);
+            assert_eq!(game.state(), GameState::GameOver);
+        }
+    }
+
+    #[test]
+    fn exhaustive_2x2() {
+        //! Test the moves of all 2x2 boards with the tiles up to 16
+
+        // a line of two cells, ordered in the direction of the move
+        fn slide_pair(front: u64, back: u64) -> ([u64; 2], u64) {
+            match (front, back) {
+                (0, back) => ([back, 0], 0),
+                (front, back) if front == back => ([front * 2, 0], front * 2),
+                (front, back) => ([front, back], 0),
+            }
+        }
+
+        let tiles = [0, 2, 4, 8, 16];
+        // the empty board is skipped, as it gets the starting tile
+        for index in 1..tiles.len().pow(4) {
+            let board = [[tiles[index % 5], tiles[index / 5 % 5]], [tiles[index / 25 % 5], tiles[index / 125 % 5]]];
+            let game = Game::from_existing(&board, 0).unwrap();
+
+            let [[a, b], [c, d]] = board;
+            let (top, score_top) = slide_pair(a, b);
+            let (bottom, score_bottom) = slide_pair(c, d);
+            let left = ([top, bottom], score_top + score_bottom);
+            let (top, score_top) = slide_pair(b, a);
+            let (bottom, score_bottom) = slide_pair(d, c);
+            let right = ([[top[1], top[0]], [bottom[1], bottom[0]]], score_top + score_bottom);
+            let (first, score_first) = slide_pair(a, c);
+            let (second, score_second) = slide_pair(b, d);
+            let up = ([[first[0], second[0]], [first[1], second[1]]], score_first + score_second);
+            let (first, score_first) = slide_pair(c, a);
+            let (second, score_second) = slide_pair(d, b);
+            let down = ([[first[1], second[1]], [first[0], second[0]]], score_first + score_second);
+
+            for (direction, expected) in [(GameMove::Left, left), (GameMove::Right, right), (GameMove::Up, up), (GameMove::Down, down)] {
+                assert_eq!(game.can_move(direction), expected.0 != board, "{board:?} {direction:?}");
+                assert_eq!(game.peek_move(direction), Some(expected).filter(|_| expected.0 != board), "{board:?} {direction:?}");
+            }
+            let any_move = GameMove::ALL.into_iter().any(|direction| game.can_move(direction));
+            assert_eq!(game.state() == GameState::GameOver, !any_move, "{board:?}");
+            assert_eq!(game.result() == GameResult::Loss, !any_move, "{board:?}");
+        }
+    }
+
+    #[test]
+    fn move_timing() {
+        //! Test the duration of the game and the times of the moves measured with a manual clock
+
+        let clock = Arc::new(ManualClock::new());
+        let mut game = Game::<4>::builder().seed(622).track_time(true).clock(clock.clone()).build().unwrap();
+        assert_eq!((game.elapsed(), game.average_move_time(), game.slowest_move()), (Some(Duration::ZERO), None, None));
+        for millis in [30, 10, 50, 50, 20] {
+            clock.advance(Duration::from_millis(millis));
+            let direction = GameMove::ALL.into_iter().find(|&direction| game.can_move(direction)).unwrap();
+            assert!(game.make_move(direction));
+        }
+        assert_eq!(game.elapsed(), Some(Duration::from_millis(160)));
+        assert_eq!(game.average_move_time(), Some(Duration::from_millis(32)));
+        assert_eq!(game.slowest_move(), Some((3, Duration::from_millis(50))));
+        let summary = game.summary();
+        assert_eq!(
+            (summary.duration, summary.average_move_time, summary.slowest_move),
+            (game.elapsed(), game.average_move_time(), game.slowest_move())
+        );
+
+        // the time runs until the next move, but the forks are not timed
+        clock.advance(Duration::from_secs(1));
+        assert_eq!(game.elapsed(), Some(Duration::from_millis(1_160)));
+        assert_eq!(game.average_move_time(), Some(Duration::from_millis(32)));
+        assert_eq!(game.fork().summary().duration, None);
+        game.reset();
+        assert_eq!((game.elapsed(), game.slowest_move()), (Some(Duration::ZERO), None));
+
+        // the time stops when the game is over
+        let mut game = Game::from_existing(&[[16, 16], [2, 4]], 0)
+            .unwrap()
+            .with_clock(clock.clone())
+            .with_spawner(Box::new(ScriptedSpawner::new([((0, 1), 4), ((1, 1), 4)])));
+        clock.advance(Duration::from_millis(7));
+        game.make_move(GameMove::Left);
+        clock.advance(Duration::from_millis(3));
+        game.make_move(GameMove::Up);
+        assert_eq!(game.state(), GameState::GameOver);
+        clock.advance(Duration::from_secs(1));
+        assert_eq!(game.elapsed(), Some(Duration::from_millis(10)));
+        assert_eq!(game.slowest_move(), Some((1, Duration::from_millis(7))));
+        assert!(game.summary().to_string().ends_with("duration: 10ms, average move time: 5ms, slowest move: 7ms on turn 1"));
+
+        assert_eq!(Game::<4>::builder().build().unwrap().elapsed(), None);
+    }
+
+    #[test]
+    fn turn_limit() {
+        //! Test that the game ends at the limit of the turns, with the AI too, and that a victory on the last turn stays a victory
+
+        let mut game = Game::<4>::builder().seed(621).max_turns(30).build().unwrap();
+        assert_eq!(game.max_turns(), Some(30));
+        let summary = game.autoplay_with(&mut RandomStrategy, None).unwrap();
+        assert_eq!((summary.turns, summary.state, summary.result), (30, GameState::GameOver, GameResult::TurnLimit));
+        assert!(GameMove::ALL.into_iter().all(|direction| !game.can_move(direction) && !game.make_move(direction)));
+        assert_eq!(game.find_best_move(10), Err(Error::NoValidMove));
+
+        let mut game = Game::<4>::builder().seed(621).max_turns(5).build().unwrap();
+        let summary = game.autoplay(20, None).unwrap();
+        assert_eq!((summary.turns, summary.result), (5, GameResult::TurnLimit));
+
+        // the simulated games of the searches stop at the limit too
+        let mut game = Game::<4>::builder().seed(621).max_turns(3).build().unwrap();
+        game.make_move(GameMove::ALL.into_iter().find(|&direction| game.can_move(direction)).unwrap());
+        let options = SearchOptions {
+            depth: 200,
+            objective: SearchObjective::Survival,
+            ..Default::default()
+        };
+        let report = game.search_report(&options, None).unwrap();
+        assert!(report.scores.iter().flatten().all(|&turns| turns <= 2.0), "{:?}", report.scores);
+        let report = game.find_best_move_mcts_report(&SearchOptions { depth: 200, ..options }, 1.4).unwrap();
+        assert!(report.scores.iter().flatten().all(|&turns| turns <= 2.0), "{:?}", report.scores);
+
+        // the limit is reached by the move that reaches the target
+        let config = HistoryConfig {
+            keep_move_log: true,
+            ..HistoryConfig::default()
+        };
+        let mut game = Game::<4>::builder().seed(621).target(32).history(config).build().unwrap();
+        let mut rng = StdRng::seed_from_u64(621);
+        while game.result() == GameResult::Pending {
+            let direction = GameMove::ALL.into_iter().filter(|&direction| game.can_move(direction)).choose(&mut rng).unwrap();
+            assert!(game.make_move(direction));
+        }
+        assert_eq!(game.result(), GameResult::Victory);
+        let mut limited = Game::<4>::builder().seed(621).target(32).max_turns(game.turns()).build().unwrap();
+        assert_eq!(limited.replay(game.move_log()), Ok(game.turns() as usize));
+        assert_eq!((limited.state(), limited.result()), (GameState::GameOver, GameResult::Victory));
+
+        // undoing the last move continues the game
+        let mut limited = Game::<4>::builder()
+            .seed(621)
+            .max_turns(3)
+            .history(HistoryConfig { max_undo: 1, ..config })
+            .build()
+            .unwrap();
+        limited.replay(game.move_log().take(3)).unwrap();
+        assert_eq!(limited.result(), GameResult::TurnLimit);
+        assert!(limited.undo());
+        assert_eq!((limited.state(), limited.result()), (GameState::InProgress, GameResult::Pending));
+    }
+
+    #[test]
+    fn game_builder() {
+        //! Test building games with custom options and rejecting conflicting options
+
+        let game: Game = Game::builder().build().unwrap();
+        let reference: Game = Game::new().unwrap();
+        assert_eq!(game.board().iter().flatten().filter(|&&tile| tile != 0).count(), 1);
+        assert_eq!((game.target(), game.spawn_config(), game.score()), (reference.target(), reference.spawn_config(), 0));
+        assert_eq!((game.state(), game.result()), (GameState::InProgress, GameResult::Pending));
+
+        let game = Game::<5>::builder()
+            .seed(42)
+            .target(4096)
+            .spawn_config(SpawnConfig::new(0.5).unwrap())
+            .starting_tiles(3)
+            .build()
+            .unwrap();
+        assert_eq!(game.size(), 5);
+        assert_eq!(game.target(), Some(4096));
+        assert_eq!(game.board().iter().flatten().filter(|&&tile| tile != 0).count(), 3);
+
+        // the same seed spawns the same tiles
+        let mut games = [1, 2].map(|_| Game::<4>::builder().seed(7).starting_tiles(2).build().unwrap());
+        for _ in 0..20 {
+            let direction = GameMove::ALL.into_iter().find(|&direction| games[0].can_move(direction)).unwrap();
+            games.iter_mut().for_each(|game| assert!(game.make_move(direction)));
+            assert_eq!(games[0].board(), games[1].board());
+        }
+
+        let game = Game::<2>::builder().endless(true).build().unwrap();
+        assert_eq!(game.target(), None);
+        let game = Game::<4>::builder().merge_rule(Arc::new(ThreesRule)).target(96).build().unwrap();
+        assert_eq!(game.spawn_config(), &ThreesRule.spawn_config());
+
+        // conflicting options
+        let builder = Game::<4>::builder();
+        assert_eq!(Game::<1>::builder().build().unwrap_err(), Error::InvalidSize { size: 1 });
+        assert_eq!(builder.clone().target(4).build().unwrap_err(), Error::InvalidConfig);
+        assert_eq!(builder.clone().target(3000).build().unwrap_err(), Error::InvalidConfig);
+        assert_eq!(builder.clone().target(2048).endless(true).build().unwrap_err(), Error::InvalidConfig);
+        assert_eq!(
+            builder.clone().spawn_config(SpawnConfig::new(0.0).unwrap()).target(2).build().unwrap_err(),
+            Error::InvalidConfig
+        );
+        assert_eq!(builder.clone().starting_tiles(0).build().unwrap_err(), Error::InvalidConfig);
+        assert_eq!(builder.clone().starting_tiles(17).build().unwrap_err(), Error::InvalidConfig);
+        assert_eq!(builder.clone().max_turns(0).build().unwrap_err(), Error::InvalidConfig);
+        assert_eq!(
+            builder.clone().merge_rule(Arc::new(ThreesRule)).spawn_config(SpawnConfig::default()).build().unwrap_err(),
+            Error::InvalidConfig
+        );
+        assert!(builder.starting_tiles(16).build().is_ok());
+    }
+
+    #[test]
+    fn board_validation() {
+        //! Test that the validation reports the first invalid tile
+
+        assert_eq!(validate_board(&[[0, 2, 4], [8, 16, 32], [64, 128, 1 << 63]]), Ok(()));
+        assert_eq!(
+            validate_board(&[[0, 2, 4], [8, 12, 32], [64, 1, 256]]),
+            Err(Error::InvalidValue { row: 1, col: 1, value: 12 })
+        );
+        assert_eq!(validate_board(&[[0, 0], [0, 1]]), Err(Error::InvalidValue { row: 1, col: 1, value: 1 }));
+        assert_eq!(validate_board(&[[u64::MAX]]), Err(Error::InvalidSize { size: 1 }));
+        assert_eq!(
+            validate_board_with_rule(&[[1, 2], [3, 4]], &ThreesRule),
+            Err(Error::InvalidValue { row: 1, col: 1, value: 4 })
+        );
+
+        let board = [[2, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 6], [0, 0, 0, 0]];
+        assert_eq!(Game::from_existing(&board, 0).unwrap_err(), validate_board(&board).unwrap_err());
+
+        let rows = vec![vec![2, 4, 8], vec![0, 0, 0], vec![0, 0, 2]];
+        assert_eq!(Game::<3>::from_rows(&rows, 12).unwrap().board(), &[[2, 4, 8], [0, 0, 0], [0, 0, 2]]);
+        assert_eq!(Game::<3>::from_rows(&rows[..2], 0).unwrap_err(), Error::InvalidBoard { expected: 3, actual: 2 });
+        assert_eq!(
+            Game::<3>::from_rows(&[vec![2, 4, 8], vec![0, 0], vec![0, 0, 2]], 0).unwrap_err(),
+            Error::InvalidBoard { expected: 3, actual: 2 }
+        );
+        assert_eq!(
+            Game::<3>::from_rows(&[vec![2, 4, 8], vec![0, 0, 0], vec![0, 0, 5]], 0).unwrap_err(),
+            Error::InvalidValue { row: 2, col: 2, value: 5 }
+        );
+        assert_eq!(
+            Error::InvalidBoard { expected: 3, actual: 2 }.to_string(),
+            "Invalid shape of a board. Expected 3 rows of 3 tiles, found a length of 2."
+        );
+        assert_eq!(
+            Error::InvalidValue { row: 2, col: 3, value: 6 }.to_string(),
+            "Invalid value 6 in a board at row 2, column 3. Must be 0 or power of 2, starting from 2."
+        );
+    }
+
+    #[test]
+    fn terminal_boards() {
+        //! Test the state and the result of games created from full and empty boards
+
+        // a full board with no equal neighbours is lost
+        let game = Game::from_existing(&[[2, 4, 2, 4], [4, 2, 4, 2], [2, 4, 2, 4], [4, 2, 4, 2]], 0).unwrap();
+        assert_eq!((game.state(), game.result()), (GameState::GameOver, GameResult::Loss));
+        assert!(GameMove::ALL.into_iter().all(|direction| !game.can_move(direction)));
+
+        // ... or won, if the target tile is on the board
+        let game = Game::from_existing(&[[2, 4, 2, 4], [4, 2048, 4, 2], [2, 4, 2, 4], [4, 2, 4, 2]], 0).unwrap();
+        assert_eq!((game.state(), game.result()), (GameState::GameOver, GameResult::Victory));
+
+        // a full board with equal neighbours can still be played, in the directions of the merges only
+        let mut game = Game::from_existing(&[[8, 8, 2, 4], [2, 4, 8, 2], [4, 2, 4, 8], [2, 4, 2, 4]], 0).unwrap();
+        assert_eq!((game.state(), game.result()), (GameState::InProgress, GameResult::Pending));
+        assert!(game.can_move(GameMove::Left) && game.can_move(GameMove::Right));
+        assert!(!game.can_move(GameMove::Up) && !game.can_move(GameMove::Down));
+        assert!(game.make_move(GameMove::Left));
+        assert_eq!(game.score(), 16);
+
+        // an empty board gets the starting tile
+        let game = Game::from_existing(&[[0; 4]; 4], 0).unwrap();
+        assert_eq!(game.board().iter().flatten().filter(|&&tile| tile != 0).count(), 1);
+        assert_eq!((game.state(), game.result()), (GameState::InProgress, GameResult::Pending));
+        let game = Game::from_existing_with_rule(&[[0; 3]; 3], 0, Arc::new(ThreesRule)).unwrap();
+        assert_eq!(game.board().iter().flatten().filter(|&&tile| tile != 0).count(), 1);
+    }
+
+    #[test]
+    fn victory_then_loss() {
+        //! Test a game that is won, continues and is lost later
+
+        let mut game = Game::from_existing(&[[16, 16], [2, 4]], 0)
+            .unwrap()
+            .with_spawner(Box::new(ScriptedSpawner::new([((0, 1), 4), ((1, 1), 4)])));
+        assert!(game.make_move(GameMove::Left));
+        assert_eq!(game.board(), &[[32, 4], [2, 4]]);
+        assert_eq!((game.state(), game.result()), (GameState::InProgress, GameResult::Victory));
+        assert!(game.result().is_victory());
+
+        assert!(game.make_move(GameMove::Up));
+        assert_eq!(game.board(), &[[32, 8], [2, 4]]);
+        assert_eq!((game.state(), game.result()), (GameState::GameOver, GameResult::VictoryThenLoss));
+        assert!(game.result().is_victory());
+        assert!(GameMove::ALL.into_iter().all(|direction| !game.make_move(direction)));
+
+        // the last possible move reaching the target is a plain victory
+        let mut game = Game::from_existing(&[[16, 16], [2, 4]], 0)
+            .unwrap()
+            .with_spawner(Box::new(ScriptedSpawner::new([((0, 1), 2)])));
+        assert!(game.make_move(GameMove::Left));
+        assert_eq!((game.state(), game.result()), (GameState::GameOver, GameResult::Victory));
+
+        let game = Game::from_existing(&[[2, 4], [4, 2]], 0).unwrap();
+        assert_eq!(game.result(), GameResult::Loss);
+        assert!(!game.result().is_victory());
+    }
+
+    #[test]
+    fn game_summary() {
+        //! Test the counters and the timing reported in the summary of the game
+
+        let mut game = Game::from_existing(&[[16, 16], [2, 4]], 0)
+            .unwrap()
+            .with_timing()
+            .with_spawner(Box::new(ScriptedSpawner::new([((0, 1), 4), ((1, 1), 4)])));
+        let summary = game.summary();
+        assert_eq!((summary.turns, summary.merges, summary.victory_turn), (0, 0, None));
+        assert!(summary.duration.is_some());
+
+        game.make_move(GameMove::Left);
+        let summary = game.summary();
+        assert_eq!((summary.score, summary.highest_tile, summary.turns, summary.merges), (32, 32, 1, 1));
+        assert_eq!((summary.state, summary.result, summary.victory_turn), (GameState::InProgress, GameResult::Victory, Some(1)));
+
+        game.make_move(GameMove::Up);
+        let summary = game.summary();
+        assert_eq!((summary.score, summary.turns, summary.merges, summary.victory_turn), (40, 2, 2, Some(1)));
+        assert_eq!(summary.result, GameResult::VictoryThenLoss);
+        // the timing stops when the game is over
+        assert_eq!(game.summary().duration, summary.duration);
+
+        let summary = GameSummary {
+            duration: None,
+            average_move_time: None,
+            slowest_move: None,
+            ..summary
+        };
+        assert_eq!(
+            summary.to_string(),
+            "Score: 40, highest tile: 32, turns: 2, merges: 2, state: GameOver, result: VictoryThenLoss, victory on turn 1"
+        );
+
+        let mut game = Game::<4>::new().unwrap();
+        let summary = game.play_with(&mut RandomStrategy);
+        assert_eq!(summary, game.summary());
+        assert_eq!(summary.duration, None);
+        assert!(summary.merges <= summary.turns * 8);
+
+        #[cfg(feature = "serde")]
+        {
+            fn serializable<T: serde::Serialize + serde::de::DeserializeOwned>() {}
+            serializable::<GameSummary>();
+        }
+    }
+
+    #[test]
+    fn symmetries() {
+        //! Test that transforming the board and the move together gives the same outcome as moving first and transforming after
+
+        type Transform = (fn(&mut Game<5>), fn(&[[u64; 5]; 5]) -> [[u64; 5]; 5], fn(GameMove) -> GameMove);
+        let transforms: [Transform; 4] = [
+            (Game::rotate_cw, symmetry::rotate_cw, GameMove::rotate_cw),
+            (Game::rotate_ccw, symmetry::rotate_ccw, GameMove::rotate_ccw),
+            (Game::flip_horizontal, symmetry::flip_horizontal, GameMove::flip_horizontal),
+            (Game::flip_vertical, symmetry::flip_vertical, GameMove::flip_vertical),
+        ];
+
+        let mut rng = thread_rng();
+        for _ in 0..200 {
+            let mut board = [[0; 5]; 5];
+            for tile in board.iter_mut().flatten() {
+                if rng.gen_bool(0.6) {
+                    *tile = 1 << rng.gen_range(1..=3);
+                }
+            }
+            if board == [[0; 5]; 5] {
+                continue;
+            }
+            let game = Game::from_existing(&board, 0).unwrap();
+
+            for (transform_game, transform_board, transform_move) in transforms {
+                let mut transformed = Game::from_existing(&board, 0).unwrap().with_tile_ids();
+                transform_game(&mut transformed);
+                assert_eq!(transformed.board(), &transform_board(&board));
+                assert_eq!(transformed.canonical_hash(), game.canonical_hash());
+                for direction in GameMove::ALL {
+                    let expected = game.peek_move(direction).map(|(next, score)| (transform_board(&next), score));
+                    assert_eq!(transformed.peek_move(transform_move(direction)), expected);
+                }
+                // the identities follow the tiles
+                let ids = transformed.tile_ids().unwrap();
+                assert!((0..25).all(|cell| (transformed.board()[cell / 5][cell % 5] != 0) == ids[cell / 5][cell % 5].is_some()));
+            }
+        }
+
+        // transforming a game won by its last possible move keeps the victory
+        let mut game = Game::from_existing(&[[16, 16], [2, 4]], 0)
+            .unwrap()
+            .with_spawner(Box::new(ScriptedSpawner::new([((0, 1), 2)])));
+        assert!(game.make_move(GameMove::Left));
+        assert_eq!((game.state(), game.result()), (GameState::GameOver, GameResult::Victory));
+        for transform in [Game::rotate_cw, Game::rotate_ccw, Game::flip_horizontal, Game::flip_vertical] {
+            transform(&mut game);
+            assert_eq!((game.state(), game.result()), (GameState::GameOver, GameResult::Victory));
+        }
+    }
+
+    #[test]
+    fn game_4_ai() {
+        //! Test the AI's ability to play a game with the default size (4x4)
+
+        let mut game: Game<4> = Game::new().unwrap();
+        while let Ok(best_move) = game.find_best_move(1_000) {
+            game.make_move(best_move);
+        }
+
+        assert_eq!(game.state(), GameState::GameOver);
+        assert_ne!(game.score(), 0);
+    }
+
+    #[test]
+    fn adaptive_depth() {
+        //! Test the scaling of the adaptive search depth and compare it with the fixed depth search
+
+        let policy = AdaptivePolicy::default();
+        assert_eq!(policy.depth(100, &[[0; 4]; 4]), 100);
+        assert_eq!(policy.depth(100, &[[2, 4, 2, 4], [4, 2, 4, 2], [2, 4, 0, 0], [0, 0, 0, 0]]), 267);
+        assert_eq!(policy.depth(100, &[[2, 4, 2, 4], [4, 2, 4, 2], [2, 4, 2, 4], [4, 2, 4, 0]]), 800);
+        assert_eq!(policy.depth(100, &[[2, 4, 2, 4], [4, 2, 4, 2], [2, 4, 2, 4], [4, 2, 4, 2]]), 800);
+        assert_eq!(
+            AdaptivePolicy { max_multiplier: 2.0 }.depth(100, &[[2, 4, 2, 4], [4, 2, 4, 2], [2, 4, 0, 0], [0, 0, 0, 0]]),
+            200
+        );
+
+        // play the same number of games with a comparable number of simulated games per move
+        // (the scores are compared instead of the win rates, as wins are rare with such shallow searches)
+        let mut fixed_score = 0;
+        let (mut adaptive_score, mut adaptive_moves, mut adaptive_budget) = (0, 0, 0);
+        for _ in 0..8 {
+            let mut game: Game<4> = Game::new().unwrap();
+            while let Ok(best_move) = game.find_best_move(60) {
+                game.make_move(best_move);
+            }
+            fixed_score += game.score();
+
+            let mut game: Game<4> = Game::new().unwrap();
+            while let Ok(best_move) = game.find_best_move_adaptive(11, &policy) {
+                adaptive_moves += 1;
+                adaptive_budget += policy.depth(11, game.board());
+                game.make_move(best_move);
+            }
+            adaptive_score += game.score();
+        }
+
+        assert!(adaptive_budget as f64 / adaptive_moves as f64 <= 60.0 * 1.25);
+        assert!(adaptive_score as f64 >= fixed_score as f64 * 0.6);
+    }
+
+    #[test]
+    fn exact_endgame() {
+        //! Test the exhaustive search on a trap position, where the Monte Carlo search goes for the bigger merge and loses
+
+        // moving left scores more, but the game rarely survives the following moves
+        let game = Game::from_existing(&[[8, 2, 8, 64], [4, 32, 16, 32], [1024, 1024, 2, 256], [2, 8, 256, 32]], 0).unwrap();
+        assert_eq!(game.find_best_move(1_000), Ok(GameMove::Left));
+        assert_eq!(game.find_best_move_exact(1), Ok(GameMove::Right));
+        assert_eq!(game.find_best_move_exact(3), Ok(GameMove::Right));
+
+        let (survival, score) = game.expectimax_move(GameMove::Left, 3, None).unwrap();
+        assert!(survival < 0.1);
+        assert!(score > 2_048.0);
+        assert_eq!(game.expectimax_move(GameMove::Right, 3, None).unwrap().0, 1.0);
+        assert_eq!(game.expectimax_move(GameMove::Up, 3, None), None);
+
+        let game = Game::from_existing(&[[2, 4, 2, 4], [4, 2, 4, 2], [2, 4, 2, 4], [4, 2, 4, 2]], 0).unwrap();
+        assert_eq!(game.find_best_move_exact(2), Err(Error::NoValidMove));
+    }
+
+    #[test]
+    fn loss_guard() {
+        //! Test that the loss guard avoids the moves after which the spawns can force the game over
+
+        let game = Game::from_existing(&[[8, 2, 8, 64], [4, 32, 16, 32], [1024, 1024, 2, 256], [2, 8, 256, 32]], 0).unwrap();
+        assert!(!game.loss_forced(GameMove::Left, 1));
+        assert!(game.loss_forced(GameMove::Left, 2));
+        assert!(!game.loss_forced(GameMove::Right, 3));
+        assert!(!game.loss_forced(GameMove::Left, 0));
+
+        let values = game.evaluate_moves(&SearchOptions::default()).unwrap();
+        assert!(values[0].unwrap() > values[1].unwrap());
+        assert_eq!(values[2], None);
+
+        assert_eq!(game.find_best_move_with_options(&SearchOptions::default()), Ok(GameMove::Left));
+        let options = SearchOptions {
+            loss_guard: 2,
+            ..Default::default()
+        };
+        assert_eq!(game.find_best_move_with_options(&options), Ok(GameMove::Right));
+    }
+
+    #[test]
+    fn search_objectives() {
+        //! Test that the survival objective prefers the move that keeps the game alive over the one that scores more
+
+        let game = Game::from_existing(&[[8, 2, 8, 64], [4, 32, 16, 32], [1024, 1024, 2, 256], [2, 8, 256, 32]], 0).unwrap();
+        let options = SearchOptions {
+            objective: SearchObjective::Survival,
+            ..Default::default()
+        };
+        assert_eq!(game.find_best_move_with_options(&options), Ok(GameMove::Right));
+        let options = SearchOptions {
+            objective: SearchObjective::Score,
+            ..Default::default()
+        };
+        assert_eq!(game.find_best_move_with_options(&options), Ok(GameMove::Left));
+
+        let options = SearchOptions {
+            objective: SearchObjective::MaxTile,
+            depth: 100,
+            ..Default::default()
+        };
+        // both valid moves merge the 1024 tiles
+        let values = game.evaluate_moves(&options).unwrap();
+        assert!(values.iter().flatten().all(|&value| value >= 2048.0));
+    }
+
+    #[test]
+    fn search_depth() {
+        //! Test the validation of the depth and that exactly the requested number of simulated games is played
+
+        let game = Game::from_existing(&[[0, 0, 0, 2], [0, 0, 0, 4], [0, 0, 0, 0], [0, 0, 0, 4]], 0).unwrap();
+        assert_eq!(game.find_best_move(0), Err(Error::InvalidDepth));
+        assert_eq!(game.evaluate_moves(&SearchOptions { depth: 0, ..Default::default() }), Err(Error::InvalidDepth));
+        let game_over = Game::from_existing(&[[2, 4, 2, 4], [4, 2, 4, 2], [2, 4, 2, 4], [4, 2, 4, 2]], 0).unwrap();
+        assert_eq!(game_over.find_best_move(0), Err(Error::InvalidDepth));
+        assert_eq!(game_over.find_best_move(1), Err(Error::NoValidMove));
+
+        // left, up and down are valid
+        for (depth, expected) in [(1, [1, 0, 0, 0]), (2, [1, 0, 1, 0]), (7, [3, 0, 2, 2]), (1_000, [334, 0, 333, 333])] {
+            let (_, simulations, _) = game.simulate_moves(&SearchOptions { depth, ..Default::default() }).unwrap();
+            assert_eq!(simulations, expected);
+        }
+        let values = game.evaluate_moves(&SearchOptions { depth: 1, ..Default::default() }).unwrap();
+        assert!(values[0].unwrap() > 0.0);
+        assert_eq!(values[1], None);
+        assert_eq!(values[2], Some(0.0));
+
+        for threads in 1..=5 {
+            for total in 0..20 {
+                assert_eq!((0..threads).map(|part| Game::<4>::split_evenly(total, threads, part)).sum::<usize>(), total);
+            }
+        }
+    }
+
+    #[test]
+    fn search_progress() {
+        //! Test that the progress callback is called every interval and once more at the end
+
+        let game = Game::from_existing(&[[0, 0, 0, 2], [0, 0, 0, 4], [0, 0, 0, 0], [0, 0, 0, 4]], 0).unwrap();
+        let reports = Arc::new(Mutex::new(Vec::new()));
+        let reports_clone = Arc::clone(&reports);
+        let options = SearchOptions {
+            depth: 105,
+            progress: Some(Arc::new(move |progress| reports_clone.lock().unwrap().push(progress))),
+            progress_interval: 10,
+            ..Default::default()
+        };
+        assert!(game.find_best_move_with_options(&options).is_ok());
+
+        let mut reports = reports.lock().unwrap().clone();
+        reports.sort_by_key(|progress| progress.rollouts_done);
+        assert_eq!(reports.len(), 11);
+        assert!(reports.iter().all(|progress| progress.rollouts_planned == 105 && progress.best_move.is_some()));
+        assert_eq!(
+            reports.iter().map(|progress| progress.rollouts_done).collect::<Vec<_>>(),
+            [10, 20, 30, 40, 50, 60, 70, 80, 90, 100, 105]
+        );
+    }
+
+    #[test]
+    fn current_thread_backend() {
+        //! Test that the search on the current thread plays all simulated games and is reproducible with a seed
+
+        let game = Game::from_existing(&[[0, 0, 0, 2], [0, 0, 0, 4], [0, 0, 0, 0], [0, 0, 0, 4]], 0).unwrap();
+        let options = SearchOptions {
+            backend: SearchBackend::CurrentThread,
+            seed: Some(3),
+            ..Default::default()
+        };
+        assert!(game.can_move(game.find_best_move_with_options(&options).unwrap()));
+        let results = game.simulate_moves(&options).unwrap();
+        assert_eq!(results.1, [334, 0, 333, 333]);
+        assert_eq!(game.simulate_moves(&options).unwrap(), results);
+    }
+
+    #[test]
+    fn custom_executor() {
+        //! Test that the search runs its jobs only on the executor of the caller and gives the same results as without it
+
+        /// An executor with a single worker thread, counting the jobs.
+        struct CountingExecutor {
+            jobs: AtomicUsize,
+            sender: Mutex<Sender<Job>>,
+        }
+        impl Executor for CountingExecutor {
+            fn execute(&self, job: Job) {
+                self.jobs.fetch_add(1, AtomicOrdering::Relaxed);
+                self.sender.lock().unwrap().send(job).unwrap();
+            }
+        }
+
+        let (sender, receiver) = mpsc::channel::<Job>();
+        let worker = thread::spawn(move || receiver.into_iter().for_each(|job| job()));
+        let executor = Arc::new(CountingExecutor {
+            jobs: AtomicUsize::new(0),
+            sender: Mutex::new(sender),
+        });
+        let threads = Arc::new(Mutex::new(HashSet::new()));
+        let threads_clone = Arc::clone(&threads);
+
+        let game = Game::from_existing(&[[0, 0, 0, 2], [0, 0, 0, 4], [0, 0, 0, 0], [0, 0, 0, 4]], 0).unwrap();
+        let options = SearchOptions {
+            backend: SearchBackend::CurrentThread,
+            seed: Some(3),
+            ..Default::default()
+        };
+        let expected = game.simulate_moves(&options).unwrap();
+        let options = SearchOptions {
+            executor: Some(executor.clone()),
+            progress: Some(Arc::new(move |_| {
+                threads_clone.lock().unwrap().insert(thread::current().id());
+            })),
+            ..options
+        };
+        assert_eq!(game.simulate_moves(&options).unwrap(), expected);
+        assert!(game.can_move(game.find_best_move_with_options(&options).unwrap()));
+        assert_eq!(executor.jobs.load(AtomicOrdering::Relaxed), 2);
+        assert_eq!(*threads.lock().unwrap(), HashSet::from([worker.thread().id()]));
+
+        let inline = SearchOptions {
+            executor: Some(Arc::new(InlineExecutor)),
+            progress: None,
+            ..options.clone()
+        };
+        assert_eq!(game.simulate_moves(&inline).unwrap(), expected);
+        let spawned = SearchOptions {
+            executor: Some(Arc::new(ThreadExecutor::new(3))),
+            ..inline
+        };
+        assert_eq!(game.simulate_moves(&spawned).unwrap().1, expected.1);
+
+        drop(options);
+        drop(executor);
+        worker.join().unwrap();
+    }
+
+    #[test]
+    fn parallel_totals() {
+        //! Test that many threads publishing their results at once give the same totals as the same chunks played on a single thread
+
+        /// An executor that runs the jobs one after another on the calling thread, but splits the games like many threads.
+        struct SplitInline(usize);
+        impl Executor for SplitInline {
+            fn execute(&self, job: Job) {
+                job();
+            }
+
+            fn threads(&self) -> usize {
+                self.0
+            }
+        }
+
+        let game = Game::from_existing(&[[0, 0, 0, 2], [0, 0, 0, 4], [0, 0, 0, 0], [0, 0, 0, 4]], 0).unwrap();
+        for progress in [None, Some(Arc::new(|_| {}) as ProgressCallback)] {
+            let options = SearchOptions {
+                depth: 3000,
+                seed: Some(8),
+                executor: Some(Arc::new(SplitInline(64))),
+                progress,
+                progress_interval: 7,
+                risk_horizon: 20,
+                ..Default::default()
+            };
+            let expected = game.simulate_moves(&options).unwrap();
+            assert_eq!(expected.1.iter().sum::<usize>(), 3000);
+            let parallel = SearchOptions {
+                executor: Some(Arc::new(ThreadExecutor::new(64))),
+                ..options
+            };
+            for _ in 0..5 {
+                assert_eq!(game.simulate_moves(&parallel).unwrap(), expected);
+            }
+        }
+
+        // the sums carry past 64 bits
+        let sum = AtomicSum::default();
+        thread::scope(|scope| {
+            for _ in 0..8 {
+                scope.spawn(|| {
+                    for _ in 0..1000 {
+                        sum.add(u128::from(u64::MAX));
+                    }
+                });
+            }
+        });
+        assert_eq!(sum.load(), 8000 * u128::from(u64::MAX));
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn rayon_backend() {
+        //! Test that both backends return a legal move and play the same number of simulated games
+
+        let game = Game::from_existing(&[[0, 0, 0, 2], [0, 0, 0, 4], [0, 0, 0, 0], [0, 0, 0, 4]], 0).unwrap();
+        for backend in [SearchBackend::Threads, SearchBackend::Rayon] {
+            let options = SearchOptions { backend, ..Default::default() };
+            assert!(game.can_move(game.find_best_move_with_options(&options).unwrap()));
+            assert_eq!(game.simulate_moves(&options).unwrap().1, [334, 0, 333, 333]);
+        }
+
+        let pool = rayon::ThreadPoolBuilder::new().num_threads(3).build().unwrap();
+        let options = SearchOptions {
+            backend: SearchBackend::Rayon,
+            depth: 100,
+            ..Default::default()
+        };
+        assert_eq!(pool.install(move || game.simulate_moves(&options)).unwrap().1, [34, 0, 33, 33]);
+    }
+
+    #[test]
+    fn game_5_ai() {
+        //! Test the AI's ability to play a big game
+
+        let mut game: Game<5> = Game::new().unwrap();
+        while let Ok(best_move) = game.find_best_move(1_000) {
+            game.make_move(best_move);
+        }
+
+        assert_eq!(game.state(), GameState::GameOver);
+        assert_ne!(game.score(), 0);
+    }
+
+    #[test]
+    fn board_exponents() {
+        //! Test the conversion of the board to the exponents of the tiles
+
+        let game = Game::from_existing(&[[0, 2, 4], [8, 0, 1 << 63], [0, 0, 1024]], 0).unwrap();
+        assert_eq!(game.board_exponents(), Some([[0, 1, 2], [3, 0, 63], [0, 0, 10]]));
+        let game = Game::from_existing(&[[0, 2, 4], [8, 0, 16], [0, 0, 1024]], 0)
+            .unwrap()
+            .with_blocked_cells(&[(0, 0)])
+            .unwrap();
+        assert_eq!(game.board_exponents(), None);
+        let game = Game::<3>::from_existing_with_rule(&[[1, 2, 3], [0, 0, 0], [0, 0, 0]], 0, Arc::new(ThreesRule)).unwrap();
+        assert_eq!(game.board_exponents(), None);
+    }
+
+    #[test]
+    fn text_forms() {
+        //! Test the exponent view and the compact line of fixed boards, and parsing the compact line back
+
+        let game = Game::from_existing(&[[2, 0, 0, 4], [0, 8, 16, 0], [0, 0, 2048, 0], [0, 0, 0, 2]], 1234).unwrap();
+        assert_eq!(
+            game.to_string(),
+            "Board:\n    2    0    0    4\n    0    8   16    0\n    0    0 2048    0\n    0    0    0    2\nScore: 1234\n"
+        );
+        assert_eq!(game.to_exponent_string(), "Board:\n  1  0  0  2\n  0  3  4  0\n  0  0 11  0\n  0  0  0  1\nScore: 1234\n");
+        assert_eq!(game.to_compact_string(), "4|2,0,0,4/0,8,16,0/0,0,2048,0/0,0,0,2/score=1234");
+        assert_eq!("4|2,0,0,4/0,8,16,0/0,0,2048,0/0,0,0,2/score=1234".parse::<Game>().unwrap(), game);
+
+        let blocked = Game::from_existing(&[[2, 0, 0], [0, 0, 0], [0, 0, 4]], 0).unwrap().with_blocked_cells(&[(1, 1)]).unwrap();
+        assert_eq!(blocked.to_exponent_string(), "Board:\n 1 0 0\n 0 # 0\n 0 0 2\nScore: 0\n");
+        assert_eq!(blocked.to_compact_string(), "3|2,0,0/0,#,0/0,0,4/score=0");
+        let parsed: Game<3> = blocked.to_compact_string().parse().unwrap();
+        assert_eq!((parsed.board(), parsed.blocked_cells()), (blocked.board(), vec![(1, 1)]));
+        let threes = Game::<3>::from_existing_with_rule(&[[1, 2, 3], [0, 0, 0], [0, 0, 6]], 0, Arc::new(ThreesRule)).unwrap();
+        assert_eq!(threes.to_exponent_string(), "Board:\n ? 1 ?\n 0 0 0\n 0 0 ?\nScore: 0\n");
+        let wide = Game::from_existing(&[[2, 1 << 20], [1 << 40, 0]], 0).unwrap();
+        assert_eq!(wide.to_string(), "Board:\n             2       1048576\n 1099511627776             0\nScore: 0\n");
+
+        // the compact line of any game round-trips
+        for seed in 0..20 {
+            let mut game = Game::<5>::builder().seed(seed).build().unwrap();
+            game.play_with(&mut RandomStrategy);
+            let parsed: Game<5> = game.to_compact_string().parse().unwrap();
+            assert_eq!(parsed, game);
+            assert_eq!(parsed.to_compact_string(), game.to_compact_string());
+        }
+
+        // the other merge rules are named in the line
+        assert_eq!(threes.to_compact_string(), "3|1,2,3/0,0,0/0,0,6/score=0/rule=threes");
+        let fibonacci = Game::<3>::from_existing_with_rule(&[[1, 2, 0], [3, 5, 0], [0, 0, 8]], 0, Arc::new(FibonacciRule)).unwrap();
+        assert_eq!(fibonacci.to_compact_string(), "3|1,2,0/3,5,0/0,0,8/score=0/rule=fibonacci");
+        for game in [threes, fibonacci] {
+            let parsed: Game<3> = game.to_compact_string().parse().unwrap();
+            assert_eq!(parsed.board(), game.board());
+            assert_eq!(parsed.merge_rule().name(), game.merge_rule().name());
+            assert_eq!(parsed.to_compact_string(), game.to_compact_string());
+        }
+
+        for (line, error) in [
+            ("", Error::InvalidEncoding),
+            ("4|2,0,0,4/0,8,16,0/0,0,2048,0/0,0,0,2", Error::InvalidEncoding),
+            ("4|2,0,0,4/0,8,16,0/0,0,2048,0/0,0,0,2/score=-1", Error::InvalidEncoding),
+            ("4|2,0,0,x/0,8,16,0/0,0,2048,0/0,0,0,2/score=0", Error::InvalidEncoding),
+            ("3|2,0,0/0,0,0/0,0,4/score=0", Error::InvalidBoard { expected: 4, actual: 3 }),
+            ("4|2,0,0,4/0,8,16,0/0,0,2048,0/score=0", Error::InvalidBoard { expected: 4, actual: 3 }),
+            ("4|2,0,0,4/0,8,16/0,0,2048,0/0,0,0,2/score=0", Error::InvalidBoard { expected: 4, actual: 3 }),
+            ("4|2,0,0,4/0,8,16,0/0,0,2048,0/0,0,0,3/score=0", Error::InvalidValue { row: 3, col: 3, value: 3 }),
+            (
+                "4|2,0,0,4/0,8,16,0/0,0,2048,0/0,0,0,2/score=0/rule=threes",
+                Error::InvalidValue { row: 0, col: 3, value: 4 },
+            ),
+            ("4|2,0,0,4/0,8,16,0/0,0,2048,0/0,0,0,2/score=0/rule=custom", Error::InvalidConfig),
+            ("4|2,0,0,4/0,8,16,0/0,0,2048,0/0,0,0,2/score=0/rule=", Error::InvalidConfig),
+        ] {
+            assert_eq!(line.parse::<Game>().unwrap_err(), error);
+        }
+    }
+
+    #[test]
+    fn forks() {
+        //! Test that the forks copy only the position and the configuration, and the searches never notify the observer
+
+        struct Counter(Arc<AtomicUsize>);
+        impl GameObserver for Counter {
+            fn on_move(&mut self, _: GameMove, _: u64) {
+                self.0.fetch_add(1, AtomicOrdering::Relaxed);
+            }
+            fn on_merge(&mut self, _: (usize, usize), _: u64) {
+                self.0.fetch_add(1, AtomicOrdering::Relaxed);
+            }
+            fn on_spawn(&mut self, _: (usize, usize), _: u64) {
+                self.0.fetch_add(1, AtomicOrdering::Relaxed);
+            }
+        }
+
+        let history = HistoryConfig {
+            max_undo: 5,
+            keep_move_log: true,
+            ..Default::default()
+        };
+        let events = Arc::new(AtomicUsize::new(0));
+        let mut game = Game::<3>::builder().seed(614).history(history).build().unwrap().with_tile_ids().with_score_history();
+        game.set_observer(Box::new(Counter(Arc::clone(&events))));
+        for _ in 0..3 {
+            let direction = game.find_best_move(50).unwrap();
+            assert!(game.make_move(direction));
+        }
+        let seen = events.load(AtomicOrdering::Relaxed);
+        assert!(seen >= 6);
+
+        // the searches on the game and on the forks
+        game.find_best_move(200).unwrap();
+        game.find_best_move_mcts(200, 1.4).unwrap();
+        game.hint(50).unwrap();
+        let mut engine = SearchEngine::new(SearchOptions { depth: 50, ..Default::default() }, 16);
+        engine.start_pondering(&game);
+        engine.stop();
+        assert_eq!(events.load(AtomicOrdering::Relaxed), seen);
+
+        let mut fork = game.fork();
+        assert_eq!(fork, game);
+        assert_eq!(fork.target(), game.target());
+        assert!(fork.tile_ids().is_none() && fork.move_log().len() == 0 && fork.score_history().len() == 0 && !fork.undo());
+        assert_eq!((fork.turns(), game.turns()), (0, 3));
+        let direction = GameMove::ALL.into_iter().find(|&direction| fork.can_move(direction)).unwrap();
+        assert!(fork.make_move(direction));
+        assert_eq!(events.load(AtomicOrdering::Relaxed), seen);
+        assert_eq!(game.move_log().len(), 3);
+
+        // the forks of a seeded game spawn the same tiles, without advancing the generator of the game
+        let mut second = game.fork();
+        assert!(second.make_move(direction));
+        assert_eq!(second.board(), fork.board());
+        let merges = game.merges;
+        assert!(game.make_move(direction));
+        assert_eq!(events.load(AtomicOrdering::Relaxed), seen + 2 + (game.merges - merges) as usize);
+    }
+
+    #[test]
+    fn replay() {
+        //! Test that replaying the logged moves, directly or through the packed log, reproduces the seeded game
+
+        let config = HistoryConfig {
+            keep_move_log: true,
+            ..HistoryConfig::default()
+        };
+        let mut game = Game::<4>::builder().seed(616).history(config).build().unwrap();
+        let mut rng = StdRng::seed_from_u64(616);
+        while let Some(direction) = GameMove::ALL.into_iter().filter(|&direction| game.can_move(direction)).choose(&mut rng) {
+            assert!(game.make_move(direction));
+        }
+        let log: MoveLog = game.move_log().collect();
+        assert_eq!(log.len() as u64, game.turns());
+
+        let mut replayed = Game::<4>::builder().seed(616).build().unwrap();
+        assert_eq!(replayed.replay(game.move_log()), Ok(log.len()));
+        assert_eq!((*replayed.board(), replayed.score()), (*game.board(), game.score()));
+
+        let decoded = MoveLog::from_base64(&log.to_base64()).unwrap();
+        let mut replayed = Game::<4>::builder().seed(616).build().unwrap();
+        assert_eq!(replayed.replay(&decoded), Ok(log.len()));
+        assert_eq!((*replayed.board(), replayed.score()), (*game.board(), game.score()));
+
+        // a move that doesn't fit the game stops the replay
+        let mut other = Game::<4>::builder().seed(617).build().unwrap();
+        assert_eq!(other.replay(&decoded), Err(Error::InvalidMove));
+
+        // the games from existing boards are seeded afterwards, and every move is allowed with std
+        let board = [[2, 0, 0, 0], [0, 0, 0, 0], [0, 0, 4, 0], [0, 0, 0, 0]];
+        let mut seeded = Game::from_existing(&board, 0).unwrap().with_seed(616);
+        let mut same = Game::from_existing(&board, 0).unwrap().with_seed(616);
+        assert!(GameMove::ALL.into_iter().all(|direction| seeded.why_invalid(direction) != Some(InvalidReason::Unseeded)));
+        for _ in 0..10 {
+            let direction = GameMove::ALL.into_iter().find(|&direction| seeded.can_move(direction)).unwrap();
+            assert!(seeded.make_move(direction) && same.make_move(direction));
+            assert_eq!(seeded.board(), same.board());
+        }
+    }
+
+    #[test]
+    fn bounded_history() {
+        //! Test that the undo and the logs keep only the configured number of the latest entries, and the undo stops at the boundary
+
+        let config = HistoryConfig {
+            max_undo: 10,
+            keep_move_log: true,
+            keep_score_log: true,
+            max_log: 25,
+        };
+        let mut game = Game::<5>::builder().seed(613).endless(true).history(config).build().unwrap().with_tile_ids();
+        assert_eq!(game.history_config(), config);
+        let mut rng = StdRng::seed_from_u64(613);
+        let mut played = Vec::new();
+        while played.len() < 100 && game.state() == GameState::InProgress {
+            let direction = GameMove::ALL.into_iter().filter(|&direction| game.can_move(direction)).choose(&mut rng).unwrap();
+            let before = (*game.board(), game.score(), game.turns(), game.best_merge(), *game.tile_ids().unwrap());
+            assert!(game.make_move(direction));
+            played.push((direction, game.last_move_gain(), before));
+
+            assert_eq!(game.history_truncated(), played.len() > 10);
+            assert_eq!(game.undo_depth(), played.len().min(10));
+            assert!(game.undo_stack.allocated() <= 10);
+            assert!(game.move_log.len() <= 25 && game.move_log.allocated() <= 25);
+            assert!(game.score_history.len() <= 25 && game.score_history.allocated() <= 25);
+        }
+        assert_eq!(played.len(), 100);
+        let kept = &played[played.len() - 25..];
+        assert!(game.move_log().eq(kept.iter().map(|&(direction, _, _)| direction)));
+        assert!(game.score_history().eq(kept.iter().map(|&(_, gained, _)| gained)));
+
+        // undoing and repeating the latest move spawns the same tile
+        let (board, score) = (*game.board(), game.score());
+        assert!(game.undo());
+        assert!(game.make_move(played[99].0));
+        assert_eq!((*game.board(), game.score()), (board, score));
+
+        for (ind, (_, _, before)) in played.iter().enumerate().rev().take(10) {
+            assert!(game.undo());
+            assert_eq!((*game.board(), game.score(), game.turns(), game.best_merge(), *game.tile_ids().unwrap()), *before);
+            assert_eq!(game.move_log().len(), ind - 75);
+        }
+        assert!(!game.undo());
+        assert_eq!(game.undo_depth(), 0);
+        assert_eq!(game.turns(), 90);
+        assert!(game.score_history().eq(played[75..90].iter().map(|&(_, gained, _)| gained)));
+
+        // the reset restarts the history, the games without the history keep nothing
+        game.reset();
+        assert!(!game.history_truncated() && game.move_log().len() == 0 && !game.undo());
+        let mut game = Game::<4>::builder().seed(613).build().unwrap();
+        assert!(game.make_move(GameMove::ALL.into_iter().find(|&direction| game.can_move(direction)).unwrap()));
+        assert!(!game.undo() && !game.history_truncated());
+        assert_eq!((game.move_log().len(), game.score_history().len()), (0, 0));
+
+        // shrinking the history discards the oldest entries
+        let game = Game::<4>::new().unwrap().with_history(config);
+        assert!(!game.history_truncated());
+        let mut game = game.with_history(HistoryConfig { max_undo: 1, ..config });
+        for _ in 0..3 {
+            let direction = GameMove::ALL.into_iter().find(|&direction| game.can_move(direction)).unwrap();
+            assert!(game.make_move(direction));
+        }
+        assert!(game.history_truncated());
+        assert!(game.undo() && !game.undo());
+    }
+
+    #[test]
+    fn score_tracking() {
+        //! Test the largest merge, the gain of the last move and the score history
+
+        let mut game = Game::from_existing(&[[2, 2, 4, 8], [0, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0]], 0)
+            .unwrap()
+            .with_score_history();
+        assert_eq!((game.best_merge(), game.last_move_gain(), game.score_history().collect::<Vec<_>>()), (0, 0, vec![]));
+        assert!(game.make_move(GameMove::Left));
+        assert_eq!((game.best_merge(), game.last_move_gain(), game.score_history().collect::<Vec<_>>()), (4, 4, vec![4]));
+        assert!(game.make_move(GameMove::Left));
+        assert_eq!((game.best_merge(), game.last_move_gain(), game.score_history().collect::<Vec<_>>()), (8, 8, vec![4, 8]));
+        assert!(game.make_move(GameMove::Left));
+        assert_eq!(game.best_merge(), 16);
+        assert!(game.last_move_gain() >= 16);
+        assert_eq!(game.score_history().len(), 3);
+        assert_eq!(game.score_history().sum::<u64>(), game.score());
+
+        // a move without merges, and the games without the history
+        let mut game = Game::from_existing(&[[0, 0, 0, 0], [0, 4, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0]], 0).unwrap();
+        assert!(game.make_move(GameMove::Left));
+        assert_eq!((game.best_merge(), game.last_move_gain(), game.score_history().collect::<Vec<_>>()), (0, 0, vec![]));
+
+        // the boards with blocked cells use the general slide
+        let mut game = Game::from_existing(&[[2, 2], [0, 0]], 0).unwrap().with_blocked_cells(&[(1, 1)]).unwrap();
+        assert!(game.make_move(GameMove::Left));
+        assert_eq!((game.best_merge(), game.last_move_gain()), (4, 4));
+    }
+
+    #[test]
+    fn hints() {
+        //! Test that the hints rank the valid moves and explain them
+
+        let game = Game::from_existing(&[[2, 2, 4, 0], [0, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0]], 0).unwrap();
+        let hints = game.hint(400).unwrap();
+        assert_eq!(hints.len(), 3);
+        assert!(hints.windows(2).all(|pair| pair[0].value >= pair[1].value));
+        assert!(hints.iter().all(|hint| game.can_move(hint.direction) && hint.value > 0.0));
+        let left = hints.iter().find(|hint| hint.direction == GameMove::Left).unwrap();
+        assert_eq!((left.gain, left.empty_cells), (4, 14));
+        let down = hints.iter().find(|hint| hint.direction == GameMove::Down).unwrap();
+        assert_eq!((down.gain, down.empty_cells), (0, 13));
+        assert!(!hints.iter().any(|hint| hint.direction == GameMove::Up));
+
+        assert_eq!(game.hint(0), Err(Error::InvalidDepth));
+        let game = Game::from_existing(&[[2, 4], [4, 2]], 0).unwrap();
+        assert_eq!(game.hint(100), Err(Error::NoValidMove));
+    }
+
+    #[test]
+    fn pending_moves() {
+        //! Test committing and cancelling the pending moves
+
+        let mut game = Game::from_existing(&[[2, 2, 0, 0], [4, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0]], 8).unwrap();
+        let board = *game.board();
+        let valid_moves = GameMove::ALL.map(|direction| game.can_move(direction));
+
+        let pending = game.begin_move(GameMove::Left).unwrap();
+        assert_eq!(pending.direction(), GameMove::Left);
+        assert_eq!(pending.board(), &[[4, 0, 0, 0], [4, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0]]);
+        assert_eq!((pending.gained(), pending.score()), (4, 12));
+        pending.cancel();
+        assert_eq!(game.board(), &board);
+        assert_eq!((game.score(), game.turns, game.merges), (8, 0, 0));
+        assert_eq!(GameMove::ALL.map(|direction| game.can_move(direction)), valid_moves);
+
+        let pending = game.begin_move(GameMove::Left).unwrap();
+        let slid = *pending.board();
+        let (position, value) = pending.commit();
+        assert_eq!(slid[position.0][position.1], 0);
+        assert_eq!(game.board()[position.0][position.1], value);
+        assert_eq!((game.score(), game.turns, game.merges), (12, 1, 1));
+
+        let mut game = Game::from_existing(&[[0, 2], [0, 0]], 0).unwrap();
+        assert_eq!(game.begin_move(GameMove::Right).unwrap_err(), Error::InvalidMove);
+        let mut game = Game::from_existing(&[[2, 4], [4, 2]], 0).unwrap();
+        assert_eq!(game.begin_move(GameMove::Up).unwrap_err(), Error::NoValidMove);
+    }
+
+    #[test]
+    fn board_diffs() {
+        //! Test the differences between the boards
+
+        let board = vec![vec![2, 2, 4, 0], vec![0, 0, 0, 0], vec![0, 0, 0, 8]];
+        let no_op = diff(&board, &board).unwrap();
+        assert!(no_op.is_empty());
+        assert_eq!((no_op.spawned, no_op.merged, no_op.moved), (0, 0, 0));
+        assert_eq!(no_op.to_string(), "No changes");
+
+        // a single merge (the move to the left) with a spawned tile
+        let after = vec![vec![4, 4, 0, 0], vec![0, 0, 2, 0], vec![8, 0, 0, 0]];
+        let single_merge = diff(&board, &after).unwrap();
+        assert_eq!(single_merge.changes.len(), 6);
+        assert_eq!(
+            single_merge.changes[0],
+            CellChange {
+                row: 0,
+                col: 0,
+                before: 2,
+                after: 4
             }
-            if !negative_index {
-                for empty_elem in self.moves_next[1][i].iter_mut().rev().skip(SIZE - 1 - j) {
-                    *empty_elem = 0;
-                }
+        );
+        assert_eq!((single_merge.spawned, single_merge.merged, single_merge.moved), (1, 1, 2));
+        assert_eq!(
+            single_merge.to_string(),
+            "6 changed cells (1 spawned, 1 merged, 2 moved): (0, 0) 2 -> 4 (0, 1) 2 -> 4 (0, 2) 4 -> 0 (1, 2) 0 -> 2 (2, 0) 0 -> 8 (2, 3) 8 -> 0"
+        );
+        let merged_with_spawn = diff(&[vec![2, 2], vec![0, 0]], &[vec![4, 0], vec![0, 2]]).unwrap();
+        assert_eq!((merged_with_spawn.spawned, merged_with_spawn.merged, merged_with_spawn.moved), (1, 1, 0));
+
+        // a full board change
+        let full = diff(&[vec![2, 4], vec![8, 16]], &[vec![32, 64], vec![128, 256]]).unwrap();
+        assert_eq!(full.changes.len(), 4);
+        assert_eq!(full.spawned, 4);
+
+        assert_eq!(diff(&board, &board[..2]).unwrap_err(), Error::InvalidBoard { expected: 3, actual: 2 });
+        assert_eq!(
+            diff(&board, &[vec![2, 2, 4, 0], vec![0, 0, 0], vec![0, 0, 0, 8]]).unwrap_err(),
+            Error::InvalidBoard { expected: 4, actual: 3 }
+        );
+
+        // the difference between the games
+        let before = Game::from_existing(&[[2, 2, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0]], 0).unwrap();
+        let mut game = Game::from_existing(before.board(), 0).unwrap();
+        assert!(game.diff_from(&before).is_empty());
+        game.make_move(GameMove::Right);
+        let game_diff = game.diff_from(&before);
+        assert_eq!((game_diff.spawned, game_diff.merged), (1, 1));
+    }
+
+    #[test]
+    fn rows_and_columns() {
+        //! Test that the rows and the columns are extracted along the right axis
+
+        let game = Game::from_existing(&[[2, 4, 8], [16, 32, 64], [128, 256, 512]], 0).unwrap();
+        assert_eq!(game.row(0), Some(&[2, 4, 8][..]));
+        assert_eq!(game.row(2), Some(&[128, 256, 512][..]));
+        assert_eq!(game.row(3), None);
+        assert_eq!(game.column(0), Some(vec![2, 16, 128]));
+        assert_eq!(game.column(2), Some(vec![8, 64, 512]));
+        assert_eq!(game.column(3), None);
+        assert_eq!((game.row_count(), game.col_count()), (3, 3));
+    }
+
+    #[cfg(feature = "arbitrary")]
+    #[test]
+    fn arbitrary_games() {
+        //! Test that the generated games are valid and reproducible
+
+        use arbitrary::{Arbitrary, Unstructured};
+
+        let mut rng = StdRng::seed_from_u64(0);
+        for len in [0, 1, 10, 100, 1000] {
+            let data: Vec<u8> = (0..len).map(|_| rng.gen()).collect();
+            let mut game = Game::<5>::arbitrary(&mut Unstructured::new(&data)).unwrap();
+            assert!(validate_board(game.board()).is_ok());
+            assert!(game.empty_count() < 25);
+            assert_eq!(Game::<5>::arbitrary(&mut Unstructured::new(&data)).unwrap(), game);
+
+            let mut moves = Unstructured::new(&data);
+            while !moves.is_empty() {
+                let direction = GameMove::arbitrary(&mut moves).unwrap();
+                let score = game.score();
+                game.make_move(direction);
+                assert!(game.score() >= score);
             }
         }
-        self.moves[1] = self.board != self.moves_next[1];
+        assert!(Game::<1>::arbitrary(&mut Unstructured::new(&[0; 8])).is_err());
+    }
 
-        // update up
-        self.score_next[2] = 0;
-        for col in 0..SIZE {
-            let mut i = 0;
-            let mut merge = false;
-            for elem in self.board.iter().map(|row| row[col]).filter(|&x| x != 0) {
-                if merge && elem == self.moves_next[2][i - 1][col] {
-                    self.moves_next[2][i - 1][col] *= 2;
-                    self.score_next[2] += self.moves_next[2][i - 1][col];
-                    merge = false;
-                } else {
-                    self.moves_next[2][i][col] = elem;
-                    i += 1;
-                    merge = true;
-                }
+    #[cfg(feature = "tracing")]
+    #[test]
+    fn search_tracing() {
+        //! Test the spans and the events of a small search
+
+        use std::fmt::Debug;
+        use tracing::field::{Field, Visit};
+        use tracing::span::{Attributes, Id, Record};
+        use tracing::{Event, Metadata, Subscriber};
+
+        /// Records the spans and the events as "name field=value ...".
+        #[derive(Default)]
+        struct Recorder(Mutex<Vec<String>>);
+        /// Writes the fields of a span or an event.
+        struct Fields(String);
+        impl Visit for Fields {
+            fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+                write!(self.0, " {}={:?}", field.name(), value).unwrap();
+            }
+        }
+        impl Subscriber for &'static Recorder {
+            fn enabled(&self, _: &Metadata<'_>) -> bool {
+                true
             }
-            for empty_elem in self.moves_next[2].iter_mut().skip(i).map(|row| &mut row[col]) {
-                *empty_elem = 0;
+            fn new_span(&self, span: &Attributes<'_>) -> Id {
+                let mut fields = Fields(span.metadata().name().to_string());
+                span.record(&mut fields);
+                self.0.lock().unwrap().push(fields.0);
+                Id::from_u64(1)
             }
+            fn record(&self, _: &Id, _: &Record<'_>) {}
+            fn record_follows_from(&self, _: &Id, _: &Id) {}
+            fn event(&self, event: &Event<'_>) {
+                let mut fields = Fields(String::new());
+                event.record(&mut fields);
+                self.0.lock().unwrap().push(fields.0.trim_start().to_string());
+            }
+            fn enter(&self, _: &Id) {}
+            fn exit(&self, _: &Id) {}
         }
-        self.moves[2] = self.board != self.moves_next[2];
 
-        // update down
-        self.score_next[3] = 0;
-        for col in 0..SIZE {
-            let mut i = SIZE - 1;
-            let mut merge = false;
-            let mut negative_index = false;
-            for elem in self.board.iter().map(|row| row[col]).filter(|&x| x != 0).rev() {
-                if merge && elem == self.moves_next[3][i + 1][col] {
-                    self.moves_next[3][i + 1][col] *= 2;
-                    self.score_next[3] += self.moves_next[3][i + 1][col];
-                    merge = false;
-                } else {
-                    self.moves_next[3][i][col] = elem;
-                    i = match i.checked_sub(1) {
-                        Some(x) => x,
-                        None => {
-                            // we processed whole column, we can safely break
-                            negative_index = true;
-                            break;
-                        }
-                    };
-                    merge = true;
-                }
+        let recorder: &'static Recorder = Box::leak(Box::default());
+        let game = Game::from_existing(&[[2, 0, 0, 2], [0, 4, 0, 0], [0, 0, 0, 0], [8, 0, 0, 0]], 0).unwrap();
+        let options = SearchOptions {
+            depth: 40,
+            backend: SearchBackend::CurrentThread,
+            seed: Some(1),
+            ..Default::default()
+        };
+        let best_move = tracing::subscriber::with_default(recorder, || game.find_best_move_with_options(&options)).unwrap();
+
+        let lines = recorder.0.lock().unwrap();
+        assert_eq!(lines[0], "search depth=40 threads=1");
+        let evaluated: Vec<&String> = lines.iter().filter(|line| line.starts_with("message=move evaluated")).collect();
+        assert_eq!(evaluated.len(), 4);
+        assert!(evaluated.iter().all(|line| line.contains("rollouts=10")));
+        let chosen = lines.last().unwrap();
+        assert!(chosen.starts_with(&format!("message=move chosen direction={:?}", best_move)));
+        assert!(chosen.contains("margin="));
+    }
+
+    #[test]
+    fn search_reports() {
+        //! Test the statistics of the search
+
+        let game = Game::from_existing(&[[2, 0, 0, 2], [0, 4, 0, 0], [0, 0, 0, 0], [8, 0, 0, 0]], 0).unwrap();
+        let report = game.find_best_move_report(1001).unwrap();
+        assert_eq!(report.rollouts.iter().sum::<usize>(), 1001);
+        assert!(report.rollouts.iter().all(|&rollouts| (250..=251).contains(&rollouts)));
+        assert!(report.scores.iter().all(|score| score.is_some_and(|score| score > 0.0)));
+        let best = report.scores[report.best.index()].unwrap();
+        assert!(report.scores.iter().all(|&score| score.unwrap() <= best));
+        assert!((1..=executor::available_threads()).contains(&report.threads_used));
+        assert!(report.elapsed > Duration::ZERO);
+        // a timed game measures its searches with its own clock
+        let timed = Game::from_existing(game.board(), 0).unwrap().with_clock(Arc::new(ManualClock::new()));
+        assert_eq!(timed.find_best_move_report(100).unwrap().elapsed, Duration::ZERO);
+
+        // the moves without a choice are not searched
+        let game = Game::from_existing(&[[0, 2, 4, 8], [0, 4, 8, 2], [0, 2, 4, 8], [0, 4, 8, 2]], 0).unwrap();
+        let report = game.find_best_move_report(100).unwrap();
+        assert_eq!((report.best, report.rollouts, report.threads_used), (GameMove::Left, [0; 4], 0));
+        assert_eq!(report.scores, [Some(0.0), None, None, None]);
+        let options = SearchOptions {
+            depth: 10,
+            backend: SearchBackend::CurrentThread,
+            ..Default::default()
+        };
+        let game = Game::from_existing(&[[2, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0]], 0).unwrap();
+        let report = game.search_report(&options, None).unwrap();
+        assert_eq!((report.rollouts.iter().sum::<usize>(), report.threads_used), (10, 1));
+        assert_eq!(game.find_best_move_report(0).unwrap_err(), Error::InvalidDepth);
+    }
+
+    #[test]
+    fn seeded_search() {
+        //! Test that the search of a seeded game is reproducible and doesn't change the spawned tiles
+
+        let statistics = |report: SearchReport| (report.best, report.scores, report.early_loss_rate, report.rollouts, report.threads_used);
+        let build = || {
+            let mut game = Game::<4>::builder().seed(21).build().unwrap();
+            for direction in [GameMove::Left, GameMove::Up, GameMove::Right, GameMove::Down] {
+                game.make_move(direction);
             }
-            if !negative_index {
-                for empty_elem in self.moves_next[3].iter_mut().rev().skip(SIZE - 1 - i).map(|row| &mut row[col]) {
-                    *empty_elem = 0;
+            game
+        };
+        let mut game = build();
+        let report = statistics(game.find_best_move_report(400).unwrap());
+        assert_eq!(statistics(game.find_best_move_report(400).unwrap()), report);
+        assert_eq!(statistics(build().find_best_move_report(400).unwrap()), report);
+        assert_eq!(game.find_best_move_mcts(200, 1.0).unwrap(), build().find_best_move_mcts(200, 1.0).unwrap());
+
+        // the search doesn't advance the generator of the game
+        let mut searched = build();
+        assert!(game.make_move(report.0) && searched.make_move(report.0));
+        assert_eq!(game.board(), searched.board());
+
+        // the games that are not seeded keep using the entropy
+        let game = Game::from_existing(game.board(), 0).unwrap();
+        assert_ne!(game.find_best_move_report(400).unwrap().scores, game.find_best_move_report(400).unwrap().scores);
+    }
+
+    #[test]
+    fn risk_aversion() {
+        //! Test that the risk aversion avoids the move with the higher average score but more early losses
+
+        // merging the 1024 tiles to the left gains more, but leaves the board more likely to be lost soon
+        let game = Game::from_existing(&[[2, 4, 2, 4], [1024, 1024, 8, 2], [2, 256, 2, 4], [4, 64, 4, 2]], 0).unwrap();
+        let mut options = SearchOptions {
+            depth: 4000,
+            seed: Some(3),
+            risk_horizon: 3,
+            ..Default::default()
+        };
+        let report = game.search_report(&options, None).unwrap();
+        let (left, right) = (GameMove::Left.index(), GameMove::Right.index());
+        assert!(report.scores[left].unwrap() > report.scores[right].unwrap());
+        assert!(report.early_loss_rate[left].unwrap() > report.early_loss_rate[right].unwrap());
+        assert_eq!(report.early_loss_rate[GameMove::Up.index()], None);
+        assert_eq!(report.best, GameMove::Left);
+
+        options.risk_aversion = 1.0;
+        let report = game.search_report(&options, None).unwrap();
+        assert_eq!(report.best, GameMove::Right);
+    }
+
+    #[test]
+    fn mcts() {
+        //! Test that the tree search chooses moves about as good as the flat search with the same number of playouts
+
+        let game = Game::from_existing(&[[0, 0, 0, 2], [0, 0, 2, 4], [0, 2, 8, 32], [4, 16, 64, 512]], 0).unwrap();
+        let options = SearchOptions {
+            depth: 400,
+            seed: Some(1),
+            ..Default::default()
+        };
+        let report = game.find_best_move_mcts_report(&options, 1.0).unwrap();
+        assert_eq!(report.rollouts.iter().sum::<usize>(), 400);
+        assert_eq!((report.rollouts[1], report.scores[1], report.threads_used), (0, None, 1));
+        assert_eq!(report.rollouts.iter().max(), Some(&report.rollouts[report.best.index()]));
+        assert_eq!(game.find_best_move_mcts_report(&options, 1.0).unwrap().best, report.best);
+        assert_eq!(game.find_best_move_mcts(0, 1.0).unwrap_err(), Error::InvalidDepth);
+        // a timed game measures the tree search with its own clock, so it works without the system clock
+        let timed = Game::from_existing(game.board(), 0).unwrap().with_clock(Arc::new(ManualClock::new()));
+        assert_eq!(timed.find_best_move_mcts_report(&options, 1.0).unwrap().elapsed, Duration::ZERO);
+        let cancel = CancellationToken::new();
+        cancel.cancel();
+        let cancelled = SearchOptions {
+            cancel: Some(cancel),
+            ..options.clone()
+        };
+        assert_eq!(game.find_best_move_mcts_report(&cancelled, 1.0).unwrap_err(), Error::Cancelled);
+
+        // the regret of the decisions on a batch of positions, the loss of the average score against the best move found by a deep search,
+        // compared position by position: the tree search may not be worse than the flat search by more than two standard errors of the difference
+        let differences: Vec<f64> = (0..200)
+            .map(|seed| {
+                let game = Game::<4>::random_position(9, 6, seed).unwrap();
+                let reference = game
+                    .evaluate_moves(&SearchOptions {
+                        depth: 4000,
+                        seed: Some(seed + 1000),
+                        backend: SearchBackend::CurrentThread,
+                        ..Default::default()
+                    })
+                    .unwrap();
+                let best = reference.iter().flatten().fold(f64::MIN, |a, &b| a.max(b));
+                let options = SearchOptions {
+                    depth: 400,
+                    seed: Some(seed + 1),
+                    backend: SearchBackend::CurrentThread,
+                    ..Default::default()
+                };
+                let flat_regret = best - reference[game.find_best_move_with_options(&options).unwrap().index()].unwrap();
+                let mcts_regret = best - reference[game.find_best_move_mcts_report(&options, 1.0).unwrap().best.index()].unwrap();
+                mcts_regret - flat_regret
+            })
+            .collect();
+        let count = differences.len() as f64;
+        let mean = differences.iter().sum::<f64>() / count;
+        let variance = differences.iter().map(|difference| (difference - mean).powi(2)).sum::<f64>() / (count - 1.0);
+        assert!(mean <= 2.0 * (variance / count).sqrt());
+    }
+
+    #[test]
+    fn evaluation() {
+        //! Test that the evaluation prefers the monotone stack in the corner to the scrambled board with the same tiles
+
+        let stacked = Game::from_existing(&[[1024, 512, 256, 128], [8, 16, 32, 64], [4, 2, 0, 0], [0, 0, 0, 0]], 0).unwrap();
+        let scrambled = Game::from_existing(&[[8, 512, 0, 128], [1024, 2, 32, 0], [4, 16, 0, 64], [0, 256, 0, 0]], 0).unwrap();
+        let weights = HeuristicWeights::default();
+        assert!(stacked.evaluate(&weights) > scrambled.evaluate(&weights));
+        assert_eq!(stacked.evaluate(&weights), heuristics::composite(stacked.board(), &weights));
+    }
+
+    #[test]
+    fn random_positions() {
+        //! Test that the generated positions are valid, reproducible and have the requested number of tiles
+
+        for seed in 0..50 {
+            for filled in [1, 5, 12, 24, 25] {
+                let game = Game::<5>::random_position(filled, 11, seed).unwrap();
+                assert!(validate_board(game.board()).is_ok());
+                assert_eq!(game.empty_count(), 25 - filled);
+                assert!(game.highest_tile() <= 2048);
+                if filled < 25 {
+                    assert_eq!(game.state(), GameState::InProgress);
                 }
+                assert_eq!(Game::<5>::random_position(filled, 11, seed).unwrap(), game);
             }
         }
-        self.moves[3] = self.board != self.moves_next[3];
+        let game = Game::<4>::random_position(16, 1, 5).unwrap();
+        assert_eq!(game.board(), &[[2; 4]; 4]);
+        assert_eq!(game.score(), 0);
+        assert_eq!(Game::<4>::random_position(3, 3, 9).unwrap().tile_histogram().values().sum::<usize>(), 3);
 
-        // update state
-        if self.moves.iter().all(|&x| !x) {
-            self.state = GameState::GameOver;
-        }
+        assert_eq!(Game::<4>::random_position(0, 11, 0).unwrap_err(), Error::InvalidConfig);
+        assert_eq!(Game::<4>::random_position(17, 11, 0).unwrap_err(), Error::InvalidConfig);
+        assert_eq!(Game::<4>::random_position(4, 0, 0).unwrap_err(), Error::InvalidConfig);
+        assert_eq!(Game::<4>::random_position(4, 64, 0).unwrap_err(), Error::InvalidConfig);
+        assert_eq!(Game::<1>::random_position(1, 11, 0).unwrap_err(), Error::InvalidSize { size: 1 });
+    }
 
-        // update result
-        match self.result {
-            GameResult::Pending => {
-                let victory = self.board.iter().flat_map(|row| row.iter()).any(|&x| x >= 2048);
-                if victory {
-                    self.result = GameResult::Victory;
-                } else if self.state == GameState::GameOver {
-                    self.result = GameResult::Loss;
-                }
-            }
-            GameResult::Victory => {}
-            GameResult::Loss => {}
+    #[test]
+    fn reset_game() {
+        //! Test that the reset game is like a freshly built one
+
+        let build = || Game::<4>::builder().seed(3).target(256).starting_tiles(2).build().unwrap();
+        let mut game = build().with_score_history();
+        while game.state() == GameState::InProgress {
+            let direction = GameMove::ALL.into_iter().find(|&direction| game.can_move(direction)).unwrap();
+            assert!(game.make_move(direction));
         }
+        assert!(game.score() > 0);
+
+        game.reset();
+        let fresh = build();
+        assert_eq!((game.state(), game.result()), (fresh.state(), fresh.result()));
+        assert_eq!((game.score(), game.turns(), game.target()), (0, 0, Some(256)));
+        assert_eq!(game.empty_count(), fresh.empty_count());
+        assert_eq!((game.best_merge(), game.last_move_gain()), (0, 0));
+        assert!(game.merge_histogram().is_empty());
+        assert_eq!(game.score_history().len(), 0);
+        // the random number generator continues, so the tiles are spawned elsewhere
+        assert_ne!(game.board(), fresh.board());
+
+        // the spawner places the same tiles again, the blocked cells stay
+        let mut game = Game::<3>::from_existing(&[[2, 0, 0], [0, 0, 0], [0, 0, 0]], 0)
+            .unwrap()
+            .with_blocked_cells(&[(2, 2)])
+            .unwrap()
+            .with_spawner(Box::new(ScriptedSpawner::new([((1, 1), 4), ((0, 1), 2)])));
+        assert!(game.make_move(GameMove::Right));
+        assert_eq!(game.board(), &[[0, 0, 2], [0, 4, 0], [0, 0, BLOCKED_CELL]]);
+        game.reset();
+        assert_eq!(game.board(), &[[0, 2, 0], [0, 0, 0], [0, 0, BLOCKED_CELL]]);
+        assert_eq!((game.score(), game.turns(), game.state()), (0, 0, GameState::InProgress));
     }
 
-    /// Find the best move to make based on the current board state.
-    /// Based on Monte Carlo algorithm (randomized guessing).
-    /// Uses multiple threads to speed up the process.
-    /// # Arguments
-    /// * ```depth``` - The number of simulated games to play to determine the best move. Recommended value is 1000.
-    /// # Returns
-    /// * ```Ok(GameMove)``` - The best move to make.
-    /// * ```Err(Error)``` - There are no valid moves left.
-    /// # Errors
-    /// * ```Error::NoValidMove``` - There are no valid moves left.
-    pub fn find_best_move(&self, depth: usize) -> Result<GameMove, Error> {
-        let possible_moves_count = self.moves.iter().filter(|&&x| x).count();
+    #[test]
+    fn histograms() {
+        //! Test counting the tiles on the board and the tiles created by the merges
 
-        match possible_moves_count {
-            0 => Err(Error::NoValidMove),
-            1 => Ok(GameMove::from_index(self.moves.iter().position(|&val| val).unwrap())),
-            2.. => {
-                let mut thread_pool = ThreadPool::new(None).unwrap();
-
-                let mut depth_per_thread = depth / (possible_moves_count * thread_pool.size());
-                if depth_per_thread == 0 {
-                    depth_per_thread = 1;
-                } else if depth_per_thread * possible_moves_count * thread_pool.size() != depth {
-                    depth_per_thread += 1;
-                }
-
-                let moves_values = Arc::new(Mutex::new([0; 4]));
-
-                for move_ind in self.moves.iter().enumerate().filter_map(|(ind, &x)| if x { Some(ind) } else { None }) {
-                    let move_type = GameMove::from_index(move_ind);
-
-                    for _ in 0..thread_pool.size() {
-                        let board_copy = self.board;
-                        let moves_values = Arc::clone(&moves_values);
-                        thread_pool.add_to_queue(move || {
-                            let mut thread_score = 0;
-
-                            for _ in 0..depth_per_thread {
-                                let mut work_game = Self::from_existing(&board_copy, 0).unwrap();
-
-                                work_game.make_move(move_type);
-                                while let GameState::InProgress = work_game.state {
-                                    if work_game.make_move(
-                                        work_game
-                                            .moves
-                                            .iter()
-                                            .enumerate()
-                                            .filter_map(|(i, &b)| if b { Some(GameMove::from_index(i)) } else { None })
-                                            .choose(&mut thread_rng())
-                                            .unwrap(),
-                                    ) && work_game.state == GameState::GameOver
-                                    {
-                                        break;
-                                    }
-                                }
-
-                                thread_score += work_game.score;
-                            }
-
-                            moves_values.lock().unwrap()[move_ind] += thread_score;
-                        });
-                    }
-                }
-                thread_pool.join();
+        let mut game = Game::from_existing(&[[2, 2, 4, 4], [2, 2, 0, 0], [8, 0, 8, 0], [0, 0, 0, 0]], 0).unwrap();
+        assert_eq!(game.tile_histogram(), BTreeMap::from([(2, 4), (4, 2), (8, 2)]));
+        assert!(game.merge_histogram().is_empty());
 
-                let max_ind = moves_values.lock().unwrap().iter().enumerate().max_by_key(|(_, &x)| x).unwrap().0;
+        assert_eq!(game.make_move_without_spawn(GameMove::Left), Ok(true));
+        assert_eq!(game.merge_histogram(), &BTreeMap::from([(4, 2), (8, 1), (16, 1)]));
+        assert_eq!(game.tile_histogram(), BTreeMap::from([(4, 2), (8, 1), (16, 1)]));
 
-                Ok(GameMove::from_index(max_ind))
-            }
-        }
+        // the merges accumulate over the moves, the blocked cells are not tiles
+        let mut game = Game::<2>::from_existing(&[[2, 2], [0, 0]], 0).unwrap().with_blocked_cells(&[(1, 1)]).unwrap();
+        assert_eq!(game.tile_histogram().values().sum::<usize>(), 2);
+        assert!(game.make_move(GameMove::Left));
+        let merged = game.merge_histogram().values().sum::<u64>();
+        assert_eq!(merged, 1);
+        assert_eq!(game.tile_histogram().get(&BLOCKED_CELL), None);
     }
-}
-impl<const SIZE: usize> Display for Game<SIZE> {
-    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        // find the maximum value in the board
-        let mut max_val = 0;
-        for row in &self.board {
-            for val in row {
-                if *val > max_val {
-                    max_val = *val;
+
+    #[test]
+    fn empty_count() {
+        //! Test that the number of empty cells always matches the board during random play
+
+        let brute_force = |board: &[[u64; 5]; 5]| board.iter().flatten().filter(|&&tile| tile == 0).count();
+        for seed in 0..20 {
+            let mut rng = StdRng::seed_from_u64(seed);
+            let game = Game::<5>::builder().seed(seed).build().unwrap();
+            let cell = if game.board()[2][2] == 0 { (2, 2) } else { (1, 1) };
+            let mut game = game.with_blocked_cells(&[cell, cell]).unwrap();
+            assert_eq!(game.empty_count(), brute_force(game.board()));
+            while game.state() == GameState::InProgress {
+                let direction = GameMove::ALL.into_iter().filter(|&direction| game.can_move(direction)).choose(&mut rng).unwrap();
+                assert!(game.make_move(direction));
+                if rng.gen_bool(0.1) {
+                    game.rotate_cw();
                 }
+                assert_eq!(game.empty_count(), brute_force(game.board()));
+                assert_eq!(game.is_full(), game.empty_count() == 0);
             }
+            assert!(game.is_full());
         }
 
-        // find the number of digits in the maximum value
-        let mut max_len = 0;
-        if max_val == 0 {
-            max_len = 1;
-        }
-        while max_val != 0 {
-            max_len += 1;
-            max_val /= 10;
+        // the tiles placed by the player
+        let mut game = Game::<2>::from_existing(&[[2, 0], [2, 0]], 0).unwrap();
+        assert_eq!(game.empty_count(), 2);
+        assert_eq!(game.make_move_without_spawn(GameMove::Up), Ok(true));
+        assert_eq!(game.empty_count(), 3);
+        game.place_tile(1, 1, 4).unwrap();
+        assert_eq!(game.empty_count(), 2);
+        assert!(!game.is_full());
+    }
+
+    #[test]
+    fn equality() {
+        //! Test that the snapshotted games compare equal and diverge after different moves
+
+        let board = [[2, 0, 0, 2], [0, 4, 0, 0], [0, 0, 0, 0], [8, 0, 0, 0]];
+        let mut first = Game::from_existing(&board, 12).unwrap();
+        let mut second = Game::from_existing(&board, 12).unwrap().with_score_history();
+        assert_eq!(first, second);
+        assert_ne!(first, Game::from_existing(&board, 16).unwrap());
+        let mut positions = HashSet::new();
+        assert!(positions.insert(Game::from_existing(first.board(), first.score()).unwrap()));
+        assert!(!positions.insert(Game::from_existing(second.board(), second.score()).unwrap()));
+
+        assert!(first.make_move(GameMove::Left));
+        assert!(second.make_move(GameMove::Right));
+        assert_ne!(first, second);
+        assert!(positions.insert(Game::from_existing(first.board(), first.score()).unwrap()));
+        assert!(positions.insert(Game::from_existing(second.board(), second.score()).unwrap()));
+        assert_eq!(positions.len(), 3);
+    }
+
+    #[test]
+    fn conversions() {
+        //! Test creating the games with the conversions and iterating over the rows
+
+        let game: Game = [[2, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0], [0, 0, 2, 0]].try_into().unwrap();
+        assert_eq!(game.board(), &[[2, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0], [0, 0, 2, 0]]);
+        assert_eq!(game.score(), 0);
+        let rows: Vec<&[u64; 4]> = game.into_iter().collect();
+        assert_eq!(rows, [&[2, 0, 0, 0], &[0, 0, 0, 0], &[0, 0, 0, 0], &[0, 0, 2, 0]]);
+        assert_eq!((&game).into_iter().flatten().filter(|&&tile| tile != 0).count(), 2);
+
+        let game = Game::<3>::try_from(vec![vec![2, 4, 8], vec![0, 0, 0], vec![0, 0, 16]]).unwrap();
+        assert_eq!(game.column(2), Some(vec![8, 0, 16]));
+        assert_eq!(
+            Game::<3>::try_from(vec![vec![2, 4, 8], vec![0, 0], vec![0, 0, 16]]).unwrap_err(),
+            Error::InvalidBoard { expected: 3, actual: 2 }
+        );
+        assert_eq!(Game::<2>::try_from([[2, 3], [0, 0]]).unwrap_err(), Error::InvalidValue { row: 0, col: 1, value: 3 });
+    }
+
+    #[test]
+    fn save_and_load() {
+        //! Test saving the game into a file and loading it back, and the corrupted files
+
+        let path = std::env::temp_dir().join(format!("tools_2048_save_{}.bin", std::process::id()));
+        let game = Game::from_existing(&[[2, 0, 0, 0], [4, 0, 0, 0], [8, 2048, 0, 0], [16, 32, 0, 2]], 3000).unwrap();
+        game.save_to(&path).unwrap();
+        let loaded = Game::<4>::load_from(&path).unwrap();
+        assert_eq!(loaded.board(), game.board());
+        assert_eq!(loaded.score(), 3000);
+        assert_eq!(loaded.state(), GameState::InProgress);
+        assert_eq!(loaded.result(), GameResult::Victory);
+        let bytes = fs::read(&path).unwrap();
+        assert_eq!(bytes.len(), 4 + 1 + 1 + 16 + 8 + 1);
+        assert!(matches!(Game::<3>::load_from(&path), Err(LoadError::Game(Error::InvalidBoard { expected: 3, actual: 4 }))));
+
+        // truncated files, wrong magic headers and other versions of the format
+        for len in [0, 3, 5, 6, bytes.len() - 1] {
+            fs::write(&path, &bytes[..len]).unwrap();
+            assert!(matches!(Game::<4>::load_from(&path), Err(LoadError::Game(Error::InvalidEncoding))));
         }
-        max_len += 1; // add one space
+        let mut corrupted = bytes.clone();
+        corrupted[0] = b'X';
+        fs::write(&path, &corrupted).unwrap();
+        assert!(matches!(Game::<4>::load_from(&path), Err(LoadError::Game(Error::InvalidEncoding))));
+        let mut newer = bytes.clone();
+        newer[4] = SAVE_VERSION + 1;
+        newer.extend([0; 8]);
+        fs::write(&path, &newer).unwrap();
+        assert!(matches!(Game::<4>::load_from(&path), Err(LoadError::UnsupportedVersion { version: 2 })));
 
-        // create the output string
-        let mut output = String::from("Board:\n");
-        for row in &self.board {
-            for val in row {
-                write!(&mut output, "{:width$}", val, width = max_len).unwrap();
+        // the exponents must fit into a u64
+        let mut invalid = bytes.clone();
+        invalid[6] = 64;
+        fs::write(&path, &invalid).unwrap();
+        assert!(matches!(Game::<4>::load_from(&path), Err(LoadError::Game(Error::InvalidEncoding))));
+
+        fs::remove_file(&path).unwrap();
+        assert!(matches!(Game::<4>::load_from(&path), Err(LoadError::Io(_))));
+        let blocked = Game::from_existing(&[[2, 0], [0, 0]], 0).unwrap().with_blocked_cells(&[(1, 1)]).unwrap();
+        assert_eq!(blocked.save_to(&path).unwrap_err().kind(), io::ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn autoplay() {
+        //! Test playing to the end, the limit of the moves and the strategies proposing invalid moves
+
+        struct InvalidStrategy;
+        impl Strategy<3> for InvalidStrategy {
+            fn choose(&mut self, game: &Game<3>) -> Option<GameMove> {
+                GameMove::ALL.into_iter().find(|&direction| !game.can_move(direction))
             }
-            output.push('\n');
         }
-        writeln!(&mut output, "Score: {}", self.score).unwrap();
 
-        write!(f, "{}", output)
+        let mut game: Game<3> = Game::new().unwrap();
+        assert_eq!(game.autoplay(0, None), Err(Error::InvalidDepth));
+        let summary = game.autoplay(10, Some(5)).unwrap();
+        assert_eq!(summary.turns, 5);
+        assert_eq!(summary.state, GameState::InProgress);
+        let summary = game.autoplay(10, None).unwrap();
+        assert_eq!(summary.state, GameState::GameOver);
+        assert_eq!(game.autoplay(10, None), Ok(summary));
+
+        let mut game = Game::from_existing(&[[2, 0, 0], [0, 0, 0], [0, 0, 0]], 0).unwrap();
+        assert_eq!(game.autoplay_with(&mut InvalidStrategy, None), Err(Error::InvalidStrategy));
+        assert_eq!(game.turns(), 0);
+        let summary = game.autoplay_with(&mut RandomStrategy, Some(3)).unwrap();
+        assert_eq!(summary.turns, 3);
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    #[test]
+    fn huge_boards() {
+        //! Test playing a 16x16 game with the search and the random moves to the limit of the turns, and the moves of the very wide tiles
+
+        let mut game = Game::<16>::builder().seed(631).max_turns(300).build().unwrap();
+        assert_eq!(game.autoplay(8, Some(40)).unwrap().turns, 40);
+        let report = game.find_best_move_report(16).unwrap();
+        assert_eq!(report.rollouts.iter().sum::<usize>(), 16);
+        let summary = game.autoplay_with(&mut RandomStrategy, None).unwrap();
+        assert_eq!((summary.turns, summary.state, game.result()), (300, GameState::GameOver, GameResult::TurnLimit));
+        let parsed: Game<16> = game.to_compact_string().parse().unwrap();
+        assert_eq!((parsed.board(), parsed.score()), (game.board(), game.score()));
+        let rendered = game.to_string();
+        let rows: Vec<&str> = rendered.lines().skip(1).take(16).collect();
+        assert!(rows.iter().all(|row| row.len() == rows[0].len() && row.split_whitespace().count() == 16));
+
+        let mut board = [[0; 16]; 16];
+        board[0][..4].copy_from_slice(&[1 << 40, 1 << 40, 1 << 62, 1 << 62]);
+        board[15][15] = 2;
+        let mut game = Game::from_existing(&board, u64::MAX - 1).unwrap();
+        assert!(game.make_move(GameMove::Left));
+        assert_eq!(game.board()[0][..3], [1 << 41, 1 << 63, 0]);
+        assert_eq!(game.score(), u64::MAX);
+        let rendered = game.to_string();
+        assert!(rendered.lines().skip(1).take(16).all(|row| row.len() == 16 * "9223372036854775808 ".len()));
+    }
 
     #[test]
-    fn create_game_4() {
-        //! Test the creation of a new game with the default size (4x4)
+    fn search_cancellation() {
+        //! Test that a cancelled search stops with an error
 
-        let game: Game<4> = Game::new().unwrap();
-        let game_from = Game::from_existing(game.board(), 0).unwrap();
+        let game = Game::from_existing(&[[2, 4, 8, 16], [0, 0, 4, 2], [0, 2, 8, 0], [0, 0, 0, 4]], 0).unwrap();
+        let cancel = CancellationToken::new();
+        let options = SearchOptions {
+            cancel: Some(cancel.clone()),
+            ..Default::default()
+        };
+        assert!(game.find_best_move_with_options(&options).is_ok());
+        cancel.cancel();
+        assert!(options.cancel.as_ref().unwrap().is_cancelled());
+        assert_eq!(game.find_best_move_with_options(&options), Err(Error::Cancelled));
+        assert_eq!(game.evaluate_moves(&options), Err(Error::Cancelled));
+    }
 
-        assert_eq!(game.size(), 4);
-        assert_eq!(game_from.size(), 4);
+    #[test]
+    fn repetition_tracking() {
+        //! Test that the repeated positions are counted and reported when the undone moves are made again
 
-        assert_eq!(game.score(), 0);
-        assert_eq!(game_from.score(), 0);
+        let script = [((0, 0), 2), ((0, 0), 2), ((0, 0), 2), ((3, 3), 2), ((0, 0), 2), ((0, 0), 2)];
+        let mut game = Game::from_existing(&[[2, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0]], 0)
+            .unwrap()
+            .with_spawner(Box::new(ScriptedSpawner::new(script)))
+            .with_history(HistoryConfig {
+                max_undo: 4,
+                ..HistoryConfig::default()
+            })
+            .with_repetition_tracking(RepetitionConfig { max_positions: 16, threshold: 3 });
+        let receiver = game.subscribe();
+        assert_eq!(game.repetitions(), 1);
+        for count in 1..=3 {
+            assert!(game.make_move(GameMove::Right));
+            assert_eq!(game.board()[0], [2, 0, 0, 2]);
+            assert_eq!(game.repetitions(), count);
+            assert!(game.undo());
+            assert_eq!(game.repetitions(), 1);
+        }
+        // the mirrored position is the same position
+        assert!(game.make_move(GameMove::Down));
+        assert_eq!(game.board()[3], [2, 0, 0, 2]);
+        assert_eq!(game.repetitions(), 4);
+        let reported: Vec<GameEvent> = receiver.try_iter().filter(|event| matches!(event, GameEvent::Repetition { .. })).collect();
+        assert_eq!(reported, [GameEvent::Repetition { count: 3 }, GameEvent::Repetition { count: 4 }]);
 
-        assert_eq!(game.state(), GameState::InProgress);
-        assert_eq!(game_from.state(), GameState::InProgress);
+        // only the latest positions are remembered
+        assert!(game.undo());
+        game = game.with_repetition_tracking(RepetitionConfig { max_positions: 2, threshold: 3 });
+        for _ in 0..2 {
+            assert!(game.make_move(GameMove::Right));
+            assert!(game.undo());
+        }
+        assert_eq!(game.repetitions(), 0);
 
-        assert_eq!(game.result(), GameResult::Pending);
-        assert_eq!(game_from.result(), GameResult::Pending);
+        let mut game = Game::<4>::builder().track_repetitions(RepetitionConfig::default()).build().unwrap();
+        assert_eq!(game.repetitions(), 1);
+        game.reset();
+        assert_eq!(game.repetitions(), 1);
+        assert_eq!(Game::<4>::new().unwrap().repetitions(), 0);
     }
 
     #[test]
-    fn create_game_5() {
-        //! Test the creation of a new game with a bigger size (5x5)
+    fn event_subscriptions() {
+        //! Test that the subscribers receive the events of the moves and that the dropped ones are removed
 
-        let game: Game<5> = Game::new().unwrap();
-        let game_from = Game::from_existing(game.board(), 0).unwrap();
+        let mut game = Game::from_existing(&[[2, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0]], 0).unwrap();
+        let receiver = game.subscribe();
+        let dropped = game.subscribe();
+        drop(dropped);
 
-        assert_eq!(game.size(), 5);
-        assert_eq!(game_from.size(), 5);
+        let mut expected = Vec::new();
+        for _ in 0..10 {
+            let direction = *GameMove::ALL.iter().find(|&&direction| game.can_move(direction)).unwrap();
+            let (moved, gained) = game.peek_move(direction).unwrap();
+            assert!(game.make_move(direction));
+            let position = (0..16).map(|ind| (ind / 4, ind % 4)).find(|&(row, col)| moved[row][col] != game.board()[row][col]).unwrap();
+            expected.push(GameEvent::MoveMade { direction, gained });
+            expected.push(GameEvent::TileSpawned {
+                position,
+                value: game.board()[position.0][position.1],
+            });
+        }
+        assert_eq!(receiver.try_iter().collect::<Vec<_>>(), expected);
+        assert_eq!(game.subscribers.len(), 1);
 
-        assert_eq!(game.score(), 0);
-        assert_eq!(game_from.score(), 0);
+        // the two-player mode
+        let mut game = Game::from_existing(&[[0, 4, 2, 4], [4, 2, 4, 2], [2, 4, 2, 4], [4, 2, 4, 2]], 0).unwrap();
+        let receiver = game.subscribe();
+        game.make_move_without_spawn(GameMove::Left).unwrap();
+        game.place_tile(0, 3, 2).unwrap();
+        let expected = [
+            GameEvent::MoveMade {
+                direction: GameMove::Left,
+                gained: 0,
+            },
+            GameEvent::TileSpawned { position: (0, 3), value: 2 },
+        ];
+        assert_eq!(receiver.try_iter().collect::<Vec<_>>(), expected);
+    }
 
-        assert_eq!(game.state(), GameState::InProgress);
-        assert_eq!(game_from.state(), GameState::InProgress);
+    #[test]
+    fn puzzles() {
+        //! Test that the puzzles don't spawn tiles, are solved by the exhaustive search and are lost when the budget runs out
 
-        assert_eq!(game.result(), GameResult::Pending);
-        assert_eq!(game_from.result(), GameResult::Pending);
+        // the solved puzzles: the board, the target and the length of the shortest solution
+        let fixtures = [
+            ([[128, 64, 32, 16], [0, 0, 0, 16], [0, 0, 0, 0], [0, 0, 0, 0]], 256, 4),
+            ([[2, 2, 4, 8], [0, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0]], 16, 3),
+        ];
+        for (board, target, shortest) in fixtures {
+            for budget in [shortest, shortest + 3] {
+                let mut game = Game::puzzle(&board, budget, target).unwrap();
+                assert!(game.is_puzzle());
+                let events = game.subscribe();
+                while game.state() == GameState::InProgress {
+                    assert!(game.make_move(game.find_best_move(1).unwrap()));
+                }
+                assert_eq!(game.result(), GameResult::Victory);
+                assert_eq!(game.turns(), shortest);
+                assert_eq!(game.highest_tile(), target);
+                assert!(!events.try_iter().any(|event| matches!(event, GameEvent::TileSpawned { .. })));
+            }
+
+            // one move short of the solution
+            let mut game = Game::puzzle(&board, shortest - 1, target).unwrap();
+            while game.state() == GameState::InProgress {
+                assert!(game.make_move(game.find_best_move(1).unwrap()));
+            }
+            assert_eq!(game.result(), GameResult::Loss);
+            assert_eq!(game.turns(), shortest - 1);
+            assert_eq!(game.find_best_move(1), Err(Error::NoValidMove));
+        }
+
+        // the moves only slide and merge the tiles
+        let mut game = Game::puzzle(&fixtures[0].0, 5, 256).unwrap();
+        let tiles = |game: &Game<4>| game.board().iter().flatten().filter(|&&tile| tile != 0).count();
+        assert_eq!(game.expand(GameMove::Up).unwrap().collect::<Vec<_>>(), [([[128, 64, 32, 32], [0; 4], [0; 4], [0; 4]], 1.0)]);
+        assert!(game.spawn_distribution().next().is_none());
+        assert!(game.make_move(GameMove::Left));
+        assert!(game.make_move(GameMove::Right));
+        assert_eq!(tiles(&game), 5);
+        assert_eq!(game.begin_move(GameMove::Up).err(), Some(Error::InvalidConfig));
+
+        // no move is possible
+        let game = Game::puzzle(&[[2, 4], [4, 2]], 3, 8).unwrap();
+        assert_eq!((game.state(), game.result()), (GameState::GameOver, GameResult::Loss));
+
+        // the invalid puzzles
+        assert_eq!(Game::puzzle(&fixtures[1].0, 0, 16).unwrap_err(), Error::InvalidConfig);
+        assert_eq!(Game::puzzle(&fixtures[1].0, 3, 0).unwrap_err(), Error::InvalidConfig);
+        assert_eq!(Game::puzzle(&fixtures[1].0, 3, 24).unwrap_err(), Error::InvalidConfig);
+        assert!(matches!(Game::puzzle(&[[3, 0], [0, 0]], 3, 8), Err(Error::InvalidValue { .. })));
     }
 
     #[test]
-    fn game_4_ai() {
-        //! Test the AI's ability to play a game with the default size (4x4)
+    fn rollouts() {
+        //! Test the public simulated games against the simulated games of the search, on the bitboard and on the game
 
-        let mut game: Game<4> = Game::new().unwrap();
-        while let Ok(best_move) = game.find_best_move(1_000) {
-            game.make_move(best_move);
+        let game = Game::<4>::builder().seed(637).build().unwrap();
+        let board = *game.board();
+        for seed in 0..20 {
+            let end = game.rollout(Some(GameMove::Left), &mut StdRng::seed_from_u64(seed), None);
+            let (value, turns) = game
+                .derive_game(&board)
+                .simulate(&board, GameMove::Left, SearchObjective::Score, &mut StdRng::seed_from_u64(seed));
+            assert_eq!((end.score, end.moves), (game.score() + value, turns));
+            assert!(matches!(end.final_result, GameResult::Loss | GameResult::VictoryThenLoss));
         }
+        assert_eq!(game.board(), &board);
 
-        assert_eq!(game.state(), GameState::GameOver);
-        assert_ne!(game.score(), 0);
+        // the bitboard and the game itself give the same distribution of the scores and of the lengths
+        let generic = Game::from_existing_with_rule(&board, 0, Arc::new(ClassicRule)).unwrap();
+        let mut rng = StdRng::seed_from_u64(637);
+        let mut fast: Vec<RolloutResult> = (0..2000).map(|_| game.rollout(None, &mut rng, None)).collect();
+        let mut slow: Vec<RolloutResult> = (0..2000).map(|_| generic.rollout(None, &mut rng, None)).collect();
+        for ends in [&mut fast, &mut slow] {
+            ends.sort_by_key(|end| end.score);
+        }
+        let mean = |ends: &[RolloutResult], key: fn(&RolloutResult) -> u64| ends.iter().map(key).sum::<u64>() as f64 / ends.len() as f64;
+        for key in [|end: &RolloutResult| end.score, |end: &RolloutResult| end.moves] {
+            assert!((mean(&fast, key) / mean(&slow, key) - 1.0).abs() < 0.05);
+        }
+        for quartile in [500, 1000, 1500] {
+            assert!((fast[quartile].score as f64 / slow[quartile].score as f64 - 1.0).abs() < 0.1);
+        }
+
+        // the maximum number of moves stops both of them
+        for game in [&game, &generic] {
+            let end = game.rollout(None, &mut rng, Some(5));
+            assert_eq!((end.moves, end.final_result), (5, GameResult::Pending));
+            assert!(end.score >= game.score() && end.highest_tile >= game.highest_tile());
+            assert_eq!(game.rollout(None, &mut rng, Some(0)).moves, 0);
+        }
+
+        // no move is made by an impossible first move or in a finished game
+        let blocked = Game::from_existing(&[[2, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0]], 8).unwrap();
+        let end = blocked.rollout(Some(GameMove::Left), &mut rng, None);
+        assert_eq!((end.score, end.moves, end.highest_tile, end.final_result), (8, 0, 2, GameResult::Pending));
+        let over = Game::from_existing(&[[2, 4], [4, 2]], 0).unwrap();
+        assert_eq!(over.rollout(None, &mut rng, None).final_result, GameResult::Loss);
+
+        // the rules of the game are kept
+        let limited = Game::<3>::builder().seed(637).max_turns(7).build().unwrap();
+        let end = limited.rollout(None, &mut rng, None);
+        assert!(end.moves <= 7);
+        assert!(end.moves == 7 || end.final_result == GameResult::Loss);
     }
 
     #[test]
-    fn game_5_ai() {
-        //! Test the AI's ability to play a big game
+    fn invalid_moves() {
+        //! Test the reasons of the invalid moves: a finished game, a move without an effect and a tile that has to be placed
 
-        let mut game: Game<5> = Game::new().unwrap();
-        while let Ok(best_move) = game.find_best_move(1_000) {
-            game.make_move(best_move);
+        let mut game = Game::from_existing(&[[2, 0, 0, 0], [4, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0]], 0).unwrap();
+        assert_eq!(game.why_invalid(GameMove::Left), Some(InvalidReason::NoEffect));
+        assert_eq!(game.why_invalid(GameMove::Up), Some(InvalidReason::NoEffect));
+        assert_eq!(game.why_invalid(GameMove::Right), None);
+        assert_eq!(game.why_invalid(GameMove::Down), None);
+        assert!(!game.make_move(GameMove::Left));
+        assert_eq!(game.why_invalid(GameMove::Left), Some(InvalidReason::NoEffect));
+
+        let over = Game::from_existing(&[[2, 4, 2], [4, 2, 4], [2, 4, 2]], 0).unwrap();
+        assert_eq!(over.state(), GameState::GameOver);
+        for direction in GameMove::ALL {
+            assert_eq!(over.why_invalid(direction), Some(InvalidReason::GameFinished));
         }
+        let won = Game::from_existing(&[[2048, 4], [4, 2]], 0).unwrap();
+        assert_eq!((won.result(), won.why_invalid(GameMove::Left)), (GameResult::Victory, Some(InvalidReason::GameFinished)));
 
-        assert_eq!(game.state(), GameState::GameOver);
-        assert_ne!(game.score(), 0);
+        // the limit of the turns finishes the game even though the tiles could still move
+        let mut limited = Game::<4>::builder().seed(639).max_turns(1).build().unwrap();
+        let direction = GameMove::ALL.into_iter().find(|&direction| limited.can_move(direction)).unwrap();
+        assert!(limited.make_move(direction));
+        assert_eq!(limited.why_invalid(direction.flip_horizontal().flip_vertical()), Some(InvalidReason::GameFinished));
+
+        game.make_move_without_spawn(GameMove::Right).unwrap();
+        assert_eq!(game.why_invalid(GameMove::Left), Some(InvalidReason::AwaitingTile));
+        game.place_tile(0, 0, 2).unwrap();
+        assert_eq!(game.why_invalid(GameMove::Left), None);
     }
 }
@@ -0,0 +1,702 @@
+//! A module that contains the cache of the evaluated positions and the search engine that keeps it between the searches.
+//! With the warm start, the engine keeps the statistics of the simulated games instead, so the next search continues from them.
+//! The engine can also ponder, searching a position on a background thread while the player thinks.
+//! The positions are keyed by their canonical hash (```symmetry::canonical_hash```), so the symmetric positions share the entries.
+//! The rules of the games searched with one cache (the spawn distribution and the merge rule) must be the same.
+
+// std imports
+use std::collections::HashMap;
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+// internal imports
+use crate::core::{CancellationToken, Game, GameMove, SearchObjective, SearchOptions, SearchReport};
+use crate::error::Error;
+
+/// An evaluation stored in the cache.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum CachedEval {
+    /// The average values of the moves found by the Monte Carlo search, in the orientation of the canonical board.
+    Moves {
+        /// The average values of the moves (in the order of ```GameMove::ALL```), ```None``` for invalid moves.
+        values: [Option<f64>; 4],
+        /// The rates of the early losses of the moves, ```None``` for invalid moves.
+        early_loss_rate: [Option<f64>; 4],
+        /// The number of moves within which the losses count as early.
+        risk_horizon: usize,
+        /// The number of simulated games the values are based on.
+        depth: usize,
+    },
+    /// The value of a position found by the exhaustive search.
+    Node {
+        /// The probability of surviving.
+        survival: f64,
+        /// The expected score.
+        score: f64,
+    },
+}
+
+/// A bounded cache of the evaluated positions.
+/// When the cache is full, the entries that were not used since the last search (the older generations) are evicted,
+/// and if that isn't enough, the whole cache is cleared.
+#[derive(Clone, Debug)]
+pub struct EvaluationCache {
+    /// The evaluations with the generation of their last use.
+    entries: HashMap<u64, (CachedEval, u64)>,
+    /// The maximum number of entries.
+    capacity: usize,
+    /// The current generation, incremented with every search.
+    generation: u64,
+    /// The number of lookups that found an entry.
+    hits: u64,
+    /// The number of lookups that didn't find an entry.
+    misses: u64,
+}
+impl EvaluationCache {
+    /// Creates an empty cache.
+    /// # Arguments
+    /// * ```capacity``` - The maximum number of entries (0 disables the cache).
+    /// # Returns
+    /// * ```EvaluationCache``` - The empty cache.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            entries: HashMap::new(),
+            capacity,
+            generation: 0,
+            hits: 0,
+            misses: 0,
+        }
+    }
+
+    /// Looks up the evaluation stored under the key, counting a hit or a miss.
+    /// # Arguments
+    /// * ```key``` - The key of the evaluation.
+    /// # Returns
+    /// * ```Some(CachedEval)``` - The stored evaluation.
+    /// * ```None``` - There is no evaluation stored under the key.
+    pub fn get(&mut self, key: u64) -> Option<CachedEval> {
+        match self.entries.get_mut(&key) {
+            Some((eval, generation)) => {
+                *generation = self.generation;
+                self.hits += 1;
+                Some(*eval)
+            }
+            None => {
+                self.misses += 1;
+                None
+            }
+        }
+    }
+
+    /// Stores the evaluation under the key, evicting the old entries if the cache is full.
+    /// # Arguments
+    /// * ```key``` - The key of the evaluation.
+    /// * ```eval``` - The evaluation to store.
+    pub fn insert(&mut self, key: u64, eval: CachedEval) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.entries.len() >= self.capacity && !self.entries.contains_key(&key) {
+            let generation = self.generation;
+            self.entries.retain(|_, (_, entry_generation)| *entry_generation == generation);
+            if self.entries.len() >= self.capacity {
+                self.entries.clear();
+            }
+        }
+        self.entries.insert(key, (eval, self.generation));
+    }
+
+    /// Starts a new generation. Entries that are not used in the new generation are evicted first.
+    pub fn next_generation(&mut self) {
+        self.generation += 1;
+    }
+
+    /// Removes all entries and resets the counters.
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.generation = 0;
+        self.hits = 0;
+        self.misses = 0;
+    }
+
+    /// Returns the number of stored entries.
+    /// # Returns
+    /// * ```usize``` - The number of entries.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Checks whether the cache is empty.
+    /// # Returns
+    /// * ```true``` - There are no entries.
+    /// * ```false``` - There is at least one entry.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Returns the maximum number of entries.
+    /// # Returns
+    /// * ```usize``` - The capacity.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Returns the number of lookups that found an entry.
+    /// # Returns
+    /// * ```u64``` - The number of hits.
+    pub fn hits(&self) -> u64 {
+        self.hits
+    }
+
+    /// Returns the number of lookups that didn't find an entry.
+    /// # Returns
+    /// * ```u64``` - The number of misses.
+    pub fn misses(&self) -> u64 {
+        self.misses
+    }
+
+    /// Creates the key of the move values found by the Monte Carlo search.
+    /// # Arguments
+    /// * ```hash``` - The canonical hash of the board.
+    /// * ```objective``` - The objective of the search.
+    /// # Returns
+    /// * ```u64``` - The key.
+    pub(crate) fn moves_key(hash: u64, objective: SearchObjective) -> u64 {
+        Self::key(hash, objective as u64)
+    }
+
+    /// Creates the key of the value of a position found by the exhaustive search.
+    /// # Arguments
+    /// * ```hash``` - The canonical hash of the board.
+    /// * ```plies``` - The number of moves looked ahead.
+    /// # Returns
+    /// * ```u64``` - The key.
+    pub(crate) fn node_key(hash: u64, plies: usize) -> u64 {
+        Self::key(hash, 3 + plies as u64)
+    }
+
+    /// Mixes the kind of the evaluation into the hash of the board.
+    fn key(hash: u64, kind: u64) -> u64 {
+        hash ^ (kind + 1).wrapping_mul(0x9e37_79b9_7f4a_7c15)
+    }
+}
+impl Default for EvaluationCache {
+    /// Creates an empty cache with the capacity of 1 000 000 entries.
+    fn default() -> Self {
+        Self::new(1_000_000)
+    }
+}
+
+/// The summed values, the numbers of the simulated games and the numbers of the early losses for every move (in the order of ```GameMove::ALL```).
+pub(crate) type MoveStats = [(u128, usize, usize); 4];
+
+/// A bounded store of the statistics of the simulated games, for the warm start of the Monte Carlo search.
+/// The statistics of the positions not reached by the last search decay by half with every search.
+/// When the store is full, the entries that were not used by the last search are evicted,
+/// and if that isn't enough, the whole store is cleared.
+#[derive(Clone, Debug)]
+pub(crate) struct RolloutStore {
+    /// The statistics, in the orientation of the canonical board, with the generation of their last use.
+    entries: HashMap<u64, (MoveStats, u64)>,
+    /// The maximum number of entries.
+    capacity: usize,
+    /// The current generation, incremented with every search.
+    generation: u64,
+}
+impl RolloutStore {
+    /// Creates an empty store.
+    /// # Arguments
+    /// * ```capacity``` - The maximum number of entries (0 disables the store).
+    /// # Returns
+    /// * ```RolloutStore``` - The empty store.
+    pub(crate) fn new(capacity: usize) -> Self {
+        Self {
+            entries: HashMap::new(),
+            capacity,
+            generation: 0,
+        }
+    }
+
+    /// Looks up the statistics stored under the key.
+    /// # Arguments
+    /// * ```key``` - The key of the position.
+    /// # Returns
+    /// * ```Some(MoveStats)``` - The stored statistics.
+    /// * ```None``` - There are no statistics stored under the key.
+    pub(crate) fn get(&mut self, key: u64) -> Option<MoveStats> {
+        self.entries.get_mut(&key).map(|(stats, generation)| {
+            *generation = self.generation;
+            *stats
+        })
+    }
+
+    /// Adds the statistics of the new simulated games to the stored ones.
+    /// # Arguments
+    /// * ```children``` - The statistics of the new simulated games.
+    pub(crate) fn merge(&mut self, children: HashMap<u64, MoveStats>) {
+        for (key, stats) in children {
+            let mut total = self.entries.get(&key).map_or([(0, 0, 0); 4], |&(stored, _)| stored);
+            for ((value, count, losses), (new_value, new_count, new_losses)) in total.iter_mut().zip(stats) {
+                (*value, *count, *losses) = (*value + new_value, *count + new_count, *losses + new_losses);
+            }
+            self.replace(key, total);
+        }
+    }
+
+    /// Stores the statistics under the key, evicting the old entries if the store is full.
+    /// # Arguments
+    /// * ```key``` - The key of the position.
+    /// * ```stats``` - The statistics to store.
+    pub(crate) fn replace(&mut self, key: u64, stats: MoveStats) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.entries.len() >= self.capacity && !self.entries.contains_key(&key) {
+            let generation = self.generation;
+            self.entries.retain(|_, (_, entry_generation)| *entry_generation == generation);
+            if self.entries.len() >= self.capacity {
+                self.entries.clear();
+            }
+        }
+        self.entries.insert(key, (stats, self.generation));
+    }
+
+    /// Starts a new generation, halving the statistics that were not used in the last one.
+    pub(crate) fn next_generation(&mut self) {
+        let generation = self.generation;
+        self.entries.retain(|_, (stats, entry_generation)| {
+            if *entry_generation != generation {
+                for (value, count, losses) in stats.iter_mut() {
+                    (*value, *count, *losses) = (*value / 2, *count / 2, *losses / 2);
+                }
+            }
+            stats.iter().any(|&(_, count, _)| count > 0)
+        });
+        self.generation += 1;
+    }
+
+    /// Removes all entries.
+    pub(crate) fn clear(&mut self) {
+        self.entries.clear();
+        self.generation = 0;
+    }
+
+    /// Returns the number of stored entries.
+    pub(crate) fn len(&self) -> usize {
+        self.entries.len()
+    }
+}
+
+/// What the Monte Carlo search of ```SearchEngine``` keeps between the searches.
+#[derive(Debug)]
+pub(crate) enum SearchMemory<'a> {
+    /// The cache of the evaluated positions.
+    Cache(&'a mut EvaluationCache),
+    /// The statistics of the simulated games, for the warm start.
+    Rollouts(&'a mut RolloutStore),
+}
+
+/// The summed values and the numbers of the simulated games for every move of the pondering,
+/// with the signal notified after every batch of the simulated games.
+type PonderStats = (Mutex<([u128; 4], [usize; 4])>, Condvar);
+
+/// A search of a position running on a background thread (see ```SearchEngine::start_pondering```).
+#[derive(Debug)]
+struct Ponder {
+    /// The valid moves of the position (in the order of ```GameMove::ALL```).
+    moves: [bool; 4],
+    /// The statistics of the simulated games, updated by the thread.
+    stats: Arc<PonderStats>,
+    /// The token stopping the thread.
+    cancel: CancellationToken,
+    /// The thread running the search.
+    thread: JoinHandle<()>,
+}
+impl Ponder {
+    /// Stops the thread and waits for it to finish.
+    /// # Returns
+    /// * ```([u128; 4], [usize; 4])``` - The summed values and the numbers of the simulated games for every move.
+    fn stop(self) -> ([u128; 4], [usize; 4]) {
+        self.cancel.cancel();
+        // a panicking search only loses its statistics
+        let _ = self.thread.join();
+        *self.stats.0.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// A search engine that keeps the cache of the evaluated positions between the searches,
+/// so that the positions reached again (also by a symmetric board) are not evaluated again.
+/// Dropping the engine stops the pondering and waits for its thread.
+#[derive(Debug, Default)]
+pub struct SearchEngine {
+    /// The options of the Monte Carlo search.
+    pub options: SearchOptions,
+    /// The cache of the evaluated positions.
+    cache: EvaluationCache,
+    /// The statistics of the simulated games, if the warm start is enabled.
+    warm_start: Option<RolloutStore>,
+    /// The search running in the background, if the engine is pondering.
+    pondering: Option<Ponder>,
+}
+impl SearchEngine {
+    /// Creates a search engine with an empty cache.
+    /// # Arguments
+    /// * ```options``` - The options of the Monte Carlo search.
+    /// * ```cache_capacity``` - The maximum number of cached evaluations.
+    /// # Returns
+    /// * ```SearchEngine``` - The search engine.
+    pub fn new(options: SearchOptions, cache_capacity: usize) -> Self {
+        Self {
+            options,
+            cache: EvaluationCache::new(cache_capacity),
+            warm_start: None,
+            pondering: None,
+        }
+    }
+
+    /// Enables the warm start of the Monte Carlo search.
+    /// The engine keeps the statistics of the simulated games of every position reached after the first move and the spawned tile,
+    /// so the search of the next position (also a symmetric one) counts the games already played from it
+    /// and plays only the rest of ```options.depth```. The values of the moves are not cached then.
+    /// The engine should be used for the positions of one game, and cleared before searching another one.
+    /// # Arguments
+    /// * ```capacity``` - The maximum number of positions kept between the searches.
+    /// # Returns
+    /// * ```SearchEngine``` - The search engine with the warm start.
+    pub fn with_warm_start(mut self, capacity: usize) -> Self {
+        self.warm_start = Some(RolloutStore::new(capacity));
+        self
+    }
+
+    /// Returns the number of positions kept for the warm start.
+    /// # Returns
+    /// * ```usize``` - The number of positions, 0 if the warm start is disabled.
+    pub fn warm_start_positions(&self) -> usize {
+        self.warm_start.as_ref().map_or(0, RolloutStore::len)
+    }
+
+    /// Removes everything kept between the searches: the cached evaluations and the statistics of the warm start.
+    pub fn clear(&mut self) {
+        self.cache.clear();
+        if let Some(store) = &mut self.warm_start {
+            store.clear();
+        }
+    }
+
+    /// Returns the cache of the evaluated positions.
+    /// # Returns
+    /// * ```&EvaluationCache``` - The cache.
+    pub fn cache(&self) -> &EvaluationCache {
+        &self.cache
+    }
+
+    /// Removes all cached evaluations (e.g. before searching a game with different rules).
+    pub fn clear_cache(&mut self) {
+        self.cache.clear();
+    }
+
+    /// Find the best move to make with the Monte Carlo search (```Game::find_best_move_with_options```),
+    /// reusing the cached move values of the same or a symmetric board if they are based on at least as many simulated games.
+    /// # Arguments
+    /// * ```game``` - The game to find the move for.
+    /// # Returns
+    /// * ```Ok(GameMove)``` - The best move to make.
+    /// * ```Err(Error)``` - There are no valid moves left or the depth is invalid.
+    /// # Errors
+    /// * ```Error::NoValidMove``` - There are no valid moves left.
+    /// * ```Error::InvalidDepth``` - The depth is 0.
+    /// * ```Error::Cancelled``` - The search was cancelled (see ```SearchOptions::cancel```).
+    pub fn find_best_move<const SIZE: usize>(&mut self, game: &Game<SIZE>) -> Result<GameMove, Error> {
+        self.find_best_move_report(game).map(|report| report.best)
+    }
+
+    /// Find the best move to make like ```find_best_move``` and report the statistics of the search (```Game::find_best_move_report```).
+    /// The reported simulated games are only the ones played by this search, not the ones reused from the cache or the warm start.
+    /// # Arguments
+    /// * ```game``` - The game to find the move for.
+    /// # Returns
+    /// * ```Ok(SearchReport)``` - The best move to make and the statistics of the search.
+    /// * ```Err(Error)``` - There are no valid moves left or the depth is invalid.
+    /// # Errors
+    /// * ```Error::NoValidMove``` - There are no valid moves left.
+    /// * ```Error::InvalidDepth``` - The depth is 0.
+    /// * ```Error::Cancelled``` - The search was cancelled (see ```SearchOptions::cancel```).
+    pub fn find_best_move_report<const SIZE: usize>(&mut self, game: &Game<SIZE>) -> Result<SearchReport, Error> {
+        let memory = match &mut self.warm_start {
+            Some(store) => {
+                store.next_generation();
+                SearchMemory::Rollouts(store)
+            }
+            None => {
+                self.cache.next_generation();
+                SearchMemory::Cache(&mut self.cache)
+            }
+        };
+        game.search_report(&self.options, Some(memory))
+    }
+
+    /// Starts searching the position on a background thread, e.g. while the player is thinking about the move.
+    /// The search plays the simulated games of all valid moves with ```options``` on one thread, leaving the other cores free,
+    /// until ```best_move_now``` or ```stop``` is called. The pondering of the previous position is stopped first.
+    /// The depth and the backend of the options are ignored.
+    /// # Arguments
+    /// * ```game``` - The game whose position to search.
+    pub fn start_pondering<const SIZE: usize>(&mut self, game: &Game<SIZE>) {
+        self.stop();
+        let cancel = CancellationToken::new();
+        let options = SearchOptions {
+            cancel: Some(cancel.clone()),
+            // the fork has its own generator, so the seed of the game is resolved here
+            seed: game.search_seed(&self.options),
+            ..self.options.clone()
+        };
+        let stats = Arc::new((Mutex::new(([0; 4], [0; 4])), Condvar::new()));
+        let thread_stats = Arc::clone(&stats);
+        let position = game.fork();
+        let thread = thread::spawn(move || position.ponder(&options, &thread_stats.0, &thread_stats.1));
+        self.pondering = Some(Ponder {
+            moves: GameMove::ALL.map(|direction| game.can_move(direction)),
+            stats,
+            cancel,
+            thread,
+        });
+    }
+
+    /// Stops the pondering and returns the best move found so far,
+    /// the move with the highest average value of the simulated games (see ```SearchObjective```).
+    /// If the pondering didn't finish any simulated games yet, the first valid move is returned.
+    /// # Returns
+    /// * ```Some(GameMove)``` - The best move found so far.
+    /// * ```None``` - The engine is not pondering or the pondered position has no valid moves.
+    pub fn best_move_now(&mut self) -> Option<GameMove> {
+        let ponder = self.pondering.take()?;
+        let moves = ponder.moves;
+        let (values, simulations) = ponder.stop();
+        let average = |ind: usize| values[ind] as f64 / simulations[ind].max(1) as f64;
+        (0..4)
+            .filter(|&ind| moves[ind])
+            .reduce(|best, ind| if average(ind) > average(best) { ind } else { best })
+            .map(|ind| GameMove::ALL[ind])
+    }
+
+    /// Waits until the pondering has played at least the given number of simulated games, e.g. to give it a budget of the games instead of the time.
+    /// The games are counted in the finished batches, so a few more games may be played before ```best_move_now``` stops the pondering.
+    /// # Arguments
+    /// * ```simulations``` - The number of the simulated games.
+    /// # Returns
+    /// * ```true``` - The pondering has played the games.
+    /// * ```false``` - The engine is not pondering, or the pondering stopped before playing the games (e.g. the position has no valid moves).
+    pub fn wait_pondering(&self, simulations: usize) -> bool {
+        let Some(ponder) = &self.pondering else {
+            return false;
+        };
+        let (stats, batch_done) = &*ponder.stats;
+        let mut played = stats.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        while played.1.iter().sum::<usize>() < simulations {
+            // the finished thread doesn't notify anymore
+            if ponder.thread.is_finished() {
+                return false;
+            }
+            played = batch_done
+                .wait_timeout(played, Duration::from_millis(10))
+                .unwrap_or_else(|poisoned| poisoned.into_inner())
+                .0;
+        }
+        true
+    }
+
+    /// Stops the pondering, if any, discarding its results. Returns after the background thread finishes.
+    pub fn stop(&mut self) {
+        if let Some(ponder) = self.pondering.take() {
+            ponder.stop();
+        }
+    }
+
+    /// Checks whether the engine is pondering.
+    /// # Returns
+    /// * ```true``` - The engine is searching a position in the background.
+    /// * ```false``` - The engine is not pondering.
+    pub fn is_pondering(&self) -> bool {
+        self.pondering.is_some()
+    }
+
+    /// Find the best move to make with the exhaustive search (```Game::find_best_move_exact```),
+    /// reusing the cached values of the positions expanded by the previous searches.
+    /// # Arguments
+    /// * ```game``` - The game to find the move for.
+    /// * ```plies``` - The number of moves to look ahead (at least 1).
+    /// # Returns
+    /// * ```Ok(GameMove)``` - The best move to make.
+    /// * ```Err(Error)``` - There are no valid moves left.
+    /// # Errors
+    /// * ```Error::NoValidMove``` - There are no valid moves left.
+    pub fn find_best_move_exact<const SIZE: usize>(&mut self, game: &Game<SIZE>, plies: usize) -> Result<GameMove, Error> {
+        self.cache.next_generation();
+        game.find_best_move_exact_cached(plies, Some(&mut self.cache))
+    }
+}
+/// Clones the options and the stored evaluations, the clone is not pondering.
+impl Clone for SearchEngine {
+    fn clone(&self) -> Self {
+        Self {
+            options: self.options.clone(),
+            cache: self.cache.clone(),
+            warm_start: self.warm_start.clone(),
+            pondering: None,
+        }
+    }
+}
+impl Drop for SearchEngine {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cache_eviction() {
+        //! Test that the entries of the older generations are evicted first and that the lookups are counted
+
+        let mut cache = EvaluationCache::new(2);
+        let eval = CachedEval::Node { survival: 1.0, score: 4.0 };
+        cache.insert(1, eval);
+        cache.next_generation();
+        cache.insert(2, eval);
+        cache.next_generation();
+        assert_eq!(cache.get(1), Some(eval));
+        cache.insert(3, eval);
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.get(2), None);
+        assert_eq!(cache.get(1), Some(eval));
+        assert_eq!((cache.hits(), cache.misses()), (2, 1));
+
+        // all entries are of the current generation, so the whole cache is cleared
+        cache.insert(4, eval);
+        assert_eq!(cache.len(), 1);
+        assert!(EvaluationCache::new(0).entries.is_empty());
+    }
+
+    #[test]
+    fn cached_searches() {
+        //! Test that the cached searches choose the same moves as the searches without the cache
+
+        let options = SearchOptions {
+            depth: 400,
+            seed: Some(42),
+            ..Default::default()
+        };
+        let mut engine = SearchEngine::new(options.clone(), 100_000);
+        let game = Game::from_existing(&[[2, 4, 8, 16], [0, 0, 4, 2], [0, 2, 8, 0], [0, 0, 0, 4]], 0).unwrap();
+        let expected = game.find_best_move_with_options(&options);
+        assert_eq!(game.find_best_move_with_options(&options), expected);
+        assert_eq!(engine.find_best_move(&game), expected);
+        assert_eq!(engine.cache().hits(), 0);
+        assert_eq!(engine.find_best_move(&game), expected);
+        assert_eq!(engine.cache().hits(), 1);
+
+        // the mirrored game uses the cached values of the original one
+        let mirrored = Game::from_existing(&crate::symmetry::flip_horizontal(game.board()), 0).unwrap();
+        assert_eq!(engine.find_best_move(&mirrored), expected.map(GameMove::flip_horizontal));
+        assert_eq!(engine.cache().hits(), 2);
+
+        let game = Game::from_existing(&[[8, 2, 8, 64], [4, 32, 16, 32], [1024, 1024, 2, 256], [2, 8, 256, 32]], 0).unwrap();
+        let expected = game.find_best_move_exact(3);
+        assert_eq!(engine.find_best_move_exact(&game, 3), expected);
+        let (hits, misses) = (engine.cache().hits(), engine.cache().misses());
+        assert_eq!(engine.find_best_move_exact(&game, 3), expected);
+        assert!(engine.cache().hits() > hits);
+        assert_eq!(engine.cache().misses(), misses);
+    }
+
+    #[test]
+    fn warm_start() {
+        //! Test that the warm start plays fewer simulated games for the same decisions on a scripted game
+
+        let options = SearchOptions {
+            depth: 400,
+            seed: Some(7),
+            backend: crate::core::SearchBackend::CurrentThread,
+            ..Default::default()
+        };
+        let reference = SearchOptions {
+            depth: 4000,
+            seed: Some(1),
+            backend: crate::core::SearchBackend::CurrentThread,
+            ..Default::default()
+        };
+        let mut cold = SearchEngine::new(options.clone(), 0);
+        let mut warm = SearchEngine::new(options, 0).with_warm_start(100_000);
+        let mut game = Game::<4>::builder().seed(3).build().unwrap();
+        let (mut cold_rollouts, mut warm_rollouts) = (0, 0);
+        // the regret of the decisions, the loss of the average score against the best move found by a deep search
+        let (mut cold_regret, mut warm_regret) = (0.0, 0.0);
+        for _ in 0..30 {
+            let cold_report = cold.find_best_move_report(&game).unwrap();
+            let warm_report = warm.find_best_move_report(&game).unwrap();
+            cold_rollouts += cold_report.rollouts.iter().sum::<usize>();
+            warm_rollouts += warm_report.rollouts.iter().sum::<usize>();
+            let values = game.evaluate_moves(&reference).unwrap();
+            let best = values.iter().flatten().fold(f64::MIN, |a, &b| a.max(b));
+            let value = |direction| values[GameMove::ALL.iter().position(|&other| other == direction).unwrap()].unwrap();
+            cold_regret += best - value(cold_report.best);
+            warm_regret += best - value(warm_report.best);
+            assert!(game.make_move(cold_report.best));
+        }
+        assert!(warm.warm_start_positions() > 0);
+        assert!(warm_rollouts < cold_rollouts);
+        assert!(warm_regret < 1.5 * cold_regret);
+
+        warm.clear();
+        assert_eq!(warm.warm_start_positions(), 0);
+    }
+
+    #[test]
+    fn pondering() {
+        //! Test that pondering improves the decision over a move chosen without any search, and that stopping it joins the thread
+
+        let game = Game::from_existing(&[[0, 0, 0, 4], [0, 0, 2, 16], [0, 2, 8, 64], [2, 4, 32, 512]], 0).unwrap();
+        let reference = game
+            .evaluate_moves(&SearchOptions {
+                depth: 40_000,
+                seed: Some(1),
+                ..Default::default()
+            })
+            .unwrap();
+        let value = |direction| reference[GameMove::ALL.iter().position(|&other| other == direction).unwrap()].unwrap();
+
+        let mut engine = SearchEngine::new(
+            SearchOptions {
+                seed: Some(603),
+                ..Default::default()
+            },
+            0,
+        );
+        assert_eq!(engine.best_move_now(), None);
+        assert!(!engine.wait_pondering(1));
+        engine.start_pondering(&game);
+        let instant = engine.best_move_now().unwrap();
+        engine.start_pondering(&game);
+        assert!(engine.is_pondering());
+        assert!(engine.wait_pondering(4_000));
+        let pondered = engine.best_move_now().unwrap();
+        assert!(!engine.is_pondering());
+        assert_eq!(pondered, GameMove::Up);
+        assert!(value(pondered) >= value(instant));
+
+        // the position without valid moves has no best move
+        let lost = Game::from_existing(&[[2, 4, 2, 4], [4, 2, 4, 2], [2, 4, 2, 4], [4, 2, 4, 2]], 0).unwrap();
+        engine.start_pondering(&lost);
+        assert!(!engine.wait_pondering(1));
+        assert_eq!(engine.best_move_now(), None);
+
+        // dropping the engine waits for the thread, which releases its handle of the statistics
+        engine.start_pondering(&game);
+        let stats = Arc::downgrade(&engine.pondering.as_ref().unwrap().stats);
+        drop(engine);
+        assert!(stats.upgrade().is_none());
+    }
+}
@@ -1,24 +1,122 @@
-//! A module that contains the Error enum.
+//! A module that contains the Error enum and the LoadError enum.
 
-use std::fmt::{self, Display, Formatter};
+use core::fmt::{self, Display, Formatter};
+#[cfg(feature = "std")]
+use std::io;
 
 /// An enum that represents the possible errors that can occur in this crate.
+/// New variants may be added in the future, so matches must include a wildcard arm.
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+#[non_exhaustive]
 pub enum Error {
-    /// Invalid game size. Must be at least 4.
-    InvalidSize,
+    /// Invalid game size. Must be at least 2.
+    InvalidSize {
+        /// The invalid size.
+        size: usize,
+    },
     /// Invalid value in a board. Must be 0 or power of 2, starting from 2.
-    InvalidValue,
+    InvalidValue {
+        /// The row of the first invalid tile.
+        row: usize,
+        /// The column of the first invalid tile.
+        col: usize,
+        /// The value of the first invalid tile.
+        value: u64,
+    },
+    /// Invalid shape of a board. The number of rows and the length of every row must be equal to the size of the game.
+    InvalidBoard {
+        /// The expected length (the size of the game).
+        expected: usize,
+        /// The actual number of rows or length of a row.
+        actual: usize,
+    },
+    /// Invalid configuration. The spawn distribution, the target or the other options of the game don't fit together.
+    InvalidConfig,
     /// There is no valid move to make. The game is over.
     NoValidMove,
+    /// Invalid search depth. Must be at least 1.
+    InvalidDepth,
+    /// It is not the turn of this player. In the two-player mode, moves and tile placements must alternate.
+    WrongTurn,
+    /// Invalid position on the board. Must be inside the board and empty.
+    InvalidPosition,
+    /// The strategy kept proposing invalid moves.
+    InvalidStrategy,
+    /// The search was cancelled before it finished.
+    Cancelled,
+    /// Invalid encoding of a game. The encoded game is malformed or corrupted.
+    InvalidEncoding,
+    /// Invalid move. The move doesn't change the board.
+    InvalidMove,
+    /// The search panicked before it finished, e.g. in its progress callback.
+    Panicked,
 }
 impl Display for Error {
     fn fmt(&self, f: &mut Formatter) -> Result<(), fmt::Error> {
         match self {
-            Error::InvalidSize => write!(f, "Invalid game size. Must be at least 4."),
-            Error::InvalidValue => write!(f, "Invalid value in a board. Must be 0 or power of 2, starting from 2."),
+            Error::InvalidSize { size } => write!(f, "Invalid game size {size}. Must be at least 2."),
+            Error::InvalidValue { row, col, value } => write!(f, "Invalid value {value} in a board at row {row}, column {col}. Must be 0 or power of 2, starting from 2."),
+            Error::InvalidBoard { expected, actual } => write!(f, "Invalid shape of a board. Expected {expected} rows of {expected} tiles, found a length of {actual}."),
+            Error::InvalidConfig => write!(f, "Invalid configuration. The options of the game don't fit together."),
             Error::NoValidMove => write!(f, "There is no valid move to make. The game is over."),
+            Error::InvalidDepth => write!(f, "Invalid search depth. Must be at least 1."),
+            Error::WrongTurn => write!(f, "It is not the turn of this player. Moves and tile placements must alternate."),
+            Error::InvalidPosition => write!(f, "Invalid position on the board. Must be inside the board and empty."),
+            Error::InvalidStrategy => write!(f, "The strategy kept proposing invalid moves."),
+            Error::Cancelled => write!(f, "The search was cancelled before it finished."),
+            Error::InvalidEncoding => write!(f, "Invalid encoding of a game. The encoded game is malformed or corrupted."),
+            Error::InvalidMove => write!(f, "Invalid move. The move doesn't change the board."),
+            Error::Panicked => write!(f, "The search panicked before it finished."),
         }
     }
 }
-impl std::error::Error for Error {}
+impl core::error::Error for Error {}
+
+/// An enum that represents the errors of loading a saved game (```Game::load_from```) or a recorded game (```GameRecorder::read```).
+/// Available with the ```std``` feature.
+#[cfg(feature = "std")]
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum LoadError {
+    /// The file could not be read.
+    Io(io::Error),
+    /// The file is not a valid saved game.
+    Game(Error),
+    /// The file was saved in a newer version of the format, which is not supported.
+    UnsupportedVersion {
+        /// The version of the format of the file.
+        version: u8,
+    },
+}
+#[cfg(feature = "std")]
+impl Display for LoadError {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), fmt::Error> {
+        match self {
+            LoadError::Io(error) => write!(f, "The saved game could not be read: {error}"),
+            LoadError::Game(error) => write!(f, "The saved game is invalid: {error}"),
+            LoadError::UnsupportedVersion { version } => write!(f, "Unsupported version {version} of the saved game. The game was saved by a newer version."),
+        }
+    }
+}
+#[cfg(feature = "std")]
+impl std::error::Error for LoadError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            LoadError::Io(error) => Some(error),
+            LoadError::Game(error) => Some(error),
+            LoadError::UnsupportedVersion { .. } => None,
+        }
+    }
+}
+#[cfg(feature = "std")]
+impl From<io::Error> for LoadError {
+    fn from(error: io::Error) -> Self {
+        LoadError::Io(error)
+    }
+}
+#[cfg(feature = "std")]
+impl From<Error> for LoadError {
+    fn from(error: Error) -> Self {
+        LoadError::Game(error)
+    }
+}
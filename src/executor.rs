@@ -0,0 +1,208 @@
+//! A module that contains the executors, which run the simulated games of the Monte Carlo search on the threads of the caller
+//! (see ```SearchOptions::executor```) instead of the threads spawned by the search.
+//! The search splits the simulated games into jobs, hands them to the executor and blocks until all of them are finished.
+
+// std imports
+use core::fmt::{self, Formatter};
+use core::num::NonZeroUsize;
+use std::env;
+#[cfg(target_os = "linux")]
+use std::fs;
+use std::thread;
+
+/// The environment variable that overrides the number of the threads of the search (see ```available_threads```).
+pub const THREADS_ENV: &str = "TOOLS_2048_THREADS";
+
+/// A job of the search, a chunk of the simulated games.
+pub type Job = Box<dyn FnOnce() + Send>;
+
+/// A trait for the executors running the jobs of the search.
+/// The search waits on the calling thread until every job reports its results, so every job has to run eventually,
+/// and an executor whose threads are all busy with the search that waits for it never finishes the search.
+pub trait Executor: Send + Sync {
+    /// Runs the job, now (e.g. on the calling thread) or later on any thread.
+    /// # Arguments
+    /// * ```job``` - The job.
+    fn execute(&self, job: Job);
+
+    /// Returns the number of the jobs the simulated games are split into, usually the number of the threads of the executor.
+    /// The default is 1, a single job.
+    /// # Returns
+    /// * ```usize``` - The number of the jobs (0 is treated as 1).
+    fn threads(&self) -> usize {
+        1
+    }
+}
+impl fmt::Debug for dyn Executor {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "Executor")
+    }
+}
+
+/// An executor that runs every job on its own new thread.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct ThreadExecutor {
+    /// The number of the jobs.
+    threads: usize,
+}
+impl ThreadExecutor {
+    /// Creates the executor.
+    /// # Arguments
+    /// * ```threads``` - The number of the jobs, and so of the threads, of every search (0 is treated as 1).
+    /// # Returns
+    /// * ```ThreadExecutor``` - The executor.
+    pub fn new(threads: usize) -> Self {
+        Self { threads: threads.max(1) }
+    }
+}
+impl Default for ThreadExecutor {
+    /// Creates the executor with one thread per available core (see ```available_threads```).
+    fn default() -> Self {
+        Self::new(available_threads())
+    }
+}
+impl Executor for ThreadExecutor {
+    fn execute(&self, job: Job) {
+        thread::spawn(job);
+    }
+
+    fn threads(&self) -> usize {
+        self.threads
+    }
+}
+
+/// An executor that runs the jobs immediately on the calling thread, without any other threads.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub struct InlineExecutor;
+impl Executor for InlineExecutor {
+    fn execute(&self, job: Job) {
+        job();
+    }
+}
+
+/// Runs the jobs on the rayon thread pool, split between its threads.
+/// The search must not run on the only thread of the pool, since that thread would wait for its own jobs.
+#[cfg(feature = "rayon")]
+impl Executor for rayon::ThreadPool {
+    fn execute(&self, job: Job) {
+        self.spawn(job);
+    }
+
+    fn threads(&self) -> usize {
+        self.current_num_threads()
+    }
+}
+
+/// Returns the number of the threads the search runs on by default.
+/// The number is taken from the ```TOOLS_2048_THREADS``` environment variable if it holds a positive integer,
+/// for the environments where the number of the cores is misreported.
+/// Otherwise, it is the number of the available cores, limited on Linux by the CPU quota of the cgroup (```cpu.max``` or ```cpu.cfs_quota_us```),
+/// rounded up, so the search doesn't oversubscribe a container that is allowed only a part of the cores of the host.
+/// # Returns
+/// * ```usize``` - The number of the threads (at least 1).
+pub fn available_threads() -> usize {
+    if let Some(threads) = env::var(THREADS_ENV).ok().as_deref().and_then(parse_threads) {
+        return threads;
+    }
+    let threads = thread::available_parallelism().map_or(1, NonZeroUsize::get);
+    cpu_quota().map_or(threads, |quota| threads.min(quota))
+}
+
+/// Parses the number of the threads from the environment variable.
+/// # Arguments
+/// * ```value``` - The value of the variable.
+/// # Returns
+/// * ```Some(usize)``` - The number of the threads.
+/// * ```None``` - The value is not a positive integer.
+fn parse_threads(value: &str) -> Option<usize> {
+    value.trim().parse().ok().filter(|&threads| threads > 0)
+}
+
+/// Reads the CPU quota of the cgroup of the process, from cgroup v2 or, if it's missing, from cgroup v1.
+/// # Returns
+/// * ```Some(usize)``` - The quota in the number of the cores, rounded up.
+/// * ```None``` - There is no quota or it can't be read.
+#[cfg(target_os = "linux")]
+fn cpu_quota() -> Option<usize> {
+    if let Ok(cpu_max) = fs::read_to_string("/sys/fs/cgroup/cpu.max") {
+        return parse_cpu_max(&cpu_max);
+    }
+    let quota = fs::read_to_string("/sys/fs/cgroup/cpu/cpu.cfs_quota_us").ok()?;
+    let period = fs::read_to_string("/sys/fs/cgroup/cpu/cpu.cfs_period_us").ok()?;
+    parse_cfs_quota(&quota, &period)
+}
+
+/// Reads the CPU quota of the cgroup of the process, which is only available on Linux.
+/// # Returns
+/// * ```None``` - There is no quota.
+#[cfg(not(target_os = "linux"))]
+fn cpu_quota() -> Option<usize> {
+    None
+}
+
+/// Parses the CPU quota from the ```cpu.max``` file of cgroup v2 (```"$MAX $PERIOD"```, ```$MAX``` is ```max``` without a quota).
+/// # Arguments
+/// * ```cpu_max``` - The content of the file.
+/// # Returns
+/// * ```Some(usize)``` - The quota in the number of the cores, rounded up.
+/// * ```None``` - There is no quota or the content is malformed.
+#[cfg(any(target_os = "linux", test))]
+fn parse_cpu_max(cpu_max: &str) -> Option<usize> {
+    let mut fields = cpu_max.split_whitespace();
+    let (quota, period) = (fields.next()?, fields.next().unwrap_or("100000"));
+    if fields.next().is_some() {
+        return None;
+    }
+    parse_cfs_quota(quota, period)
+}
+
+/// Parses the CPU quota from the ```cpu.cfs_quota_us``` and ```cpu.cfs_period_us``` files of cgroup v1 (the quota is -1 without a limit).
+/// # Arguments
+/// * ```quota``` - The quota in microseconds.
+/// * ```period``` - The period in microseconds.
+/// # Returns
+/// * ```Some(usize)``` - The quota in the number of the cores, rounded up (at least 1).
+/// * ```None``` - There is no quota or the content is malformed.
+#[cfg(any(target_os = "linux", test))]
+fn parse_cfs_quota(quota: &str, period: &str) -> Option<usize> {
+    let quota: u64 = quota.trim().parse().ok().filter(|&quota| quota > 0)?;
+    let period: u64 = period.trim().parse().ok().filter(|&period| period > 0)?;
+    usize::try_from(quota.div_ceil(period)).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn thread_overrides() {
+        //! Test the parsing of the environment variable overriding the number of the threads
+
+        assert_eq!(parse_threads("4"), Some(4));
+        assert_eq!(parse_threads(" 12\n"), Some(12));
+        for value in ["0", "-2", "", "two", "1.5"] {
+            assert_eq!(parse_threads(value), None);
+        }
+        assert!(available_threads() >= 1);
+        assert!(ThreadExecutor::default().threads() >= 1);
+    }
+
+    #[test]
+    fn cgroup_quotas() {
+        //! Test the parsing of the CPU quotas of cgroup v2 and v1
+
+        assert_eq!(parse_cpu_max("200000 100000\n"), Some(2));
+        assert_eq!(parse_cpu_max("150000 100000"), Some(2));
+        assert_eq!(parse_cpu_max("50000 100000"), Some(1));
+        assert_eq!(parse_cpu_max("400000"), Some(4));
+        assert_eq!(parse_cpu_max("max 100000\n"), None);
+        for malformed in ["", "100000 0", "a b", "100000 100000 1"] {
+            assert_eq!(parse_cpu_max(malformed), None);
+        }
+
+        assert_eq!(parse_cfs_quota("300000\n", "100000\n"), Some(3));
+        assert_eq!(parse_cfs_quota("250000", "100000"), Some(3));
+        assert_eq!(parse_cfs_quota("-1\n", "100000\n"), None);
+        assert_eq!(parse_cfs_quota("100000", "0"), None);
+    }
+}
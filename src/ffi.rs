@@ -0,0 +1,255 @@
+//! A module that contains the C-compatible interface of the game, for driving the game from other languages.
+//! The games are passed around as opaque pointers created by ```game_new``` and destroyed by ```game_free```.
+//! All functions accept null pointers and report them as errors.
+//! Available with the ```ffi``` feature. The C header can be generated with ```cbindgen``` (see ```cbindgen.toml```).
+
+// std imports
+use std::ptr;
+
+// internal imports
+use crate::core::{Game, GameMove, GameState};
+
+/// The move to the left.
+pub const GAME_MOVE_LEFT: i32 = 0;
+/// The move to the right.
+pub const GAME_MOVE_RIGHT: i32 = 1;
+/// The move up.
+pub const GAME_MOVE_UP: i32 = 2;
+/// The move down.
+pub const GAME_MOVE_DOWN: i32 = 3;
+
+/// The game is in progress.
+pub const GAME_STATE_IN_PROGRESS: i32 = 0;
+/// The game is over.
+pub const GAME_STATE_GAME_OVER: i32 = 1;
+
+/// The value returned on errors (a null pointer, an invalid argument, no valid move).
+pub const GAME_ERROR: i32 = -1;
+
+/// The smallest size of a game created by ```game_new```.
+pub const GAME_MIN_SIZE: usize = 2;
+/// The largest size of a game created by ```game_new```.
+pub const GAME_MAX_SIZE: usize = 8;
+
+/// A game of one of the supported sizes, behind the opaque pointer of the C interface.
+/// The games are boxed, so that the small games don't take the space of the large ones.
+#[derive(Debug)]
+pub enum FfiGame {
+    /// A 2x2 game.
+    Size2(Box<Game<2>>),
+    /// A 3x3 game.
+    Size3(Box<Game<3>>),
+    /// A 4x4 game.
+    Size4(Box<Game<4>>),
+    /// A 5x5 game.
+    Size5(Box<Game<5>>),
+    /// A 6x6 game.
+    Size6(Box<Game<6>>),
+    /// A 7x7 game.
+    Size7(Box<Game<7>>),
+    /// An 8x8 game.
+    Size8(Box<Game<8>>),
+}
+
+/// Runs the expression with the game of any size bound to the given name.
+macro_rules! with_game {
+    ($ffi_game:expr, $game:ident => $body:expr) => {
+        match $ffi_game {
+            FfiGame::Size2($game) => $body,
+            FfiGame::Size3($game) => $body,
+            FfiGame::Size4($game) => $body,
+            FfiGame::Size5($game) => $body,
+            FfiGame::Size6($game) => $body,
+            FfiGame::Size7($game) => $body,
+            FfiGame::Size8($game) => $body,
+        }
+    };
+}
+
+/// Converts the integer constant of a move to the move.
+fn move_from_i32(direction: i32) -> Option<GameMove> {
+    match direction {
+        GAME_MOVE_LEFT => Some(GameMove::Left),
+        GAME_MOVE_RIGHT => Some(GameMove::Right),
+        GAME_MOVE_UP => Some(GameMove::Up),
+        GAME_MOVE_DOWN => Some(GameMove::Down),
+        _ => None,
+    }
+}
+
+/// Converts the move to its integer constant.
+fn move_to_i32(direction: GameMove) -> i32 {
+    match direction {
+        GameMove::Left => GAME_MOVE_LEFT,
+        GameMove::Right => GAME_MOVE_RIGHT,
+        GameMove::Up => GAME_MOVE_UP,
+        GameMove::Down => GAME_MOVE_DOWN,
+    }
+}
+
+/// Creates a new game with the given size.
+/// # Arguments
+/// * ```size``` - The size of the board (```GAME_MIN_SIZE``` to ```GAME_MAX_SIZE```).
+/// # Returns
+/// * ```*mut FfiGame``` - The new game, to be destroyed with ```game_free```, or null if the size is not supported.
+#[no_mangle]
+pub extern "C" fn game_new(size: usize) -> *mut FfiGame {
+    let game = match size {
+        2 => Game::new().map(|game| FfiGame::Size2(Box::new(game))),
+        3 => Game::new().map(|game| FfiGame::Size3(Box::new(game))),
+        4 => Game::new().map(|game| FfiGame::Size4(Box::new(game))),
+        5 => Game::new().map(|game| FfiGame::Size5(Box::new(game))),
+        6 => Game::new().map(|game| FfiGame::Size6(Box::new(game))),
+        7 => Game::new().map(|game| FfiGame::Size7(Box::new(game))),
+        8 => Game::new().map(|game| FfiGame::Size8(Box::new(game))),
+        _ => return ptr::null_mut(),
+    };
+    game.map_or(ptr::null_mut(), |game| Box::into_raw(Box::new(game)))
+}
+
+/// Destroys a game created by ```game_new```. Does nothing for a null pointer.
+/// # Arguments
+/// * ```game``` - The game to destroy.
+/// # Safety
+/// The pointer must be null or returned by ```game_new``` and not destroyed yet.
+#[no_mangle]
+pub unsafe extern "C" fn game_free(game: *mut FfiGame) {
+    if !game.is_null() {
+        drop(Box::from_raw(game));
+    }
+}
+
+/// Makes a move in the game.
+/// # Arguments
+/// * ```game``` - The game.
+/// * ```direction``` - The move (one of the ```GAME_MOVE_*``` constants).
+/// # Returns
+/// * ```i32``` - 1 if the move was made, 0 if the move is not possible, ```GAME_ERROR``` for a null game or an invalid move.
+/// # Safety
+/// The pointer must be null or a live game returned by ```game_new```.
+#[no_mangle]
+pub unsafe extern "C" fn game_make_move(game: *mut FfiGame, direction: i32) -> i32 {
+    match (game.as_mut(), move_from_i32(direction)) {
+        (Some(ffi_game), Some(direction)) => i32::from(with_game!(ffi_game, game => game.make_move(direction))),
+        _ => GAME_ERROR,
+    }
+}
+
+/// Copies the board of the game into the buffer, row by row.
+/// # Arguments
+/// * ```game``` - The game.
+/// * ```out_buf``` - The buffer for the tiles.
+/// * ```len``` - The length of the buffer (at least size * size).
+/// # Returns
+/// * ```i32``` - The number of copied tiles, ```GAME_ERROR``` for a null pointer or a short buffer.
+/// # Safety
+/// The game pointer must be null or a live game returned by ```game_new```,
+/// and the buffer must be null or valid for writing ```len``` values.
+#[no_mangle]
+pub unsafe extern "C" fn game_board(game: *const FfiGame, out_buf: *mut u64, len: usize) -> i32 {
+    let Some(ffi_game) = game.as_ref() else {
+        return GAME_ERROR;
+    };
+    if out_buf.is_null() {
+        return GAME_ERROR;
+    }
+    with_game!(ffi_game, game => {
+        let tiles = game.board().as_flattened();
+        if len < tiles.len() {
+            return GAME_ERROR;
+        }
+        ptr::copy_nonoverlapping(tiles.as_ptr(), out_buf, tiles.len());
+        tiles.len() as i32
+    })
+}
+
+/// Returns the score of the game.
+/// # Arguments
+/// * ```game``` - The game.
+/// # Returns
+/// * ```u64``` - The score, 0 for a null game.
+/// # Safety
+/// The pointer must be null or a live game returned by ```game_new```.
+#[no_mangle]
+pub unsafe extern "C" fn game_score(game: *const FfiGame) -> u64 {
+    game.as_ref().map_or(0, |ffi_game| with_game!(ffi_game, game => game.score()))
+}
+
+/// Returns the state of the game.
+/// # Arguments
+/// * ```game``` - The game.
+/// # Returns
+/// * ```i32``` - One of the ```GAME_STATE_*``` constants, ```GAME_ERROR``` for a null game.
+/// # Safety
+/// The pointer must be null or a live game returned by ```game_new```.
+#[no_mangle]
+pub unsafe extern "C" fn game_state(game: *const FfiGame) -> i32 {
+    match game.as_ref().map(|ffi_game| with_game!(ffi_game, game => game.state())) {
+        Some(GameState::InProgress) => GAME_STATE_IN_PROGRESS,
+        Some(GameState::GameOver) => GAME_STATE_GAME_OVER,
+        None => GAME_ERROR,
+    }
+}
+
+/// Finds the best move with the Monte Carlo search (```Game::find_best_move```).
+/// # Arguments
+/// * ```game``` - The game.
+/// * ```depth``` - The number of simulated games.
+/// # Returns
+/// * ```i32``` - One of the ```GAME_MOVE_*``` constants, ```GAME_ERROR``` for a null game, the depth of 0 or no valid move.
+/// # Safety
+/// The pointer must be null or a live game returned by ```game_new```.
+#[no_mangle]
+pub unsafe extern "C" fn game_find_best_move(game: *const FfiGame, depth: usize) -> i32 {
+    game.as_ref()
+        .and_then(|ffi_game| with_game!(ffi_game, game => game.find_best_move(depth)).ok())
+        .map_or(GAME_ERROR, move_to_i32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn c_interface() {
+        //! Test the functions through the C ABI, including the null pointers and the invalid arguments
+
+        let new: extern "C" fn(usize) -> *mut FfiGame = game_new;
+        let free: unsafe extern "C" fn(*mut FfiGame) = game_free;
+        let make_move: unsafe extern "C" fn(*mut FfiGame, i32) -> i32 = game_make_move;
+        let board: unsafe extern "C" fn(*const FfiGame, *mut u64, usize) -> i32 = game_board;
+        let score: unsafe extern "C" fn(*const FfiGame) -> u64 = game_score;
+        let state: unsafe extern "C" fn(*const FfiGame) -> i32 = game_state;
+        let find_best_move: unsafe extern "C" fn(*const FfiGame, usize) -> i32 = game_find_best_move;
+
+        assert!(new(1).is_null());
+        assert!(new(9).is_null());
+        unsafe {
+            free(ptr::null_mut());
+            assert_eq!(make_move(ptr::null_mut(), GAME_MOVE_LEFT), GAME_ERROR);
+            assert_eq!(board(ptr::null(), ptr::null_mut(), 0), GAME_ERROR);
+            assert_eq!(score(ptr::null()), 0);
+            assert_eq!(state(ptr::null()), GAME_ERROR);
+            assert_eq!(find_best_move(ptr::null(), 10), GAME_ERROR);
+
+            let game = new(3);
+            assert!(!game.is_null());
+            let mut buf = [0; 9];
+            assert_eq!(board(game, buf.as_mut_ptr(), 8), GAME_ERROR);
+            assert_eq!(board(game, buf.as_mut_ptr(), 9), 9);
+            assert_eq!(buf.iter().filter(|&&tile| tile != 0).count(), 1);
+            assert_eq!(make_move(game, 4), GAME_ERROR);
+            assert_eq!(find_best_move(game, 0), GAME_ERROR);
+
+            while state(game) == GAME_STATE_IN_PROGRESS {
+                let direction = find_best_move(game, 10);
+                assert!((GAME_MOVE_LEFT..=GAME_MOVE_DOWN).contains(&direction));
+                assert_eq!(make_move(game, direction), 1);
+            }
+            assert_eq!(state(game), GAME_STATE_GAME_OVER);
+            assert_eq!(find_best_move(game, 10), GAME_ERROR);
+            assert!(score(game) > 0);
+            free(game);
+        }
+    }
+}
@@ -0,0 +1,347 @@
+//! A module that contains the standard heuristics for evaluating 2048 boards.
+//! All metrics work with the exponents of the tiles (a 2048 tile counts as 11), empty cells count as 0.
+
+// std imports
+use alloc::vec::Vec;
+
+/// Returns the exponent of a tile (0 for empty cells).
+fn exponent(tile: u64) -> f64 {
+    if tile == 0 {
+        0.0
+    } else {
+        tile.ilog2() as f64
+    }
+}
+
+/// Counts the empty cells of the board.
+/// # Arguments
+/// * ```board``` - The board to evaluate.
+/// # Returns
+/// * ```usize``` - The number of empty cells.
+pub fn empty_cells<const SIZE: usize>(board: &[[u64; SIZE]; SIZE]) -> usize {
+    board.iter().flatten().filter(|&&tile| tile == 0).count()
+}
+
+/// Measures how monotone the rows and columns of the board are.
+/// For every row and column, the increases and the decreases of the exponents along the line are summed up
+/// and the smaller of the two sums is the penalty of the line.
+/// The metric is the negated sum of the penalties, so a board whose every line is monotone scores 0,
+/// and less monotone boards score lower.
+/// # Arguments
+/// * ```board``` - The board to evaluate.
+/// # Returns
+/// * ```f64``` - The monotonicity of the board (0 or less).
+pub fn monotonicity<const SIZE: usize>(board: &[[u64; SIZE]; SIZE]) -> f64 {
+    let line_penalty = |line: [f64; SIZE]| {
+        let (mut increase, mut decrease) = (0.0, 0.0);
+        for pair in line.windows(2) {
+            if pair[1] > pair[0] {
+                increase += pair[1] - pair[0];
+            } else {
+                decrease += pair[0] - pair[1];
+            }
+        }
+        f64::min(increase, decrease)
+    };
+
+    let mut penalty = 0.0;
+    for (i, row) in board.iter().enumerate() {
+        penalty += line_penalty(core::array::from_fn(|j| exponent(row[j])));
+        penalty += line_penalty(core::array::from_fn(|j| exponent(board[j][i])));
+    }
+    -penalty
+}
+
+/// Measures how similar the neighbouring tiles of the board are.
+/// The metric is the negated sum of the exponent differences of all horizontally and vertically neighbouring tiles
+/// (empty cells are skipped), so a board whose neighbouring tiles are all equal scores 0.
+/// # Arguments
+/// * ```board``` - The board to evaluate.
+/// # Returns
+/// * ```f64``` - The smoothness of the board (0 or less).
+pub fn smoothness<const SIZE: usize>(board: &[[u64; SIZE]; SIZE]) -> f64 {
+    let mut penalty = 0.0;
+    for i in 0..SIZE {
+        for j in 0..SIZE {
+            if board[i][j] == 0 {
+                continue;
+            }
+            if i + 1 < SIZE && board[i + 1][j] != 0 {
+                penalty += (exponent(board[i][j]) - exponent(board[i + 1][j])).abs();
+            }
+            if j + 1 < SIZE && board[i][j + 1] != 0 {
+                penalty += (exponent(board[i][j]) - exponent(board[i][j + 1])).abs();
+            }
+        }
+    }
+    -penalty
+}
+
+/// Checks whether the highest tile of the board is in one of the corners.
+/// # Arguments
+/// * ```board``` - The board to evaluate.
+/// # Returns
+/// * ```true``` - The highest tile is in a corner.
+/// * ```false``` - The highest tile is not in a corner (or the board is empty).
+pub fn max_tile_in_corner<const SIZE: usize>(board: &[[u64; SIZE]; SIZE]) -> bool {
+    let max_tile = board.iter().flatten().copied().max().unwrap_or(0);
+    max_tile != 0 && [board[0][0], board[0][SIZE - 1], board[SIZE - 1][0], board[SIZE - 1][SIZE - 1]].contains(&max_tile)
+}
+
+/// Measures how mixed up the tiles of the board are.
+/// The metric is the product of the diversity of the tiles and the roughness of their neighbourhoods:
+/// * the diversity is the Gini-Simpson index of the exponents of the tiles, normalized to 0..=1,
+///   ```(1 - Σ p(e)²) / (1 - 1 / n)```, where ```p(e)``` is the fraction of the ```n``` tiles with the exponent ```e``` (0 if ```n``` is below 2),
+/// * the roughness is the fraction of the horizontally and vertically neighbouring tiles (empty cells are skipped)
+///   whose exponents differ by more than 1 (0 if there are no neighbouring tiles).
+///
+/// So the boards of equal tiles and the boards whose neighbours always could merge after one more merge score 0,
+/// and the boards of many different tiles scattered at random score close to 1.
+/// # Arguments
+/// * ```board``` - The board to evaluate.
+/// # Returns
+/// * ```f64``` - The disorder of the board (0..=1).
+pub fn disorder<const SIZE: usize>(board: &[[u64; SIZE]; SIZE]) -> f64 {
+    let mut exponents: Vec<u32> = board.iter().flatten().filter(|&&tile| tile != 0).map(|tile| tile.ilog2()).collect();
+    let tiles = exponents.len();
+    if tiles < 2 {
+        return 0.0;
+    }
+    exponents.sort_unstable();
+    let concentration: f64 = exponents
+        .chunk_by(|a, b| a == b)
+        .map(|group| (group.len() as f64 / tiles as f64) * (group.len() as f64 / tiles as f64))
+        .sum();
+    let diversity = (1.0 - concentration) / (1.0 - 1.0 / tiles as f64);
+
+    let (mut pairs, mut rough_pairs) = (0, 0);
+    for i in 0..SIZE {
+        for j in 0..SIZE {
+            if board[i][j] == 0 {
+                continue;
+            }
+            for (ni, nj) in [(i + 1, j), (i, j + 1)] {
+                if ni < SIZE && nj < SIZE && board[ni][nj] != 0 {
+                    pairs += 1;
+                    if board[i][j].ilog2().abs_diff(board[ni][nj].ilog2()) > 1 {
+                        rough_pairs += 1;
+                    }
+                }
+            }
+        }
+    }
+    let roughness = if pairs == 0 { 0.0 } else { rough_pairs as f64 / pairs as f64 };
+    diversity * roughness
+}
+
+/// Measures how closely the board follows the snake layout, the tiles decreasing along a path that winds through the rows
+/// (left to right in the first row, right to left in the second one, and so on), starting in any corner, along the rows or the columns.
+/// The cells of the path are weighted by ```w(k) = 2^-k``` for the k-th cell, and the metric is the best orientation of the path of
+/// ```Σ w(k) e(k) / Σ w(k) s(k)```, where ```e(k)``` is the exponent of the tile on the k-th cell (0 for empty cells)
+/// and ```s(k)``` is the k-th largest exponent of the board. The tiles sorted along the path give the highest sum, so the metric is at most 1.
+/// # Arguments
+/// * ```board``` - The board to evaluate.
+/// # Returns
+/// * ```f64``` - The adherence to the snake layout (0..=1, 1 for an ideal snake and for the empty board).
+pub fn snake_score<const SIZE: usize>(board: &[[u64; SIZE]; SIZE]) -> f64 {
+    let mut sorted: Vec<f64> = board.iter().flatten().map(|&tile| exponent(tile)).collect();
+    sorted.sort_by(|a, b| b.total_cmp(a));
+    let best: f64 = sorted.iter().zip(path_weights(SIZE)).map(|(exponent, weight)| exponent * weight).sum();
+    if best == 0.0 {
+        return 1.0;
+    }
+
+    (0..8)
+        .map(|orientation| {
+            let along_path: f64 = (0..SIZE * SIZE)
+                .zip(path_weights(SIZE))
+                .map(|(k, weight)| {
+                    let (row, col) = (k / SIZE, if (k / SIZE).is_multiple_of(2) { k % SIZE } else { SIZE - 1 - k % SIZE });
+                    // the orientation transposes the path and mirrors its rows and columns
+                    let (row, col) = if orientation & 1 != 0 { (col, row) } else { (row, col) };
+                    let row = if orientation & 2 != 0 { SIZE - 1 - row } else { row };
+                    let col = if orientation & 4 != 0 { SIZE - 1 - col } else { col };
+                    exponent(board[row][col]) * weight
+                })
+                .sum();
+            along_path / best
+        })
+        .fold(0.0, f64::max)
+}
+
+/// Returns the weights of the cells along the snake path, halving with every cell.
+fn path_weights(size: usize) -> impl Iterator<Item = f64> {
+    core::iter::successors(Some(1.0), |weight| Some(weight / 2.0)).take(size * size)
+}
+
+/// The weights of the heuristics combined by ```composite```.
+/// With the ```serde``` feature, the missing fields of the serialized weights take the default values.
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct HeuristicWeights {
+    /// The weight of ```empty_cells```.
+    pub empty_cells: f64,
+    /// The weight of ```monotonicity```.
+    pub monotonicity: f64,
+    /// The weight of ```smoothness```.
+    pub smoothness: f64,
+    /// The weight of ```max_tile_in_corner``` (which counts as 1 if true, 0 otherwise).
+    pub max_tile_in_corner: f64,
+    /// The weight of ```disorder```, which is subtracted, so the positive weights prefer the ordered boards.
+    pub disorder: f64,
+    /// The weight of ```snake_score```.
+    pub snake_score: f64,
+}
+impl Default for HeuristicWeights {
+    /// The weights tuned by a random search over the greedy self-play games on the 4x4 board
+    /// (each move chosen by the highest ```composite``` of the resulting board).
+    /// The disorder and the snake layout were added later and are not weighted by default.
+    fn default() -> Self {
+        Self {
+            empty_cells: 4.2,
+            monotonicity: 0.8,
+            smoothness: 0.5,
+            max_tile_in_corner: 8.0,
+            disorder: 0.0,
+            snake_score: 0.0,
+        }
+    }
+}
+
+/// Combines all heuristics into a single value using the given weights.
+/// The disorder and the snake layout are not computed if their weights are 0.
+/// # Arguments
+/// * ```board``` - The board to evaluate.
+/// * ```weights``` - The weights of the heuristics.
+/// # Returns
+/// * ```f64``` - The weighted sum of the heuristics.
+pub fn composite<const SIZE: usize>(board: &[[u64; SIZE]; SIZE], weights: &HeuristicWeights) -> f64 {
+    let mut value = weights.empty_cells * empty_cells(board) as f64
+        + weights.monotonicity * monotonicity(board)
+        + weights.smoothness * smoothness(board)
+        + weights.max_tile_in_corner * if max_tile_in_corner(board) { 1.0 } else { 0.0 };
+    if weights.disorder != 0.0 {
+        value -= weights.disorder * disorder(board);
+    }
+    if weights.snake_score != 0.0 {
+        value += weights.snake_score * snake_score(board);
+    }
+    value
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SNAKE: [[u64; 4]; 4] = [[1024, 512, 256, 128], [8, 16, 32, 64], [4, 2, 0, 0], [0, 0, 0, 0]];
+
+    #[test]
+    fn empty_cells_count() {
+        //! Test counting the empty cells
+
+        assert_eq!(empty_cells(&[[0; 4]; 4]), 16);
+        assert_eq!(empty_cells(&SNAKE), 6);
+        assert_eq!(empty_cells(&[[2; 5]; 5]), 0);
+    }
+
+    #[test]
+    fn monotonicity_metric() {
+        //! Test the monotonicity of monotone and non-monotone boards
+
+        assert_eq!(monotonicity(&SNAKE), 0.0);
+        assert_eq!(monotonicity(&[[0; 4]; 4]), 0.0);
+
+        // the row goes 1 -> 3 -> 1 -> 3: increases sum to 4, decreases to 2
+        // the columns are 2/0/0/0, 8/0/0/0, ... which are monotone
+        assert_eq!(monotonicity(&[[2, 8, 2, 8], [0, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0]]), -2.0);
+    }
+
+    #[test]
+    fn smoothness_metric() {
+        //! Test the smoothness of smooth and rough boards
+
+        assert_eq!(smoothness(&[[4; 4]; 4]), 0.0);
+        assert_eq!(smoothness(&[[2, 4, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0]]), -1.0);
+        assert_eq!(smoothness(&[[2, 0, 0, 2048], [0, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0]]), 0.0);
+        assert_eq!(smoothness(&[[2, 32, 0, 0], [8, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0]]), -6.0);
+    }
+
+    #[test]
+    fn max_tile_corner() {
+        //! Test the detection of the highest tile in a corner
+
+        assert!(max_tile_in_corner(&SNAKE));
+        assert!(max_tile_in_corner(&[[0, 0, 0, 0], [0, 2, 0, 0], [0, 0, 0, 0], [0, 0, 0, 4]]));
+        assert!(!max_tile_in_corner(&[[2, 0, 0, 0], [0, 4, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0]]));
+        assert!(!max_tile_in_corner(&[[0; 4]; 4]));
+    }
+
+    #[test]
+    fn order_metrics() {
+        //! Test the disorder and the snake layout of an ideal snake and of the same tiles shuffled
+
+        let shuffled = [[8, 512, 0, 128], [1024, 2, 32, 0], [4, 16, 0, 64], [0, 256, 0, 0]];
+        assert_eq!(snake_score(&SNAKE), 1.0);
+        assert!(snake_score(&shuffled) < 0.8);
+        // the same snake starting in another corner along the columns
+        let transposed: [[u64; 4]; 4] = core::array::from_fn(|i| core::array::from_fn(|j| SNAKE[j][3 - i]));
+        assert_eq!(snake_score(&transposed), 1.0);
+        assert_eq!(snake_score(&[[0; 4]; 4]), 1.0);
+
+        // 4 of the 13 neighbouring pairs of the snake are rough, all the tiles differ
+        assert!((disorder(&SNAKE) - 4.0 / 13.0).abs() < 1e-12);
+        assert!(disorder(&shuffled) > 0.8);
+        assert_eq!(disorder(&[[4; 4]; 4]), 0.0);
+        assert_eq!(disorder(&[[2, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 1024]]), 0.0);
+        // two kinds of tiles, half of each, always rough: the diversity is (1 - 0.5) / (1 - 1 / 4)
+        assert!((disorder(&[[2, 64], [64, 2]]) - (1.0 - 0.5) / (1.0 - 0.25)).abs() < 1e-12);
+    }
+
+    #[test]
+    fn composite_metric() {
+        //! Test the weighted combination of the heuristics
+
+        let weights = HeuristicWeights {
+            empty_cells: 2.0,
+            monotonicity: 1.0,
+            smoothness: 0.5,
+            max_tile_in_corner: 10.0,
+            disorder: 3.0,
+            snake_score: 4.0,
+        };
+        let expected = 2.0 * 6.0 + monotonicity(&SNAKE) + 0.5 * smoothness(&SNAKE) + 10.0 - 3.0 * disorder(&SNAKE) + 4.0;
+        assert_eq!(composite(&SNAKE, &weights), expected);
+
+        let scrambled = [[8, 512, 0, 128], [1024, 2, 32, 0], [4, 16, 0, 64], [0, 256, 0, 0]];
+        assert!(composite(&SNAKE, &weights) > composite(&scrambled, &weights));
+        assert!(composite(&SNAKE, &HeuristicWeights::default()) > composite(&scrambled, &HeuristicWeights::default()));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn weights_serde() {
+        //! Test loading the weights from JSON, with the missing fields taking the default values
+
+        let weights = HeuristicWeights {
+            empty_cells: 2.0,
+            monotonicity: 1.0,
+            smoothness: 0.5,
+            max_tile_in_corner: 10.0,
+            disorder: 1.5,
+            snake_score: 2.5,
+        };
+        let json = serde_json::to_string(&weights).unwrap();
+        assert_eq!(serde_json::from_str::<HeuristicWeights>(&json).unwrap(), weights);
+
+        let partial: HeuristicWeights = serde_json::from_str(r#"{"smoothness":2.0}"#).unwrap();
+        assert_eq!(
+            partial,
+            HeuristicWeights {
+                smoothness: 2.0,
+                ..Default::default()
+            }
+        );
+        assert!(serde_json::from_str::<HeuristicWeights>(r#"{"smoothness":"high"}"#).is_err());
+    }
+}
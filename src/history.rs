@@ -0,0 +1,447 @@
+//! A module that contains the retention of the history of the game: the undo, the move log and the score log.
+//! The history is kept in ring buffers, so the memory stays bounded even in the endless games,
+//! and the oldest entries are discarded once the configured number of entries is reached (see ```Game::history_truncated```).
+//! The tile identities (```Game::with_tile_ids```) take one entry per cell, so they don't need a limit.
+//! The logged moves are stored compactly by ```MoveLog```, four moves per byte.
+//! The repeated positions are tracked in a bounded window of the latest positions (see ```RepetitionConfig```).
+
+// std imports
+use alloc::collections::vec_deque::{self, VecDeque};
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+// internal imports
+use crate::core::GameMove;
+use crate::error::Error;
+use crate::share;
+
+/// The retention of the history of the game (see ```GameBuilder::history``` and ```Game::with_history```).
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct HistoryConfig {
+    /// The number of the latest moves that can be undone (see ```Game::undo```), 0 disables the undo.
+    pub max_undo: usize,
+    /// Whether the moves are logged (see ```Game::move_log```).
+    pub keep_move_log: bool,
+    /// Whether the score gained by every move is logged (see ```Game::score_history```).
+    pub keep_score_log: bool,
+    /// The number of the latest entries kept in the move log and in the score log.
+    pub max_log: usize,
+}
+impl Default for HistoryConfig {
+    /// Creates the configuration without the undo and the logs, keeping up to 65,536 entries in the logs once they are enabled.
+    fn default() -> Self {
+        Self {
+            max_undo: 0,
+            keep_move_log: false,
+            keep_score_log: false,
+            max_log: 1 << 16,
+        }
+    }
+}
+
+/// The tracking of the repeated positions (see ```GameBuilder::track_repetitions``` and ```Game::with_repetition_tracking```).
+/// The positions are compared by their canonical hashes, so the positions that differ only by a rotation or a reflection are the same.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct RepetitionConfig {
+    /// The number of the latest positions remembered, the oldest ones are forgotten first.
+    pub max_positions: usize,
+    /// The number of occurrences of a position from which its every occurrence is reported
+    /// (see ```GameEvent::Repetition``` and ```GameObserver::on_repetition```).
+    pub threshold: usize,
+}
+impl Default for RepetitionConfig {
+    /// Creates the configuration remembering the latest 4,096 positions and reporting the third and every further occurrence of a position.
+    fn default() -> Self {
+        Self {
+            max_positions: 1 << 12,
+            threshold: 3,
+        }
+    }
+}
+
+/// A recorded sequence of moves (e.g. ```Game::move_log```), replayed with ```Game::replay```.
+/// The moves are packed into bytes, four moves per byte (two bits each, in the order of ```GameMove::ALL```),
+/// after the number of the moves (4 bytes, little-endian), so a game of 10,000 moves takes 2,504 bytes.
+/// With the ```serde``` feature, the log is serialized as its base64 code (see ```to_base64```).
+#[derive(Clone, Debug, Default, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(into = "String", try_from = "String"))]
+pub struct MoveLog {
+    /// The moves, in the order they were made.
+    moves: Vec<GameMove>,
+}
+impl MoveLog {
+    /// Creates an empty log.
+    /// # Returns
+    /// * ```MoveLog``` - The empty log.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a move to the end of the log.
+    /// # Arguments
+    /// * ```direction``` - The move.
+    pub fn push(&mut self, direction: GameMove) {
+        self.moves.push(direction);
+    }
+
+    /// Returns the moves of the log.
+    /// # Returns
+    /// * ```&[GameMove]``` - The moves, in the order they were made.
+    pub fn moves(&self) -> &[GameMove] {
+        &self.moves
+    }
+
+    /// Returns the number of the moves.
+    /// # Returns
+    /// * ```usize``` - The number of the moves.
+    pub fn len(&self) -> usize {
+        self.moves.len()
+    }
+
+    /// Checks whether the log has no moves.
+    /// # Returns
+    /// * ```true``` - The log is empty.
+    /// * ```false``` - The log has some moves.
+    pub fn is_empty(&self) -> bool {
+        self.moves.is_empty()
+    }
+
+    /// Packs the log into bytes: the number of the moves (4 bytes, little-endian) followed by the moves, four per byte
+    /// (the first move in the lowest two bits). The unused bits of the last byte are zero.
+    /// # Returns
+    /// * ```Vec<u8>``` - The packed log.
+    /// # Panics
+    /// The log has more than ```u32::MAX``` moves.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let len = u32::try_from(self.moves.len()).expect("the log can't have more than u32::MAX moves");
+        let mut bytes = Vec::with_capacity(4 + self.moves.len().div_ceil(4));
+        bytes.extend_from_slice(&len.to_le_bytes());
+        for chunk in self.moves.chunks(4) {
+            bytes.push(chunk.iter().enumerate().fold(0, |byte, (ind, &direction)| byte | (move_bits(direction) << (2 * ind))));
+        }
+        bytes
+    }
+
+    /// Unpacks the log packed by ```to_bytes```.
+    /// # Arguments
+    /// * ```bytes``` - The packed log.
+    /// # Returns
+    /// * ```Ok(MoveLog)``` - The log.
+    /// * ```Err(Error)``` - The bytes are not a packed log.
+    /// # Errors
+    /// * ```Error::InvalidEncoding``` - The bytes are shorter or longer than their length prefix says, or the unused bits of the last byte are not zero.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        let (len, packed) = bytes.split_first_chunk::<4>().ok_or(Error::InvalidEncoding)?;
+        let len = u32::from_le_bytes(*len) as usize;
+        if packed.len() != len.div_ceil(4) {
+            return Err(Error::InvalidEncoding);
+        }
+        // the unused bits must be zero, so every log has a single encoding
+        if !len.is_multiple_of(4) && packed.last().is_some_and(|&last| last >> (2 * (len % 4)) != 0) {
+            return Err(Error::InvalidEncoding);
+        }
+
+        let moves = (0..len).map(|ind| GameMove::ALL[((packed[ind / 4] >> (2 * (ind % 4))) & 0b11) as usize]).collect();
+        Ok(Self { moves })
+    }
+
+    /// Encodes the log as the URL-safe base64 (without padding) of the packed bytes (see ```to_bytes```) followed by their checksum,
+    /// like the codes of the shared positions (```Game::share_code```).
+    /// # Returns
+    /// * ```String``` - The code of the log.
+    pub fn to_base64(&self) -> String {
+        share::encode(&self.to_bytes())
+    }
+
+    /// Decodes the log encoded by ```to_base64```.
+    /// # Arguments
+    /// * ```code``` - The code of the log.
+    /// # Returns
+    /// * ```Ok(MoveLog)``` - The log.
+    /// * ```Err(Error)``` - The code is invalid.
+    /// # Errors
+    /// * ```Error::InvalidEncoding``` - The code is not valid base64, its checksum doesn't match or the packed log is malformed.
+    pub fn from_base64(code: &str) -> Result<Self, Error> {
+        Self::from_bytes(&share::decode(code)?)
+    }
+}
+impl FromIterator<GameMove> for MoveLog {
+    fn from_iter<I: IntoIterator<Item = GameMove>>(iter: I) -> Self {
+        Self {
+            moves: iter.into_iter().collect(),
+        }
+    }
+}
+impl<'a> IntoIterator for &'a MoveLog {
+    type Item = GameMove;
+    type IntoIter = core::iter::Copied<core::slice::Iter<'a, GameMove>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.moves.iter().copied()
+    }
+}
+impl From<MoveLog> for String {
+    /// Encodes the log as its base64 code (see ```MoveLog::to_base64```).
+    fn from(log: MoveLog) -> Self {
+        log.to_base64()
+    }
+}
+impl TryFrom<String> for MoveLog {
+    type Error = Error;
+
+    /// Decodes the log from its base64 code (see ```MoveLog::from_base64```).
+    fn try_from(code: String) -> Result<Self, Self::Error> {
+        Self::from_base64(&code)
+    }
+}
+
+/// Returns the two bits of a move, its index in ```GameMove::ALL```.
+fn move_bits(direction: GameMove) -> u8 {
+    GameMove::ALL.iter().position(|&other| other == direction).unwrap() as u8
+}
+
+/// The multiset of the latest positions, counting the occurrences of every position.
+#[derive(Clone, Debug)]
+pub(crate) struct Repetitions {
+    /// The configuration of the tracking.
+    config: RepetitionConfig,
+    /// The hashes of the positions, from the oldest to the latest.
+    positions: Ring<u64>,
+    /// The number of occurrences of every remembered position.
+    counts: BTreeMap<u64, usize>,
+}
+impl Repetitions {
+    /// Creates the empty multiset.
+    /// # Arguments
+    /// * ```config``` - The configuration of the tracking.
+    pub(crate) fn new(config: RepetitionConfig) -> Self {
+        Self {
+            config,
+            positions: Ring::new(config.max_positions),
+            counts: BTreeMap::new(),
+        }
+    }
+
+    /// Returns the configuration of the tracking.
+    pub(crate) fn config(&self) -> RepetitionConfig {
+        self.config
+    }
+
+    /// Adds an occurrence of the position, forgetting the oldest one if the window is full.
+    /// # Arguments
+    /// * ```hash``` - The canonical hash of the position.
+    /// # Returns
+    /// * ```usize``` - The number of occurrences of the position, including this one (0 if no positions are remembered).
+    pub(crate) fn record(&mut self, hash: u64) -> usize {
+        if self.config.max_positions == 0 {
+            return 0;
+        }
+        let oldest = self.positions.iter().next().copied();
+        if self.positions.push(hash) {
+            if let Some(oldest) = oldest {
+                if let Some(count) = self.counts.get_mut(&oldest) {
+                    *count -= 1;
+                    if *count == 0 {
+                        self.counts.remove(&oldest);
+                    }
+                }
+            }
+        }
+        let count = self.counts.entry(hash).or_insert(0);
+        *count += 1;
+        *count
+    }
+
+    /// Returns the number of remembered occurrences of the position.
+    /// # Arguments
+    /// * ```hash``` - The canonical hash of the position.
+    pub(crate) fn count(&self, hash: u64) -> usize {
+        self.counts.get(&hash).copied().unwrap_or(0)
+    }
+
+    /// Forgets all positions.
+    pub(crate) fn clear(&mut self) {
+        self.positions.clear();
+        self.counts.clear();
+    }
+}
+
+/// A ring buffer keeping the latest entries, up to its capacity.
+/// The storage grows with the entries, but never beyond the capacity.
+#[derive(Clone, Debug)]
+pub(crate) struct Ring<T> {
+    /// The entries, from the oldest to the latest.
+    entries: VecDeque<T>,
+    /// The largest number of entries.
+    capacity: usize,
+}
+impl<T> Ring<T> {
+    /// Creates an empty ring buffer.
+    /// # Arguments
+    /// * ```capacity``` - The largest number of entries.
+    pub(crate) fn new(capacity: usize) -> Self {
+        Self {
+            entries: VecDeque::new(),
+            capacity,
+        }
+    }
+
+    /// Adds the latest entry, discarding the oldest one if the ring buffer is full.
+    /// # Arguments
+    /// * ```entry``` - The entry.
+    /// # Returns
+    /// * ```true``` - An entry was discarded (the added one if the capacity is 0).
+    /// * ```false``` - No entry was discarded.
+    pub(crate) fn push(&mut self, entry: T) -> bool {
+        if self.capacity == 0 {
+            return true;
+        }
+        let discarded = if self.entries.len() == self.capacity {
+            self.entries.pop_front();
+            true
+        } else {
+            if self.entries.len() == self.entries.capacity() {
+                // grow by doubling like the deque itself, but never beyond the capacity
+                let additional = self.entries.len().max(4).min(self.capacity - self.entries.len());
+                self.entries.reserve_exact(additional);
+            }
+            false
+        };
+        self.entries.push_back(entry);
+        discarded
+    }
+
+    /// Removes the latest entry.
+    /// # Returns
+    /// * ```Some(T)``` - The latest entry.
+    /// * ```None``` - The ring buffer is empty.
+    pub(crate) fn pop(&mut self) -> Option<T> {
+        self.entries.pop_back()
+    }
+
+    /// Removes all entries.
+    pub(crate) fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    /// Returns the number of entries.
+    pub(crate) fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns the number of entries the storage can hold without growing.
+    #[cfg(test)]
+    pub(crate) fn allocated(&self) -> usize {
+        self.entries.capacity()
+    }
+
+    /// Returns the entries, from the oldest to the latest.
+    pub(crate) fn iter(&self) -> vec_deque::Iter<'_, T> {
+        self.entries.iter()
+    }
+
+    /// Changes the capacity, discarding the oldest entries that don't fit.
+    /// # Arguments
+    /// * ```capacity``` - The largest number of entries.
+    /// # Returns
+    /// * ```true``` - Some entries were discarded.
+    /// * ```false``` - All entries were kept.
+    pub(crate) fn set_capacity(&mut self, capacity: usize) -> bool {
+        self.capacity = capacity;
+        let excess = self.entries.len().saturating_sub(capacity);
+        self.entries.drain(..excess);
+        self.entries.shrink_to(capacity);
+        excess > 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ring_buffer() {
+        //! Test that the ring buffer keeps the latest entries and never grows beyond its capacity
+
+        let mut ring = Ring::new(5);
+        for entry in 0..5 {
+            assert!(!ring.push(entry));
+        }
+        assert!(ring.push(5));
+        assert_eq!(ring.iter().copied().collect::<Vec<_>>(), vec![1, 2, 3, 4, 5]);
+        for entry in 6..100 {
+            assert!(ring.push(entry));
+            assert_eq!(ring.len(), 5);
+            assert!(ring.allocated() <= 5);
+        }
+        assert_eq!(ring.pop(), Some(99));
+        assert_eq!(ring.iter().copied().collect::<Vec<_>>(), vec![95, 96, 97, 98]);
+
+        assert!(ring.set_capacity(2));
+        assert_eq!(ring.iter().copied().collect::<Vec<_>>(), vec![97, 98]);
+        assert!(!ring.set_capacity(10));
+        ring.clear();
+        assert_eq!(ring.pop(), None);
+
+        let mut disabled = Ring::new(0);
+        assert!(disabled.push(1));
+        assert_eq!(disabled.len(), 0);
+    }
+
+    #[test]
+    fn repetition_window() {
+        //! Test that the occurrences of the positions are counted within the window of the latest positions
+
+        let mut repetitions = Repetitions::new(RepetitionConfig { max_positions: 3, threshold: 2 });
+        assert_eq!(repetitions.record(1), 1);
+        assert_eq!(repetitions.record(2), 1);
+        assert_eq!(repetitions.record(1), 2);
+        assert_eq!(repetitions.count(1), 2);
+        // the first 1 is forgotten, then the 2
+        assert_eq!(repetitions.record(3), 1);
+        assert_eq!(repetitions.count(1), 1);
+        assert_eq!(repetitions.record(1), 2);
+        assert_eq!(repetitions.count(2), 0);
+        assert_eq!(repetitions.counts.values().sum::<usize>(), 3);
+
+        repetitions.clear();
+        assert_eq!(repetitions.count(1), 0);
+        let mut disabled = Repetitions::new(RepetitionConfig { max_positions: 0, threshold: 1 });
+        assert_eq!(disabled.record(1), 0);
+        assert_eq!(disabled.count(1), 0);
+    }
+
+    #[test]
+    fn move_log_bytes() {
+        //! Test that the move log round-trips through the bytes and base64, and that the corrupted encodings are rejected
+
+        for len in [0, 1, 3, 4, 5, 10_000] {
+            let log: MoveLog = (0..len).map(|ind| GameMove::ALL[(ind * 7 + ind / 3) % 4]).collect();
+            let bytes = log.to_bytes();
+            assert_eq!(bytes.len(), 4 + len.div_ceil(4));
+            assert_eq!(MoveLog::from_bytes(&bytes).unwrap(), log);
+            assert_eq!(MoveLog::from_base64(&log.to_base64()).unwrap(), log);
+            assert!((&log).into_iter().eq(log.moves().iter().copied()));
+        }
+        let log: MoveLog = GameMove::ALL.into_iter().cycle().take(10_000).collect();
+        assert_eq!(log.to_bytes().len(), 2504);
+
+        let log: MoveLog = [GameMove::Left, GameMove::Up, GameMove::Right, GameMove::Down, GameMove::Left].into_iter().collect();
+        let bytes = log.to_bytes();
+        assert_eq!(MoveLog::from_bytes(&bytes[..3]), Err(Error::InvalidEncoding));
+        assert_eq!(MoveLog::from_bytes(&bytes[..bytes.len() - 1]), Err(Error::InvalidEncoding));
+        assert_eq!(MoveLog::from_bytes(&[bytes.as_slice(), &[0]].concat()), Err(Error::InvalidEncoding));
+        let mut padded = bytes.clone();
+        *padded.last_mut().unwrap() |= 0b100;
+        assert_eq!(MoveLog::from_bytes(&padded), Err(Error::InvalidEncoding));
+        let mut longer = bytes.clone();
+        longer[0] = 9;
+        assert_eq!(MoveLog::from_bytes(&longer), Err(Error::InvalidEncoding));
+
+        let mut code = log.to_base64().into_bytes();
+        code[2] = if code[2] == b'A' { b'B' } else { b'A' };
+        assert_eq!(MoveLog::from_base64(core::str::from_utf8(&code).unwrap()), Err(Error::InvalidEncoding));
+        assert_eq!(MoveLog::from_base64("not base64!"), Err(Error::InvalidEncoding));
+    }
+}
@@ -1,5 +1,5 @@
 //! **tools-2048** is a library that provides the core logic of the popular game 2048, along with a basic AI to play the game.
-//! Arbitrary board sizes are supported with the minimum being 4x4.
+//! Arbitrary board sizes are supported with the minimum being 2x2.
 //!
 //! Example usage:
 //! ```rust
@@ -21,8 +21,47 @@
 //! assert_eq!(game.result(), GameResult::Pending);  // the result shouldn't be decided yet
 //! ```
 
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+#[cfg(feature = "std")]
+pub mod analysis;
+#[cfg(feature = "std")]
+mod bitboard;
+pub mod book;
+#[cfg(feature = "std")]
+pub mod clock;
 pub mod core;
+#[cfg(feature = "std")]
+pub mod engine;
 pub mod error;
+#[cfg(feature = "std")]
+pub mod executor;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+pub mod heuristics;
+pub mod history;
+#[cfg(feature = "std")]
+pub mod recorder;
+pub mod rules;
+pub mod session;
+mod share;
+pub mod spawn;
+#[cfg(feature = "proptest")]
+pub mod strategies;
+pub mod strategy;
+pub mod symmetry;
+#[cfg(feature = "async")]
+pub mod task;
+#[cfg(feature = "std")]
+pub mod tournament;
+#[cfg(feature = "std")]
+pub mod tuning;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+#[cfg(feature = "serde")]
+mod web;
 
 #[doc(inline)]
 pub use core::*;
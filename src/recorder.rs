@@ -0,0 +1,782 @@
+//! A module that contains the recorder streaming the events of a game to a writer as they happen, e.g. into a file for offline analysis,
+//! and the replays read back from such logs.
+//!
+//! The log is a text with one comma separated record per line, the first field is the kind of the record:
+//! * ```start,<game>``` - The first line, the position the recording started from, as the compact line of the game (see ```Game::to_compact_string```).
+//! * ```rules,<rule>,<target>,<turn limit>,<turns>,<puzzle>``` - The second line, the rules of the game: the name of the merge rule (see ```MergeRule::name```, ```custom``` for the others),
+//!   the target and the limit of the turns (```none``` if not set), the number of the moves made before the recording and whether the game is a puzzle.
+//!   The logs without it are replayed with the classic rules.
+//! * ```move,<direction>,<gained>,<score>``` - A move was made (```left```, ```right```, ```up``` or ```down```), with the gained score and the score after it.
+//! * ```spawn,<row>,<column>,<value>``` - A new tile was spawned (or placed in the two-player mode).
+//! * ```repetition,<count>``` - The move reached a repeated position (see ```Game::with_repetition_tracking```).
+//! * ```victory``` - The game was won.
+//! * ```game_over``` - The game is over.
+//! * ```undo``` - The latest move was undone (see ```Game::undo```).
+//! * ```reset,<game>``` - The game was restarted (see ```Game::reset```), with the new starting position as the compact line of the game.
+
+// std imports
+use core::ops::Deref;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::sync::mpsc::Receiver;
+
+// internal imports
+use crate::core::{Game, GameEvent, GameMove, GameSummary, BLOCKED_CELL, JUNK_TILE};
+use crate::error::{Error, LoadError};
+use crate::history::HistoryConfig;
+use crate::rules;
+use crate::strategy::{MonteCarloStrategy, Strategy};
+
+/// A game that writes every event to the writer as it happens, one line per event (see the format in the documentation of the module).
+/// The game is read through ```Deref```, the moves are made with the methods of the recorder, which forward to the game.
+/// The games played by a strategy are written move by move, the writer is flushed when the game is over.
+/// The errors of the writer don't stop the game, the first one is kept and returned by ```into_inner```, and nothing more is written after it.
+#[derive(Debug)]
+pub struct GameRecorder<const SIZE: usize, W: Write> {
+    /// The recorded game.
+    game: Game<SIZE>,
+    /// The log of the game.
+    log: EventLog<W>,
+}
+impl<const SIZE: usize, W: Write> GameRecorder<SIZE, W> {
+    /// Starts recording the game, writing its current position as the first line.
+    /// # Arguments
+    /// * ```game``` - The game to record.
+    /// * ```writer``` - The writer of the log.
+    /// # Returns
+    /// * ```GameRecorder``` - The recorder.
+    pub fn new(mut game: Game<SIZE>, writer: W) -> Self {
+        let mut log = EventLog {
+            writer,
+            events: game.subscribe(),
+            score: game.score(),
+            error: None,
+        };
+        log.write_line(&format!("start,{}", game.to_compact_string()));
+        let rules = Rules {
+            rule: String::from(game.merge_rule().name().unwrap_or("custom")),
+            target: game.target(),
+            max_turns: game.max_turns(),
+            turns: game.turns(),
+            puzzle: game.is_puzzle(),
+        };
+        log.write_line(&rules.to_line());
+        Self { game, log }
+    }
+
+    /// Makes the move and records it (see ```Game::make_move```).
+    /// # Arguments
+    /// * ```direction``` - The direction to move in.
+    /// # Returns
+    /// * ```true``` - The move was successful.
+    /// * ```false``` - The move was invalid/impossible.
+    pub fn make_move(&mut self, direction: GameMove) -> bool {
+        let moved = self.game.make_move(direction);
+        self.log.record();
+        moved
+    }
+
+    /// Makes the move without the new tile and records it (see ```Game::make_move_without_spawn```).
+    /// # Arguments
+    /// * ```direction``` - The direction to move in.
+    /// # Returns
+    /// * ```Ok(bool)``` - Whether the move was successful.
+    /// * ```Err(Error)``` - It is not the turn of the moving player.
+    /// # Errors
+    /// * ```Error::WrongTurn``` - The tile from the previous move hasn't been placed yet.
+    pub fn make_move_without_spawn(&mut self, direction: GameMove) -> Result<bool, Error> {
+        let moved = self.game.make_move_without_spawn(direction);
+        self.log.record();
+        moved
+    }
+
+    /// Places the tile after a move made with ```make_move_without_spawn``` and records it (see ```Game::place_tile```).
+    /// # Arguments
+    /// * ```row``` - The row of the tile.
+    /// * ```col``` - The column of the tile.
+    /// * ```value``` - The value of the tile. Must be 2 or 4.
+    /// # Returns
+    /// * ```Ok(())``` - The tile was placed.
+    /// * ```Err(Error)``` - The tile was not placed, see ```Game::place_tile```.
+    pub fn place_tile(&mut self, row: usize, col: usize, value: u64) -> Result<(), Error> {
+        let placed = self.game.place_tile(row, col, value);
+        self.log.record();
+        placed
+    }
+
+    /// Undoes the latest move and records it (see ```Game::undo```).
+    /// # Returns
+    /// * ```true``` - The move was undone.
+    /// * ```false``` - No move can be undone, nothing is recorded.
+    pub fn undo(&mut self) -> bool {
+        let undone = self.game.undo();
+        if undone {
+            self.log.score = self.game.score();
+            self.log.write_line("undo");
+        }
+        undone
+    }
+
+    /// Restarts the game and records it with the new starting position (see ```Game::reset```).
+    pub fn reset(&mut self) {
+        self.game.reset();
+        self.log.score = self.game.score();
+        self.log.write_line(&format!("reset,{}", self.game.to_compact_string()));
+    }
+
+    /// Replays the moves and records them move by move (see ```Game::replay```).
+    /// # Arguments
+    /// * ```moves``` - The moves, in the order they were made.
+    /// # Returns
+    /// * ```Ok(usize)``` - The number of the replayed moves.
+    /// * ```Err(Error)``` - A move couldn't be made, see ```Game::replay```. The moves before it stay made and recorded.
+    pub fn replay(&mut self, moves: impl IntoIterator<Item = GameMove>) -> Result<usize, Error> {
+        let log = &mut self.log;
+        // the events of the previous move are written before the next move is made
+        let replayed = self.game.replay(moves.into_iter().inspect(|_| log.record()));
+        self.log.record();
+        replayed
+    }
+
+    /// Plays the game to the end using the strategy and records it move by move (see ```Game::play_with```).
+    /// # Arguments
+    /// * ```strategy``` - The strategy choosing the moves.
+    /// # Returns
+    /// * ```GameSummary``` - The summary of the game.
+    pub fn play_with(&mut self, strategy: &mut dyn Strategy<SIZE>) -> GameSummary {
+        let summary = self.game.play_with(&mut Streaming { strategy, log: &mut self.log });
+        self.log.record();
+        summary
+    }
+
+    /// Plays the game using the AI and records it move by move (see ```Game::autoplay```).
+    /// # Arguments
+    /// * ```depth``` - The number of simulated games of every search.
+    /// * ```max_moves``` - The maximum number of moves, ```None``` for playing to the end.
+    /// # Returns
+    /// * ```Ok(GameSummary)``` - The summary of the game.
+    /// * ```Err(Error)``` - The game couldn't be played, see ```Game::autoplay```.
+    pub fn autoplay(&mut self, depth: usize, max_moves: Option<u64>) -> Result<GameSummary, Error> {
+        if depth == 0 {
+            return Err(Error::InvalidDepth);
+        }
+        self.autoplay_with(&mut MonteCarloStrategy { depth }, max_moves)
+    }
+
+    /// Plays the game using the strategy and records it move by move (see ```Game::autoplay_with```).
+    /// # Arguments
+    /// * ```strategy``` - The strategy choosing the moves.
+    /// * ```max_moves``` - The maximum number of moves, ```None``` for playing to the end.
+    /// # Returns
+    /// * ```Ok(GameSummary)``` - The summary of the game.
+    /// * ```Err(Error)``` - The game couldn't be played, see ```Game::autoplay_with```.
+    pub fn autoplay_with(&mut self, strategy: &mut dyn Strategy<SIZE>, max_moves: Option<u64>) -> Result<GameSummary, Error> {
+        let summary = self.game.autoplay_with(&mut Streaming { strategy, log: &mut self.log }, max_moves);
+        self.log.record();
+        summary
+    }
+
+    /// Stops the recording and flushes the writer.
+    /// # Returns
+    /// * ```Ok((Game, W))``` - The game and the writer.
+    /// * ```Err(io::Error)``` - The first error of the writer.
+    pub fn into_inner(mut self) -> io::Result<(Game<SIZE>, W)> {
+        self.log.flush();
+        match self.log.error {
+            Some(error) => Err(error),
+            None => Ok((self.game, self.log.writer)),
+        }
+    }
+}
+
+/// The writer of the log of a recorded game, kept apart from the game so the events are written while the game is played.
+#[derive(Debug)]
+struct EventLog<W: Write> {
+    /// The writer of the log.
+    writer: W,
+    /// The events of the game.
+    events: Receiver<GameEvent>,
+    /// The score after the recorded events.
+    score: u64,
+    /// The first error of the writer.
+    error: Option<io::Error>,
+}
+impl<W: Write> EventLog<W> {
+    /// Writes the lines of the events received since the last recording.
+    fn record(&mut self) {
+        while let Ok(event) = self.events.try_recv() {
+            let line = match event {
+                GameEvent::MoveMade { direction, gained } => {
+                    self.score = self.score.saturating_add(gained);
+                    format!("move,{},{},{}", direction_name(direction), gained, self.score)
+                }
+                GameEvent::TileSpawned { position: (row, col), value } => format!("spawn,{row},{col},{value}"),
+                GameEvent::Repetition { count } => format!("repetition,{count}"),
+                GameEvent::Victory => String::from("victory"),
+                GameEvent::GameOver => String::from("game_over"),
+            };
+            self.write_line(&line);
+            if event == GameEvent::GameOver {
+                self.flush();
+            }
+        }
+    }
+
+    /// Writes the line, unless the writer already failed.
+    /// # Arguments
+    /// * ```line``` - The line, without the line break.
+    fn write_line(&mut self, line: &str) {
+        if self.error.is_none() {
+            if let Err(error) = writeln!(self.writer, "{line}") {
+                self.error = Some(error);
+            }
+        }
+    }
+
+    /// Flushes the writer, unless it already failed.
+    fn flush(&mut self) {
+        if self.error.is_none() {
+            if let Err(error) = self.writer.flush() {
+                self.error = Some(error);
+            }
+        }
+    }
+}
+
+/// A strategy writing the events of the previous move before choosing the next one, so the games played by a strategy are streamed.
+struct Streaming<'a, const SIZE: usize, W: Write> {
+    /// The strategy choosing the moves.
+    strategy: &'a mut dyn Strategy<SIZE>,
+    /// The log of the game.
+    log: &'a mut EventLog<W>,
+}
+impl<const SIZE: usize, W: Write> Strategy<SIZE> for Streaming<'_, SIZE, W> {
+    fn choose(&mut self, game: &Game<SIZE>) -> Option<GameMove> {
+        self.log.record();
+        self.strategy.choose(game)
+    }
+}
+
+impl<const SIZE: usize> GameRecorder<SIZE, io::Sink> {
+    /// Reads the log written by a recorder.
+    /// # Arguments
+    /// * ```reader``` - The reader of the log.
+    /// # Returns
+    /// * ```Ok(Replay)``` - The recorded game.
+    /// * ```Err(LoadError)``` - The log could not be read.
+    /// # Errors
+    /// * ```LoadError::Io``` - The reader failed.
+    /// * ```LoadError::Game``` - The log is malformed (```Error::InvalidEncoding```), the merge rule is not built in (```Error::InvalidConfig```),
+    ///   or the starting position is invalid (see ```Game::from_str``` and ```GameBuilder::build```).
+    pub fn read(reader: impl Read) -> Result<Replay<SIZE>, LoadError> {
+        let mut lines = BufReader::new(reader).lines().peekable();
+        let first = lines.next().transpose().map_err(LoadError::Io)?.ok_or(LoadError::Game(Error::InvalidEncoding))?;
+        let start = first.strip_prefix("start,").ok_or(LoadError::Game(Error::InvalidEncoding))?;
+        let rules = match lines.next_if(|line| line.as_ref().is_ok_and(|line| line.starts_with("rules,"))) {
+            Some(line) => Some(Rules::parse(&line.map_err(LoadError::Io)?).ok_or(LoadError::Game(Error::InvalidEncoding))?),
+            None => None,
+        };
+
+        let mut entries = Vec::new();
+        for line in lines {
+            let line = line.map_err(LoadError::Io)?;
+            entries.push(parse_entry(&line).ok_or(LoadError::Game(Error::InvalidEncoding))?);
+        }
+        let replay = Replay {
+            start: String::from(start),
+            rules,
+            entries,
+        };
+        replay.try_start().map_err(LoadError::Game)?;
+        Ok(replay)
+    }
+}
+impl<const SIZE: usize, W: Write> Deref for GameRecorder<SIZE, W> {
+    type Target = Game<SIZE>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.game
+    }
+}
+
+/// A line of the log after the starting position and the rules.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum LogEntry<const SIZE: usize> {
+    /// An event of the game.
+    Event(GameEvent),
+    /// The latest move was undone (see ```Game::undo```).
+    Undo,
+    /// The game was restarted (see ```Game::reset```), with the board after the restart (```BLOCKED_CELL``` in the blocked cells).
+    Reset([[u64; SIZE]; SIZE]),
+}
+
+/// A game read back from the log of a recorder (see ```GameRecorder::read```).
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+pub struct Replay<const SIZE: usize> {
+    /// The compact line of the starting position.
+    start: String,
+    /// The rules of the game, ```None``` for the logs written without them.
+    rules: Option<Rules>,
+    /// The recorded events, undos and restarts, in the order they happened.
+    pub entries: Vec<LogEntry<SIZE>>,
+}
+impl<const SIZE: usize> Replay<SIZE> {
+    /// Returns the position the recording started from.
+    /// # Returns
+    /// * ```Game``` - The starting position.
+    pub fn start(&self) -> Game<SIZE> {
+        self.try_start().expect("the starting position is validated when the log is read")
+    }
+
+    /// Builds the starting position with the recorded rules.
+    /// # Returns
+    /// * ```Ok(Game)``` - The starting position.
+    /// * ```Err(Error)``` - The rules or the position are invalid, see ```GameRecorder::read```.
+    fn try_start(&self) -> Result<Game<SIZE>, Error> {
+        let Some(rules) = &self.rules else {
+            return self.start.parse();
+        };
+        let mut builder = Game::builder().puzzle(rules.puzzle);
+        if rules.rule != "classic" {
+            builder = builder.merge_rule(rules::from_name(&rules.rule).ok_or(Error::InvalidConfig)?);
+        }
+        builder = match rules.target {
+            Some(target) => builder.target(target),
+            None => builder.endless(true),
+        };
+        if let Some(max_turns) = rules.max_turns {
+            builder = builder.max_turns(max_turns);
+        }
+        let (board, score, _) = Game::<SIZE>::parse_compact_string(&self.start)?;
+        builder.build_resumed(&board, score, rules.turns)
+    }
+
+    /// Returns the recorded moves.
+    /// # Returns
+    /// * ```impl Iterator<Item = GameMove>``` - The moves, in the order they were made.
+    pub fn moves(&self) -> impl Iterator<Item = GameMove> + '_ {
+        self.entries.iter().filter_map(|entry| match entry {
+            LogEntry::Event(GameEvent::MoveMade { direction, .. }) => Some(*direction),
+            _ => None,
+        })
+    }
+
+    /// Plays the recorded moves, tiles, undos and restarts from the starting position.
+    /// # Returns
+    /// * ```Ok(Game)``` - The game after the recorded entries.
+    /// * ```Err(Error)``` - The entries don't fit the game.
+    /// # Errors
+    /// * ```Error::InvalidMove``` - A recorded move doesn't change the board, or a recorded undo has no move to undo.
+    /// * ```Error::WrongTurn``` - The moves and the tiles don't alternate.
+    /// * ```Error::InvalidPosition``` - A recorded tile is not on an empty cell, or a restart moves the blocked cells.
+    /// * ```Error::InvalidValue``` - A recorded tile is neither a valid tile of the merge rule nor a junk tile.
+    pub fn play(&self) -> Result<Game<SIZE>, Error> {
+        self.play_each(|_, _| {})
+    }
+
+    /// Plays the recorded entries from the starting position like ```play```, showing every entry with the game before it.
+    /// # Arguments
+    /// * ```visit``` - Called with the game and the entry, before the entry is played.
+    /// # Returns
+    /// * ```Ok(Game)``` - The game after the recorded entries.
+    /// * ```Err(Error)``` - The entries don't fit the game, see ```play```.
+    pub(crate) fn play_each(&self, mut visit: impl FnMut(&Game<SIZE>, &LogEntry<SIZE>)) -> Result<Game<SIZE>, Error> {
+        let mut game = self.start();
+        if self.entries.contains(&LogEntry::Undo) {
+            // every recorded move can be undone
+            let history = HistoryConfig {
+                max_undo: self.moves().count(),
+                ..game.history_config()
+            };
+            game = game.with_history(history);
+        }
+        for entry in &self.entries {
+            visit(&game, entry);
+            match *entry {
+                // the moves of a puzzle don't wait for a tile
+                LogEntry::Event(GameEvent::MoveMade { direction, .. }) if game.is_puzzle() => game.make_move(direction).then_some(()).ok_or(Error::InvalidMove)?,
+                LogEntry::Event(GameEvent::MoveMade { direction, .. }) => game.make_move_without_spawn(direction)?.then_some(()).ok_or(Error::InvalidMove)?,
+                LogEntry::Event(GameEvent::TileSpawned { position: (row, col), value }) => game.place_spawned_tile(row, col, value)?,
+                LogEntry::Event(_) => {}
+                LogEntry::Undo => game.undo().then_some(()).ok_or(Error::InvalidMove)?,
+                LogEntry::Reset(board) => {
+                    for (row, (tiles, current)) in board.iter().zip(game.board()).enumerate() {
+                        for (col, (&value, &current)) in tiles.iter().zip(current).enumerate() {
+                            if (value == BLOCKED_CELL) != (current == BLOCKED_CELL) {
+                                return Err(Error::InvalidPosition);
+                            }
+                            if value != 0 && value != BLOCKED_CELL && value != JUNK_TILE && !game.merge_rule().is_valid_tile(value) {
+                                return Err(Error::InvalidValue { row, col, value });
+                            }
+                        }
+                    }
+                    game.restart_on(&board);
+                }
+            }
+        }
+        Ok(game)
+    }
+}
+
+/// The rules of a recorded game, written in the second line of the log.
+#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+struct Rules {
+    /// The name of the merge rule, ```custom``` for the rules that are not built in.
+    rule: String,
+    /// The target, ```None``` in the endless mode.
+    target: Option<u64>,
+    /// The limit of the turns.
+    max_turns: Option<u64>,
+    /// The number of the moves made before the recording.
+    turns: u64,
+    /// Whether the game is a puzzle.
+    puzzle: bool,
+}
+impl Rules {
+    /// Returns the line of the rules in the log.
+    /// # Returns
+    /// * ```String``` - The line.
+    fn to_line(&self) -> String {
+        let optional = |value: Option<u64>| value.map_or_else(|| String::from("none"), |value| value.to_string());
+        format!("rules,{},{},{},{},{}", self.rule, optional(self.target), optional(self.max_turns), self.turns, self.puzzle)
+    }
+
+    /// Parses the line of the rules in the log.
+    /// # Arguments
+    /// * ```line``` - The line.
+    /// # Returns
+    /// * ```Some(Rules)``` - The rules.
+    /// * ```None``` - The line is malformed.
+    fn parse(line: &str) -> Option<Self> {
+        let optional = |value: &str| match value {
+            "none" => Some(None),
+            value => value.parse().ok().map(Some),
+        };
+        let fields: Vec<&str> = line.split(',').collect();
+        match fields.as_slice() {
+            ["rules", rule, target, max_turns, turns, puzzle] => Some(Self {
+                rule: String::from(*rule),
+                target: optional(target)?,
+                max_turns: optional(max_turns)?,
+                turns: turns.parse().ok()?,
+                puzzle: puzzle.parse().ok()?,
+            }),
+            _ => None,
+        }
+    }
+}
+
+/// Returns the name of the move in the log.
+/// # Arguments
+/// * ```direction``` - The move.
+/// # Returns
+/// * ```&str``` - The name of the move.
+fn direction_name(direction: GameMove) -> &'static str {
+    match direction {
+        GameMove::Left => "left",
+        GameMove::Right => "right",
+        GameMove::Up => "up",
+        GameMove::Down => "down",
+    }
+}
+
+/// Parses a line of the log after the starting position and the rules.
+/// # Arguments
+/// * ```line``` - The line.
+/// # Returns
+/// * ```Some(LogEntry)``` - The entry of the line.
+/// * ```None``` - The line is malformed.
+fn parse_entry<const SIZE: usize>(line: &str) -> Option<LogEntry<SIZE>> {
+    // the compact line of the game contains the separators
+    if let Some(position) = line.strip_prefix("reset,") {
+        return Game::<SIZE>::parse_compact_string(position).ok().map(|(board, _, _)| LogEntry::Reset(board));
+    }
+    let fields: Vec<&str> = line.split(',').collect();
+    let event = match fields.as_slice() {
+        ["move", direction, gained, _score] => GameEvent::MoveMade {
+            direction: GameMove::ALL.into_iter().find(|&candidate| direction_name(candidate) == *direction)?,
+            gained: gained.parse().ok()?,
+        },
+        ["spawn", row, col, value] => GameEvent::TileSpawned {
+            position: (row.parse().ok()?, col.parse().ok()?),
+            value: value.parse().ok()?,
+        },
+        ["repetition", count] => GameEvent::Repetition { count: count.parse().ok()? },
+        ["victory"] => GameEvent::Victory,
+        ["game_over"] => GameEvent::GameOver,
+        ["undo"] => return Some(LogEntry::Undo),
+        _ => return None,
+    };
+    Some(LogEntry::Event(event))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{GameResult, GameState, JUNK_TILE};
+    use crate::rules::{FibonacciRule, ThreesRule};
+    use crate::spawn::SpawnConfig;
+    use crate::strategy::RandomStrategy;
+    use core::cell::{Cell, RefCell};
+    use std::rc::Rc;
+    use std::sync::Arc;
+
+    #[test]
+    fn record_and_read() {
+        //! Test that a recorded game is read back losslessly and replays to the same position
+
+        let build = || Game::<4>::builder().seed(632).build().unwrap();
+        let mut reference = build();
+        let expected_events = reference.subscribe();
+        let moves = [GameMove::Left, GameMove::Up, GameMove::Right, GameMove::Down, GameMove::Left, GameMove::Up];
+        let made = moves.into_iter().filter(|&direction| reference.make_move(direction)).count();
+
+        let mut recorder = GameRecorder::new(build(), Vec::new());
+        assert_eq!(moves.into_iter().filter(|&direction| recorder.make_move(direction)).count(), made);
+        assert_eq!((recorder.board(), recorder.score()), (reference.board(), reference.score()));
+        let (game, log) = recorder.into_inner().unwrap();
+
+        let text = String::from_utf8(log.clone()).unwrap();
+        assert_eq!(text.lines().next().unwrap(), format!("start,{}", build().to_compact_string()));
+        assert_eq!(text.lines().nth(1).unwrap(), "rules,classic,2048,none,0,false");
+        assert_eq!(text.lines().count(), 2 + 2 * made);
+        let replay: Replay<4> = GameRecorder::read(log.as_slice()).unwrap();
+        assert_eq!(replay.entries, expected_events.try_iter().map(LogEntry::Event).collect::<Vec<_>>());
+        assert_eq!(replay.moves().count(), made);
+        assert_eq!(replay.start().board(), build().board());
+        let replayed = replay.play().unwrap();
+        assert_eq!((replayed.board(), replayed.score(), replayed.turns()), (game.board(), game.score(), game.turns()));
+
+        // the two-player moves are recorded as well
+        let mut recorder = GameRecorder::new(Game::from_existing(&[[2, 2], [0, 0]], 0).unwrap(), Vec::new());
+        assert_eq!(recorder.make_move_without_spawn(GameMove::Left), Ok(true));
+        assert_eq!(recorder.place_tile(1, 1, 4), Ok(()));
+        let log = recorder.into_inner().unwrap().1;
+        assert_eq!(
+            String::from_utf8(log.clone()).unwrap(),
+            "start,2|2,2/0,0/score=0\nrules,classic,32,none,0,false\nmove,left,4,4\nspawn,1,1,4\n"
+        );
+        let replay: Replay<2> = GameRecorder::read(log.as_slice()).unwrap();
+        assert_eq!(replay.play().unwrap().board(), &[[4, 0], [0, 4]]);
+        // the logs written without the rules are replayed with the classic rules
+        let replay: Replay<2> = GameRecorder::read("start,2|2,2/0,0/score=0\nmove,left,4,4\nspawn,1,1,4\n".as_bytes()).unwrap();
+        assert_eq!(replay.start().target(), Some(32));
+        assert_eq!(replay.play().unwrap().board(), &[[4, 0], [0, 4]]);
+
+        // the malformed logs
+        for log in [
+            "",
+            "move,left,4,4\n",
+            "start,2|2,2/0,0/score=0\nmove,diagonal,4,4\n",
+            "start,2|2,2/0,0/score=0\nspawn,1,1\n",
+            "start,2|2,2/0,0/score=0\nrules,classic,32\n",
+            "start,2|2,2/0,0/score=0\nrules,classic,32,none,zero,false\n",
+        ] {
+            assert!(matches!(GameRecorder::<2, _>::read(log.as_bytes()), Err(LoadError::Game(Error::InvalidEncoding))));
+        }
+        assert!(matches!(
+            GameRecorder::<4, _>::read("start,2|2,2/0,0/score=0\n".as_bytes()),
+            Err(LoadError::Game(Error::InvalidBoard { .. }))
+        ));
+        assert!(matches!(
+            GameRecorder::<2, _>::read("start,2|2,2/0,0/score=0\nrules,custom,32,none,0,false\n".as_bytes()),
+            Err(LoadError::Game(Error::InvalidConfig))
+        ));
+    }
+
+    #[test]
+    fn replay_rules() {
+        //! Test that the games of the other rules and spawns are replayed with their rules and tiles
+
+        let games = [
+            Game::<4>::builder().seed(7).junk_every(3).max_turns(40).build().unwrap(),
+            Game::<4>::builder().seed(7).merge_rule(Arc::new(ThreesRule)).endless(true).build().unwrap(),
+            Game::<4>::builder().seed(7).merge_rule(Arc::new(FibonacciRule)).build().unwrap(),
+            Game::<4>::builder()
+                .seed(7)
+                .spawn_config(SpawnConfig::from_values(&[(8, 0.5), (16, 0.5)]).unwrap())
+                .build()
+                .unwrap(),
+        ];
+        for game in games {
+            let mut recorder = GameRecorder::new(game, Vec::new());
+            for _ in 0..30 {
+                recorder.make_move(GameMove::ALL.into_iter().find(|&direction| recorder.can_move(direction)).unwrap_or(GameMove::Left));
+            }
+            let (game, log) = recorder.into_inner().unwrap();
+            let replay: Replay<4> = GameRecorder::read(log.as_slice()).unwrap();
+            let start = replay.start();
+            assert_eq!(
+                (start.merge_rule().name(), start.target(), start.max_turns()),
+                (game.merge_rule().name(), game.target(), game.max_turns())
+            );
+            let replayed = replay.play().unwrap();
+            assert_eq!(
+                (replayed.board(), replayed.score(), replayed.turns(), replayed.state(), replayed.result()),
+                (game.board(), game.score(), game.turns(), game.state(), game.result())
+            );
+        }
+        // the junk tiles are recorded and replayed
+        let junk = Game::<4>::builder().seed(7).junk_every(3).build().unwrap();
+        let mut recorder = GameRecorder::new(junk, Vec::new());
+        for direction in [GameMove::Left, GameMove::Up, GameMove::Right, GameMove::Down].into_iter().cycle().take(12) {
+            recorder.make_move(direction);
+        }
+        let log = recorder.into_inner().unwrap().1;
+        assert!(String::from_utf8(log.clone()).unwrap().contains(&format!(",{JUNK_TILE}\n")));
+        let replay: Replay<4> = GameRecorder::read(log.as_slice()).unwrap();
+        assert!(replay.play().unwrap().board().iter().flatten().any(|&tile| tile == JUNK_TILE));
+
+        // the puzzles and the games resumed after some moves
+        let puzzle = Game::<2>::puzzle(&[[2, 2], [4, 0]], 2, 8).unwrap();
+        let mut recorder = GameRecorder::new(puzzle, Vec::new());
+        assert!(recorder.make_move(GameMove::Left));
+        assert!(recorder.make_move(GameMove::Up));
+        let (game, log) = recorder.into_inner().unwrap();
+        assert_eq!(game.result(), GameResult::Victory);
+        let replay: Replay<2> = GameRecorder::read(log.as_slice()).unwrap();
+        assert!(replay.start().is_puzzle());
+        let replayed = replay.play().unwrap();
+        assert_eq!((replayed.board(), replayed.result()), (game.board(), game.result()));
+        let mut game = Game::<4>::builder().seed(7).max_turns(10).build().unwrap();
+        for _ in 0..3 {
+            assert!(game.make_move(GameMove::ALL.into_iter().find(|&direction| game.can_move(direction)).unwrap()));
+        }
+        let log = GameRecorder::new(game, Vec::new()).into_inner().unwrap().1;
+        let replay: Replay<4> = GameRecorder::read(log.as_slice()).unwrap();
+        assert_eq!((replay.start().turns(), replay.start().max_turns()), (3, Some(10)));
+    }
+
+    #[test]
+    fn streaming() {
+        //! Test that the games played by a strategy are written move by move, not when the game ends
+
+        /// A writer shared with the test.
+        struct Shared(Rc<RefCell<Vec<u8>>>);
+        impl Write for Shared {
+            fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+                self.0.borrow_mut().write(buf)
+            }
+
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+
+        /// A strategy checking that every move made so far is already written.
+        struct Checking(Rc<RefCell<Vec<u8>>>, usize);
+        impl Strategy<4> for Checking {
+            fn choose(&mut self, game: &Game<4>) -> Option<GameMove> {
+                let log = String::from_utf8(self.0.borrow().clone()).unwrap();
+                assert_eq!(log.lines().filter(|line| line.starts_with("move,")).count() as u64, game.turns());
+                self.1 += 1;
+                GameMove::ALL.into_iter().find(|&direction| game.can_move(direction))
+            }
+        }
+
+        let log = Rc::new(RefCell::new(Vec::new()));
+        let mut recorder = GameRecorder::new(Game::<4>::builder().seed(632).build().unwrap(), Shared(Rc::clone(&log)));
+        let mut strategy = Checking(Rc::clone(&log), 0);
+        recorder.autoplay_with(&mut strategy, Some(20)).unwrap();
+        assert_eq!(strategy.1, 20);
+        recorder.play_with(&mut strategy);
+        assert!(strategy.1 > 20);
+        assert_eq!(recorder.replay([GameMove::Left]), Err(Error::InvalidMove));
+        let (game, _) = recorder.into_inner().unwrap();
+        let replay: Replay<4> = GameRecorder::read(log.borrow().as_slice()).unwrap();
+        assert_eq!(replay.play().unwrap().board(), game.board());
+        assert_eq!(
+            GameRecorder::new(Game::<4>::builder().seed(632).build().unwrap(), Vec::new()).autoplay(0, None),
+            Err(Error::InvalidDepth)
+        );
+    }
+
+    #[test]
+    fn undo_and_reset() {
+        //! Test that the undos and the restarts are recorded and replayed
+
+        let game = Game::<4>::builder()
+            .seed(632)
+            .history(HistoryConfig {
+                max_undo: 8,
+                ..Default::default()
+            })
+            .build()
+            .unwrap();
+        let mut recorder = GameRecorder::new(game, Vec::new());
+        let next = |recorder: &GameRecorder<4, Vec<u8>>| GameMove::ALL.into_iter().find(|&direction| recorder.can_move(direction)).unwrap();
+        for _ in 0..5 {
+            assert!(recorder.make_move(next(&recorder)));
+        }
+        assert!(recorder.undo());
+        assert!(recorder.undo());
+        assert!(recorder.make_move(next(&recorder)));
+        recorder.reset();
+        for _ in 0..3 {
+            assert!(recorder.make_move(next(&recorder)));
+        }
+        assert!(recorder.undo());
+        assert!(recorder.make_move(GameMove::ALL.into_iter().rev().find(|&direction| recorder.can_move(direction)).unwrap()));
+        let (game, log) = recorder.into_inner().unwrap();
+
+        let text = String::from_utf8(log.clone()).unwrap();
+        assert_eq!(text.lines().filter(|&line| line == "undo").count(), 3);
+        assert_eq!(text.lines().filter(|line| line.starts_with("reset,4|")).count(), 1);
+        // the score of the moves after an undo continues from the restored score
+        let last_move = text.lines().rev().find(|line| line.starts_with("move,")).unwrap();
+        assert!(last_move.ends_with(&format!(",{}", game.score())));
+        let replay: Replay<4> = GameRecorder::read(log.as_slice()).unwrap();
+        assert_eq!(replay.entries.iter().filter(|&&entry| entry == LogEntry::Undo).count(), 3);
+        let replayed = replay.play().unwrap();
+        assert_eq!(
+            (replayed.board(), replayed.score(), replayed.turns(), replayed.state()),
+            (game.board(), game.score(), game.turns(), game.state())
+        );
+
+        // the undos without a move and the restarts moving the blocked cells don't fit the game
+        let replay: Replay<2> = GameRecorder::read("start,2|2,2/0,0/score=0\nundo\n".as_bytes()).unwrap();
+        assert_eq!(replay.play().unwrap_err(), Error::InvalidMove);
+        let replay: Replay<2> = GameRecorder::read("start,2|2,2/0,0/score=0\nreset,2|#,2/0,0/score=0\n".as_bytes()).unwrap();
+        assert_eq!(replay.play().unwrap_err(), Error::InvalidPosition);
+        let replay: Replay<2> = GameRecorder::read("start,2|2,2/0,0/score=0\nreset,2|3,0/0,0/score=0\n".as_bytes()).unwrap();
+        assert_eq!(replay.play().unwrap_err(), Error::InvalidValue { row: 0, col: 0, value: 3 });
+        assert!(matches!(
+            GameRecorder::<2, _>::read("start,2|2,2/0,0/score=0\nreset,2|2,2\n".as_bytes()),
+            Err(LoadError::Game(Error::InvalidEncoding))
+        ));
+    }
+
+    #[test]
+    fn flush_on_game_over() {
+        //! Test that the log is flushed when the game is over, and that the errors of the writer are kept
+
+        /// A writer counting the flushes, failing after the given number of lines.
+        struct Flushes(Rc<Cell<usize>>, usize);
+        impl Write for Flushes {
+            fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+                if self.1 == 0 {
+                    return Err(io::Error::other("full"));
+                }
+                self.1 -= buf.iter().filter(|&&byte| byte == b'\n').count();
+                Ok(buf.len())
+            }
+
+            fn flush(&mut self) -> io::Result<()> {
+                self.0.set(self.0.get() + 1);
+                Ok(())
+            }
+        }
+
+        let flushes = Rc::new(Cell::new(0));
+        let mut recorder = GameRecorder::new(Game::<2>::builder().seed(1).build().unwrap(), Flushes(Rc::clone(&flushes), usize::MAX));
+        assert_eq!(flushes.get(), 0);
+        let summary = recorder.play_with(&mut RandomStrategy);
+        assert_eq!(summary.state, GameState::GameOver);
+        assert_eq!(flushes.get(), 1);
+        assert!(recorder.into_inner().is_ok());
+
+        let mut recorder = GameRecorder::new(Game::<2>::builder().seed(1).build().unwrap(), Flushes(Rc::new(Cell::new(0)), 3));
+        recorder.play_with(&mut RandomStrategy);
+        assert_eq!(recorder.state(), GameState::GameOver);
+        assert_eq!(recorder.into_inner().err().unwrap().to_string(), "full");
+    }
+}
@@ -0,0 +1,265 @@
+//! A module that contains the rules deciding which tiles merge.
+//! The classic rules are used by default, other rules can be set with ```Game::from_existing_with_rule``` or ```Game::new_with_rule```.
+
+// std imports
+use alloc::sync::Arc;
+use core::fmt::{self, Formatter};
+
+// internal imports
+use crate::core::{DEFAULT_WIN_TARGET, MAX_SUPPORTED_EXPONENT};
+use crate::spawn::SpawnConfig;
+
+/// A trait for the rules that decide which tiles merge.
+pub trait MergeRule: Send + Sync {
+    /// Decides whether two tiles merge.
+    /// # Arguments
+    /// * ```a``` - The tile closer to the side the tiles slide towards.
+    /// * ```b``` - The tile sliding into it.
+    /// # Returns
+    /// * ```Some((u64, u64))``` - The value of the merged tile and the score gained by the merge.
+    /// * ```None``` - The tiles don't merge.
+    fn merge(&self, a: u64, b: u64) -> Option<(u64, u64)>;
+
+    /// Checks whether a value can appear on the board under these rules.
+    /// # Arguments
+    /// * ```tile``` - The value of the tile (never 0).
+    /// # Returns
+    /// * ```true``` - The value is a valid tile.
+    /// * ```false``` - The value is not a valid tile.
+    fn is_valid_tile(&self, tile: u64) -> bool;
+
+    /// Returns the distribution of the spawned tiles that fits these rules.
+    /// The default is the classic distribution (2 with the probability of 0.9, 4 with the probability of 0.1).
+    fn spawn_config(&self) -> SpawnConfig {
+        SpawnConfig::default()
+    }
+
+    /// Returns the tile that wins the games of these rules by default (see ```Game::target```).
+    /// The default is the classic target: 2048 for boards of 4x4 and larger, 512 for 3x3 boards and 32 for 2x2 boards.
+    /// # Arguments
+    /// * ```size``` - The size of the board.
+    /// # Returns
+    /// * ```u64``` - The target tile, a valid tile of these rules.
+    fn default_target(&self, size: usize) -> u64 {
+        match size {
+            ..=2 => 32,
+            3 => 512,
+            _ => DEFAULT_WIN_TARGET,
+        }
+    }
+
+    /// Returns the name identifying the built-in rules in the saved games (see ```from_name```).
+    /// The default is ```None```, the games of the other rules are saved without their rules.
+    /// # Returns
+    /// * ```Some(&str)``` - The name of the built-in rules.
+    /// * ```None``` - The rules are not built in.
+    fn name(&self) -> Option<&'static str> {
+        None
+    }
+}
+impl fmt::Debug for dyn MergeRule {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "MergeRule")
+    }
+}
+
+/// The classic rules of 2048: two equal tiles merge into their sum, which is also the score of the merge.
+/// Tiles of the value 2^63 (2^```MAX_SUPPORTED_EXPONENT```) don't merge, since the merged tile wouldn't fit into a ```u64```.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub struct ClassicRule;
+impl MergeRule for ClassicRule {
+    fn merge(&self, a: u64, b: u64) -> Option<(u64, u64)> {
+        if a == b && a < 1 << MAX_SUPPORTED_EXPONENT {
+            Some((a * 2, a * 2))
+        } else {
+            None
+        }
+    }
+
+    /// The valid tiles are the powers of 2, starting from 2 (up to 2^```MAX_SUPPORTED_EXPONENT```).
+    fn is_valid_tile(&self, tile: u64) -> bool {
+        tile >= 2 && tile.is_power_of_two() && tile.ilog2() <= MAX_SUPPORTED_EXPONENT
+    }
+    fn name(&self) -> Option<&'static str> {
+        Some("classic")
+    }
+}
+
+/// The rules of Threes: a 1 and a 2 merge into a 3, and two equal tiles of at least 3 merge into their sum.
+/// The score of a merge is the value of the merged tile.
+/// The valid tiles are 1, 2 and 3 times the powers of 2, and the spawned tiles are 1, 2 and 3 with equal probabilities.
+/// The default target is 3072 for boards of 4x4 and larger, 768 for 3x3 boards and 48 for 2x2 boards.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub struct ThreesRule;
+impl MergeRule for ThreesRule {
+    fn merge(&self, a: u64, b: u64) -> Option<(u64, u64)> {
+        match (a, b) {
+            (1, 2) | (2, 1) => Some((3, 3)),
+            (a, b) if a == b && a >= 3 => a.checked_mul(2).map(|merged| (merged, merged)),
+            _ => None,
+        }
+    }
+
+    fn is_valid_tile(&self, tile: u64) -> bool {
+        tile == 1 || tile == 2 || (tile.is_multiple_of(3) && (tile / 3).is_power_of_two())
+    }
+
+    fn spawn_config(&self) -> SpawnConfig {
+        SpawnConfig::from_values_with_rule(&[(1, 1.0 / 3.0), (2, 1.0 / 3.0), (3, 1.0 / 3.0)], self).unwrap()
+    }
+
+    fn default_target(&self, size: usize) -> u64 {
+        match size {
+            ..=2 => 48,
+            3 => 768,
+            _ => 3072,
+        }
+    }
+    fn name(&self) -> Option<&'static str> {
+        Some("threes")
+    }
+}
+
+/// The Fibonacci numbers that fit into a ```u64```, without the repeated 1.
+const FIBONACCI: [u64; 92] = {
+    let mut numbers = [0; 92];
+    let (mut a, mut b) = (1u64, 2u64);
+    let mut i = 0;
+    while i < numbers.len() {
+        numbers[i] = a;
+        (a, b) = (b, a.wrapping_add(b));
+        i += 1;
+    }
+    numbers
+};
+
+/// The rules of Fib2584: the tiles are Fibonacci numbers and two neighbouring Fibonacci numbers (including two 1s) merge into their sum.
+/// The score of a merge is the value of the merged tile.
+/// The spawned tiles are 1 with the probability of 0.9 and 2 with the probability of 0.1,
+/// and the default target is 2584 for boards of 4x4 and larger, 610 for 3x3 boards and 34 for 2x2 boards.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub struct FibonacciRule;
+impl MergeRule for FibonacciRule {
+    fn merge(&self, a: u64, b: u64) -> Option<(u64, u64)> {
+        let (small, large) = (a.min(b), a.max(b));
+        let ind = FIBONACCI.binary_search(&small).ok()?;
+        let neighbours = large == small && small == 1 || FIBONACCI.get(ind + 1) == Some(&large);
+        // the sum of the two largest numbers doesn't fit
+        neighbours.then(|| small.checked_add(large)).flatten().map(|merged| (merged, merged))
+    }
+
+    /// The valid tiles are the Fibonacci numbers, starting from 1.
+    fn is_valid_tile(&self, tile: u64) -> bool {
+        FIBONACCI.binary_search(&tile).is_ok()
+    }
+
+    fn spawn_config(&self) -> SpawnConfig {
+        SpawnConfig::from_values_with_rule(&[(1, 0.9), (2, 0.1)], self).unwrap()
+    }
+
+    fn default_target(&self, size: usize) -> u64 {
+        match size {
+            ..=2 => 34,
+            3 => 610,
+            _ => 2584,
+        }
+    }
+    fn name(&self) -> Option<&'static str> {
+        Some("fibonacci")
+    }
+}
+
+/// Returns the built-in rules with the given name (see ```MergeRule::name```).
+/// # Arguments
+/// * ```name``` - The name of the rules.
+/// # Returns
+/// * ```Some(Arc<dyn MergeRule>)``` - The rules.
+/// * ```None``` - There are no built-in rules with the name.
+pub fn from_name(name: &str) -> Option<Arc<dyn MergeRule>> {
+    match name {
+        "classic" => Some(Arc::new(ClassicRule)),
+        "threes" => Some(Arc::new(ThreesRule)),
+        "fibonacci" => Some(Arc::new(FibonacciRule)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classic_rule() {
+        //! Test the merges and the valid tiles of the classic rules
+
+        assert_eq!(ClassicRule.merge(2, 2), Some((4, 4)));
+        assert_eq!(ClassicRule.merge(1 << 62, 1 << 62), Some((1 << 63, 1 << 63)));
+        assert_eq!(ClassicRule.merge(1 << 63, 1 << 63), None);
+        assert_eq!(ClassicRule.merge(2, 4), None);
+
+        assert!(ClassicRule.is_valid_tile(2));
+        assert!(ClassicRule.is_valid_tile(1 << 63));
+        assert!(!ClassicRule.is_valid_tile(1));
+        assert!(!ClassicRule.is_valid_tile(6));
+        assert_eq!(ClassicRule.spawn_config(), SpawnConfig::default());
+    }
+
+    #[test]
+    fn rule_names() {
+        //! Test that the built-in rules are found by their names
+
+        let rules: [&dyn MergeRule; 3] = [&ClassicRule, &ThreesRule, &FibonacciRule];
+        for rule in rules {
+            let name = rule.name().unwrap();
+            assert_eq!(from_name(name).unwrap().name(), Some(name));
+        }
+        assert!(from_name("custom").is_none());
+    }
+
+    #[test]
+    fn threes_rule() {
+        //! Test the merges, the valid tiles and the spawned tiles of the Threes rules
+
+        assert_eq!(ThreesRule.merge(1, 2), Some((3, 3)));
+        assert_eq!(ThreesRule.merge(2, 1), Some((3, 3)));
+        assert_eq!(ThreesRule.merge(1, 1), None);
+        assert_eq!(ThreesRule.merge(2, 2), None);
+        assert_eq!(ThreesRule.merge(3, 3), Some((6, 6)));
+        assert_eq!(ThreesRule.merge(3, 6), None);
+        assert_eq!(ThreesRule.merge(3 << 62, 3 << 62), None);
+
+        for tile in [1, 2, 3, 6, 12, 3 << 62] {
+            assert!(ThreesRule.is_valid_tile(tile), "{tile}");
+        }
+        for tile in [4, 8, 9, 15, 18] {
+            assert!(!ThreesRule.is_valid_tile(tile), "{tile}");
+        }
+        assert_eq!(ThreesRule.spawn_config().max_value(), 3);
+        assert!([2, 3, 4].into_iter().all(|size| ThreesRule.is_valid_tile(ThreesRule.default_target(size))));
+    }
+
+    #[test]
+    fn fibonacci_rule() {
+        //! Test the merges, the valid tiles and the spawned tiles of the Fibonacci rules
+
+        assert_eq!(FibonacciRule.merge(1, 1), Some((2, 2)));
+        assert_eq!(FibonacciRule.merge(1, 2), Some((3, 3)));
+        assert_eq!(FibonacciRule.merge(3, 2), Some((5, 5)));
+        assert_eq!(FibonacciRule.merge(987, 1597), Some((2584, 2584)));
+        assert_eq!(FibonacciRule.merge(2, 2), None);
+        assert_eq!(FibonacciRule.merge(1, 3), None);
+        assert_eq!(FibonacciRule.merge(3, 8), None);
+        assert_eq!(FibonacciRule.merge(4, 5), None);
+        assert_eq!(FibonacciRule.merge(FIBONACCI[90], FIBONACCI[89]), Some((FIBONACCI[91], FIBONACCI[91])));
+        assert_eq!(FibonacciRule.merge(FIBONACCI[91], FIBONACCI[90]), None);
+
+        for tile in [1, 2, 3, 5, 8, 13, 2584, FIBONACCI[91]] {
+            assert!(FibonacciRule.is_valid_tile(tile), "{tile}");
+        }
+        for tile in [4, 6, 7, 9, 2048, u64::MAX] {
+            assert!(!FibonacciRule.is_valid_tile(tile), "{tile}");
+        }
+        assert_eq!(FibonacciRule.spawn_config().values(), [(1, 0.9), (2, 0.1)]);
+        assert!([2, 3, 4].into_iter().all(|size| FibonacciRule.is_valid_tile(FibonacciRule.default_target(size))));
+    }
+}
@@ -0,0 +1,336 @@
+//! A module that contains the session of the games played one after another, e.g. in a frontend,
+//! with the aggregates kept across the games: the best score, the best tile, the number of the games played and won.
+//! The aggregates of the active game are read from the game itself, so the moves (also the undone ones) are made on the game directly.
+
+// std imports
+#[cfg(feature = "serde")]
+use alloc::string::String;
+
+// external imports
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+// internal imports
+use crate::core::{Game, GameBuilder, GameResult};
+use crate::error::Error;
+
+/// The aggregates of the games of a session (see ```Session::stats```).
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct SessionStats {
+    /// The best score of any game of the session.
+    pub best_score: u64,
+    /// The highest tile of any game of the session.
+    pub best_tile: u64,
+    /// The number of the games started in the session, including the active game.
+    pub games_played: u64,
+    /// The number of the games that reached the target tile.
+    pub games_won: u64,
+}
+impl SessionStats {
+    /// Adds the game to the aggregates.
+    /// # Arguments
+    /// * ```game``` - The game, counted as won if it reached the target tile in its current position.
+    /// # Returns
+    /// * ```SessionStats``` - The aggregates including the game.
+    fn with_game<const SIZE: usize>(self, game: &Game<SIZE>) -> Self {
+        Self {
+            best_score: self.best_score.max(game.score()),
+            best_tile: self.best_tile.max(game.highest_tile()),
+            games_played: self.games_played + 1,
+            games_won: self.games_won + u64::from(is_won(game)),
+        }
+    }
+}
+
+/// A session of the games, owning the active game and the aggregates of all its games.
+/// The new games are built with the builder of the session, so the seeded builders start every game from the same seed.
+/// The active game counts with its current position, so the undone moves (```Game::undo```) and the restarts (```Game::reset```)
+/// are taken back from the aggregates as well, and a game is counted as won at most once.
+#[derive(Debug)]
+pub struct Session<const SIZE: usize = 4> {
+    /// The builder of the new games.
+    builder: GameBuilder<SIZE>,
+    /// The active game.
+    game: Game<SIZE>,
+    /// The aggregates of the previous games, without the active game.
+    previous: SessionStats,
+}
+impl<const SIZE: usize> Session<SIZE> {
+    /// Creates the session and starts its first game.
+    /// # Arguments
+    /// * ```builder``` - The builder of the games of the session.
+    /// # Returns
+    /// * ```Ok(Session)``` - The session.
+    /// * ```Err(Error)``` - The game could not be built, see ```GameBuilder::build```.
+    pub fn new(builder: GameBuilder<SIZE>) -> Result<Self, Error> {
+        let game = builder.clone().build()?;
+        Ok(Self {
+            builder,
+            game,
+            previous: SessionStats::default(),
+        })
+    }
+
+    /// Starts a new game built with the builder of the session, the active game is discarded.
+    /// # Returns
+    /// * ```Ok(&mut Game)``` - The new game.
+    /// * ```Err(Error)``` - The game could not be built, see ```GameBuilder::build```. The active game is kept.
+    pub fn new_game(&mut self) -> Result<&mut Game<SIZE>, Error> {
+        let game = self.builder.clone().build()?;
+        Ok(self.start_game(game))
+    }
+
+    /// Starts the given game, e.g. one that is not built with the builder of the session, the active game is discarded.
+    /// # Arguments
+    /// * ```game``` - The game to start.
+    /// # Returns
+    /// * ```&mut Game``` - The started game.
+    pub fn start_game(&mut self, game: Game<SIZE>) -> &mut Game<SIZE> {
+        self.previous = self.stats();
+        self.game = game;
+        &mut self.game
+    }
+
+    /// Returns the active game.
+    /// # Returns
+    /// * ```&Game``` - The active game.
+    pub fn game(&self) -> &Game<SIZE> {
+        &self.game
+    }
+
+    /// Returns the active game, to make the moves on it.
+    /// # Returns
+    /// * ```&mut Game``` - The active game.
+    pub fn game_mut(&mut self) -> &mut Game<SIZE> {
+        &mut self.game
+    }
+
+    /// Returns the aggregates of the games of the session, including the active game.
+    /// # Returns
+    /// * ```SessionStats``` - The aggregates.
+    pub fn stats(&self) -> SessionStats {
+        self.previous.with_game(&self.game)
+    }
+
+    /// Saves the aggregates and the active game (in its compact form, see ```Game::to_compact_string```, and its number of turns) as JSON,
+    /// e.g. ```{"stats":{"best_score":1234,"best_tile":128,"games_played":3,"games_won":0},"game":"4|2,0,0,4/0,8,16,0/0,0,0,0/0,0,0,2/score=1234","turns":130}```.
+    /// The active game is restored with the rules of the builder of the session, the merge rule named in its compact form is ignored.
+    /// Available with the ```serde``` feature.
+    /// # Returns
+    /// * ```String``` - The JSON of the session.
+    #[cfg(feature = "serde")]
+    pub fn to_json(&self) -> String {
+        let save = SessionSave {
+            stats: self.stats(),
+            game: self.game.to_compact_string(),
+            turns: self.game.turns(),
+        };
+        serde_json::to_string(&save).unwrap()
+    }
+
+    /// Loads the session saved by ```to_json```.
+    /// The active game is rebuilt with the builder from its board, its score and its number of turns, so it keeps the rules of the session
+    /// (the target, the limit of the turns, the merge rule, the spawned tiles and the seed, from whose start the next tiles are spawned).
+    /// Available with the ```serde``` feature.
+    /// # Arguments
+    /// * ```builder``` - The builder of the new games of the session.
+    /// * ```json``` - The JSON of the session.
+    /// # Returns
+    /// * ```Ok(Session)``` - The loaded session.
+    /// * ```Err(Error)``` - The session could not be loaded.
+    /// # Errors
+    /// * ```Error::InvalidEncoding```: The JSON is malformed or is not a saved session.
+    /// * ```Error::InvalidBoard```: The size of the saved game is not SIZE.
+    /// * ```Error::InvalidValue```: The saved game contains a value that is not 0, a blocked cell, a junk tile or a valid tile of the merge rule of the builder.
+    /// * ```Error::InvalidSize```, ```Error::InvalidConfig```: The builder can't build a game, see ```GameBuilder::build```.
+    #[cfg(feature = "serde")]
+    pub fn from_json(builder: GameBuilder<SIZE>, json: &str) -> Result<Self, Error> {
+        let save: SessionSave = serde_json::from_str(json).map_err(|_| Error::InvalidEncoding)?;
+        let (board, score, _) = Game::<SIZE>::parse_compact_string(&save.game)?;
+        let game = builder.clone().build_resumed(&board, score, save.turns)?;
+        // the saved aggregates include the active game
+        let previous = SessionStats {
+            games_played: save.stats.games_played.saturating_sub(1),
+            games_won: save.stats.games_won.saturating_sub(u64::from(is_won(&game))),
+            ..save.stats
+        };
+        Ok(Self { builder, game, previous })
+    }
+}
+
+/// Checks whether the game reached the target tile in its current position.
+/// # Arguments
+/// * ```game``` - The game.
+/// # Returns
+/// * ```bool``` - Whether the game is won.
+fn is_won<const SIZE: usize>(game: &Game<SIZE>) -> bool {
+    matches!(game.result(), GameResult::Victory | GameResult::VictoryThenLoss)
+}
+
+/// The saved session (see ```Session::to_json```).
+#[cfg(feature = "serde")]
+#[derive(Debug, Serialize, Deserialize)]
+struct SessionSave {
+    /// The aggregates.
+    stats: SessionStats,
+    /// The compact form of the active game.
+    game: String,
+    /// The number of the turns of the active game.
+    #[serde(default)]
+    turns: u64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{GameMove, GameState, JUNK_TILE};
+    use crate::history::HistoryConfig;
+    #[cfg(feature = "serde")]
+    use crate::rules::ThreesRule;
+    use crate::spawn::ScriptedSpawner;
+    use alloc::boxed::Box;
+    #[cfg(feature = "serde")]
+    use alloc::sync::Arc;
+
+    #[test]
+    fn two_games() {
+        //! Test that the aggregates of the session are updated by two scripted games, a won one and a lost one
+
+        let mut session = Session::new(Game::<2>::builder().seed(636)).unwrap();
+        assert_eq!(session.stats().games_played, 1);
+        assert_eq!(session.stats().best_tile, session.game().highest_tile());
+
+        // the won game, with the score of 12
+        let won = Game::from_existing(&[[2, 2], [0, 0]], 0)
+            .unwrap()
+            .with_target(8)
+            .unwrap()
+            .with_spawner(Box::new(ScriptedSpawner::new([((0, 1), 4), ((1, 0), 2)])));
+        let game = session.start_game(won);
+        assert!(game.make_move(GameMove::Left));
+        assert!(game.make_move(GameMove::Left));
+        assert_eq!((game.result(), game.score()), (GameResult::Victory, 12));
+        let stats = session.stats();
+        assert_eq!((stats.best_score, stats.best_tile, stats.games_played, stats.games_won), (12, 8, 2, 1));
+
+        // the lost game, with a higher tile but a lower score
+        let lost = Game::from_existing(&[[2, 4], [16, 0]], 0)
+            .unwrap()
+            .with_spawner(Box::new(ScriptedSpawner::new([((1, 0), 8)])));
+        let game = session.start_game(lost);
+        assert!(game.make_move(GameMove::Right));
+        assert_eq!((game.state(), game.result()), (GameState::GameOver, GameResult::Loss));
+        assert_eq!(
+            session.stats(),
+            SessionStats {
+                best_score: 12,
+                best_tile: 16,
+                games_played: 3,
+                games_won: 1,
+            }
+        );
+
+        // the games built by the session are tracked as well
+        let game = session.new_game().unwrap();
+        let moved = GameMove::ALL.into_iter().find(|&direction| game.make_move(direction));
+        assert!(moved.is_some());
+        assert_eq!(session.stats().games_played, 4);
+        assert_eq!(session.stats().best_score, 12.max(session.game().score()));
+
+        // the junk tiles are not counted as the best tile
+        let mut session = Session::new(Game::<4>::builder().seed(636).junk_every(1)).unwrap();
+        for direction in [GameMove::Left, GameMove::Up, GameMove::Right, GameMove::Down] {
+            session.game_mut().make_move(direction);
+        }
+        assert!(session.game().board().iter().flatten().any(|&tile| tile == JUNK_TILE));
+        assert_eq!(session.stats().best_tile, session.game().highest_tile());
+    }
+
+    #[test]
+    fn undo_and_reset() {
+        //! Test that the undone moves and the restarts are taken back from the aggregates, and a game won again is counted once
+
+        let mut session = Session::new(Game::<2>::builder().seed(636)).unwrap();
+        let won = Game::from_existing(&[[2, 2], [0, 0]], 0)
+            .unwrap()
+            .with_target(8)
+            .unwrap()
+            .with_history(HistoryConfig {
+                max_undo: 4,
+                ..Default::default()
+            })
+            .with_spawner(Box::new(ScriptedSpawner::new([((0, 1), 4), ((1, 0), 2), ((1, 0), 2), ((0, 0), 2), ((1, 1), 2)])));
+        let previous = session.stats();
+        let game = session.start_game(won);
+        assert!(game.make_move(GameMove::Left));
+        assert!(game.make_move(GameMove::Left));
+        assert_eq!((game.result(), game.score()), (GameResult::Victory, 12));
+        let stats = session.stats();
+        assert_eq!((stats.best_score, stats.best_tile, stats.games_won), (12, 8, 1));
+
+        // the undone winning move is taken back
+        assert!(session.game_mut().undo());
+        let stats = session.stats();
+        assert_eq!(
+            (stats.best_score, stats.best_tile, stats.games_won),
+            (previous.best_score.max(4), previous.best_tile.max(4), 0)
+        );
+
+        // the game won again is counted once
+        assert!(session.game_mut().make_move(GameMove::Left));
+        assert_eq!(session.game().result(), GameResult::Victory);
+        let stats = session.stats();
+        assert_eq!((stats.best_score, stats.best_tile, stats.games_played, stats.games_won), (12, 8, 2, 1));
+
+        // the restarted game counts with its new position
+        session.game_mut().reset();
+        let stats = session.stats();
+        assert_eq!(stats.games_won, 0);
+        assert_eq!(stats.best_score, previous.best_score);
+
+        // the finished game keeps its aggregates
+        session.new_game().unwrap();
+        assert_eq!(session.stats().games_played, 3);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn persistence() {
+        //! Test that the aggregates and the active game are saved and loaded
+
+        let mut session = Session::new(Game::<4>::builder().seed(636)).unwrap();
+        for direction in [GameMove::Left, GameMove::Up, GameMove::Right, GameMove::Down] {
+            session.game_mut().make_move(direction);
+        }
+        let json = session.to_json();
+        let mut loaded = Session::<4>::from_json(Game::builder().seed(636), &json).unwrap();
+        assert_eq!(loaded.stats(), session.stats());
+        assert_eq!((loaded.game().board(), loaded.game().score()), (session.game().board(), session.game().score()));
+        assert_eq!(loaded.game().turns(), session.game().turns());
+
+        // the loaded session keeps counting
+        loaded.new_game().unwrap();
+        assert_eq!(loaded.stats().games_played, 2);
+
+        // the resumed game keeps the rules of the builder
+        let builder = || Game::<3>::builder().seed(636).merge_rule(Arc::new(ThreesRule)).target(48).max_turns(30).junk_every(5);
+        let mut session = Session::new(builder()).unwrap();
+        for direction in [GameMove::Left, GameMove::Up, GameMove::Right, GameMove::Down].repeat(3) {
+            session.game_mut().make_move(direction);
+        }
+        let loaded = Session::from_json(builder(), &session.to_json()).unwrap();
+        let (game, resumed) = (session.game(), loaded.game());
+        assert_eq!((resumed.board(), resumed.score(), resumed.turns()), (game.board(), game.score(), game.turns()));
+        assert_eq!((resumed.target(), resumed.junk_every(), resumed.state()), (Some(48), Some(5), game.state()));
+        assert_eq!(resumed.spawn_config(), game.spawn_config());
+        assert!(GameMove::ALL.into_iter().all(|direction| resumed.peek_move(direction) == game.peek_move(direction)));
+        assert!(matches!(Session::<3>::from_json(Game::builder(), &session.to_json()), Err(Error::InvalidValue { .. })));
+
+        assert_eq!(Session::<4>::from_json(Game::builder(), "{}").unwrap_err(), Error::InvalidEncoding);
+        assert!(matches!(
+            Session::<4>::from_json(Game::builder(), &json.replace("\"4|", "\"3|")),
+            Err(Error::InvalidBoard { .. })
+        ));
+    }
+}
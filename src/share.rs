@@ -0,0 +1,151 @@
+//! A module that contains the encoding of the shared positions (```Game::share_code```):
+//! the URL-safe base64 (without padding) of the bytes followed by their CRC-8 checksum.
+
+// std imports
+use alloc::string::String;
+use alloc::vec::Vec;
+
+// internal imports
+use crate::error::Error;
+
+/// The URL-safe base64 alphabet.
+const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+/// Encodes the bytes with their checksum.
+/// # Arguments
+/// * ```bytes``` - The bytes to encode.
+/// # Returns
+/// * ```String``` - The code.
+pub(crate) fn encode(bytes: &[u8]) -> String {
+    let mut data = bytes.to_vec();
+    data.push(checksum(bytes));
+
+    let mut code = String::with_capacity((data.len() * 4).div_ceil(3));
+    for chunk in data.chunks(3) {
+        let group = chunk.iter().enumerate().fold(0_u32, |group, (ind, &byte)| group | u32::from(byte) << (16 - 8 * ind));
+        for ind in 0..=chunk.len() {
+            code.push(char::from(ALPHABET[(group >> (18 - 6 * ind)) as usize & 0x3f]));
+        }
+    }
+    code
+}
+
+/// Decodes the code and verifies its checksum.
+/// # Arguments
+/// * ```code``` - The code.
+/// # Returns
+/// * ```Ok(Vec<u8>)``` - The decoded bytes, without the checksum.
+/// * ```Err(Error)``` - The code is invalid.
+/// # Errors
+/// * ```Error::InvalidEncoding``` - The code contains a character outside of the alphabet, has an invalid length or its checksum doesn't match.
+pub(crate) fn decode(code: &str) -> Result<Vec<u8>, Error> {
+    let code = code.as_bytes();
+    if code.len() % 4 == 1 {
+        return Err(Error::InvalidEncoding);
+    }
+
+    let mut data = Vec::with_capacity(code.len() * 3 / 4);
+    for chunk in code.chunks(4) {
+        let mut group = 0_u32;
+        for (ind, &symbol) in chunk.iter().enumerate() {
+            let value = ALPHABET.iter().position(|&letter| letter == symbol).ok_or(Error::InvalidEncoding)?;
+            group |= (value as u32) << (18 - 6 * ind);
+        }
+        let bytes = group.to_be_bytes();
+        let len = chunk.len() - 1;
+        // the unused bits of the last character must be zero, so every code is unique
+        if bytes[1 + len..].iter().any(|&byte| byte != 0) {
+            return Err(Error::InvalidEncoding);
+        }
+        data.extend(&bytes[1..=len]);
+    }
+
+    match data.pop() {
+        Some(sum) if sum == checksum(&data) => Ok(data),
+        _ => Err(Error::InvalidEncoding),
+    }
+}
+
+/// Calculates the CRC-8 checksum (the polynomial 0x07) of the bytes.
+/// It detects every corrupted character of a code, as a character covers only 6 consecutive bits.
+/// # Arguments
+/// * ```bytes``` - The bytes.
+/// # Returns
+/// * ```u8``` - The checksum.
+fn checksum(bytes: &[u8]) -> u8 {
+    bytes
+        .iter()
+        .fold(0, |crc, &byte| (0..8).fold(crc ^ byte, |crc, _| if crc & 0x80 != 0 { (crc << 1) ^ 0x07 } else { crc << 1 }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{Game, GameMove, GameResult, BLOCKED_CELL, JUNK_TILE};
+    use crate::rules::{FibonacciRule, ThreesRule};
+    use alloc::sync::Arc;
+
+    #[test]
+    fn share_encoding() {
+        //! Test the base64 encoding and the checksum
+
+        for len in 0..10_usize {
+            let bytes: Vec<u8> = (0..len).map(|byte| (byte * 37 + 200) as u8).collect();
+            let code = encode(&bytes);
+            assert_eq!(code.len(), ((len + 1) * 4).div_ceil(3));
+            assert!(code.bytes().all(|symbol| ALPHABET.contains(&symbol)));
+            assert_eq!(decode(&code), Ok(bytes));
+        }
+        assert_eq!(checksum(b"123456789"), 0xf4);
+        assert_eq!(encode(b"\xfb\xff"), "-_9w");
+        assert_eq!(decode("-_9w"), Ok(vec![0xfb, 0xff]));
+        assert_eq!(decode(""), Err(Error::InvalidEncoding));
+        assert_eq!(decode("-_9x"), Err(Error::InvalidEncoding));
+        assert_eq!(decode("-_+w"), Err(Error::InvalidEncoding));
+        assert_eq!(decode("-_9wA"), Err(Error::InvalidEncoding));
+    }
+
+    #[test]
+    fn share_codes() {
+        //! Test sharing the positions and that every corrupted character is detected
+
+        let game = Game::from_existing(&[[2, 0, 4, 0], [4, 2048, 0, 0], [8, 16, 0, 0], [1 << 40, 32, 0, 2]], 123_456).unwrap();
+        let code = game.share_code().unwrap();
+        assert_eq!(code.len(), 36);
+        let shared = Game::<4>::from_share_code(&code).unwrap();
+        assert_eq!(shared.board(), game.board());
+        assert_eq!(shared.score(), 123_456);
+        assert_eq!(shared.result(), GameResult::Victory);
+        assert_eq!(shared.share_code(), Ok(code.clone()));
+        assert_eq!(Game::<3>::from_share_code(&code).unwrap_err(), Error::InvalidBoard { expected: 3, actual: 4 });
+
+        for ind in 0..code.len() {
+            for symbol in ALPHABET.iter().filter(|&&symbol| symbol != code.as_bytes()[ind]) {
+                let mut corrupted = code.clone().into_bytes();
+                corrupted[ind] = *symbol;
+                assert_eq!(Game::<4>::from_share_code(&String::from_utf8(corrupted).unwrap()).unwrap_err(), Error::InvalidEncoding);
+            }
+        }
+        assert_eq!(Game::<4>::from_share_code(&code[..32]).unwrap_err(), Error::InvalidEncoding);
+        assert_eq!(Game::<4>::from_share_code(&code.replace('A', "=")).unwrap_err(), Error::InvalidEncoding);
+        assert_eq!(Game::<4>::from_share_code("").unwrap_err(), Error::InvalidEncoding);
+
+        // the games that can't be shared
+        let blocked = game.with_blocked_cells(&[(0, 1)]).unwrap();
+        assert_eq!(
+            blocked.share_code(),
+            Err(Error::InvalidValue {
+                row: 0,
+                col: 1,
+                value: BLOCKED_CELL
+            })
+        );
+        let mut junk = Game::<4>::builder().seed(5).junk_every(1).build().unwrap();
+        assert!(junk.make_move(GameMove::ALL.into_iter().find(|&direction| junk.can_move(direction)).unwrap()));
+        assert!(matches!(junk.share_code(), Err(Error::InvalidValue { value: JUNK_TILE, .. })));
+        let threes = Game::<3>::from_existing_with_rule(&[[1, 2, 3], [0, 0, 0], [0, 0, 0]], 0, Arc::new(ThreesRule)).unwrap();
+        assert_eq!(threes.share_code(), Err(Error::InvalidConfig));
+        let fibonacci = Game::<3>::from_existing_with_rule(&[[1, 2, 0], [0, 0, 0], [0, 0, 0]], 0, Arc::new(FibonacciRule)).unwrap();
+        assert_eq!(fibonacci.share_code(), Err(Error::InvalidConfig));
+    }
+}
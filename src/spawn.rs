@@ -0,0 +1,464 @@
+//! A module that contains the configuration of the tiles spawned after every move,
+//! the spawners that decide where the tiles appear, and the policies weighting the cells of the spawned tiles.
+
+// std imports
+use alloc::collections::VecDeque;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt::{self, Formatter};
+
+// external imports
+#[cfg(feature = "std")]
+use rand::seq::SliceRandom;
+#[cfg(feature = "std")]
+use rand::thread_rng;
+use rand::Rng;
+
+// internal imports
+use crate::core::{Game, GameMove, BLOCKED_CELL, DEFAULT_FOUR_PROBABILITY, JUNK_TILE};
+use crate::error::Error;
+use crate::rules::{ClassicRule, MergeRule};
+
+/// The distribution of the values of the spawned tiles.
+/// The default distribution spawns a 4 with the probability of ```DEFAULT_FOUR_PROBABILITY``` (0.1), otherwise a 2.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SpawnConfig {
+    /// The spawned values with their probabilities, summing up to 1.
+    values: Vec<(u64, f64)>,
+}
+impl Default for SpawnConfig {
+    fn default() -> Self {
+        Self {
+            values: vec![(2, 1.0 - DEFAULT_FOUR_PROBABILITY), (4, DEFAULT_FOUR_PROBABILITY)],
+        }
+    }
+}
+impl SpawnConfig {
+    /// Creates the distribution that spawns a 4 with the given probability, otherwise a 2.
+    /// # Arguments
+    /// * ```four_probability``` - The probability of spawning a 4.
+    /// # Returns
+    /// * ```Ok(SpawnConfig)``` - The distribution.
+    /// * ```Err(Error)``` - The probability is invalid.
+    /// # Errors
+    /// * ```Error::InvalidValue``` - The probability is not in the range [0, 1].
+    pub fn new(four_probability: f64) -> Result<Self, Error> {
+        Self::from_values(&[(2, 1.0 - four_probability), (4, four_probability)])
+    }
+
+    /// Creates the distribution that spawns the given values with the given probabilities.
+    /// # Arguments
+    /// * ```values``` - The spawned values with their probabilities.
+    /// # Returns
+    /// * ```Ok(SpawnConfig)``` - The distribution.
+    /// * ```Err(Error)``` - The values or the probabilities are invalid.
+    /// # Errors
+    /// * ```Error::InvalidValue``` - Some value is not a power of 2 (starting from 2), or its probability is not in the range [0, 1].
+    /// * ```Error::InvalidConfig``` - There are no values, or the probabilities don't sum up to 1.
+    pub fn from_values(values: &[(u64, f64)]) -> Result<Self, Error> {
+        Self::from_values_with_rule(values, &ClassicRule)
+    }
+
+    /// Creates the distribution that spawns the given values with the given probabilities,
+    /// where the values must be valid tiles of the given merge rule.
+    /// # Arguments
+    /// * ```values``` - The spawned values with their probabilities.
+    /// * ```rule``` - The merge rule the values are checked against.
+    /// # Returns
+    /// * ```Ok(SpawnConfig)``` - The distribution.
+    /// * ```Err(Error)``` - The values or the probabilities are invalid.
+    /// # Errors
+    /// * ```Error::InvalidValue``` - Some value is not a valid tile of the rule, or its probability is not in the range [0, 1].
+    ///   The ```row``` is always 0 and the ```col``` is the index of the first invalid entry in ```values```.
+    /// * ```Error::InvalidConfig``` - There are no values, or the probabilities don't sum up to 1.
+    pub fn from_values_with_rule(values: &[(u64, f64)], rule: &dyn MergeRule) -> Result<Self, Error> {
+        let invalid_entry = values
+            .iter()
+            .position(|&(value, probability)| value == 0 || !rule.is_valid_tile(value) || !(0.0..=1.0).contains(&probability));
+        if let Some(col) = invalid_entry {
+            return Err(Error::InvalidValue {
+                row: 0,
+                col,
+                value: values[col].0,
+            });
+        }
+        let total: f64 = values.iter().map(|&(_, probability)| probability).sum();
+        if values.is_empty() || (total - 1.0).abs() > 1e-9 {
+            return Err(Error::InvalidConfig);
+        }
+
+        Ok(Self { values: values.to_vec() })
+    }
+
+    /// Returns the spawned values with their probabilities.
+    pub fn values(&self) -> &[(u64, f64)] {
+        &self.values
+    }
+
+    /// Returns the probability of spawning the given value.
+    /// # Arguments
+    /// * ```value``` - The value of the tile.
+    /// # Returns
+    /// * ```f64``` - The probability of spawning the value.
+    pub fn probability(&self, value: u64) -> f64 {
+        self.values.iter().filter(|&&(spawned, _)| spawned == value).map(|&(_, probability)| probability).sum()
+    }
+
+    /// Returns the highest value that can be spawned.
+    pub fn max_value(&self) -> u64 {
+        self.values
+            .iter()
+            .filter(|&&(_, probability)| probability > 0.0)
+            .map(|&(value, _)| value)
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Chooses the value of a spawned tile.
+    /// # Arguments
+    /// * ```rng``` - The random number generator to use.
+    /// # Returns
+    /// * ```u64``` - The value of the tile.
+    pub fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> u64 {
+        let mut roll = rng.gen::<f64>();
+        for &(value, probability) in &self.values {
+            if roll < probability {
+                return value;
+            }
+            roll -= probability;
+        }
+        // rounding errors can leave a tiny part of the range uncovered
+        self.values.iter().rev().find(|&&(_, probability)| probability > 0.0).map_or(2, |&(value, _)| value)
+    }
+}
+
+/// A trait for the spawners that place a new tile after every move.
+pub trait TileSpawner<const SIZE: usize>: Send {
+    /// Chooses the position and the value of the new tile.
+    /// # Arguments
+    /// * ```empty``` - The empty cells of the board (never empty).
+    /// * ```board``` - The board after the move.
+    /// # Returns
+    /// * ```((usize, usize), u64)``` - The position (one of the empty cells) and the value of the new tile.
+    fn spawn(&mut self, empty: &[(usize, usize)], board: &[[u64; SIZE]; SIZE]) -> ((usize, usize), u64);
+}
+impl<const SIZE: usize> fmt::Debug for dyn TileSpawner<SIZE> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "TileSpawner")
+    }
+}
+
+/// A spawner that places a tile from the spawn distribution to a random empty cell.
+/// This is how the tiles are spawned when no spawner is set.
+#[cfg(feature = "std")]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RandomSpawner {
+    /// The distribution of the values of the spawned tiles.
+    pub config: SpawnConfig,
+}
+#[cfg(feature = "std")]
+impl<const SIZE: usize> TileSpawner<SIZE> for RandomSpawner {
+    fn spawn(&mut self, empty: &[(usize, usize)], _board: &[[u64; SIZE]; SIZE]) -> ((usize, usize), u64) {
+        let mut rng = thread_rng();
+        (*empty.choose(&mut rng).unwrap(), self.config.sample(&mut rng))
+    }
+}
+
+/// A spawner that plays back a fixed list of spawns.
+/// Useful for tests, as it makes the games deterministic.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub struct ScriptedSpawner {
+    /// The remaining spawns.
+    script: VecDeque<((usize, usize), u64)>,
+}
+impl ScriptedSpawner {
+    /// Creates the spawner.
+    /// # Arguments
+    /// * ```script``` - The positions and the values of the spawned tiles, in the order of spawning.
+    /// # Returns
+    /// * ```ScriptedSpawner``` - The spawner.
+    pub fn new(script: impl IntoIterator<Item = ((usize, usize), u64)>) -> Self {
+        Self {
+            script: script.into_iter().collect(),
+        }
+    }
+
+    /// Returns the number of the remaining spawns.
+    pub fn remaining(&self) -> usize {
+        self.script.len()
+    }
+}
+impl<const SIZE: usize> TileSpawner<SIZE> for ScriptedSpawner {
+    /// Returns the next spawn from the script.
+    /// # Panics
+    /// * The script has run out.
+    /// * The scripted cell is not empty.
+    fn spawn(&mut self, empty: &[(usize, usize)], _board: &[[u64; SIZE]; SIZE]) -> ((usize, usize), u64) {
+        let (position, value) = self.script.pop_front().expect("the script of spawns has run out");
+        assert!(empty.contains(&position), "the scripted cell {position:?} is not empty");
+        (position, value)
+    }
+}
+
+/// A spawner that places the worst tile in the worst cell for the player.
+/// Every empty cell is tried with a 2 and a 4, and the placement is rated by the most empty cells
+/// the player can get with the next move (a placement after which no move is possible is the best for the spawner).
+/// Ties are resolved in favour of the tiles that differ from their neighbours.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub struct AdversarialSpawner;
+impl<const SIZE: usize> TileSpawner<SIZE> for AdversarialSpawner {
+    fn spawn(&mut self, empty: &[(usize, usize)], board: &[[u64; SIZE]; SIZE]) -> ((usize, usize), u64) {
+        let mut best = None;
+        for &(i, j) in empty {
+            for value in [2, 4] {
+                let mut candidate = *board;
+                candidate[i][j] = value;
+
+                let game = Game::from_board_unchecked(&candidate, 0);
+                let best_reply = GameMove::ALL
+                    .into_iter()
+                    .filter_map(|direction| game.peek_move(direction))
+                    .map(|(next, _)| next.iter().flatten().filter(|&&tile| tile == 0).count() + 1)
+                    .max()
+                    .unwrap_or(0);
+                let neighbours = [(i.wrapping_sub(1), j), (i + 1, j), (i, j.wrapping_sub(1)), (i, j + 1)];
+                let equal_neighbours = neighbours.iter().filter(|&&(x, y)| x < SIZE && y < SIZE && board[x][y] == value).count();
+
+                let rating = (best_reply, equal_neighbours);
+                if best.is_none_or(|(best_rating, _)| rating < best_rating) {
+                    best = Some((rating, ((i, j), value)));
+                }
+            }
+        }
+        best.expect("the spawners are only called with empty cells").1
+    }
+}
+
+/// A trait for the policies weighting the empty cells where the new tile is spawned (see ```GameBuilder::spawn_weight```).
+/// The tile is spawned in a cell with the probability proportional to its weight. The cells of the weight 0 are never chosen,
+/// unless all empty cells have the weight 0, in which case the cell is chosen uniformly.
+/// The policy applies to the tiles spawned by the game from its spawn distribution and to the simulated games of the AI,
+/// not to the tiles placed by a spawner (see ```Game::with_spawner```).
+pub trait SpawnPositionWeight<const SIZE: usize>: Send + Sync {
+    /// Returns the relative weight of an empty cell.
+    /// # Arguments
+    /// * ```board``` - The board after the move.
+    /// * ```position``` - The position (row, column) of the empty cell.
+    /// # Returns
+    /// * ```f64``` - The weight of the cell. Negative and NaN weights count as 0.
+    fn weight(&self, board: &[[u64; SIZE]; SIZE], position: (usize, usize)) -> f64;
+}
+impl<const SIZE: usize> fmt::Debug for dyn SpawnPositionWeight<SIZE> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "SpawnPositionWeight")
+    }
+}
+
+/// The policy that gives every empty cell the same weight, like the games without a policy.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub struct Uniform;
+impl<const SIZE: usize> SpawnPositionWeight<SIZE> for Uniform {
+    fn weight(&self, _board: &[[u64; SIZE]; SIZE], _position: (usize, usize)) -> f64 {
+        1.0
+    }
+}
+
+/// The hard-mode policy that spawns the tiles next to the largest tile (in the same row or column), to break the corner strategies.
+/// The cells that don't touch any largest tile have the weight 0.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub struct NearMaxTile;
+impl<const SIZE: usize> SpawnPositionWeight<SIZE> for NearMaxTile {
+    fn weight(&self, board: &[[u64; SIZE]; SIZE], (i, j): (usize, usize)) -> f64 {
+        let largest = board.iter().flatten().copied().filter(|&tile| tile != BLOCKED_CELL && tile != JUNK_TILE).max().unwrap_or(0);
+        let neighbours = [(i.wrapping_sub(1), j), (i + 1, j), (i, j.wrapping_sub(1)), (i, j + 1)];
+        let touches = largest != 0 && neighbours.iter().any(|&(x, y)| x < SIZE && y < SIZE && board[x][y] == largest);
+        if touches {
+            1.0
+        } else {
+            0.0
+        }
+    }
+}
+
+/// The policy that spawns the tiles only in the corners of the board.
+/// The other cells have the weight 0.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub struct Corners;
+impl<const SIZE: usize> SpawnPositionWeight<SIZE> for Corners {
+    fn weight(&self, _board: &[[u64; SIZE]; SIZE], (i, j): (usize, usize)) -> f64 {
+        if (i == 0 || i == SIZE - 1) && (j == 0 || j == SIZE - 1) {
+            1.0
+        } else {
+            0.0
+        }
+    }
+}
+
+/// Finds the probabilities of the empty cells under the policy.
+/// # Arguments
+/// * ```policy``` - The policy.
+/// * ```board``` - The board.
+/// # Returns
+/// * ```Some(Vec<f64>)``` - The probabilities of the empty cells (row by row), summing up to 1.
+/// * ```None``` - The cells are chosen uniformly, since all weights are 0.
+pub(crate) fn position_probabilities<const SIZE: usize>(policy: &dyn SpawnPositionWeight<SIZE>, board: &[[u64; SIZE]; SIZE]) -> Option<Vec<f64>> {
+    let mut weights: Vec<f64> = (0..SIZE * SIZE)
+        .map(|cell| (cell / SIZE, cell % SIZE))
+        .filter(|&(i, j)| board[i][j] == 0)
+        .map(|position| policy.weight(board, position))
+        .map(|weight| if weight > 0.0 && weight.is_finite() { weight } else { 0.0 })
+        .collect();
+    let total: f64 = weights.iter().sum();
+    if total <= 0.0 {
+        return None;
+    }
+    weights.iter_mut().for_each(|weight| *weight /= total);
+    Some(weights)
+}
+
+/// Chooses the index of an empty cell from its probabilities, never one of the probability 0.
+/// # Arguments
+/// * ```probabilities``` - The probabilities of the empty cells (see ```position_probabilities```).
+/// * ```rng``` - The random number generator to use.
+/// # Returns
+/// * ```usize``` - The index of the chosen cell.
+pub(crate) fn sample_position<R: Rng>(probabilities: &[f64], rng: &mut R) -> usize {
+    let mut remaining: f64 = rng.gen();
+    let mut chosen = 0;
+    for (ind, &probability) in probabilities.iter().enumerate().filter(|&(_, &probability)| probability > 0.0) {
+        chosen = ind;
+        if remaining < probability {
+            break;
+        }
+        remaining -= probability;
+    }
+    // rounding errors can leave a tiny part of the range uncovered, which falls to the last possible cell
+    chosen
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rules::ThreesRule;
+    use rand::rngs::StdRng;
+    use rand::{thread_rng, SeedableRng};
+
+    #[test]
+    fn spawn_config_validation() {
+        //! Test the validation of the spawn distributions
+
+        assert_eq!(SpawnConfig::new(0.1).unwrap(), SpawnConfig::default());
+        assert!(SpawnConfig::new(0.0).is_ok());
+        assert!(SpawnConfig::new(1.0).is_ok());
+        assert_eq!(SpawnConfig::new(-0.1), Err(Error::InvalidValue { row: 0, col: 0, value: 2 }));
+        assert_eq!(SpawnConfig::new(1.5), Err(Error::InvalidValue { row: 0, col: 0, value: 2 }));
+        assert_eq!(SpawnConfig::new(f64::NAN), Err(Error::InvalidValue { row: 0, col: 0, value: 2 }));
+
+        assert!(SpawnConfig::from_values(&[(2, 0.75), (4, 0.2), (8, 0.05)]).is_ok());
+        assert_eq!(SpawnConfig::from_values(&[]), Err(Error::InvalidConfig));
+        assert_eq!(SpawnConfig::from_values(&[(2, 0.5), (6, 0.5)]), Err(Error::InvalidValue { row: 0, col: 1, value: 6 }));
+        assert_eq!(SpawnConfig::from_values(&[(1, 0.5), (2, 0.5)]), Err(Error::InvalidValue { row: 0, col: 0, value: 1 }));
+        assert_eq!(SpawnConfig::from_values(&[(2, 1.2), (4, -0.2)]), Err(Error::InvalidValue { row: 0, col: 0, value: 2 }));
+        assert_eq!(SpawnConfig::from_values(&[(2, 0.5), (4, 0.4)]), Err(Error::InvalidConfig));
+
+        assert!(SpawnConfig::from_values_with_rule(&[(1, 0.5), (3, 0.5)], &ThreesRule).is_ok());
+        assert_eq!(
+            SpawnConfig::from_values_with_rule(&[(2, 0.5), (4, 0.5)], &ThreesRule),
+            Err(Error::InvalidValue { row: 0, col: 1, value: 4 })
+        );
+    }
+
+    #[test]
+    fn spawners() {
+        //! Test the scripted and the adversarial spawners
+
+        let mut spawner = ScriptedSpawner::new([((0, 0), 2), ((3, 3), 4)]);
+        let mut game = Game::from_existing(&[[0, 0, 0, 2], [0, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0]], 0)
+            .unwrap()
+            .with_spawner(Box::new(spawner.clone()));
+        assert!(game.make_move(GameMove::Down));
+        assert_eq!(game.board(), &[[2, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 2]]);
+        assert!(game.make_move(GameMove::Up));
+        assert_eq!(game.board(), &[[2, 0, 0, 2], [0, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 4]]);
+        assert_eq!(spawner.remaining(), 2);
+        assert_eq!(TileSpawner::<4>::spawn(&mut spawner, &[(0, 0)], &[[0; 4]; 4]), ((0, 0), 2));
+        assert_eq!(spawner.remaining(), 1);
+
+        // only the top left cell is empty, the tile that doesn't merge with its neighbours ends the game
+        let board = [[0, 4, 2, 4], [4, 2, 4, 2], [2, 4, 2, 4], [4, 2, 4, 2]];
+        assert_eq!(AdversarialSpawner.spawn(&[(0, 0)], &board), ((0, 0), 2));
+        let board = [[0, 2, 4, 2], [2, 4, 2, 4], [4, 2, 4, 2], [2, 4, 2, 4]];
+        assert_eq!(AdversarialSpawner.spawn(&[(0, 0)], &board), ((0, 0), 4));
+
+        let mut game = Game::<4>::new().unwrap().with_spawner(Box::new(AdversarialSpawner));
+        let mut turns = 0;
+        while let Some(direction) = GameMove::ALL.into_iter().find(|&direction| game.can_move(direction)) {
+            game.make_move(direction);
+            turns += 1;
+        }
+        assert_eq!(game.turns(), turns);
+    }
+
+    #[test]
+    fn spawn_config_sampling() {
+        //! Test that the sampled values follow the distribution
+
+        let config = SpawnConfig::from_values(&[(2, 0.5), (4, 0.25), (8, 0.25)]).unwrap();
+        assert_eq!(config.probability(8), 0.25);
+        assert_eq!(config.probability(16), 0.0);
+        assert_eq!(config.max_value(), 8);
+
+        let mut rng = thread_rng();
+        let mut counts = [0; 3];
+        for _ in 0..100_000 {
+            counts[config.sample(&mut rng).trailing_zeros() as usize - 1] += 1;
+        }
+        assert!((counts[0] as f64 / 100_000.0 - 0.5).abs() < 0.02);
+        assert!((counts[1] as f64 / 100_000.0 - 0.25).abs() < 0.02);
+        assert!((counts[2] as f64 / 100_000.0 - 0.25).abs() < 0.02);
+
+        let config = SpawnConfig::new(1.0).unwrap();
+        assert!((0..1_000).all(|_| config.sample(&mut rng) == 4));
+        assert_eq!(config.max_value(), 4);
+    }
+
+    #[test]
+    fn spawn_position_weights() {
+        //! Test the probabilities of the cells under the policies and that the cells of the weight 0 are never chosen
+
+        let board = [[0, 2, 0, 0], [0, 0, 0, 0], [0, 0, 64, 0], [0, 0, 0, 2]];
+        let uniform = position_probabilities(&Uniform, &board).unwrap();
+        assert_eq!(uniform.len(), 13);
+        assert!(uniform.iter().all(|&probability| (probability - 1.0 / 13.0).abs() < 1e-12));
+
+        // corners (0, 0), (0, 3) and (3, 0) are empty, the cells next to 64 are (1, 2), (2, 1), (2, 3) and (3, 2)
+        let corners = position_probabilities(&Corners, &board).unwrap();
+        let near = position_probabilities(&NearMaxTile, &board).unwrap();
+        let empty: Vec<(usize, usize)> = (0..16).map(|cell| (cell / 4, cell % 4)).filter(|&(i, j)| board[i][j] == 0).collect();
+        for (ind, &(i, j)) in empty.iter().enumerate() {
+            let corner = [(0, 0), (0, 3), (3, 0)].contains(&(i, j));
+            let next_to_max = [(1, 2), (2, 1), (2, 3), (3, 2)].contains(&(i, j));
+            assert_eq!(corners[ind], if corner { 1.0 / 3.0 } else { 0.0 });
+            assert_eq!(near[ind], if next_to_max { 0.25 } else { 0.0 });
+        }
+
+        // the frequencies of the chosen cells follow the probabilities
+        let mut rng = StdRng::seed_from_u64(3);
+        let mut counts = [0usize; 13];
+        for _ in 0..40_000 {
+            counts[sample_position(&near, &mut rng)] += 1;
+        }
+        for (ind, &count) in counts.iter().enumerate() {
+            if near[ind] == 0.0 {
+                assert_eq!(count, 0);
+            } else {
+                assert!((count as f64 / 40_000.0 - 0.25).abs() < 0.02);
+            }
+        }
+
+        // all weights 0 fall back to the uniform choice
+        let full_corners = [[2, 0, 0, 4], [0, 0, 0, 0], [0, 0, 0, 0], [8, 0, 0, 16]];
+        assert_eq!(position_probabilities(&Corners, &full_corners), None);
+        assert_eq!(position_probabilities(&NearMaxTile, &[[0; 4]; 4]), None);
+    }
+}
@@ -0,0 +1,129 @@
+//! A module that contains the [proptest](https://crates.io/crates/proptest) strategies generating boards, games and moves,
+//! for the property tests of the code built on the game:
+//! ```rust
+//! use proptest::prelude::*;
+//! use tools_2048::strategies::{game, moves};
+//!
+//! proptest!(|(mut game in game::<4>(), moves in moves(0..100))| {
+//!     for direction in moves {
+//!         let score = game.score();
+//!         game.make_move(direction);
+//!         prop_assert!(game.score() >= score);
+//!     }
+//! });
+//! ```
+//! The size of the board is the SIZE parameter of the generated board or game.
+//! Available with the ```proptest``` feature.
+
+// std imports
+use std::ops::Range;
+
+// external imports
+use proptest::collection;
+use proptest::prelude::*;
+
+// internal imports
+use crate::core::{Game, GameMove};
+
+/// The largest exponent of the generated tiles.
+const MAX_EXPONENT: u32 = 17;
+
+/// Generates the exponent of a tile, 0 for an empty cell.
+/// The empty cells and the small tiles are more common than the large tiles.
+fn exponent() -> impl Strategy<Value = u32> {
+    prop_oneof![
+        3 => Just(0),
+        3 => 1..=4_u32,
+        2 => 5..=10_u32,
+        1 => 11..=MAX_EXPONENT,
+    ]
+}
+
+/// Generates a valid board: every cell is empty or a power of 2 (up to 2^17), and at least one cell is not empty.
+/// # Returns
+/// * ```impl Strategy<Value = [[u64; SIZE]; SIZE]>``` - The strategy of the boards.
+pub fn board<const SIZE: usize>() -> impl Strategy<Value = [[u64; SIZE]; SIZE]> {
+    (collection::vec(exponent(), SIZE * SIZE), 0..SIZE * SIZE).prop_map(|(exponents, first_tile)| {
+        let mut board = [[0; SIZE]; SIZE];
+        for (tile, exponent) in board.iter_mut().flatten().zip(exponents) {
+            if exponent > 0 {
+                *tile = 1 << exponent;
+            }
+        }
+        // the empty board would get a random starting tile
+        if board.iter().flatten().all(|&tile| tile == 0) {
+            board[first_tile / SIZE][first_tile % SIZE] = 2;
+        }
+        board
+    })
+}
+
+/// Generates a game on a valid board (see ```board```).
+/// The score is at most the score of creating all the tiles by merging twos,
+/// and the game is seeded, so the spawned tiles are reproducible.
+/// # Returns
+/// * ```impl Strategy<Value = Game<SIZE>>``` - The strategy of the games.
+pub fn game<const SIZE: usize>() -> impl Strategy<Value = Game<SIZE>> {
+    (board::<SIZE>(), 0..=100_u64, any::<u64>()).prop_map(|(board, score_percent, seed)| {
+        let max_score: u64 = board
+            .iter()
+            .flatten()
+            .filter(|&&tile| tile != 0)
+            .map(|&tile| u64::from(tile.trailing_zeros() - 1) * tile)
+            .sum();
+        Game::from_existing(&board, max_score / 100 * score_percent)
+            .expect("the generated boards are valid")
+            .with_seed(seed)
+    })
+}
+
+/// Generates a sequence of moves.
+/// # Arguments
+/// * ```len``` - The range of the number of moves.
+/// # Returns
+/// * ```impl Strategy<Value = Vec<GameMove>>``` - The strategy of the sequences of moves.
+pub fn moves(len: Range<usize>) -> impl Strategy<Value = Vec<GameMove>> {
+    collection::vec(proptest::sample::select(GameMove::ALL.to_vec()), len)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{validate_board, GameState};
+
+    /// Plays the moves and checks the properties of the game after every move.
+    fn check_moves<const SIZE: usize>(mut game: Game<SIZE>, moves: Vec<GameMove>) -> Result<(), TestCaseError> {
+        for direction in moves {
+            let (board, score) = (*game.board(), game.score());
+            let moved = game.make_move(direction);
+
+            prop_assert!(game.score() >= score);
+            prop_assert_eq!(moved, game.board() != &board);
+            prop_assert!(game.board().iter().flatten().any(|&tile| tile != 0));
+            if game.state() == GameState::GameOver {
+                prop_assert!(GameMove::ALL.into_iter().all(|direction| !game.can_move(direction)));
+            }
+        }
+        Ok(())
+    }
+
+    proptest! {
+        /// Test that the generated boards are valid
+        #[test]
+        fn generated_boards(board in board::<5>()) {
+            prop_assert!(validate_board(&board).is_ok());
+        }
+
+        /// Test the properties of the moves on the 4x4 games
+        #[test]
+        fn game_properties_4(game in game::<4>(), moves in moves(0..100)) {
+            check_moves(game, moves)?;
+        }
+
+        /// Test the properties of the moves on the 6x6 games
+        #[test]
+        fn game_properties_6(game in game::<6>(), moves in moves(0..100)) {
+            check_moves(game, moves)?;
+        }
+    }
+}
@@ -0,0 +1,164 @@
+//! A module that contains the strategies for playing the 2048 game.
+
+// external imports
+#[cfg(feature = "std")]
+use rand::seq::IteratorRandom;
+#[cfg(feature = "std")]
+use rand::thread_rng;
+
+// internal imports
+use crate::core::{Game, GameMove};
+use crate::heuristics::{self, HeuristicWeights};
+
+/// A trait for the strategies that play the game of 2048.
+pub trait Strategy<const SIZE: usize> {
+    /// Chooses the next move to make.
+    /// # Arguments
+    /// * ```game``` - The game to choose the move for.
+    /// # Returns
+    /// * ```Some(GameMove)``` - The move to make.
+    /// * ```None``` - The strategy has no move to make (usually because the game is over).
+    fn choose(&mut self, game: &Game<SIZE>) -> Option<GameMove>;
+}
+
+/// A strategy that chooses a random valid move.
+#[cfg(feature = "std")]
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub struct RandomStrategy;
+#[cfg(feature = "std")]
+impl<const SIZE: usize> Strategy<SIZE> for RandomStrategy {
+    fn choose(&mut self, game: &Game<SIZE>) -> Option<GameMove> {
+        GameMove::ALL.into_iter().filter(|&direction| game.can_move(direction)).choose(&mut thread_rng())
+    }
+}
+
+/// A strategy that chooses the valid move with the highest immediate score.
+/// Ties are resolved in the order of ```GameMove::ALL```.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub struct GreedyStrategy;
+impl<const SIZE: usize> Strategy<SIZE> for GreedyStrategy {
+    fn choose(&mut self, game: &Game<SIZE>) -> Option<GameMove> {
+        let mut best = None;
+        for direction in GameMove::ALL {
+            if let Some((_, gained)) = game.peek_move(direction) {
+                match best {
+                    Some((_, best_gained)) if best_gained >= gained => {}
+                    _ => best = Some((direction, gained)),
+                }
+            }
+        }
+        best.map(|(direction, _)| direction)
+    }
+}
+
+/// A strategy that chooses the first valid move from a fixed order of moves.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct PriorityStrategy {
+    /// The order in which the moves are tried.
+    pub order: [GameMove; 4],
+}
+impl Default for PriorityStrategy {
+    /// Creates the strategy with the order Down, Left, Right, Up.
+    fn default() -> Self {
+        Self {
+            order: [GameMove::Down, GameMove::Left, GameMove::Right, GameMove::Up],
+        }
+    }
+}
+impl<const SIZE: usize> Strategy<SIZE> for PriorityStrategy {
+    fn choose(&mut self, game: &Game<SIZE>) -> Option<GameMove> {
+        self.order.into_iter().find(|&direction| game.can_move(direction))
+    }
+}
+
+/// A strategy that chooses the valid move with the best board after the move, as evaluated by the heuristics (```heuristics::composite```).
+/// The board is evaluated before the new tile is spawned. Ties are resolved in the order of ```GameMove::ALL```.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct HeuristicStrategy {
+    /// The weights of the heuristics.
+    pub weights: HeuristicWeights,
+}
+impl<const SIZE: usize> Strategy<SIZE> for HeuristicStrategy {
+    fn choose(&mut self, game: &Game<SIZE>) -> Option<GameMove> {
+        let mut best = None;
+        for direction in GameMove::ALL {
+            if let Some((board, _)) = game.peek_move(direction) {
+                let value = heuristics::composite(&board, &self.weights);
+                match best {
+                    Some((_, best_value)) if best_value >= value => {}
+                    _ => best = Some((direction, value)),
+                }
+            }
+        }
+        best.map(|(direction, _)| direction)
+    }
+}
+
+/// A strategy that uses the Monte Carlo AI (```Game::find_best_move```).
+#[cfg(feature = "std")]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct MonteCarloStrategy {
+    /// The number of simulated games to play to determine the best move.
+    pub depth: usize,
+}
+#[cfg(feature = "std")]
+impl<const SIZE: usize> Strategy<SIZE> for MonteCarloStrategy {
+    fn choose(&mut self, game: &Game<SIZE>) -> Option<GameMove> {
+        game.find_best_move(self.depth).ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{GameResult, GameState};
+
+    fn play_to_end(strategy: &mut dyn Strategy<4>) {
+        let mut game: Game<4> = Game::new().unwrap();
+        let summary = game.play_with(strategy);
+
+        assert_eq!(summary.state, GameState::GameOver);
+        assert_ne!(summary.result, GameResult::Pending);
+        assert_eq!(summary.score, game.score());
+        assert_eq!(summary.turns, game.turns());
+        assert_eq!(summary.highest_tile, game.highest_tile());
+        assert!(summary.turns > 0);
+    }
+
+    #[test]
+    fn baseline_strategies() {
+        //! Test that the baseline strategies play the game to the end
+
+        play_to_end(&mut RandomStrategy);
+        play_to_end(&mut GreedyStrategy);
+        play_to_end(&mut PriorityStrategy::default());
+        play_to_end(&mut HeuristicStrategy::default());
+        play_to_end(&mut MonteCarloStrategy { depth: 10 });
+    }
+
+    #[test]
+    fn strategy_choices() {
+        //! Test the choices of the deterministic strategies
+
+        let game = Game::from_existing(&[[0, 0, 0, 2], [0, 0, 0, 4], [0, 0, 0, 0], [0, 0, 0, 4]], 0).unwrap();
+        assert_eq!(GreedyStrategy.choose(&game), Some(GameMove::Up));
+        assert_eq!(PriorityStrategy::default().choose(&game), Some(GameMove::Down));
+        assert_eq!(
+            PriorityStrategy {
+                order: [GameMove::Right, GameMove::Up, GameMove::Left, GameMove::Down]
+            }
+            .choose(&game),
+            Some(GameMove::Up)
+        );
+        // moving to the right takes the highest tile out of the corner
+        let game = Game::from_existing(&[[0, 0, 0, 0], [0, 0, 0, 0], [0, 0, 0, 0], [64, 4, 8, 0]], 0).unwrap();
+        assert_eq!(HeuristicStrategy::default().choose(&game), Some(GameMove::Up));
+
+        let game = Game::from_existing(&[[2, 4, 2, 4], [4, 2, 4, 2], [2, 4, 2, 4], [4, 2, 4, 2]], 0).unwrap();
+        assert_eq!(RandomStrategy.choose(&game), None);
+        assert_eq!(GreedyStrategy.choose(&game), None);
+        assert_eq!(PriorityStrategy::default().choose(&game), None);
+        assert_eq!(HeuristicStrategy::default().choose(&game), None);
+        assert_eq!(MonteCarloStrategy { depth: 10 }.choose(&game), None);
+    }
+}
@@ -0,0 +1,193 @@
+//! A module that contains the symmetries of the board: the rotations and the mirrorings.
+//! The functions work with any square matrix, so they can be used without a game (e.g. for the tile identities).
+
+// internal imports
+use crate::core::GameMove;
+
+/// Rotates the board clockwise by 90 degrees.
+/// # Arguments
+/// * ```board``` - The board to rotate.
+/// # Returns
+/// * ```[[T; SIZE]; SIZE]``` - The rotated board.
+pub fn rotate_cw<T: Copy, const SIZE: usize>(board: &[[T; SIZE]; SIZE]) -> [[T; SIZE]; SIZE] {
+    core::array::from_fn(|i| core::array::from_fn(|j| board[SIZE - 1 - j][i]))
+}
+
+/// Rotates the board counterclockwise by 90 degrees.
+/// # Arguments
+/// * ```board``` - The board to rotate.
+/// # Returns
+/// * ```[[T; SIZE]; SIZE]``` - The rotated board.
+pub fn rotate_ccw<T: Copy, const SIZE: usize>(board: &[[T; SIZE]; SIZE]) -> [[T; SIZE]; SIZE] {
+    core::array::from_fn(|i| core::array::from_fn(|j| board[j][SIZE - 1 - i]))
+}
+
+/// Mirrors the board horizontally (the left column becomes the right one).
+/// # Arguments
+/// * ```board``` - The board to mirror.
+/// # Returns
+/// * ```[[T; SIZE]; SIZE]``` - The mirrored board.
+pub fn flip_horizontal<T: Copy, const SIZE: usize>(board: &[[T; SIZE]; SIZE]) -> [[T; SIZE]; SIZE] {
+    core::array::from_fn(|i| core::array::from_fn(|j| board[i][SIZE - 1 - j]))
+}
+
+/// Mirrors the board vertically (the top row becomes the bottom one).
+/// # Arguments
+/// * ```board``` - The board to mirror.
+/// # Returns
+/// * ```[[T; SIZE]; SIZE]``` - The mirrored board.
+pub fn flip_vertical<T: Copy, const SIZE: usize>(board: &[[T; SIZE]; SIZE]) -> [[T; SIZE]; SIZE] {
+    core::array::from_fn(|i| board[SIZE - 1 - i])
+}
+
+/// Returns all eight symmetric variants of the board (the four rotations and their mirror images).
+/// The first variant is the board itself.
+/// # Arguments
+/// * ```board``` - The board.
+/// # Returns
+/// * ```[[[T; SIZE]; SIZE]; 8]``` - The symmetric variants.
+pub fn variants<T: Copy, const SIZE: usize>(board: &[[T; SIZE]; SIZE]) -> [[[T; SIZE]; SIZE]; 8] {
+    let mut variants = [*board; 8];
+    for i in 1..4 {
+        variants[i] = rotate_cw(&variants[i - 1]);
+    }
+    for i in 4..8 {
+        variants[i] = flip_horizontal(&variants[i - 4]);
+    }
+    variants
+}
+
+/// Returns the canonical variant of the board, the lexicographically smallest of its eight symmetric variants.
+/// Symmetric boards have the same canonical variant.
+/// # Arguments
+/// * ```board``` - The board.
+/// # Returns
+/// * ```[[u64; SIZE]; SIZE]``` - The canonical variant.
+pub fn canonical<const SIZE: usize>(board: &[[u64; SIZE]; SIZE]) -> [[u64; SIZE]; SIZE] {
+    variants(board).into_iter().min().unwrap()
+}
+
+/// Returns the index of the canonical variant among the symmetric variants (see ```variants```).
+/// # Arguments
+/// * ```board``` - The board.
+/// # Returns
+/// * ```usize``` - The index of the canonical variant (0 to 7).
+pub fn canonical_index<const SIZE: usize>(board: &[[u64; SIZE]; SIZE]) -> usize {
+    let variants = variants(board);
+    let canonical = variants.iter().min().unwrap();
+    variants.iter().position(|variant| variant == canonical).unwrap()
+}
+
+/// Transforms a move the same way as the board of the symmetric variant of the given index is transformed (see ```variants```),
+/// so that making the transformed move on the variant has the same effect as making the move on the board.
+/// # Arguments
+/// * ```direction``` - The move to transform.
+/// * ```index``` - The index of the symmetric variant (0 to 7).
+/// # Returns
+/// * ```GameMove``` - The transformed move.
+pub fn transform_move(direction: GameMove, index: usize) -> GameMove {
+    let rotated = (0..index % 4).fold(direction, |direction, _| direction.rotate_cw());
+    if index >= 4 {
+        rotated.flip_horizontal()
+    } else {
+        rotated
+    }
+}
+
+/// Hashes the canonical variant of the board, so that all symmetric boards have the same hash.
+/// The hash is FNV-1a over the exponents of the tiles (other values, like the blocked cells, are hashed whole),
+/// so it is the same on every platform and in every run.
+/// # Arguments
+/// * ```board``` - The board.
+/// # Returns
+/// * ```u64``` - The hash.
+pub fn canonical_hash<const SIZE: usize>(board: &[[u64; SIZE]; SIZE]) -> u64 {
+    const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+    const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+    let mix = |hash: u64, byte: u8| (hash ^ byte as u64).wrapping_mul(FNV_PRIME);
+
+    canonical(board).iter().flatten().fold(FNV_OFFSET, |hash, &tile| {
+        if tile == 0 || tile.is_power_of_two() {
+            mix(hash, if tile == 0 { 0 } else { tile.trailing_zeros() as u8 + 1 })
+        } else {
+            tile.to_le_bytes().into_iter().fold(mix(hash, u8::MAX), mix)
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{thread_rng, Rng};
+    use std::collections::HashSet;
+
+    const BOARD: [[u64; 3]; 3] = [[2, 4, 8], [16, 32, 64], [128, 256, 512]];
+
+    #[test]
+    fn rotations() {
+        //! Test the rotations and their compositions
+
+        assert_eq!(rotate_cw(&BOARD), [[128, 16, 2], [256, 32, 4], [512, 64, 8]]);
+        assert_eq!(rotate_ccw(&BOARD), [[8, 64, 512], [4, 32, 256], [2, 16, 128]]);
+        assert_eq!(rotate_ccw(&rotate_cw(&BOARD)), BOARD);
+        assert_eq!(rotate_cw(&rotate_cw(&rotate_cw(&rotate_cw(&BOARD)))), BOARD);
+        assert_eq!(rotate_cw(&rotate_cw(&BOARD)), rotate_ccw(&rotate_ccw(&BOARD)));
+    }
+
+    #[test]
+    fn flips() {
+        //! Test the mirrorings and their relation to the rotations
+
+        assert_eq!(flip_horizontal(&BOARD), [[8, 4, 2], [64, 32, 16], [512, 256, 128]]);
+        assert_eq!(flip_vertical(&BOARD), [[128, 256, 512], [16, 32, 64], [2, 4, 8]]);
+        assert_eq!(flip_horizontal(&flip_horizontal(&BOARD)), BOARD);
+        assert_eq!(flip_vertical(&flip_horizontal(&BOARD)), rotate_cw(&rotate_cw(&BOARD)));
+    }
+
+    #[test]
+    fn move_transforms() {
+        //! Test that the transformed moves have the same effect on the variants as the moves on the board
+
+        let board = [[2, 0, 4], [0, 8, 0], [2, 4, 0]];
+        let game = crate::Game::from_existing(&board, 0).unwrap();
+        for (index, variant) in variants(&board).iter().enumerate() {
+            let variant_game = crate::Game::from_existing(variant, 0).unwrap();
+            for direction in GameMove::ALL {
+                let moved = game.peek_move(direction).map(|(board, _)| variants(&board)[index]);
+                assert_eq!(variant_game.peek_move(transform_move(direction, index)).map(|(board, _)| board), moved);
+            }
+        }
+        assert_eq!(variants(&board)[canonical_index(&board)], canonical(&board));
+    }
+
+    #[test]
+    fn canonical_hashing() {
+        //! Test that symmetric boards hash identically and that distinct boards don't collide
+
+        let symmetric = variants(&BOARD);
+        let distinct: HashSet<_> = symmetric.iter().collect();
+        assert_eq!(distinct.len(), 8);
+        assert!(symmetric.iter().all(|variant| canonical(variant) == canonical(&BOARD)));
+        assert!(symmetric.iter().all(|variant| canonical_hash(variant) == canonical_hash(&BOARD)));
+
+        // the hash is fixed, so it can be stored
+        assert_eq!(canonical_hash(&[[0u64; 2]; 2]), 0x4d25_767f_9dce_13f5);
+
+        let mut rng = thread_rng();
+        let mut boards = HashSet::new();
+        let mut hashes = HashSet::new();
+        for _ in 0..100_000 {
+            let mut board = [[0; 4]; 4];
+            for tile in board.iter_mut().flatten() {
+                if rng.gen_bool(0.5) {
+                    *tile = 1 << rng.gen_range(1..=11);
+                }
+            }
+            let hash = canonical_hash(&board);
+            assert!(variants(&board).iter().all(|variant| canonical_hash(variant) == hash));
+            boards.insert(canonical(&board));
+            hashes.insert(hash);
+        }
+        assert_eq!(hashes.len(), boards.len());
+    }
+}
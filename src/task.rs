@@ -0,0 +1,143 @@
+//! A module that contains the future of the AI search running in the background (```Game::find_best_move_async```).
+//! The search runs on its own thread, so the future doesn't block the executor and works with any async runtime.
+//! Available with the ```async``` feature.
+
+// std imports
+use std::future::Future;
+use std::panic::{self, AssertUnwindSafe};
+use std::pin::Pin;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::task::{Context, Poll, Waker};
+use std::thread;
+
+// internal imports
+use crate::core::{CancellationToken, GameMove};
+use crate::error::Error;
+
+/// The state shared by the future and the thread running the search.
+#[derive(Debug, Default)]
+struct SearchTaskState {
+    /// The result of the search, once it is finished.
+    result: Option<Result<GameMove, Error>>,
+    /// The waker of the task awaiting the future.
+    waker: Option<Waker>,
+}
+
+/// The future of a search running on a background thread.
+/// Dropping the future cancels the search, so the thread stops soon after.
+/// A search that panics (e.g. in its progress callback) resolves the future to ```Error::Panicked```.
+#[derive(Debug)]
+pub struct SearchFuture {
+    /// The state shared with the search thread.
+    state: Arc<Mutex<SearchTaskState>>,
+    /// The token cancelling the search when the future is dropped.
+    cancel: CancellationToken,
+}
+impl SearchFuture {
+    /// Starts the search on a new thread.
+    /// # Arguments
+    /// * ```search``` - The search to run, given the token that cancels it.
+    /// # Returns
+    /// * ```SearchFuture``` - The future of the result of the search.
+    pub(crate) fn spawn<F>(search: F) -> Self
+    where
+        F: FnOnce(CancellationToken) -> Result<GameMove, Error> + Send + 'static,
+    {
+        let state = Arc::new(Mutex::new(SearchTaskState::default()));
+        let cancel = CancellationToken::new();
+
+        let thread_state = Arc::clone(&state);
+        let thread_cancel = cancel.clone();
+        thread::spawn(move || {
+            let result = panic::catch_unwind(AssertUnwindSafe(|| search(thread_cancel))).unwrap_or(Err(Error::Panicked));
+            let mut state = thread_state.lock().unwrap_or_else(PoisonError::into_inner);
+            state.result = Some(result);
+            if let Some(waker) = state.waker.take() {
+                waker.wake();
+            }
+        });
+
+        Self { state, cancel }
+    }
+}
+impl Future for SearchFuture {
+    type Output = Result<GameMove, Error>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut state = self.state.lock().unwrap();
+        match state.result.take() {
+            Some(result) => Poll::Ready(result),
+            None => {
+                state.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+impl Drop for SearchFuture {
+    fn drop(&mut self) {
+        self.cancel.cancel();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{Game, SearchOptions};
+    use std::task::Wake;
+    use std::thread::Thread;
+
+    /// Wakes the test thread blocked on a future.
+    struct ThreadWaker(Thread);
+    impl Wake for ThreadWaker {
+        fn wake(self: Arc<Self>) {
+            self.0.unpark();
+        }
+    }
+
+    /// Runs the future to completion on the current thread.
+    fn block_on<F: Future>(future: F) -> F::Output {
+        let mut future = std::pin::pin!(future);
+        let waker = Waker::from(Arc::new(ThreadWaker(thread::current())));
+        let mut cx = Context::from_waker(&waker);
+        loop {
+            if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+                return output;
+            }
+            thread::park();
+        }
+    }
+
+    #[test]
+    fn async_search() {
+        //! Test the search in the background and its cancellation
+
+        let game = Game::from_existing(&[[8, 2, 8, 64], [4, 32, 16, 32], [1024, 1024, 2, 256], [2, 8, 256, 32]], 0).unwrap();
+        assert!(block_on(game.find_best_move_async(100)).is_ok());
+        let game = Game::from_existing(&[[2, 4, 2, 4], [4, 2, 4, 2], [2, 4, 2, 4], [4, 2, 4, 2]], 0).unwrap();
+        assert_eq!(block_on(game.find_best_move_async(100)), Err(Error::NoValidMove));
+
+        // dropping the future cancels the search
+        let (sender, receiver) = std::sync::mpsc::channel();
+        drop(SearchFuture::spawn(move |cancel| {
+            while !cancel.is_cancelled() {
+                thread::yield_now();
+            }
+            sender.send(()).unwrap();
+            Err(Error::Cancelled)
+        }));
+        assert!(receiver.recv().is_ok());
+
+        // the panicking search resolves the future
+        let game = Game::<4>::builder().seed(578).build().unwrap();
+        let future = SearchFuture::spawn(move |cancel| {
+            game.find_best_move_with_options(&SearchOptions {
+                depth: 100,
+                cancel: Some(cancel),
+                progress: Some(Arc::new(|_| panic!("the progress callback failed"))),
+                ..Default::default()
+            })
+        });
+        assert_eq!(block_on(future), Err(Error::Panicked));
+    }
+}
@@ -0,0 +1,222 @@
+//! A module that contains the tournament runner for comparing the strategies.
+//! Every strategy plays the same seeded games, so the spawned tiles come from the same random sequences for every strategy.
+
+// std imports
+use std::ops::Range;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+
+// internal imports
+use crate::core::{Game, GameSummary};
+use crate::error::Error;
+use crate::executor;
+use crate::strategy::Strategy;
+
+/// The number of the games of a strategy played by one instance of the strategy, in one thread.
+/// The chunks don't depend on the number of the threads, so the stateful strategies play the same games on every machine.
+const CHUNK_GAMES: usize = 16;
+
+/// A strategy entering a tournament: the function creating an instance of the strategy for every chunk of its games,
+/// so the games of one strategy are played in parallel.
+pub type Entrant<const SIZE: usize> = Box<dyn Fn() -> Box<dyn Strategy<SIZE> + Send> + Send + Sync>;
+
+/// Creates the entrant of the strategy, cloned for every chunk of its games.
+/// # Arguments
+/// * ```strategy``` - The strategy.
+/// # Returns
+/// * ```Entrant``` - The entrant.
+pub fn entrant<const SIZE: usize, S: Strategy<SIZE> + Clone + Send + Sync + 'static>(strategy: S) -> Entrant<SIZE> {
+    Box::new(move || Box::new(strategy.clone()))
+}
+
+/// The results of one strategy in a tournament.
+/// The confidence intervals are the half-widths of the normal approximation of the 95% intervals of the means.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct EntrantReport {
+    /// The number of played games.
+    pub games: usize,
+    /// The number of won games (the target tile was reached).
+    pub wins: usize,
+    /// The ratio of the won games.
+    pub win_rate: f64,
+    /// The confidence interval of the win rate.
+    pub win_rate_ci: f64,
+    /// The mean final score.
+    pub mean_score: f64,
+    /// The confidence interval of the mean score.
+    pub mean_score_ci: f64,
+    /// The mean highest tile.
+    pub mean_highest_tile: f64,
+    /// The confidence interval of the mean highest tile.
+    pub mean_highest_tile_ci: f64,
+}
+impl EntrantReport {
+    /// Creates the report from the summaries of the played games.
+    /// # Arguments
+    /// * ```summaries``` - The summaries of the games.
+    /// # Returns
+    /// * ```EntrantReport``` - The report.
+    fn from_summaries(summaries: &[GameSummary]) -> Self {
+        let wins = summaries.iter().filter(|summary| summary.result.is_victory()).count();
+        let (win_rate, win_rate_ci) = mean_with_ci(summaries.iter().map(|summary| if summary.result.is_victory() { 1.0 } else { 0.0 }));
+        let (mean_score, mean_score_ci) = mean_with_ci(summaries.iter().map(|summary| summary.score as f64));
+        let (mean_highest_tile, mean_highest_tile_ci) = mean_with_ci(summaries.iter().map(|summary| summary.highest_tile as f64));
+        Self {
+            games: summaries.len(),
+            wins,
+            win_rate,
+            win_rate_ci,
+            mean_score,
+            mean_score_ci,
+            mean_highest_tile,
+            mean_highest_tile_ci,
+        }
+    }
+}
+
+/// The results of a tournament.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TournamentReport {
+    /// The results of the strategies, in the order of the entrants.
+    pub entrants: Vec<EntrantReport>,
+}
+
+/// Plays the same seeded games with every strategy.
+/// The games of every strategy are split into the chunks of 16 games, played in parallel by the threads of the executor (see ```executor::available_threads```),
+/// every chunk by a new instance of the strategy.
+/// A game ends when it is over, when the strategy gives up or when the strategy keeps proposing invalid moves (see ```Game::autoplay_with```).
+/// # Arguments
+/// * ```entrants``` - The strategies to compare.
+/// * ```games_per_entrant``` - The number of games every strategy plays.
+/// * ```seed``` - The seed of the games, the game of the index i is seeded with ```seed + i```.
+/// # Returns
+/// * ```Ok(TournamentReport)``` - The results of the strategies.
+/// * ```Err(Error)``` - The games can't be created or a strategy can't play them.
+/// # Errors
+/// * ```Error::InvalidSize``` - The SIZE is invalid. Must be at least 2.
+/// * ```Error::InvalidStrategy``` - A strategy proposed 16 invalid moves in a row (see ```Game::autoplay_with```).
+pub fn run<const SIZE: usize>(entrants: Vec<Entrant<SIZE>>, games_per_entrant: usize, seed: u64) -> Result<TournamentReport, Error> {
+    // the games differ only in the seeds, so they are all valid if the first one is
+    Game::<SIZE>::builder().seed(seed).build()?;
+
+    // the jobs are the chunks of the games of every strategy, in the order of the entrants
+    let chunks: Vec<Range<usize>> = (0..games_per_entrant)
+        .step_by(CHUNK_GAMES)
+        .map(|start| start..(start + CHUNK_GAMES).min(games_per_entrant))
+        .collect();
+    let jobs: Vec<(usize, Range<usize>)> = (0..entrants.len()).flat_map(|entrant| chunks.iter().map(move |games| (entrant, games.clone()))).collect();
+    let results: Vec<Mutex<Result<Vec<GameSummary>, Error>>> = jobs.iter().map(|_| Mutex::new(Ok(Vec::new()))).collect();
+    let next = AtomicUsize::new(0);
+    thread::scope(|scope| {
+        for _ in 0..executor::available_threads().min(jobs.len()) {
+            scope.spawn(|| loop {
+                let ind = next.fetch_add(1, Ordering::Relaxed);
+                let Some((entrant, games)) = jobs.get(ind) else {
+                    break;
+                };
+                let mut strategy = entrants[*entrant]();
+                let summaries = games
+                    .clone()
+                    .map(|game_ind| {
+                        let mut game = Game::<SIZE>::builder()
+                            .seed(seed.wrapping_add(game_ind as u64))
+                            .build()
+                            .expect("the options of the games are checked before the tournament");
+                        game.autoplay_with(strategy.as_mut(), None)
+                    })
+                    .collect();
+                *results[ind].lock().unwrap() = summaries;
+            });
+        }
+    });
+
+    let mut results = results.into_iter().map(|result| result.into_inner().unwrap());
+    let entrants = (0..entrants.len())
+        .map(|_| {
+            let summaries = results.by_ref().take(chunks.len()).collect::<Result<Vec<_>, Error>>()?;
+            Ok(EntrantReport::from_summaries(&summaries.concat()))
+        })
+        .collect::<Result<_, Error>>()?;
+
+    Ok(TournamentReport { entrants })
+}
+
+/// Calculates the mean of the values and the half-width of its 95% confidence interval.
+/// # Arguments
+/// * ```values``` - The values.
+/// # Returns
+/// * ```(f64, f64)``` - The mean and the half-width of the confidence interval (0 for fewer than 2 values).
+fn mean_with_ci(values: impl Iterator<Item = f64> + Clone) -> (f64, f64) {
+    let count = values.clone().count();
+    if count == 0 {
+        return (0.0, 0.0);
+    }
+    let mean = values.clone().sum::<f64>() / count as f64;
+    if count == 1 {
+        return (mean, 0.0);
+    }
+    let variance = values.map(|value| (value - mean).powi(2)).sum::<f64>() / (count - 1) as f64;
+    (mean, 1.96 * (variance / count as f64).sqrt())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::GameMove;
+    use crate::strategy::{GreedyStrategy, PriorityStrategy};
+
+    #[test]
+    fn tournament() {
+        //! Test the reports of a small tournament and that the seeded games are reproducible
+
+        let report = run::<3>(vec![entrant(GreedyStrategy), entrant(PriorityStrategy::default())], 5, 7).unwrap();
+        assert_eq!(report.entrants.len(), 2);
+        for entrant in &report.entrants {
+            assert_eq!(entrant.games, 5);
+            assert!(entrant.wins <= 5);
+            assert_eq!(entrant.win_rate, entrant.wins as f64 / 5.0);
+            assert!(entrant.mean_score > 0.0);
+            assert!(entrant.mean_highest_tile >= 8.0);
+            assert!(entrant.mean_score_ci >= 0.0);
+        }
+
+        // the deterministic strategies play the same games again
+        assert_eq!(run::<3>(vec![entrant(GreedyStrategy), entrant(PriorityStrategy::default())], 5, 7), Ok(report));
+        assert_eq!(run::<3>(Vec::new(), 5, 7).unwrap().entrants, Vec::new());
+        assert_eq!(run::<1>(vec![entrant(GreedyStrategy)], 5, 7), Err(Error::InvalidSize { size: 1 }));
+
+        // the games split into the chunks are the same as the games played one after another
+        let report = run::<3>(vec![entrant(GreedyStrategy), entrant(GreedyStrategy)], 2 * CHUNK_GAMES + 3, 7).unwrap();
+        let summaries: Vec<GameSummary> = (0..2 * CHUNK_GAMES as u64 + 3)
+            .map(|ind| {
+                let mut game = Game::<3>::builder().seed(7 + ind).build().unwrap();
+                let _ = game.autoplay_with(&mut GreedyStrategy, None);
+                game.summary()
+            })
+            .collect();
+        assert_eq!(report.entrants, vec![EntrantReport::from_summaries(&summaries); 2]);
+
+        // the strategy stuck on an invalid move fails the tournament
+        #[derive(Clone)]
+        struct UpStrategy;
+        impl Strategy<3> for UpStrategy {
+            fn choose(&mut self, _game: &Game<3>) -> Option<GameMove> {
+                Some(GameMove::Up)
+            }
+        }
+        assert_eq!(run::<3>(vec![entrant(GreedyStrategy), entrant(UpStrategy)], 5, 7), Err(Error::InvalidStrategy));
+    }
+
+    #[test]
+    fn confidence_intervals() {
+        //! Test the means and the confidence intervals
+
+        assert_eq!(mean_with_ci([].into_iter()), (0.0, 0.0));
+        assert_eq!(mean_with_ci([4.0].into_iter()), (4.0, 0.0));
+        assert_eq!(mean_with_ci([2.0, 2.0, 2.0].into_iter()), (2.0, 0.0));
+        let (mean, ci) = mean_with_ci([1.0, 3.0].into_iter());
+        assert_eq!(mean, 2.0);
+        assert!((ci - 1.96).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,270 @@
+//! A module that contains the tuning of the heuristic weights (```HeuristicWeights```) by self-play.
+//! The weights are tuned with the cross-entropy method: every iteration samples a population of weights around the current mean,
+//! plays the same seeded games with every sampled weights (```HeuristicStrategy```) and moves the mean to the weights that scored the best.
+
+// std imports
+use std::fmt;
+use std::sync::Arc;
+use std::thread;
+
+// external imports
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+// internal imports
+use crate::core::Game;
+use crate::error::Error;
+use crate::executor;
+use crate::heuristics::HeuristicWeights;
+use crate::strategy::HeuristicStrategy;
+
+/// The number of the tuned weights, the fields of ```HeuristicWeights```.
+const WEIGHTS: usize = 6;
+
+/// The progress of the tuning, passed to the progress callback after every iteration.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct TuneProgress {
+    /// The number of finished iterations.
+    pub iteration: usize,
+    /// The number of planned iterations.
+    pub iterations: usize,
+    /// The best weights found so far.
+    pub best_weights: HeuristicWeights,
+    /// The mean score of the games played with the best weights.
+    pub best_score: f64,
+}
+
+/// A callback notified about the progress of the tuning.
+pub type TuneCallback = Arc<dyn Fn(TuneProgress) + Send + Sync>;
+
+/// The configuration of the tuning (```optimize```).
+#[derive(Clone)]
+pub struct TuneConfig {
+    /// The number of iterations of the cross-entropy method.
+    pub iterations: usize,
+    /// The number of weights evaluated in every iteration (0 is treated as 1).
+    pub population: usize,
+    /// The number of the best weights the next mean and spread are taken from (clamped to 1..=population).
+    pub elite: usize,
+    /// The number of games played with every weights.
+    pub games: usize,
+    /// The seed of the sampled weights and of the games, the game of the index i is seeded with ```seed + i```.
+    pub seed: u64,
+    /// The weights the search starts from.
+    pub initial: HeuristicWeights,
+    /// The standard deviation of every weight in the first iteration.
+    pub initial_spread: f64,
+    /// The callback notified about the progress of the tuning.
+    pub progress: Option<TuneCallback>,
+}
+impl Default for TuneConfig {
+    /// Creates the configuration with 10 iterations of 16 weights with 4 elite ones, 8 games per weights, the seed 0,
+    /// the search starting from ```HeuristicWeights::default``` with the spread of 1 and no progress callback.
+    fn default() -> Self {
+        Self {
+            iterations: 10,
+            population: 16,
+            elite: 4,
+            games: 8,
+            seed: 0,
+            initial: HeuristicWeights::default(),
+            initial_spread: 1.0,
+            progress: None,
+        }
+    }
+}
+impl fmt::Debug for TuneConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TuneConfig")
+            .field("iterations", &self.iterations)
+            .field("population", &self.population)
+            .field("elite", &self.elite)
+            .field("games", &self.games)
+            .field("seed", &self.seed)
+            .field("initial", &self.initial)
+            .field("initial_spread", &self.initial_spread)
+            .field("progress", &self.progress.as_ref().map(|_| "TuneCallback"))
+            .finish()
+    }
+}
+
+/// Tunes the heuristic weights by self-play with the cross-entropy method.
+/// The first weights of every population are the current mean, so the tuned weights never score less than the initial ones on the played games.
+/// The weights are evaluated in parallel, each thread plays all games of its weights.
+/// The tuning is deterministic, the same configuration always gives the same weights.
+/// # Arguments
+/// * ```config``` - The configuration of the tuning.
+/// # Returns
+/// * ```Ok(HeuristicWeights)``` - The weights with the highest mean score of all evaluated weights.
+/// * ```Err(Error)``` - The games can't be created.
+/// # Errors
+/// * ```Error::InvalidSize``` - The SIZE is invalid. Must be at least 2.
+pub fn optimize<const SIZE: usize>(config: TuneConfig) -> Result<HeuristicWeights, Error> {
+    // the games differ only in the seeds, so they are all valid if the first one is
+    Game::<SIZE>::builder().seed(config.seed).build()?;
+
+    let population = config.population.max(1);
+    let elite = config.elite.clamp(1, population);
+    let mut rng = StdRng::seed_from_u64(config.seed);
+
+    let mut mean = to_array(&config.initial);
+    let mut spread = [config.initial_spread.abs(); WEIGHTS];
+    let mut best = (config.initial, f64::NEG_INFINITY);
+    for iteration in 0..config.iterations {
+        let candidates: Vec<[f64; WEIGHTS]> = (0..population)
+            .map(|ind| {
+                if ind == 0 {
+                    mean
+                } else {
+                    let mut weights = mean;
+                    for (weight, spread) in weights.iter_mut().zip(spread) {
+                        *weight += spread * standard_normal(&mut rng);
+                    }
+                    weights
+                }
+            })
+            .collect();
+        let scores = evaluate_all::<SIZE>(&candidates, config.games, config.seed);
+
+        let mut ranked: Vec<usize> = (0..population).collect();
+        ranked.sort_by(|&first, &second| scores[second].total_cmp(&scores[first]).then(first.cmp(&second)));
+        if scores[ranked[0]] > best.1 {
+            best = (from_array(candidates[ranked[0]]), scores[ranked[0]]);
+        }
+
+        // the mean and the spread of the elite weights
+        for (weight, (mean, spread)) in mean.iter_mut().zip(spread.iter_mut()).enumerate() {
+            *mean = ranked[..elite].iter().map(|&ind| candidates[ind][weight]).sum::<f64>() / elite as f64;
+            let variance = ranked[..elite].iter().map(|&ind| (candidates[ind][weight] - *mean).powi(2)).sum::<f64>() / elite as f64;
+            *spread = variance.sqrt();
+        }
+
+        if let Some(callback) = &config.progress {
+            callback(TuneProgress {
+                iteration: iteration + 1,
+                iterations: config.iterations,
+                best_weights: best.0,
+                best_score: best.1,
+            });
+        }
+    }
+
+    Ok(best.0)
+}
+
+/// Evaluates the weights by the mean score of the seeded games, in parallel.
+/// # Arguments
+/// * ```candidates``` - The weights to evaluate.
+/// * ```games``` - The number of games played with every weights.
+/// * ```seed``` - The seed of the first game.
+/// # Returns
+/// * ```Vec<f64>``` - The mean scores, in the order of the weights (0 if no games are played).
+fn evaluate_all<const SIZE: usize>(candidates: &[[f64; WEIGHTS]], games: usize, seed: u64) -> Vec<f64> {
+    let threads = executor::available_threads();
+    let chunk_size = candidates.len().div_ceil(threads).max(1);
+    thread::scope(|scope| {
+        let handles: Vec<_> = candidates
+            .chunks(chunk_size)
+            .map(|chunk| scope.spawn(move || chunk.iter().map(|&weights| mean_score::<SIZE>(from_array(weights), games, seed)).collect::<Vec<_>>()))
+            .collect();
+        handles.into_iter().flat_map(|handle| handle.join().unwrap()).collect()
+    })
+}
+
+/// Plays the seeded games with the weights.
+/// # Arguments
+/// * ```weights``` - The weights of the heuristics.
+/// * ```games``` - The number of games.
+/// * ```seed``` - The seed of the first game.
+/// # Returns
+/// * ```f64``` - The mean score of the games (0 if no games are played).
+fn mean_score<const SIZE: usize>(weights: HeuristicWeights, games: usize, seed: u64) -> f64 {
+    if games == 0 {
+        return 0.0;
+    }
+    let mut strategy = HeuristicStrategy { weights };
+    let total: u64 = (0..games)
+        .map(|ind| {
+            let mut game = Game::<SIZE>::builder()
+                .seed(seed.wrapping_add(ind as u64))
+                .build()
+                .expect("the options of the games are checked before the tuning");
+            game.play_with(&mut strategy).score
+        })
+        .sum();
+    total as f64 / games as f64
+}
+
+/// Samples the standard normal distribution (the Box-Muller transform).
+fn standard_normal<R: Rng + ?Sized>(rng: &mut R) -> f64 {
+    let first: f64 = 1.0 - rng.gen::<f64>();
+    let second: f64 = rng.gen();
+    (-2.0 * first.ln()).sqrt() * (std::f64::consts::TAU * second).cos()
+}
+
+/// Converts the weights to an array.
+fn to_array(weights: &HeuristicWeights) -> [f64; WEIGHTS] {
+    [
+        weights.empty_cells,
+        weights.monotonicity,
+        weights.smoothness,
+        weights.max_tile_in_corner,
+        weights.disorder,
+        weights.snake_score,
+    ]
+}
+
+/// Converts the array back to the weights.
+fn from_array(weights: [f64; WEIGHTS]) -> HeuristicWeights {
+    HeuristicWeights {
+        empty_cells: weights[0],
+        monotonicity: weights[1],
+        smoothness: weights[2],
+        max_tile_in_corner: weights[3],
+        disorder: weights[4],
+        snake_score: weights[5],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    #[test]
+    fn tuning() {
+        //! Test a tiny tuning: the weights are finite, the progress is reported and the tuning is reproducible
+
+        let reports = Arc::new(Mutex::new(Vec::new()));
+        let reports_clone = Arc::clone(&reports);
+        let config = TuneConfig {
+            iterations: 3,
+            population: 6,
+            elite: 2,
+            games: 4,
+            seed: 11,
+            progress: Some(Arc::new(move |progress| reports_clone.lock().unwrap().push(progress))),
+            ..Default::default()
+        };
+        let weights = optimize::<3>(config.clone()).unwrap();
+        assert!(to_array(&weights).iter().all(|weight| weight.is_finite()));
+
+        let reports = reports.lock().unwrap();
+        assert_eq!(reports.iter().map(|progress| progress.iteration).collect::<Vec<_>>(), vec![1, 2, 3]);
+        assert!(reports.iter().all(|progress| progress.iterations == 3 && progress.best_score > 0.0));
+        assert!(reports.windows(2).all(|pair| pair[0].best_score <= pair[1].best_score));
+        assert_eq!(reports[2].best_weights, weights);
+        // the tuned weights score at least as much as the initial ones on the same games
+        assert!(reports[2].best_score >= mean_score::<3>(HeuristicWeights::default(), 4, 11));
+
+        assert_eq!(optimize::<3>(TuneConfig { progress: None, ..config }), Ok(weights));
+        assert_eq!(
+            optimize::<3>(TuneConfig {
+                iterations: 0,
+                ..Default::default()
+            }),
+            Ok(HeuristicWeights::default())
+        );
+        assert_eq!(optimize::<1>(TuneConfig::default()), Err(Error::InvalidSize { size: 1 }));
+    }
+}
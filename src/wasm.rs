@@ -0,0 +1,151 @@
+//! A module that contains the WebAssembly bindings of the game, for driving the game from JavaScript.
+//! The search runs on the current thread (```SearchBackend::CurrentThread```), since the threads are not available in the browser.
+//! Available with the ```wasm``` feature. The random numbers come from the ```crypto``` API of the browser (the ```js``` feature of ```getrandom```).
+
+// external imports
+use wasm_bindgen::prelude::*;
+
+// internal imports
+use crate::core::{Game, GameMove, GameState, SearchBackend, SearchOptions};
+
+/// A game of one of the supported sizes.
+/// The games are boxed, so that the small games don't take the space of the large ones.
+#[derive(Debug)]
+enum AnyGame {
+    Size2(Box<Game<2>>),
+    Size3(Box<Game<3>>),
+    Size4(Box<Game<4>>),
+    Size5(Box<Game<5>>),
+    Size6(Box<Game<6>>),
+    Size7(Box<Game<7>>),
+    Size8(Box<Game<8>>),
+}
+
+/// Runs the expression with the game of any size bound to the given name.
+macro_rules! with_game {
+    ($any_game:expr, $game:ident => $body:expr) => {
+        match $any_game {
+            AnyGame::Size2($game) => $body,
+            AnyGame::Size3($game) => $body,
+            AnyGame::Size4($game) => $body,
+            AnyGame::Size5($game) => $body,
+            AnyGame::Size6($game) => $body,
+            AnyGame::Size7($game) => $body,
+            AnyGame::Size8($game) => $body,
+        }
+    };
+}
+
+/// The game of 2048 exported to JavaScript.
+/// The moves are numbered in the order of ```GameMove::ALL``` (0 - left, 1 - right, 2 - up, 3 - down).
+#[wasm_bindgen]
+#[derive(Debug)]
+pub struct WasmGame {
+    /// The game.
+    game: AnyGame,
+}
+
+#[wasm_bindgen]
+impl WasmGame {
+    /// Creates a new game with the given size.
+    /// # Arguments
+    /// * ```size``` - The size of the board (2 to 8).
+    /// # Returns
+    /// * ```Ok(WasmGame)``` - The new game.
+    /// * ```Err(JsError)``` - The size is not supported.
+    #[wasm_bindgen(constructor)]
+    pub fn new(size: usize) -> Result<WasmGame, JsError> {
+        let game = match size {
+            2 => Game::new().map(|game| AnyGame::Size2(Box::new(game))),
+            3 => Game::new().map(|game| AnyGame::Size3(Box::new(game))),
+            4 => Game::new().map(|game| AnyGame::Size4(Box::new(game))),
+            5 => Game::new().map(|game| AnyGame::Size5(Box::new(game))),
+            6 => Game::new().map(|game| AnyGame::Size6(Box::new(game))),
+            7 => Game::new().map(|game| AnyGame::Size7(Box::new(game))),
+            8 => Game::new().map(|game| AnyGame::Size8(Box::new(game))),
+            _ => return Err(JsError::new("The size of the board must be between 2 and 8.")),
+        };
+        game.map(|game| Self { game }).map_err(|error| JsError::new(&error.to_string()))
+    }
+
+    /// Makes a move.
+    /// # Arguments
+    /// * ```dir``` - The number of the move.
+    /// # Returns
+    /// * ```true``` - The move was made.
+    /// * ```false``` - The move is not possible or the number of the move is invalid.
+    #[wasm_bindgen(js_name = makeMove)]
+    pub fn make_move(&mut self, dir: u8) -> bool {
+        match GameMove::ALL.get(usize::from(dir)) {
+            Some(&direction) => with_game!(&mut self.game, game => game.make_move(direction)),
+            None => false,
+        }
+    }
+
+    /// Returns the board, row by row.
+    /// # Returns
+    /// * ```Vec<u64>``` - The tiles of the board.
+    pub fn board(&self) -> Vec<u64> {
+        with_game!(&self.game, game => game.board().as_flattened().to_vec())
+    }
+
+    /// Returns the score.
+    /// # Returns
+    /// * ```u64``` - The score.
+    pub fn score(&self) -> u64 {
+        with_game!(&self.game, game => game.score())
+    }
+
+    /// Returns the state of the game.
+    /// # Returns
+    /// * ```u8``` - 0 if the game is in progress, 1 if the game is over.
+    pub fn state(&self) -> u8 {
+        match with_game!(&self.game, game => game.state()) {
+            GameState::InProgress => 0,
+            GameState::GameOver => 1,
+        }
+    }
+
+    /// Finds the best move with the Monte Carlo search on the current thread.
+    /// # Arguments
+    /// * ```depth``` - The number of simulated games.
+    /// # Returns
+    /// * ```Some(u8)``` - The number of the best move.
+    /// * ```None``` - There are no valid moves left or the depth is 0.
+    #[wasm_bindgen(js_name = findBestMove)]
+    pub fn find_best_move(&self, depth: usize) -> Option<u8> {
+        let options = SearchOptions {
+            depth,
+            backend: SearchBackend::CurrentThread,
+            ..Default::default()
+        };
+        let best_move = with_game!(&self.game, game => game.find_best_move_with_options(&options)).ok()?;
+        GameMove::ALL.iter().position(|&direction| direction == best_move).map(|ind| ind as u8)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test::wasm_bindgen_test)]
+    #[cfg_attr(not(target_arch = "wasm32"), test)]
+    fn wasm_game() {
+        //! Test playing a few moves through the bindings
+
+        let mut game = WasmGame::new(4).unwrap();
+        assert_eq!(game.board().len(), 16);
+        assert_eq!(game.board().iter().filter(|&&tile| tile != 0).count(), 1);
+        assert_eq!(game.state(), 0);
+        assert!(!game.make_move(4));
+
+        for _ in 0..5 {
+            let direction = game.find_best_move(50).unwrap();
+            assert!(direction < 4);
+            assert!(game.make_move(direction));
+        }
+        // every move spawned a new tile
+        assert!(game.board().iter().sum::<u64>() >= 12);
+        assert_eq!(game.find_best_move(0), None);
+    }
+}
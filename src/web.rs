@@ -0,0 +1,222 @@
+//! A module that contains the save format of the original web game (gabrielecirulli/2048).
+//! The web game keeps its state in ```localStorage``` as JSON, with the tiles stored column by column:
+//! ```{"grid":{"size":4,"cells":[[null,{"position":{"x":0,"y":1},"value":2},...],...]},"score":0,"over":false,"won":false,"keepPlaying":false}```
+//! Used by ```Game::from_web_json``` and ```Game::to_web_json```. Available with the ```serde``` feature.
+
+// std imports
+use alloc::string::String;
+use alloc::vec::Vec;
+
+// external imports
+use serde::{Deserialize, Serialize};
+
+// internal imports
+use crate::core::{BLOCKED_CELL, JUNK_TILE};
+use crate::error::Error;
+
+/// The state of the web game, as stored in ```localStorage```.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct WebGameState {
+    grid: WebGrid,
+    score: u64,
+    over: bool,
+    won: bool,
+    keep_playing: bool,
+}
+
+/// The grid of the web game, the cells are indexed by the column (x) and then by the row (y).
+#[derive(Debug, Serialize, Deserialize)]
+struct WebGrid {
+    size: usize,
+    cells: Vec<Vec<Option<WebTile>>>,
+}
+
+/// A tile of the web game.
+#[derive(Debug, Serialize, Deserialize)]
+struct WebTile {
+    position: WebPosition,
+    value: u64,
+}
+
+/// The position of a tile of the web game.
+#[derive(Debug, Serialize, Deserialize)]
+struct WebPosition {
+    x: usize,
+    y: usize,
+}
+
+/// The parts of a game stored in the save of the web game.
+#[derive(Debug)]
+pub(crate) struct WebSave<const SIZE: usize> {
+    /// The board, row by row.
+    pub(crate) board: [[u64; SIZE]; SIZE],
+    /// The score.
+    pub(crate) score: u64,
+    /// Whether the game is won.
+    pub(crate) won: bool,
+    /// Whether the game is over.
+    pub(crate) over: bool,
+}
+impl<const SIZE: usize> WebSave<SIZE> {
+    /// Parses the save of the web game. The values of the tiles are not validated.
+    /// # Arguments
+    /// * ```json``` - The JSON stored by the web game.
+    /// # Returns
+    /// * ```Ok(WebSave)``` - The parsed save.
+    /// * ```Err(Error)``` - The save is malformed.
+    /// # Errors
+    /// * ```Error::InvalidEncoding``` - The JSON is malformed, or a tile is stored in the cell of another position.
+    /// * ```Error::InvalidBoard``` - The size of the grid is not SIZE.
+    pub(crate) fn from_json(json: &str) -> Result<Self, Error> {
+        let state: WebGameState = serde_json::from_str(json).map_err(|_| Error::InvalidEncoding)?;
+        let grid = state.grid;
+        if grid.size != SIZE {
+            return Err(Error::InvalidBoard {
+                expected: SIZE,
+                actual: grid.size,
+            });
+        }
+        if grid.cells.len() != SIZE {
+            return Err(Error::InvalidBoard {
+                expected: SIZE,
+                actual: grid.cells.len(),
+            });
+        }
+
+        let mut board = [[0; SIZE]; SIZE];
+        for (x, column) in grid.cells.into_iter().enumerate() {
+            if column.len() != SIZE {
+                return Err(Error::InvalidBoard {
+                    expected: SIZE,
+                    actual: column.len(),
+                });
+            }
+            for (y, tile) in column.into_iter().enumerate() {
+                if let Some(tile) = tile {
+                    if tile.position.x != x || tile.position.y != y {
+                        return Err(Error::InvalidEncoding);
+                    }
+                    board[y][x] = tile.value;
+                }
+            }
+        }
+
+        Ok(Self {
+            board,
+            score: state.score,
+            won: state.won,
+            over: state.over,
+        })
+    }
+
+    /// Writes the save in the format of the web game.
+    /// The won game is saved as continued (```keepPlaying```), since the games of this crate continue after the victory.
+    /// The blocked cells and the junk tiles can't be represented in the web game, so they are saved as empty cells.
+    /// # Returns
+    /// * ```String``` - The JSON of the save.
+    pub(crate) fn to_json(&self) -> String {
+        let cells = (0..SIZE)
+            .map(|x| {
+                (0..SIZE)
+                    .map(|y| match self.board[y][x] {
+                        0 | BLOCKED_CELL | JUNK_TILE => None,
+                        value => Some(WebTile {
+                            position: WebPosition { x, y },
+                            value,
+                        }),
+                    })
+                    .collect()
+            })
+            .collect();
+        let state = WebGameState {
+            grid: WebGrid { size: SIZE, cells },
+            score: self.score,
+            over: self.over,
+            won: self.won,
+            keep_playing: self.won,
+        };
+        // the plain structs always serialize
+        serde_json::to_string(&state).unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::core::{Game, GameMove, GameResult, GameState, InvalidReason};
+    use crate::error::Error;
+
+    /// The ```localStorage``` of the web game in the middle of a game, as dumped by ```JSON.stringify(localStorage)``` in the console of the browser.
+    /// The web game stores the state under ```gameState``` as a JSON string and the best score under ```bestScore``` as a number string.
+    const LOCAL_STORAGE: &str = r#"{"bestScore":"5472","gameState":"{\"grid\":{\"size\":4,\"cells\":[[null,{\"position\":{\"x\":0,\"y\":1},\"value\":4},{\"position\":{\"x\":0,\"y\":2},\"value\":8},{\"position\":{\"x\":0,\"y\":3},\"value\":256}],[{\"position\":{\"x\":1,\"y\":0},\"value\":2},null,{\"position\":{\"x\":1,\"y\":2},\"value\":16},{\"position\":{\"x\":1,\"y\":3},\"value\":64}],[null,null,{\"position\":{\"x\":2,\"y\":2},\"value\":4},{\"position\":{\"x\":2,\"y\":3},\"value\":32}],[null,{\"position\":{\"x\":3,\"y\":1},\"value\":2},null,{\"position\":{\"x\":3,\"y\":3},\"value\":8}]]},\"score\":2252,\"over\":false,\"won\":false,\"keepPlaying\":false}"}"#;
+
+    /// Reads the state of the game and the best score from the dump of ```localStorage```.
+    fn web_storage() -> (String, u64) {
+        let storage: serde_json::Value = serde_json::from_str(LOCAL_STORAGE).unwrap();
+        let state = storage["gameState"].as_str().unwrap().to_string();
+        let best_score = storage["bestScore"].as_str().unwrap().parse().unwrap();
+        (state, best_score)
+    }
+
+    #[test]
+    fn web_round_trip() {
+        //! Test loading the state of the web game and saving it back
+
+        let (state, best_score) = web_storage();
+        let mut game = Game::<4>::from_web_json(&state).unwrap();
+        assert_eq!(game.board(), &[[0, 2, 0, 0], [4, 0, 0, 2], [8, 16, 4, 0], [256, 64, 32, 8]]);
+        assert_eq!(game.score(), 2252);
+        assert!(game.score() <= best_score);
+        assert_eq!(game.state(), GameState::InProgress);
+        assert_eq!(game.result(), GameResult::Pending);
+        assert_eq!(game.to_web_json(), state);
+
+        // the saved game continues like the original one
+        assert!(game.make_move(GameMove::Right));
+        let loaded = Game::<4>::from_web_json(&game.to_web_json()).unwrap();
+        assert_eq!(loaded.board(), game.board());
+        assert_eq!(loaded.score(), game.score());
+
+        // the flags of the won and the lost games
+        let won = state
+            .replace(r#""value":256"#, r#""value":2048"#)
+            .replace(r#""won":false,"keepPlaying":false"#, r#""won":true,"keepPlaying":true"#);
+        let game = Game::<4>::from_web_json(&won).unwrap();
+        assert_eq!(game.result(), GameResult::Victory);
+        assert_eq!(game.to_web_json(), won);
+        let over = won.replace(r#""over":false"#, r#""over":true"#);
+        let mut game = Game::<4>::from_web_json(&over).unwrap();
+        assert_eq!(game.state(), GameState::GameOver);
+        assert_eq!(game.result(), GameResult::VictoryThenLoss);
+        assert_eq!(game.to_web_json(), over);
+        // the game was saved as over, so no move is possible although the board allows one
+        for direction in GameMove::ALL {
+            assert!(!game.can_move(direction));
+            assert_eq!(game.why_invalid(direction), Some(InvalidReason::GameFinished));
+            assert!(!game.make_move(direction));
+        }
+    }
+
+    #[test]
+    fn web_errors() {
+        //! Test that the malformed states are rejected
+
+        let (state, _) = web_storage();
+        assert_eq!(Game::<4>::from_web_json("").unwrap_err(), Error::InvalidEncoding);
+        assert_eq!(Game::<4>::from_web_json(&state[..100]).unwrap_err(), Error::InvalidEncoding);
+        assert_eq!(Game::<4>::from_web_json(r#"{"score":4}"#).unwrap_err(), Error::InvalidEncoding);
+        assert_eq!(
+            Game::<4>::from_web_json(&state.replace(r#""value":256"#, r#""value":-128"#)).unwrap_err(),
+            Error::InvalidEncoding
+        );
+        assert_eq!(
+            Game::<4>::from_web_json(&state.replace(r#""x":3,"y":3"#, r#""x":3,"y":2"#)).unwrap_err(),
+            Error::InvalidEncoding
+        );
+        assert_eq!(Game::<3>::from_web_json(&state).unwrap_err(), Error::InvalidBoard { expected: 3, actual: 4 });
+        assert_eq!(
+            Game::<4>::from_web_json(&state.replace(r#""value":256"#, r#""value":100"#)).unwrap_err(),
+            Error::InvalidValue { row: 3, col: 0, value: 100 }
+        );
+    }
+}